@@ -0,0 +1,136 @@
+use std::io::StdinLock;
+use std::time::SystemTime;
+use engine::tablebase::{Ending, Outcome, Tablebases};
+use ress::{DrawClaim, GameOutcome, MoveError, PlayerMove};
+use ress::annotation::MarkColor;
+use ress::render::RenderOptions;
+use crate::prompt;
+
+pub fn parse_ending(raw: &str) -> Option<Ending> {
+    match raw {
+        "kqk" => Some(Ending::Queen),
+        "krk" => Some(Ending::Rook),
+        "kpk" => Some(Ending::Pawn),
+        _ => None,
+    }
+}
+
+fn claimed(claim: DrawClaim) -> &'static str {
+    match claim {
+        DrawClaim::ThreefoldRepetition => "the position has repeated three times",
+        DrawClaim::FiftyMoves => "50 moves have passed without a capture or a pawn move",
+    }
+}
+
+// close to the longest each ending takes, so there's something to get wrong
+fn min_plies(ending: Ending) -> u8 {
+    match ending {
+        Ending::Queen => 15,
+        Ending::Rook => 25,
+        Ending::Pawn => 35,
+    }
+}
+
+fn moves(plies: u8) -> u8 {
+    plies.div_ceil(2)
+}
+
+// the user plays white and has to mate against perfect defense, returns whether they want to exit altogether
+pub fn run(stdin: &mut StdinLock, tablebases: &Tablebases, ending: Ending) -> bool {
+    let seed = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |time| time.as_nanos() as u64);
+    let Some(mut board) = tablebases.position(ending, min_plies(ending), seed) else {
+        println!("there is no position for this drill, sorry.");
+        return false;
+    };
+    let Some(Outcome::Win(best)) = tablebases.probe(&board) else {
+        println!("there is no position for this drill, sorry.");
+        return false;
+    };
+    println!("mate with white against perfect defense, it can be done in {} moves.", moves(best));
+
+    let mut played = 0;
+    println!("{board}");
+    loop {
+        let command = prompt(stdin);
+        if command.len() <= 1 {
+            println!("to leave the drill enter /abort.");
+            continue;
+        };
+
+        match command.trim() {
+            "/help" => println!("you can /help, /hint, /abort, /exit or enter a move."),
+            "/hint" => match tablebases.best_move(&board) {
+                Some((r#move, _)) => {
+                    let arrow = RenderOptions::default().arrow(r#move.resolve_from(board.move_color), r#move.resolve_to(board.move_color), MarkColor::Green);
+                    println!("{}\nhint: {}", board.grid().render(&arrow), board.san(r#move));
+                },
+                None => println!("no hint here, sorry."),
+            },
+            "/abort" => return false,
+            "/exit" => return true,
+            raw if raw.starts_with('/') => println!("unknown command. enter /help for help."),
+            raw_move => {
+                let Some(r#move) = board.parse_move(raw_move) else {
+                    println!("move is invalid, you can enter san (e.g. Nf3), long algebraic or internal notation.");
+                    continue;
+                };
+                let Some(Outcome::Win(before)) = tablebases.probe(&board) else {
+                    return false;
+                };
+                match board.play_move(r#move) {
+                    Err(MoveError::AmbiguousMove) => {
+                        println!("the move you have entered is ambiguous.");
+                        continue;
+                    },
+                    Err(MoveError::PromotionRequired) => {
+                        println!("name the piece the pawn becomes, e.g. e7e8q.");
+                        continue;
+                    },
+                    Err(_) => {
+                        println!("the move you have entered is illegal.");
+                        continue;
+                    },
+                    Ok(_) => played += 1,
+                };
+
+                match (board.game_outcome, tablebases.probe(&board)) {
+                    (Some(GameOutcome::Decisive { .. }), _) => {
+                        println!("checkmate in {played} moves, the best was {}. well done!", moves(best));
+                        return false;
+                    },
+                    (Some(GameOutcome::Draw(reason)), _) => {
+                        println!("{board}\nthat's a draw by {reason}, the drill is failed.");
+                        return false;
+                    },
+                    // perfect defense takes the draw as soon as it can
+                    _ if board.can_claim_draw().is_some() => {
+                        println!("{board}\n{} and black claims the draw, the drill is failed.", board.can_claim_draw().map_or("", claimed));
+                        return false;
+                    },
+                    (None, Some(Outcome::Loss(after))) => match after + 1 == before {
+                        true => println!("the best move, mate in {}.", moves(after)),
+                        false => println!("mate in {}, the best move would have kept it at {}.", moves(after), moves(before - 1)),
+                    },
+                    _ => {
+                        println!("{board}\nthe win has slipped away, the drill is failed.");
+                        return false;
+                    },
+                };
+
+                // the position is in the tables and not over, so there is a reply
+                let Some((reply, _)) = tablebases.best_move(&board) else {
+                    return false;
+                };
+                println!("black answers with {}.", board.san(reply));
+                if board.play_move(PlayerMove::Internal(reply)).is_err() {
+                    return false;
+                };
+                println!("{board}");
+                if let Some(claim) = board.can_claim_draw() {
+                    println!("{} and black claims the draw, the drill is failed.", claimed(claim));
+                    return false;
+                };
+            },
+        };
+    };
+}
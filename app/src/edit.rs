@@ -0,0 +1,119 @@
+use std::io::StdinLock;
+use ress::Board;
+use ress::coordinate::Coordinate;
+use ress::annotation::MarkColor;
+use ress::editor::BoardEditor;
+use ress::piece::{Color, Piece};
+use ress::render::RenderOptions;
+use crate::prompt;
+use crate::seat::analyze;
+
+const ANALYSIS_DEPTH: u8 = 5;
+
+fn rights(editor: &BoardEditor) -> String {
+    let letters = [(editor.white_castle.0, 'K'), (editor.white_castle.1, 'Q'), (editor.black_castle.0, 'k'), (editor.black_castle.1, 'q')]
+        .into_iter().filter(|(allowed, _)| *allowed).map(|(_, letter)| letter).collect::<String>();
+    if letters.is_empty() { "-".to_string() } else { letters }
+}
+
+fn show(editor: &BoardEditor) {
+    println!("{}\n{} to move, castling: {}", editor.grid(), editor.move_color, rights(editor));
+}
+
+fn square(raw: &str) -> Option<Coordinate> {
+    (raw.len() == 2 && raw.is_ascii()).then(|| Coordinate::parse(raw)).flatten()
+}
+
+// `Ke1` puts a white king on e1, `ke8` a black one on e8, `-e4` empties e4
+fn parse_edit(raw: &str) -> Option<(Coordinate, Option<Piece>)> {
+    if let Some(coord) = raw.strip_prefix('-').and_then(square) {
+        return Some((coord, None));
+    };
+    Some((raw.get(1..).and_then(square)?, Some(raw.get(..1).and_then(Piece::parse)?)))
+}
+
+// returns the position to play from, none if the editing was given up
+pub fn run(stdin: &mut StdinLock, start: &Board, weights: &str) -> Option<Board> {
+    let mut editor = BoardEditor::from_board(start);
+    // the last analysis' suggestion, until the position changes
+    let mut suggestion = RenderOptions::default();
+    println!("editing the position, enter /help to see how.");
+    show(&editor);
+
+    loop {
+        let command = prompt(stdin);
+        if command.len() <= 1 {
+            println!("to stop editing enter /play or /cancel.");
+            continue;
+        };
+
+        match command.trim() {
+            "/help" => println!(
+                "enter Ke1 to put a white king on e1, ke8 for a black one and -e4 to empty e4.\n\
+                 you can also /turn, /castle KQkq (or -), /clear, /reset, /show, /analyze, /svg <file>, /play or /cancel."
+            ),
+            "/show" => show(&editor),
+            "/clear" => {
+                editor = BoardEditor::default();
+                suggestion = RenderOptions::default();
+                show(&editor);
+            },
+            "/reset" => {
+                editor = BoardEditor::from_board(&Board::default());
+                suggestion = RenderOptions::default();
+                show(&editor);
+            },
+            "/turn" => {
+                editor.move_color = editor.move_color.the_other();
+                suggestion = RenderOptions::default();
+                println!("{} to move.", editor.move_color);
+            },
+            raw if raw.starts_with("/castle") => {
+                let letters = raw["/castle".len()..].trim();
+                if !letters.chars().all(|c| "KQkq-".contains(c)) {
+                    println!("castling rights are given like in a fen, e.g. KQkq or - for none.");
+                    continue;
+                };
+                for (color, king, queen) in [(Color::White, 'K', 'Q'), (Color::Black, 'k', 'q')] {
+                    *editor.castle_mut(color) = (letters.contains(king), letters.contains(queen));
+                };
+                println!("castling: {}", rights(&editor));
+            },
+            "/analyze" => match editor.build() {
+                Ok(board) => if let Some(r#move) = analyze(&board, weights, ANALYSIS_DEPTH) {
+                    suggestion = RenderOptions::default().arrow(r#move.resolve_from(board.move_color), r#move.resolve_to(board.move_color), MarkColor::Green);
+                    println!("{}", editor.grid().render(&suggestion));
+                },
+                Err(err) => println!("the position can't be played: {err}."),
+            },
+            raw if raw.starts_with("/svg ") => {
+                let path = raw["/svg ".len()..].trim();
+                match std::fs::write(path, editor.grid().to_svg(&suggestion)) {
+                    Ok(()) => println!("the position has been saved to {path}."),
+                    Err(err) => println!("couldn't save the position to {path}: {err}"),
+                };
+            },
+            "/play" => match editor.build() {
+                Ok(board) => {
+                    println!("the next game starts from this position.");
+                    return Some(board);
+                },
+                Err(err) => println!("the position can't be played: {err}."),
+            },
+            "/cancel" => return None,
+            raw if raw.starts_with('/') => println!("unknown command. enter /help for help."),
+            // several at once are either all applied or none is
+            raw => match raw.split_whitespace().map(parse_edit).collect::<Option<Vec<_>>>() {
+                Some(edits) => {
+                    for (coord, piece) in edits {
+                        editor.put(coord, piece);
+                    };
+                    suggestion = RenderOptions::default();
+                    show(&editor);
+                },
+                None => println!("that's not a square to edit, e.g. Ke1, ke8 or -e4."),
+            },
+        };
+    };
+}
+
@@ -0,0 +1,69 @@
+use std::io::StdinLock;
+use ress::GameOutcome;
+use ress::game::Game;
+use ress::piece::Color;
+use engine::search::Score;
+use crate::prompt;
+use crate::seat::hint;
+
+const ANALYSIS_DEPTH: u8 = 5;
+
+// tries out moves from the game's position without playing them. the game is the same afterwards,
+// but for the line being kept as a variation if the player asks for it.
+pub fn run(stdin: &mut StdinLock, game: &mut Game, weights: &str, perspective: Color) {
+    let mut exploration = game.explore();
+    println!("exploring from here, the game continues where it is when you go /back. enter /help to see how.");
+
+    loop {
+        let command = prompt(stdin);
+        if command.trim().is_empty() {
+            println!("to return to the game enter /back.");
+            continue;
+        };
+
+        match command.trim() {
+            "/help" => println!("enter moves for either side, you can also /undo, /line, /show, /eval, /keep or /back."),
+            "/show" => println!("{}", exploration.board().view(perspective)),
+            "/line" => match exploration.moves().is_empty() {
+                true => println!("no moves have been tried yet."),
+                false => println!("{}", exploration.line()),
+            },
+            "/undo" => match exploration.undo() {
+                true => println!("{}", exploration.board().view(perspective)),
+                false => println!("this is where the game is, there is nothing to take back."),
+            },
+            "/eval" if !game.policy.hints => println!("the engine can't be asked in this game."),
+            "/eval" => match hint(exploration.board(), weights, ANALYSIS_DEPTH) {
+                Some((r#move, score, _)) => println!("the engine would play {} ({}).", exploration.board().san(r#move), Score::of(score)),
+                None => println!("there is nothing to play here."),
+            },
+            "/keep" => {
+                if !exploration.moves().is_empty() {
+                    println!("the line {} has been kept.", exploration.line());
+                };
+                game.keep(exploration);
+                break;
+            },
+            "/back" => break,
+            raw if raw.starts_with('/') => println!("unknown command. enter /help for help."),
+            raw_move => {
+                let Some(r#move) = exploration.board().parse_move(raw_move) else {
+                    println!("move is invalid, you can enter san (e.g. Nf3), long algebraic or internal notation.");
+                    continue;
+                };
+                match exploration.play_move(r#move) {
+                    Ok(outcome) => {
+                        println!("{}", exploration.board().view(perspective));
+                        match outcome {
+                            Some(GameOutcome::Decisive { won, reason }) => println!("{won} would win by {reason} here."),
+                            Some(GameOutcome::Draw(reason)) => println!("it would be a draw by {reason} here."),
+                            None => {},
+                        };
+                    },
+                    Err(err) => println!("{err}."),
+                };
+            },
+        };
+    };
+    println!("back to the game.\n{}", game.board().view(perspective));
+}
@@ -0,0 +1,161 @@
+use serde::Deserialize;
+use ress::{Board, PlayerMove};
+use ress::coordinate::Move;
+
+// a lesson file is json:
+// { "title": "...", "steps": [{ "fen": "...", "prompt": "...", "expected": ["e2e4"], "hint": "...",
+//   "reply": "e7e5", "explanation": "...", "branches": [{ "on": "d2d4", "message": "...", "goto": 0 }] }] }
+// steps without a fen continue from where the previous one (its first expected move and the reply) left off.
+#[derive(Debug, Deserialize)]
+pub struct Lesson {
+    pub title: String,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Step {
+    #[serde(default)]
+    pub fen: Option<String>,
+    pub prompt: String,
+    pub expected: Vec<String>,
+    #[serde(default)]
+    pub hint: Option<String>,
+    // the opponent's answer to a correct move
+    #[serde(default)]
+    pub reply: Option<String>,
+    #[serde(default)]
+    pub explanation: Option<String>,
+    #[serde(default)]
+    pub branches: Vec<Branch>,
+}
+
+// a wrong answer worth a specific response, optionally sending the student to another step
+#[derive(Debug, Deserialize)]
+pub struct Branch {
+    pub on: String,
+    pub message: String,
+    #[serde(default)]
+    pub goto: Option<usize>,
+}
+
+pub enum Feedback<'a> {
+    Correct { explanation: Option<&'a str>, reply: Option<Move> },
+    Branch { message: &'a str, moved_to: Option<usize> },
+    Wrong,
+    Illegal,
+}
+
+fn resolve(board: &Board, raw: &str) -> Option<Move> {
+    board.resolve_move(&board.parse_move(raw)?)
+}
+
+impl Lesson {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path).map_err(|err| format!("couldn't read {path}: {err}"))?;
+        serde_json::from_str(&raw).map_err(|err| format!("{path} isn't a valid lesson: {err}"))
+    }
+
+    // the position each step starts from. replaying the whole lesson up front
+    // catches broken fens and moves before anyone gets to them.
+    fn starts(&self) -> Result<Vec<Board>, String> {
+        let mut starts: Vec<Board> = Vec::with_capacity(self.steps.len());
+        let mut next: Option<Board> = None;
+        for (i, step) in self.steps.iter().enumerate() {
+            let board = match (&step.fen, next.take()) {
+                (Some(fen), _) => Board::from_fen(fen).ok_or(format!("step {i} has an invalid fen"))?,
+                (None, Some(board)) => board,
+                (None, None) => Board::default(),
+            };
+
+            if step.expected.is_empty() {
+                return Err(format!("step {i} doesn't expect any move"));
+            };
+            for raw in step.expected.iter().chain(step.branches.iter().map(|branch| &branch.on)) {
+                resolve(&board, raw).ok_or(format!("{raw} isn't legal in step {i}"))?;
+            };
+            if let Some(goto) = step.branches.iter().find_map(|branch| branch.goto.filter(|goto| *goto >= self.steps.len())) {
+                return Err(format!("step {i} branches to a step {goto} which doesn't exist"));
+            };
+
+            let mut after = board.clone();
+            for raw in [Some(&step.expected[0]), step.reply.as_ref()].into_iter().flatten() {
+                let r#move = resolve(&after, raw).ok_or(format!("{raw} isn't legal in step {i}"))?;
+                after.play_move(PlayerMove::Internal(r#move)).map_err(|_| format!("{raw} isn't legal in step {i}"))?;
+            };
+
+            starts.push(board);
+            next = Some(after);
+        };
+        Ok(starts)
+    }
+}
+
+pub struct LessonRunner {
+    lesson: Lesson,
+    starts: Vec<Board>,
+    step: usize,
+    pub board: Board,
+}
+
+impl LessonRunner {
+    pub fn new(lesson: Lesson) -> Result<Self, String> {
+        let starts = lesson.starts()?;
+        let board = starts.first().cloned().unwrap_or_default();
+        Ok(Self { lesson, starts, step: 0, board })
+    }
+
+    pub fn title(&self) -> &str {
+        &self.lesson.title
+    }
+
+    // none once the lesson is over
+    pub fn step(&self) -> Option<&Step> {
+        self.lesson.steps.get(self.step)
+    }
+
+    pub fn step_index(&self) -> usize {
+        self.step
+    }
+
+    pub fn goto(&mut self, step: usize) {
+        self.step = step;
+        if let Some(start) = self.starts.get(step) {
+            self.board = start.clone();
+        };
+    }
+
+    pub fn attempt(&mut self, r#move: &PlayerMove) -> Feedback<'_> {
+        let Some(r#move) = self.board.resolve_move(r#move) else {
+            return Feedback::Illegal;
+        };
+        let step = &self.lesson.steps[self.step];
+
+        if step.expected.iter().any(|raw| resolve(&self.board, raw) == Some(r#move)) {
+            let reply = step.reply.as_ref().and_then(|raw| {
+                let mut after = self.board.clone();
+                after.play_move(PlayerMove::Internal(r#move)).ok()?;
+                resolve(&after, raw)
+            });
+
+            self.step += 1;
+            // the next step may start elsewhere, or just continue this line
+            self.board = self.starts.get(self.step).cloned().unwrap_or_else(|| {
+                let mut board = self.board.clone();
+                let _ = board.play_move(PlayerMove::Internal(r#move));
+                board
+            });
+
+            return Feedback::Correct { explanation: step.explanation.as_deref(), reply };
+        };
+
+        if let Some(branch) = step.branches.iter().find(|branch| resolve(&self.board, &branch.on) == Some(r#move)) {
+            if let Some(goto) = branch.goto {
+                self.step = goto;
+                self.board = self.starts[goto].clone();
+            };
+            return Feedback::Branch { message: &branch.message, moved_to: branch.goto };
+        };
+
+        Feedback::Wrong
+    }
+}
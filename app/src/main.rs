@@ -1,7 +1,41 @@
 use std::io::{BufRead, StdinLock, Write};
-use engine::Engine;
-use ress::{Board, GameOutcome, MoveError, PlayerMove};
+use std::time::Duration;
+use ress::{Board, DrawClaim, GameOutcome, MoveError, PlayerMove};
+use ress::clock::{format_clock, Clock, ClockError, ClockStyle, TimeControl};
+use ress::editor::BoardEditor;
+use ress::fen::{self, Dialect};
+use ress::game::{Game, GamePolicy};
+use ress::render::{self, TextStyle};
 use ress::piece::Color;
+use ress::store::GameRecord;
+use engine::options::DEFAULT_WEIGHTS;
+use engine::search::Score;
+use engine::tablebase::Tablebases;
+use lesson::{Feedback, Lesson, LessonRunner};
+use seat::{hint, EngineSeat, EngineSettings, MAX_SKILL};
+use profile::Profile;
+use saved::SavedGame;
+use status::Status;
+
+mod drill;
+mod edit;
+mod explore;
+mod lesson;
+mod profile;
+mod saved;
+mod seat;
+mod status;
+#[cfg(feature = "tui")]
+mod tui;
+
+// between the moves of a game the engine plays against itself, so it can be followed
+const DEFAULT_DELAY: Duration = Duration::from_millis(500);
+
+// with less than this left, the start of a move is enough when it names only one
+const SCRAMBLE_TIME: Duration = Duration::from_secs(20);
+
+// deep enough to be worth following, shallow enough to not keep the player waiting
+const HINT_DEPTH: u8 = 5;
 
 fn prompt(stdin: &mut StdinLock) -> String {
     print!(">>> ");
@@ -11,12 +45,215 @@ fn prompt(stdin: &mut StdinLock) -> String {
     buf
 }
 
+// asks for one setting, an empty answer keeps the default
+fn ask<T: std::str::FromStr>(stdin: &mut StdinLock, question: &str, default: T, valid: impl Fn(&T) -> bool) -> T {
+    loop {
+        print!("{question}: ");
+        std::io::stdout().flush().unwrap();
+        let mut buf = String::new();
+        stdin.read_line(&mut buf).unwrap();
+
+        let answer = buf.trim();
+        if answer.is_empty() {
+            return default;
+        };
+        match answer.parse() {
+            Ok(value) if valid(&value) => return value,
+            _ => println!("that's not a valid answer, enter nothing to keep the default."),
+        };
+    };
+}
+
+fn setup_engine(stdin: &mut StdinLock, color: Color, weights: &str) -> Option<EngineSeat> {
+    let defaults = EngineSettings { weights: weights.to_string(), ..Default::default() };
+    println!("setting up the engine playing {color}, enter nothing to keep the value in brackets.");
+    let settings = EngineSettings {
+        weights: ask(stdin, &format!("weight file [{}]", defaults.weights), defaults.weights, |_| true),
+        skill: ask(stdin, &format!("skill level, 1 to {MAX_SKILL} [{}]", defaults.skill), defaults.skill, |skill| (1..=MAX_SKILL).contains(skill)),
+        temperature: ask(stdin, &format!("temperature in centipawns [{}]", defaults.temperature), defaults.temperature, |_| true),
+        depth: ask(stdin, &format!("depth [{}]", defaults.depth), defaults.depth, |depth| *depth > 0),
+        movetime: match ask(stdin, "milliseconds per move, 0 for no limit [0]", 0, |_| true) {
+            0 => None,
+            millis => Some(Duration::from_millis(millis)),
+        },
+        book: Some(ask(stdin, "opening book file, nothing for none", String::new(), |_| true)).filter(|path| !path.is_empty()),
+        book_learning: defaults.book_learning,
+    };
+    let settings = match settings.book {
+        Some(_) => EngineSettings { book_learning: ask(stdin, "learn from the results, y or n [y]", String::from("y"), |answer| answer == "y" || answer == "n") == "y", ..settings },
+        None => settings,
+    };
+
+    match EngineSeat::new(settings) {
+        Ok(seat) => Some(seat),
+        Err(err) => {
+            println!("{err}");
+            None
+        },
+    }
+}
+
+// a position to start games from, it has to be one a game could be played from
+fn parse_start(raw: &str) -> ress::Result<Board> {
+    let (board, _) = fen::parse(raw.trim(), Dialect::Standard)?;
+    BoardEditor::from_board(&board).validate()?;
+    Ok(board)
+}
+
+// returns whether the user wants to exit altogether
+fn run_lesson(stdin: &mut StdinLock, path: &str) -> bool {
+    let mut runner = match Lesson::load(path).and_then(LessonRunner::new) {
+        Ok(runner) => runner,
+        Err(err) => {
+            println!("{err}");
+            return false;
+        },
+    };
+
+    println!("lesson: {}", runner.title());
+    let mut last_illegal = None;
+    while let Some(step) = runner.step() {
+        println!("{}\nstep #{}: {}", runner.board, runner.step_index()+1, step.prompt);
+
+        loop {
+            let command = prompt(stdin);
+            if command.len() <= 1 {
+                println!("to leave the lesson enter /abort.");
+                continue;
+            };
+
+            match &command.as_str()[..command.len()-1] {
+                "/help" => println!("you can /help, /hint, /why, /skip, /abort, /exit or enter a move."),
+                "/hint" => match &runner.step().unwrap().hint {
+                    Some(hint) => println!("hint: {hint}"),
+                    None => println!("no hint for this one, sorry."),
+                },
+                "/why" => match last_illegal.clone().and_then(|r#move| runner.board.explain_illegal(r#move)) {
+                    Some(reason) => println!("{reason}."),
+                    None => println!("there is no rejected move to explain."),
+                },
+                "/skip" => {
+                    runner.goto(runner.step_index()+1);
+                    break;
+                },
+                "/abort" => return false,
+                "/exit" => return true,
+                _ if &command[0..1] == "/" => println!("unknown command. enter /help for help."),
+                raw_move => {
+                    let Some(r#move) = runner.board.parse_move(raw_move) else {
+                        println!("move is invalid, you can enter san (e.g. Nf3), long algebraic or internal notation.");
+                        continue;
+                    };
+
+                    match runner.attempt(&r#move) {
+                        Feedback::Correct { explanation, reply } => {
+                            println!("correct!{}", explanation.map(|text| format!(" {text}")).unwrap_or_default());
+                            if let Some(reply) = reply {
+                                println!("the opponent answers with {reply}.");
+                            };
+                            break;
+                        },
+                        Feedback::Branch { message, moved_to } => {
+                            println!("{message}");
+                            if moved_to.is_some() {
+                                break;
+                            };
+                        },
+                        Feedback::Wrong => println!("that's legal, but not what we're looking for. try again or enter /hint."),
+                        Feedback::Illegal => {
+                            println!("the move you have entered is illegal. enter /why to find out why.");
+                            last_illegal = Some(r#move);
+                        },
+                    };
+                },
+            };
+        };
+    };
+
+    println!("the lesson is over, well done!");
+    false
+}
+
+#[cfg(feature = "tui")]
+fn run_tui(start: Board, weights: Option<String>, color: Color) {
+    let opponent = match weights {
+        Some(weights) => match EngineSeat::new(EngineSettings { weights, ..Default::default() }) {
+            Ok(seat) => Some((color.the_other(), seat)),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            },
+        },
+        None => None,
+    };
+    let weights = opponent.as_ref().map_or(DEFAULT_WEIGHTS.to_string(), |(_, seat)| seat.settings.weights.clone());
+    if let Err(err) = tui::run(start, opponent, &weights) {
+        eprintln!("the terminal failed: {err}");
+        std::process::exit(1);
+    };
+}
+
+#[cfg(not(feature = "tui"))]
+fn run_tui(_: Board, _: Option<String>, _: Color) {
+    eprintln!("built without the tui feature, rebuild with --features tui.");
+    std::process::exit(1);
+}
+
 fn main() {
+    engine::logging::init("warn");
     let mut stdin = std::io::stdin().lock();
 
-    let mut engine = None;
-    let mut engine_white = false;
-    let mut engine_black = false;
+    // the weight file engines start out with, each color is set up on its own
+    let mut weights = DEFAULT_WEIGHTS.to_string();
+    // whether a pawn moved to the last rank without a piece becomes a queen, instead of the move being refused
+    let mut auto_queen = false;
+    let mut engine_white: Option<EngineSeat> = None;
+    let mut engine_black: Option<EngineSeat> = None;
+    let mut delay = DEFAULT_DELAY;
+    // none to play without a clock, only the time spent is shown then
+    let mut time_control: Option<TimeControl> = None;
+    // games against the engine are rated for whoever is playing
+    let mut profile: Option<Profile> = None;
+    // set up with /edit, /fen or --fen, none for the standard starting position
+    let mut start: Option<Board> = None;
+    // NO_COLOR and dumb terminals are respected unless the flags say otherwise
+    let mut style = TextStyle::from_env();
+    // the full screen board instead of the prompt, against an engine with these weights if any
+    let mut full_screen = false;
+    let mut tui_engine: Option<String> = None;
+    // the player's color against the engine
+    let mut tui_color = Color::White;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ascii" => style.unicode = false,
+            "--no-color" => style.colors = false,
+            "--color" => style.colors = true,
+            "--no-coordinates" => style.coordinates = false,
+            "--tui" => full_screen = true,
+            "--engine" => tui_engine = args.next(),
+            "--black" => tui_color = Color::Black,
+            // the fen's fields may come as separate arguments when it isn't quoted, so it goes last
+            "--fen" => match parse_start(&args.by_ref().collect::<Vec<_>>().join(" ")) {
+                Ok(board) => start = Some(board),
+                Err(err) => {
+                    eprintln!("{err}.");
+                    std::process::exit(1);
+                },
+            },
+            _ => {
+                eprintln!("usage: app [--ascii] [--no-color] [--color] [--no-coordinates] [--tui [--engine <weights>] [--black]] [--fen <fen>]");
+                std::process::exit(1);
+            },
+        };
+    };
+    render::set_style(style);
+    if full_screen {
+        run_tui(start.unwrap_or_default(), tui_engine, tui_color);
+        return;
+    };
+    // generated the first time a drill asks for them
+    let mut tablebases: Option<Tablebases> = None;
     println!("to start a new game enter /start or enter /help for more commands.");
     'menu: loop {
         println!("menu:");
@@ -31,46 +268,207 @@ fn main() {
         };
         
         match &command.as_str()[..command.len()-1] {
-            "/enginew" => {
-                if engine.is_none() {
-                    engine = Some(Engine::load("engine.rew").unwrap());
+            raw if raw.starts_with("/engine ") => {
+                weights = raw["/engine ".len()..].trim().to_string();
+                println!("engines set up from now on will start with {weights}.");
+            },
+            raw @ ("/enginew" | "/engineb") => {
+                let (color, seat) = match raw {
+                    "/enginew" => (Color::White, &mut engine_white),
+                    _ => (Color::Black, &mut engine_black),
+                };
+
+                if seat.take().is_some() {
+                    println!("the engine no longer plays {color}.");
+                } else {
+                    *seat = setup_engine(&mut stdin, color, &weights);
+                    if let Some(seat) = seat {
+                        println!("the engine now plays {color} with {}.", seat.settings.weights);
+                    };
                 };
-                
-                engine_white ^= true;
-                println!("switching engine playing white (now {engine_white}).");
             },
-            "/engineb" => {
-                if engine.is_none() {
-                    engine = Some(Engine::load("engine.rew").unwrap());
+            "/engineboth" => {
+                if engine_white.is_some() && engine_black.is_some() {
+                    engine_white = None;
+                    engine_black = None;
+                    println!("the engine no longer plays either color.");
+                    continue;
+                };
+                for (color, seat) in [(Color::White, &mut engine_white), (Color::Black, &mut engine_black)] {
+                    if seat.is_none() {
+                        *seat = setup_engine(&mut stdin, color, &weights);
+                    };
+                };
+                if engine_white.is_some() && engine_black.is_some() {
+                    println!("the engine now plays both colors, /start to watch it. enter /delay <milliseconds> to change how long each move stays on screen.");
+                };
+            },
+            raw if raw.starts_with("/clock ") => match raw["/clock ".len()..].trim() {
+                "off" => {
+                    time_control = None;
+                    println!("games are played without a clock from now on.");
+                },
+                raw => match TimeControl::parse(raw) {
+                    Ok(control) => {
+                        time_control = Some(control);
+                        println!("games are played with a {control} clock from now on.");
+                    },
+                    Err(ClockError::Format) => println!("{}, e.g. 5, 3+2, 90s+0 or 15d5.", ClockError::Format),
+                    Err(err) => println!("{err}."),
+                },
+            },
+            raw if raw.starts_with("/delay ") => match raw["/delay ".len()..].trim().parse() {
+                Ok(millis) => {
+                    delay = Duration::from_millis(millis);
+                    println!("a game between engines now waits {millis}ms after every move.");
+                },
+                Err(_) => println!("the delay is in milliseconds, e.g. /delay 500."),
+            },
+            raw if raw.starts_with("/profile ") => match Profile::load_or_create(raw["/profile ".len()..].trim()) {
+                Ok(loaded) => {
+                    println!("playing as {}, rated {:.0}.", loaded.name, loaded.rating.rating);
+                    profile = Some(loaded);
+                },
+                Err(err) => println!("{err}"),
+            },
+            raw if raw.starts_with("/fen ") => match parse_start(&raw["/fen ".len()..]) {
+                Ok(board) => {
+                    println!("the next game starts from this position.\n{board}");
+                    start = Some(board);
+                },
+                Err(err) => println!("{err}."),
+            },
+            "/edit" => {
+                if let Some(board) = edit::run(&mut stdin, start.as_ref().unwrap_or(&Board::default()), &weights) {
+                    start = Some(board);
+                };
+            },
+            raw if raw.starts_with("/drill") => {
+                let Some(ending) = drill::parse_ending(raw["/drill".len()..].trim()) else {
+                    println!("the drills are /drill kqk, /drill krk and /drill kpk.");
+                    continue;
+                };
+                if tablebases.is_none() {
+                    println!("generating the tablebases...");
+                };
+                if drill::run(&mut stdin, tablebases.get_or_insert_with(Tablebases::generate), ending) {
+                    break;
+                };
+            },
+            "/stats" => match &profile {
+                Some(profile) => println!("{}", profile.stats()),
+                None => println!("there is no profile selected, enter /profile <name> first."),
+            },
+            "/autoqueen" => {
+                auto_queen = !auto_queen;
+                println!("promoting to a queen without naming it is now {}.", if auto_queen { "on" } else { "off" });
+            },
+            "/help" => { println!("you can /start, /lesson <file>, /engine <weight file>, /engineboth, /delay <ms>, /clock <control|off>, /load <file>, /profile <name>, /stats, /edit, /fen <fen>, /drill <kqk|krk|kpk>, /autoqueen, /exit, /enginew or /engineb.") },
+            raw if raw.starts_with("/lesson ") => {
+                if run_lesson(&mut stdin, raw["/lesson ".len()..].trim()) {
+                    break;
+                };
+            },
+            raw if raw == "/start" || raw.starts_with("/load ") => {
+                let saved = match raw.strip_prefix("/load ") {
+                    Some(path) => match SavedGame::load(path.trim()) {
+                        Ok(saved) => Some(saved),
+                        Err(err) => {
+                            println!("{err}");
+                            continue;
+                        },
+                    },
+                    None => None,
+                };
+                println!("{}", if saved.is_some() { "resuming game..." } else { "starting game..." });
+                // only a game of one person against the engine from the usual start is rated, and only when played in one go
+                let rated = match (&engine_white, &engine_black) {
+                    _ if start.is_some() || saved.is_some() => None,
+                    (Some(seat), None) => Some((Color::Black, seat.settings.skill)),
+                    (None, Some(seat)) => Some((Color::White, seat.settings.skill)),
+                    _ => None,
+                };
+                if rated.is_some() && profile.is_none() {
+                    let name = ask(&mut stdin, "profile to play as, nothing to play unrated", String::new(), |name| Profile::valid_name(name));
+                    if !name.is_empty() {
+                        match Profile::load_or_create(&name) {
+                            Ok(loaded) => profile = Some(loaded),
+                            Err(err) => println!("{err}"),
+                        };
+                    };
+                };
+                // rated games don't let the player take moves back or agree to an early draw
+                let policy = if rated.is_some() { GamePolicy::rated() } else { GamePolicy::casual() };
+                let policy = GamePolicy { auto_queen, ..policy };
+                let (mut game, mut status) = match saved {
+                    Some(SavedGame { mut game, used, .. }) => {
+                        game.policy.auto_queen = auto_queen;
+                        let status = Status::restore(&game, used);
+                        (game, status)
+                    },
+                    None => {
+                        let mut game = Game::new(start.clone().unwrap_or_default(), policy);
+                        game.set_clock(time_control.map(Clock::new));
+                        let status = Status::new(game.board());
+                        (game, status)
+                    },
                 };
-                
-                engine_black ^= true;
-                println!("switching engine playing black (now {engine_black})");
-            },
-            "/help" => { println!("you can /start, /exit, /enginew or /engineb.") },
-            "/start" => {
-                println!("starting game...");
-                // let mut board = Board::from_fen("rnb2bnr/ppp1pppp/5k2/3K4/6Q1/2N5/PPPPPPPP/R1B2BNR b HAha - 0 1").unwrap();
-                let mut board = Board::default();
                 let mut board_changed = true;
+                // both sides played by the engine, the board is redrawn in place instead of scrolling by
+                let spectating = engine_white.is_some() && engine_black.is_some();
+                // kept around for /why
+                let mut last_illegal = None;
+                // the start of a san is enough when it names only one move, to keep up when short on time
+                let mut scramble = false;
                 'game: loop {
-                    for color in [board.move_color, board.move_color.the_other()] {
+                    for color in [game.board().move_color, game.board().move_color.the_other()] {
                         if board_changed {
-                            println!("{board}");
+                            if spectating {
+                                print!("\x1b[2J\x1b[H");
+                            };
+                            // whoever plays at the keyboard sees their side at the bottom, both take turns at it without an engine
+                            let perspective = match (&engine_white, &engine_black) {
+                                (Some(_), None) => Color::Black,
+                                (None, None) => game.board().move_color,
+                                _ => Color::White,
+                            };
+                            println!("{}\n{}", game.board().view(perspective), status.line(&game));
+                            match game.board().can_claim_draw() {
+                                Some(DrawClaim::ThreefoldRepetition) if !spectating => println!("the position has repeated three times, either side can /draw to claim a draw."),
+                                Some(DrawClaim::FiftyMoves) if !spectating => println!("there's been no capture or pawn move for 50 moves, either side can /draw to claim a draw."),
+                                _ => {},
+                            };
                             board_changed = false;
                         };
 
-                        println!("\n{color}:");
+                        if !spectating {
+                            match status.clock(&game) {
+                                Some(clock) => println!("\n{color} ({} left):", format_clock(clock.remaining(color), ClockStyle::Standard)),
+                                None => println!("\n{color}:"),
+                            };
+                        };
 
-                        if (engine_white && color == Color::White) || (engine_black && color == Color::Black) {
-                            if board.draw_pending.is_some() {
+                        let seat = match color {
+                            Color::White => engine_white.as_mut(),
+                            Color::Black => engine_black.as_mut(),
+                        };
+                        if let Some(seat) = seat {
+                            if game.draw_offer().is_some() {
                                 println!("e>> /decline");
-                                board.decline_draw();
-                            } else {
-                                let r#move = engine.as_ref().unwrap().choose_move(&board, color);
-                                println!("e>> {} (c{:.0}%)", r#move.0, r#move.1*100.0);
-                                board.play_move(r#move.0).unwrap();
-                                board_changed = true;
+                                game.decline_draw().unwrap();
+                            } else if let Some((r#move, score)) = status.refresh_while(&game, || seat.choose_move(game.board(), status.clock(&game))) {
+                                let (before, played) = (game.board().clone(), game.moves().len());
+                                let thinking = status.thinking();
+                                game.play_timed(PlayerMove::Internal(r#move), thinking).unwrap();
+                                // the flag could have fallen while it was thinking, the move isn't played then
+                                if game.moves().len() > played {
+                                    println!("e>> {} ({})", before.san(r#move), Score::of(score));
+                                    status.record(&before, r#move, thinking);
+                                    board_changed = true;
+                                    if spectating {
+                                        std::thread::sleep(delay);
+                                    };
+                                };
                             };
                         } else {
                             loop {
@@ -85,15 +483,99 @@ fn main() {
                                 };
 
                                 match &command.as_str()[..command.len()-1] {
-                                    "/draw" => { board.propose_draw(color); println!("{color} has proposed a draw."); break; },
-                                    "/decline" => { board.decline_draw(); println!("the draw has been declined."); break; },
-                                    "/resign" => { board.resign(color); break; },
-                                    "/help" => { println!("you can /help, /abort, /exit, /draw, /decline, /resign, /moves or enter a move."); },
+                                    "/draw" => match game.propose_draw(color) {
+                                        Ok(()) => { println!("{color} has proposed a draw."); break; },
+                                        Err(err) => println!("{err}."),
+                                    },
+                                    "/decline" => match game.decline_draw() {
+                                        Ok(()) => { println!("the draw has been declined."); break; },
+                                        Err(err) => println!("{err}."),
+                                    },
+                                    "/resign" => match game.resign(color) {
+                                        Ok(()) => break,
+                                        Err(err) => println!("{err}."),
+                                    },
+                                    "/help" => { println!("you can /help, /abort, /exit, /draw, /decline, /resign, /undo, /moves, /history, /explore, /scramble, /hint, /why, /autoqueen, /save <file> or enter a move."); },
+                                    "/undo" => {
+                                        // against the engine its reply is taken back too, so it's this side's turn again
+                                        let engine_opponent = match color {
+                                            Color::White => engine_black.is_some(),
+                                            Color::Black => engine_white.is_some(),
+                                        };
+                                        let plies = if engine_opponent { 2 } else { 1 };
+                                        if game.moves().len() < plies {
+                                            println!("there is no move of yours to take back.");
+                                            continue;
+                                        };
+                                        for _ in 0..plies {
+                                            if let Err(err) = game.takeback() {
+                                                println!("{err}.");
+                                                break;
+                                            };
+                                            status.undo();
+                                            board_changed = true;
+                                        };
+                                        last_illegal = None;
+                                        if board_changed {
+                                            continue 'game;
+                                        };
+                                    },
+                                    "/autoqueen" => {
+                                        auto_queen = !auto_queen;
+                                        game.policy.auto_queen = auto_queen;
+                                        println!("promoting to a queen without naming it is now {}.", if auto_queen { "on" } else { "off" });
+                                    },
+                                    "/why" => match last_illegal.clone().and_then(|r#move| game.board().explain_illegal(r#move)) {
+                                        Some(reason) => println!("{reason}."),
+                                        None => println!("there is no rejected move to explain."),
+                                    },
+                                    "/hint" if !game.policy.hints => println!("hints aren't allowed in this game."),
+                                    "/hint" => {
+                                        // the engine playing the other side knows best what it's up against
+                                        let weights = engine_white.as_ref().or(engine_black.as_ref()).map_or(&weights, |seat| &seat.settings.weights);
+                                        if let Some((r#move, score, settled)) = hint(game.board(), weights, HINT_DEPTH) {
+                                            let sureness = if settled == 1 { String::from("it hasn't changed its mind") } else { format!("it's been the best since depth {settled} of {HINT_DEPTH}") };
+                                            println!("the engine would play {} ({}), {sureness}.", game.board().san(r#move), Score::of(score));
+                                        };
+                                    },
+                                    raw if raw.starts_with("/save ") => {
+                                        let path = raw["/save ".len()..].trim();
+                                        let mut saved = game.clone();
+                                        saved.set_clock(status.clock(&game));
+                                        match (SavedGame { game: saved, used: status.used(game.board()), clock: None }).save(path) {
+                                            Ok(()) => println!("the game has been saved to {path}, enter /load {path} in the menu to resume it."),
+                                            Err(err) => println!("{err}"),
+                                        };
+                                    },
                                     "/exit" => { break 'menu; },
                                     "/abort" => { break 'game; },
+                                    "/scramble" => {
+                                        scramble = !scramble;
+                                        match scramble {
+                                            true => println!("the start of a move is now enough when no other move starts like it, /moves shows how little."),
+                                            false => println!("moves have to be entered in full again."),
+                                        };
+                                    },
+                                    "/explore" => {
+                                        let weights = engine_white.as_ref().or(engine_black.as_ref()).map_or(&weights, |seat| &seat.settings.weights).clone();
+                                        let perspective = match (&engine_white, &engine_black) {
+                                            (Some(_), None) => Color::Black,
+                                            (None, None) => color,
+                                            _ => Color::White,
+                                        };
+                                        explore::run(&mut stdin, &mut game, &weights, perspective);
+                                    },
+                                    "/history" => match game.moves().is_empty() {
+                                        true => println!("no moves have been played yet."),
+                                        false => println!("{}", game.history()),
+                                    },
                                     "/moves" => {
                                         println!("possible moves are:");
-                                        for (i, r#move) in board.possible_moves(board.move_color).iter().enumerate() {
+                                        let moves = match scramble || status.short_on_time(&game, SCRAMBLE_TIME) {
+                                            true => game.board().shortest_prefixes().into_iter().map(|(_, prefix)| prefix).collect::<Vec<_>>(),
+                                            false => game.board().possible_moves(game.board().move_color).into_iter().map(|r#move| game.board().san(r#move)).collect(),
+                                        };
+                                        for (i, r#move) in moves.into_iter().enumerate() {
                                             print!("{move} ");
                                             if (i+1) % 6 == 0 {
                                                 println!();
@@ -103,26 +585,48 @@ fn main() {
                                     },
                                     _ if &command[0..1] == "/" => { println!("unknown command. enter /help for help.") }
                                     raw_move => {
-                                        if board.draw_pending.is_some() {
+                                        if game.draw_offer().is_some() {
                                             println!("there is a draw pending. accept or decline it.");
                                             continue;
                                         };
 
-                                        let r#move = PlayerMove::parse(raw_move);
+                                        let scramble = scramble || status.short_on_time(&game, SCRAMBLE_TIME);
+                                        let r#move = match scramble {
+                                            true => game.board().complete_san(raw_move).map(PlayerMove::Internal).or_else(|| game.board().parse_move(raw_move)),
+                                            false => game.board().parse_move(raw_move),
+                                        };
+                                        if r#move.is_none() && scramble {
+                                            let completions = game.board().san_completions(raw_move);
+                                            if completions.len() > 1 {
+                                                println!("that could be {}.", completions.into_iter().map(|(_, san)| san).collect::<Vec<_>>().join(", "));
+                                                continue;
+                                            };
+                                        };
 
                                         match r#move {
-                                            None => println!("move is invalid, you can enter either long algebraic or internal notation."),
+                                            None => println!("move is invalid, you can enter san (e.g. Nf3), long algebraic or internal notation."),
                                             Some(r#move) => {
-                                                if let Err(move_err) = board.play_move(r#move) {
+                                                let (before, played) = (game.board().clone(), game.moves().len());
+                                                let thinking = status.thinking();
+                                                if let Err(move_err) = game.play_timed(r#move.clone(), thinking) {
                                                     match move_err {
-                                                        MoveError::IllegalMove => { println!("the move you have entered is illegal."); },
+                                                        MoveError::IllegalMove => {
+                                                            println!("the move you have entered is illegal. enter /why to find out why.");
+                                                            last_illegal = Some(r#move);
+                                                        },
                                                         MoveError::AmbiguousMove => { println!("the move you have entered is ambiguous."); },
+                                                        MoveError::PromotionRequired => { println!("name the piece the pawn becomes, e.g. e7e8q, or enter /autoqueen to always get a queen."); },
                                                         _ => unreachable!(),
                                                     };
 
                                                     continue;
                                                 };
 
+                                                // nothing was played if the flag fell first
+                                                if let Some(resolved) = game.moves().get(played) {
+                                                    status.record(&before, *resolved, thinking);
+                                                };
+                                                last_illegal = None;
                                                 board_changed = true;
                                                 break;
                                             },
@@ -132,10 +636,10 @@ fn main() {
                             };
                         };
 
-                        if let Some(outcome) = board.game_outcome {
+                        if let Some(outcome) = game.outcome() {
                             if board_changed {
-                                let plies_count = board.grid_history.len();
-                                println!("\nmove #{} (ply #{plies_count}), {color}'s turn:\n{board}", plies_count.div_ceil(2));
+                                let plies_count = game.board().grid_history.len();
+                                println!("\nmove #{} (ply #{plies_count}), {color}'s turn:\n{}", plies_count.div_ceil(2), game.board());
                             };
 
                             println!();
@@ -148,6 +652,24 @@ fn main() {
                                 },
                             };
 
+                            let winner = match outcome {
+                                GameOutcome::Decisive { won, .. } => Some(won),
+                                GameOutcome::Draw(_) => None,
+                            };
+                            for (seat, color) in [(&mut engine_white, Color::White), (&mut engine_black, Color::Black)] {
+                                if let Err(err) = seat.as_mut().map_or(Ok(()), |seat| seat.learn(game.start(), game.moves(), color, winner)) {
+                                    println!("{err}");
+                                };
+                            };
+
+                            if let (Some(profile), Some((color, skill))) = (&mut profile, rated) {
+                                let record = GameRecord { fen: None, moves: game.moves().to_vec() };
+                                match profile.record(&record, outcome, color, skill) {
+                                    Ok(change) => println!("{} is now rated {:.0} ({change:+.0}).", profile.name, profile.rating.rating),
+                                    Err(err) => println!("{err}"),
+                                };
+                            };
+
                             break 'game;
                         };
                     };
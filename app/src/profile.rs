@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use ress::GameOutcome;
+use ress::glicko::Rating;
+use ress::piece::Color;
+use ress::store::{FsStore, GameRecord, GameStore};
+use crate::seat::MAX_SKILL;
+
+const PROFILES_DIR: &str = "profiles";
+const GAMES_DIR: &str = "games";
+
+// a rough guess of how strong each skill level plays, the engine is never rated itself
+fn engine_rating(skill: u8) -> Rating {
+    Rating::new(700.0 + 60.0*skill as f64, 100.0)
+}
+
+// a game against the engine, the moves are in the game store under `id`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayedGame {
+    pub id: String,
+    pub color: Color,
+    pub skill: u8,
+    // 1 a win, 0.5 a draw, 0 a loss
+    pub score: f64,
+}
+
+// profiles/<name>.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub rating: Rating,
+    #[serde(default)]
+    pub games: Vec<PlayedGame>,
+}
+
+impl Profile {
+    fn path(name: &str) -> PathBuf {
+        PathBuf::from(PROFILES_DIR).join(format!("{name}.json"))
+    }
+
+    // the name ends up in file names and game ids, so it's held to what the game store allows
+    pub fn valid_name(name: &str) -> bool {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+
+    // a fresh profile if there isn't one under this name yet
+    pub fn load_or_create(name: &str) -> Result<Self, String> {
+        if !Self::valid_name(name) {
+            return Err("a profile name can only have letters, digits, - and _.".to_string());
+        };
+
+        match std::fs::read_to_string(Self::path(name)) {
+            Ok(raw) => serde_json::from_str(&raw).map_err(|err| format!("the profile of {name} is damaged: {err}")),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self { name: name.to_string(), rating: Rating::default(), games: Vec::new() }),
+            Err(err) => Err(format!("couldn't read the profile of {name}: {err}")),
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let raw = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        std::fs::create_dir_all(PROFILES_DIR).and_then(|_| std::fs::write(Self::path(&self.name), raw))
+            .map_err(|err| format!("couldn't save the profile of {}: {err}", self.name))
+    }
+
+    // stores the game, rates it and saves the profile, returns how much the rating changed
+    pub fn record(&mut self, game: &GameRecord, outcome: GameOutcome, color: Color, skill: u8) -> Result<f64, String> {
+        let score = match outcome {
+            GameOutcome::Decisive { won, .. } if won == color => 1.0,
+            GameOutcome::Decisive { .. } => 0.0,
+            GameOutcome::Draw(_) => 0.5,
+        };
+
+        let id = format!("{}-{}", self.name, self.games.len() + 1);
+        let store = FsStore::new(GAMES_DIR).map_err(|err| format!("couldn't open the game store: {err}"))?;
+        store.save(&id, game).map_err(|err| format!("couldn't store the game: {err:?}"))?;
+
+        let before = self.rating.rating;
+        self.rating = self.rating.updated(&[(engine_rating(skill), score)]);
+        self.games.push(PlayedGame { id, color, skill, score });
+        self.save()?;
+        Ok(self.rating.rating - before)
+    }
+
+    // results against each skill level played so far
+    pub fn stats(&self) -> String {
+        let mut lines = vec![format!("{}: rating {:.0} ±{:.0}, {} games", self.name, self.rating.rating, 2.0*self.rating.deviation, self.games.len())];
+        for skill in 1..=MAX_SKILL {
+            let games = self.games.iter().filter(|game| game.skill == skill).collect::<Vec<_>>();
+            if games.is_empty() {
+                continue;
+            };
+
+            let count = |score: f64| games.iter().filter(|game| game.score == score).count();
+            let total = games.iter().map(|game| game.score).sum::<f64>();
+            lines.push(format!(
+                "  skill {skill:>2}: +{} ={} -{} ({:.0}%)",
+                count(1.0), count(0.5), count(0.0), 100.0*total/games.len() as f64,
+            ));
+        };
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,31 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use ress::clock::Clock;
+use ress::game::Game;
+
+// a game put aside with /save, the game keeps the draw offer, the clock and everything the rules need to go on
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    pub game: Game,
+    // the time each side has spent thinking, black's first
+    pub used: [Duration; 2],
+    // what each side had left, from saves made before the game kept its clock
+    #[serde(default, skip_serializing)]
+    pub clock: Option<Clock>,
+}
+
+impl SavedGame {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path).map_err(|err| format!("couldn't read {path}: {err}"))?;
+        let mut saved: Self = serde_json::from_str(&raw).map_err(|err| format!("{path} isn't a saved game: {err}"))?;
+        if let Some(clock) = saved.clock.take() {
+            saved.game.set_clock(Some(clock));
+        };
+        Ok(saved)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let raw = serde_json::to_string(self).map_err(|err| err.to_string())?;
+        std::fs::write(path, raw).map_err(|err| format!("couldn't save the game to {path}: {err}"))
+    }
+}
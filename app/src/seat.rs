@@ -0,0 +1,186 @@
+use std::time::{Duration, SystemTime};
+use engine::Engine;
+use engine::analysis::AnalysisSession;
+use engine::book::Book;
+use engine::options::DEFAULT_WEIGHTS;
+use engine::search::{search_until, Evaluator, Score, SearchLimits};
+use engine::time::{TimeControl, TimeManager};
+use ress::{Board, PlayerMove};
+use ress::clock::Clock;
+use ress::coordinate::Move;
+use ress::piece::Color;
+use ress::position::Position;
+
+pub const MAX_SKILL: u8 = 20;
+
+// how an engine playing one of the colors is set up
+#[derive(Debug, Clone)]
+pub struct EngineSettings {
+    pub weights: String,
+    // 1..=MAX_SKILL, below the maximum the engine now and then plays a random move instead
+    pub skill: u8,
+    // up to this many centipawns of noise on every evaluation, at 0 it always plays the same
+    pub temperature: u32,
+    pub depth: u8,
+    pub movetime: Option<Duration>,
+    // an opening book to play from while the game is in it
+    pub book: Option<String>,
+    // whether the book's weights follow the engine's results, saved next to the book after every game
+    pub book_learning: bool,
+}
+
+impl Default for EngineSettings {
+    fn default() -> Self {
+        Self { weights: DEFAULT_WEIGHTS.to_string(), skill: MAX_SKILL, temperature: 0, depth: 4, movetime: None, book: None, book_learning: true }
+    }
+}
+
+// the noise has to stay the same for a position during a search, or the table would keep contradicting itself
+struct Noisy<'a> {
+    engine: &'a Engine,
+    temperature: u32,
+    seed: u64,
+}
+
+impl Evaluator for Noisy<'_> {
+    fn evaluate(&self, position: &Position) -> i32 {
+        let eval = Evaluator::evaluate(self.engine, position);
+        if self.temperature == 0 {
+            return eval;
+        };
+
+        let mut hash = position.key().0 ^ self.seed;
+        next_random(&mut hash);
+        eval + (hash % (self.temperature as u64*2 + 1)) as i32 - self.temperature as i32
+    }
+}
+
+// xorshift, picking the odd random move doesn't need more
+fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+pub struct EngineSeat {
+    pub settings: EngineSettings,
+    engine: Engine,
+    book: Option<Book>,
+    rng: u64,
+}
+
+impl EngineSeat {
+    pub fn new(settings: EngineSettings) -> Result<Self, String> {
+        let engine = Engine::load(&settings.weights).map_err(|err| format!("couldn't load the weights from {}: {err}", settings.weights))?;
+        let book = match &settings.book {
+            Some(path) => Some(Book::load(path).map_err(|err| format!("couldn't load the book from {path}: {err}"))?),
+            None => None,
+        };
+        let seed = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(1, |time| time.as_nanos() as u64);
+        Ok(Self { settings, engine, book, rng: seed.max(1) })
+    }
+
+    // lets the book learn from a finished game the engine played as `color`, the winner is none for a draw
+    pub fn learn(&mut self, start: &Board, moves: &[Move], color: Color, result: Option<Color>) -> Result<(), String> {
+        let (Some(book), Some(path)) = (&mut self.book, &self.settings.book) else {
+            return Ok(());
+        };
+        if !self.settings.book_learning || !book.learn(start, moves, color, result) {
+            return Ok(());
+        };
+        book.save_learning(path).map_err(|err| format!("couldn't save what the engine learned to {}: {err}", Book::learning_path(path)))
+    }
+
+    // the move and its score in centipawns for the side to move, none if the game is over.
+    // with a clock the time is planned from it, the movetime setting is for games without one.
+    pub fn choose_move(&mut self, board: &Board, clock: Option<Clock>) -> Option<(Move, i32)> {
+        let legal_moves = board.possible_moves(board.move_color);
+        if legal_moves.is_empty() {
+            return None;
+        };
+
+        // scored 0, a book move isn't searched
+        if let Some(book) = &self.book {
+            let roll = (next_random(&mut self.rng) % 1_000_000) as f32/1_000_000.0;
+            if let Some(r#move) = book.choose(board, roll) {
+                return Some((r#move, 0));
+            };
+        };
+
+        let blunder_chance = (MAX_SKILL - self.settings.skill.clamp(1, MAX_SKILL)) as u64*5;
+        if next_random(&mut self.rng) % 100 < blunder_chance {
+            let r#move = legal_moves[next_random(&mut self.rng) as usize % legal_moves.len()];
+            return Some((r#move, 0));
+        };
+
+        let evaluator = Noisy { engine: &self.engine, temperature: self.settings.temperature, seed: next_random(&mut self.rng) };
+        let limits = SearchLimits { depth: self.settings.depth, ..Default::default() };
+        let control = match clock {
+            // the delay is as good as an increment for planning
+            Some(clock) => {
+                let increment = Some(clock.control.increment + clock.control.delay);
+                TimeControl { wtime: Some(clock.remaining(Color::White)), btime: Some(clock.remaining(Color::Black)), winc: increment, binc: increment, ..Default::default() }
+            },
+            None => TimeControl { movetime: self.settings.movetime, ..Default::default() },
+        };
+        let time = TimeManager::new(&control, board.move_color);
+        let result = search_until(board, &evaluator, limits, &time);
+        result.best_move.map(|r#move| (r#move, result.score))
+    }
+}
+
+// the best move for the side to move, its score and the depth it's been the best since, the deeper the surer.
+// none if the game is over or the weights couldn't be loaded, which is printed.
+pub fn hint(board: &Board, weights: &str, depth: u8) -> Option<(Move, i32, u8)> {
+    let engine = match Engine::load(weights) {
+        Ok(engine) => engine,
+        Err(err) => {
+            println!("couldn't load the weights from {weights}: {err}");
+            return None;
+        },
+    };
+
+    let mut session = AnalysisSession::new(engine, board);
+    session.start(SearchLimits { depth, ..Default::default() });
+    let result = session.wait()?;
+    let settled = session.changes().last().map_or(1, |(depth, _)| *depth);
+    Some((result.best_move?, result.score, settled))
+}
+
+// prints the best line found at each depth, scores are from white's point of view. returns the best move.
+pub fn analyze(board: &Board, weights: &str, depth: u8) -> Option<Move> {
+    let engine = match Engine::load(weights) {
+        Ok(engine) => engine,
+        Err(err) => {
+            println!("couldn't load the weights from {weights}: {err}");
+            return None;
+        },
+    };
+
+    let sign = match board.move_color {
+        Color::White => 1,
+        Color::Black => -1,
+    };
+    let mut session = AnalysisSession::new(engine, board);
+    session.start(SearchLimits { depth, ..Default::default() });
+    let result = session.wait()?;
+    if result.best_move.is_none() {
+        println!("there's nothing to analyze, the game is over.");
+    };
+
+    for info in session.history() {
+        let mut line = board.clone();
+        let pv = info.pv.iter().map(|r#move| {
+            let san = line.san(*r#move);
+            let _ = line.play_move(PlayerMove::Internal(*r#move));
+            san
+        }).collect::<Vec<_>>();
+        println!("depth {}: {} {}", info.depth, Score::of(info.score*sign), pv.join(" "));
+    };
+    // the first depth has nothing to change from
+    if let Some((depth, r#move)) = session.changes().last() {
+        println!("the engine last changed its mind at depth {depth}, to {}.", board.san(*r#move));
+    };
+    result.best_move
+}
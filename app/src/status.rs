@@ -0,0 +1,147 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use ress::{Board, PlayerMove};
+use ress::clock::{render_clocks, Clock, ClockStyle};
+use ress::coordinate::Move;
+use ress::game::Game;
+use ress::piece::{Color, PieceKind, ValueTable};
+
+const SHOWN_MOVES: usize = 8;
+const REFRESH: Duration = Duration::from_millis(250);
+
+// what a full set has, whatever is missing from the board got captured
+const STARTING_SET: [(PieceKind, u8); 5] = [(PieceKind::Queen, 1), (PieceKind::Rook, 2), (PieceKind::Bishop, 2), (PieceKind::Knight, 2), (PieceKind::Pawn, 8)];
+
+// the line under the board: time each side has spent (or has left, with the game's clock), captured material and the last few moves
+pub struct Status {
+    // san of every move, the first one played by `first_color`
+    moves: Vec<String>,
+    first_color: Color,
+    used: [Duration; 2],
+    turn_start: Instant,
+}
+
+impl Status {
+    pub fn new(board: &Board) -> Self {
+        Self { moves: Vec::new(), first_color: board.move_color, used: [Duration::ZERO; 2], turn_start: Instant::now() }
+    }
+
+    // picks a game up again, with the time each side had already spent on it
+    pub fn restore(game: &Game, used: [Duration; 2]) -> Self {
+        let mut board = game.start().clone();
+        let moves = game.moves().iter().map(|r#move| {
+            let san = board.san(*r#move);
+            let _ = board.play_move(PlayerMove::Internal(*r#move));
+            san
+        }).collect();
+        Self { moves, first_color: game.start().move_color, used, turn_start: Instant::now() }
+    }
+
+    // how long the side to move has been thinking, what `Game::play_timed` is given
+    pub fn thinking(&self) -> Duration {
+        self.turn_start.elapsed()
+    }
+
+    // `board` is the position the move was played from, the san depends on it
+    pub fn record(&mut self, board: &Board, r#move: Move, thinking: Duration) {
+        self.moves.push(board.san(r#move));
+        self.used[board.move_color as usize] += thinking;
+        self.turn_start = Instant::now();
+    }
+
+    // the game's clock as it stands right now, with the side to move's thinking so far charged
+    pub fn clock(&self, game: &Game) -> Option<Clock> {
+        let mut clock = game.clock()?;
+        clock.charge(game.board().move_color, self.turn_start.elapsed());
+        Some(clock)
+    }
+
+    // whether the side to move has less than `time` left, never without a clock
+    pub fn short_on_time(&self, game: &Game, time: Duration) -> bool {
+        game.clock().is_some_and(|clock| clock.remaining_after(game.board().move_color, self.turn_start.elapsed()) < time)
+    }
+
+    // the last move was taken back, the time spent on it stays spent
+    pub fn undo(&mut self) {
+        self.moves.pop();
+    }
+
+    // what each side has spent so far, the one to move included
+    pub fn used(&self, board: &Board) -> [Duration; 2] {
+        [Color::Black, Color::White].map(|color| self.time(color, board.move_color))
+    }
+
+    // the time left with a clock, the time spent without one
+    fn shown_time(&self, game: &Game, color: Color) -> Duration {
+        let thinking = if color == game.board().move_color { self.turn_start.elapsed() } else { Duration::ZERO };
+        match game.clock() {
+            Some(clock) => clock.remaining_after(color, thinking),
+            None => self.used[color as usize] + thinking,
+        }
+    }
+
+    fn time(&self, color: Color, to_move: Color) -> Duration {
+        self.used[color as usize] + if color == to_move { self.turn_start.elapsed() } else { Duration::ZERO }
+    }
+
+    // pieces of `color` that aren't on the board anymore
+    pub(crate) fn captured(board: &Board, color: Color) -> Vec<PieceKind> {
+        STARTING_SET.iter().flat_map(|&(kind, count)| {
+            let left = board.pieces_of(color, kind).count() as u8;
+            std::iter::repeat_n(kind, count.saturating_sub(left) as usize)
+        }).collect()
+    }
+
+    fn recent_moves(&self) -> String {
+        let offset = (self.first_color == Color::Black) as usize;
+        let start = self.moves.len().saturating_sub(SHOWN_MOVES);
+        let mut line = String::new();
+        for (i, san) in self.moves.iter().enumerate().skip(start) {
+            let ply = i + offset;
+            if ply.is_multiple_of(2) {
+                line.push_str(&format!("{}. ", ply/2 + 1));
+            } else if i == start {
+                line.push_str(&format!("{}... ", ply/2 + 1));
+            };
+            line.push_str(san);
+            line.push(' ');
+        };
+        line.trim_end().to_string()
+    }
+
+    pub fn line(&self, game: &Game) -> String {
+        let board = game.board();
+        let captures = [Color::White, Color::Black].map(|color| Self::captured(board, color));
+        let value = |kinds: &[PieceKind]| kinds.iter().map(|kind| kind.value(&ValueTable::CLASSICAL)).sum::<i32>();
+        let material = value(&captures[1]) - value(&captures[0]);
+        let letters = |kinds: &[PieceKind]| kinds.iter().map(|kind| kind.letter()).collect::<String>();
+
+        format!(
+            "{} | captured: white {} black {} ({material:+}) | {}",
+            render_clocks(self.shown_time(game, Color::White), self.shown_time(game, Color::Black), Some(board.move_color),
+                game.clock().map(|clock| clock.control.increment), ClockStyle::Standard),
+            letters(&captures[1]), letters(&captures[0]), self.recent_moves(),
+        )
+    }
+
+    // redraws the status line in place while `work` (e.g. the engine thinking) runs
+    pub fn refresh_while<T>(&self, game: &Game, work: impl FnOnce() -> T) -> T {
+        let done = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    print!("\r\x1b[2K{}", self.line(game));
+                    std::io::stdout().flush().unwrap();
+                    std::thread::sleep(REFRESH);
+                };
+                print!("\r\x1b[2K");
+                std::io::stdout().flush().unwrap();
+            });
+
+            let result = work();
+            done.store(true, Ordering::Relaxed);
+            result
+        })
+    }
+}
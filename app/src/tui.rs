@@ -0,0 +1,352 @@
+use std::io::{Stdout, Write};
+use crossterm::{cursor, execute, queue, terminal};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{self, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use engine::Engine;
+use ress::{Board, GameOutcome, PlayerMove};
+use ress::coordinate::{Coordinate, File, Move, Rank};
+use ress::game::{Game, GamePolicy};
+use ress::piece::{Color, Piece, PieceKind};
+use ress::render;
+use crate::seat::EngineSeat;
+use crate::status::Status;
+
+// a full screen board: the cursor keys move around it, enter picks a piece up and puts it down on one of the
+// squares it can go to. next to it the engine's evaluation, what's been captured and the moves so far.
+
+const LIGHT: style::Color = style::Color::Rgb { r: 240, g: 217, b: 181 };
+const DARK: style::Color = style::Color::Rgb { r: 181, g: 136, b: 99 };
+const CURSOR: style::Color = style::Color::Rgb { r: 220, g: 180, b: 40 };
+const SELECTED: style::Color = style::Color::Rgb { r: 60, g: 160, b: 80 };
+const TARGET: style::Color = style::Color::Rgb { r: 60, g: 110, b: 200 };
+const LAST_MOVE: style::Color = style::Color::Rgb { r: 170, g: 190, b: 110 };
+const CHECK: style::Color = style::Color::Rgb { r: 220, g: 40, b: 40 };
+// a square is this many columns wide, so it's about as wide as it's high
+const SQUARE_WIDTH: u16 = 3;
+const BOARD_LEFT: u16 = 2;
+const BOARD_TOP: u16 = 2;
+// right of the board and the eval bar
+const PANE_LEFT: u16 = BOARD_LEFT + 8*SQUARE_WIDTH + 6;
+const SHOWN_MOVE_ROWS: usize = 12;
+const HELP: &str = "arrows move, enter picks up and puts down, esc drops, u takes back, f flips, q quits";
+
+// puts the terminal back however the game ends, a panic included
+struct Screen(Stdout);
+
+impl Screen {
+    fn enter() -> std::io::Result<Self> {
+        let mut stdout = std::io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self(stdout))
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(self.0, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct Tui {
+    game: Game,
+    // the engine's side and the seat playing it, none for two players at one keyboard
+    opponent: Option<(Color, EngineSeat)>,
+    // for the eval bar, none without weights to load
+    evaluator: Option<Engine>,
+    cursor: Coordinate,
+    selected: Option<Coordinate>,
+    perspective: Color,
+    message: String,
+}
+
+impl Tui {
+    fn targets(&self) -> Vec<Move> {
+        let Some(from) = self.selected else {
+            return Vec::new();
+        };
+        let color = self.game.board().move_color;
+        self.game.board().possible_moves(color).into_iter().filter(|r#move| r#move.resolve_from(color) == from).collect()
+    }
+
+    fn step(&mut self, files: i8, ranks: i8) {
+        // up is away from whoever sits at the bottom
+        let sign = if self.perspective == Color::White { 1 } else { -1 };
+        let file = (self.cursor.file as i8 + files*sign).clamp(0, 7);
+        let rank = (self.cursor.rank as i8 + ranks*sign).clamp(0, 7);
+        if let (Ok(file), Ok(rank)) = (File::try_from(file), Rank::try_from(rank)) {
+            self.cursor = Coordinate { file, rank };
+        };
+    }
+
+    fn screen_position(&self, coord: Coordinate) -> (u16, u16) {
+        let (column, row) = match self.perspective {
+            Color::White => (coord.file as u16, 7 - coord.rank as u16),
+            Color::Black => (7 - coord.file as u16, coord.rank as u16),
+        };
+        (BOARD_LEFT + column*SQUARE_WIDTH, BOARD_TOP + row)
+    }
+
+    fn draw(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let board = self.game.board();
+        let unicode = render::style().unicode;
+        queue!(out, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(BOARD_LEFT, 0))?;
+        let title = match self.game.outcome() {
+            Some(outcome) => outcome_text(outcome),
+            None => format!("{} to move", board.move_color),
+        };
+        queue!(out, Print(title))?;
+
+        let color = board.move_color;
+        let targets = self.targets().into_iter().map(|r#move| r#move.resolve_to(color)).collect::<Vec<_>>();
+        let last = board.history().last().map(|(r#move, by)| [r#move.resolve_from(*by), r#move.resolve_to(*by)]);
+        let check = board.checked_king();
+        for coord in Coordinate::iter() {
+            let background = if coord == self.cursor {
+                CURSOR
+            } else if Some(coord) == self.selected {
+                SELECTED
+            } else if targets.contains(&coord) {
+                TARGET
+            } else if check == Some(coord) {
+                CHECK
+            } else if last.is_some_and(|squares| squares.contains(&coord)) {
+                LAST_MOVE
+            } else if (coord.file as u8 + coord.rank as u8).is_multiple_of(2) {
+                DARK
+            } else {
+                LIGHT
+            };
+
+            let (x, y) = self.screen_position(coord);
+            let symbol = board.grid()[coord].map_or(' ', |piece| if unicode { solid(piece) } else { piece.letter() });
+            let foreground = match board.grid()[coord] {
+                Some(Piece { color: Color::White, .. }) => style::Color::White,
+                _ => style::Color::Black,
+            };
+            queue!(out, cursor::MoveTo(x, y), SetBackgroundColor(background), SetForegroundColor(foreground), Print(format!(" {symbol} ")), ResetColor)?;
+        };
+
+        for i in 0..8u16 {
+            let (file, rank) = match self.perspective {
+                Color::White => (i, 7 - i),
+                Color::Black => (7 - i, i),
+            };
+            queue!(out, cursor::MoveTo(BOARD_LEFT - 2, BOARD_TOP + i), Print(rank + 1))?;
+            queue!(out, cursor::MoveTo(BOARD_LEFT + i*SQUARE_WIDTH + 1, BOARD_TOP + 8), Print((b'a' + file as u8) as char))?;
+        };
+
+        self.draw_eval(out)?;
+        self.draw_pane(out)?;
+
+        queue!(out, cursor::MoveTo(BOARD_LEFT, BOARD_TOP + 10), Print(&self.message))?;
+        queue!(out, cursor::MoveTo(BOARD_LEFT, BOARD_TOP + 11), SetForegroundColor(style::Color::DarkGrey), Print(HELP), ResetColor)?;
+        out.flush()
+    }
+
+    // white's share of the column, from the bottom up
+    fn draw_eval(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let Some(engine) = &self.evaluator else {
+            return Ok(());
+        };
+        let value = engine.evaluate(self.game.board());
+        let white = (((value + 1.0)/2.0*16.0).round() as i32).clamp(0, 16);
+        let x = BOARD_LEFT + 8*SQUARE_WIDTH + 2;
+        for row in 0..8 {
+            // two halves a row, the white part being on the side at the bottom
+            let from_bottom = 7 - row;
+            let filled = (white - from_bottom*2).clamp(0, 2);
+            let filled = if self.perspective == Color::White { filled } else { 2 - filled };
+            let symbol = match filled {
+                0 => "  ",
+                1 => "▄▄",
+                _ => "██",
+            };
+            queue!(out, cursor::MoveTo(x, BOARD_TOP + row as u16), SetBackgroundColor(style::Color::Black), SetForegroundColor(style::Color::White), Print(symbol), ResetColor)?;
+        };
+        queue!(out, cursor::MoveTo(x - 1, BOARD_TOP + 8), Print(format!("{value:+.2}")))
+    }
+
+    fn draw_pane(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let board = self.game.board();
+        let letters = |color: Color| Status::captured(board, color).into_iter().map(|kind| {
+            let piece = Piece { kind, color };
+            if render::style().unicode { piece.glyph() } else { piece.letter() }
+        }).collect::<String>();
+        queue!(out, cursor::MoveTo(PANE_LEFT, BOARD_TOP), Print(format!("captured by white: {}", letters(Color::Black))))?;
+        queue!(out, cursor::MoveTo(PANE_LEFT, BOARD_TOP + 1), Print(format!("captured by black: {}", letters(Color::White))))?;
+
+        let rows = move_rows(&self.game);
+        for (i, row) in rows.iter().skip(rows.len().saturating_sub(SHOWN_MOVE_ROWS)).enumerate() {
+            queue!(out, cursor::MoveTo(PANE_LEFT, BOARD_TOP + 3 + i as u16), Print(row))?;
+        };
+        Ok(())
+    }
+
+    // enter on the cursor's square
+    fn pick(&mut self, out: &mut impl Write) -> std::io::Result<()> {
+        let board = self.game.board();
+        if self.game.outcome().is_some() || self.opponent.as_ref().is_some_and(|(color, _)| *color == board.move_color) {
+            return Ok(());
+        };
+
+        let moves = self.targets().into_iter().filter(|r#move| r#move.resolve_to(board.move_color) == self.cursor).collect::<Vec<_>>();
+        if moves.is_empty() {
+            let own = board.grid()[self.cursor].is_some_and(|piece| piece.color == board.move_color);
+            self.selected = own.then_some(self.cursor);
+            return Ok(());
+        };
+
+        let r#move = match moves[..] {
+            [r#move] => r#move,
+            // only promotions share their squares
+            _ => {
+                self.message = String::from("promote to: q, r, b or n");
+                self.draw(out)?;
+                let kind = loop {
+                    if let Some(kind) = key().and_then(|code| match code {
+                        KeyCode::Char(c) => PieceKind::parse(&c.to_ascii_lowercase().to_string()).filter(|kind| !matches!(kind, PieceKind::Pawn | PieceKind::King)),
+                        _ => None,
+                    }) {
+                        break kind;
+                    };
+                };
+                let Some(r#move) = moves.into_iter().find(|r#move| matches!(r#move, Move::Promotion { piece, .. } if *piece == kind)) else {
+                    return Ok(());
+                };
+                r#move
+            },
+        };
+        self.play(r#move);
+        Ok(())
+    }
+
+    fn play(&mut self, r#move: Move) {
+        let san = self.game.board().san(r#move);
+        self.selected = None;
+        self.message = match self.game.play_move(PlayerMove::Internal(r#move)) {
+            Ok(_) => format!("{} played {san}", self.game.board().history().last().map_or(Color::White, |(_, by)| *by)),
+            Err(err) => format!("{err}"),
+        };
+        // with two players at the keyboard it's turned for each of them
+        if self.opponent.is_none() && self.game.outcome().is_none() {
+            self.perspective = self.game.board().move_color;
+        };
+    }
+
+    fn engine_turn(&mut self, out: &mut impl Write) -> std::io::Result<()> {
+        let board = self.game.board().clone();
+        let Some((color, _)) = &self.opponent else {
+            return Ok(());
+        };
+        if *color != board.move_color || self.game.outcome().is_some() {
+            return Ok(());
+        };
+
+        self.message = String::from("the engine is thinking...");
+        self.draw(out)?;
+        let Some((_, seat)) = &mut self.opponent else {
+            return Ok(());
+        };
+        if let Some((r#move, _)) = seat.choose_move(&board, None) {
+            self.play(r#move);
+        };
+        Ok(())
+    }
+
+    fn takeback(&mut self) {
+        // against the engine its reply goes too, so it's the player's turn again
+        let plies = match &self.opponent {
+            Some((color, _)) if self.game.board().move_color != *color => 2,
+            _ => 1,
+        };
+        for _ in 0..plies {
+            if let Err(err) = self.game.takeback() {
+                self.message = format!("{err}");
+                return;
+            };
+        };
+        self.selected = None;
+        self.message = String::from("taken back");
+    }
+}
+
+// the filled glyphs stand out better on colored squares, the color tells the sides apart
+fn solid(piece: Piece) -> char {
+    Piece { color: Color::Black, ..piece }.glyph()
+}
+
+fn outcome_text(outcome: GameOutcome) -> String {
+    match outcome {
+        GameOutcome::Decisive { won, reason } => format!("{won} won by {reason}"),
+        GameOutcome::Draw(reason) => format!("draw by {reason}"),
+    }
+}
+
+// `12. Nf3 Nc6`, a row per move
+fn move_rows(game: &Game) -> Vec<String> {
+    let mut board = game.start().clone();
+    let mut rows = Vec::new();
+    for r#move in game.moves() {
+        match board.move_color {
+            Color::White => rows.push(format!("{:>3}. {}", board.move_number(), board.san(*r#move))),
+            Color::Black => match rows.last_mut() {
+                Some(row) => row.push_str(&format!(" {}", board.san(*r#move))),
+                None => rows.push(format!("{:>3}... {}", board.move_number(), board.san(*r#move))),
+            },
+        };
+        if board.play_move(PlayerMove::Internal(*r#move)).is_err() {
+            break;
+        };
+    };
+    rows
+}
+
+// the next key pressed, none for anything else that happens to the terminal
+fn key() -> Option<KeyCode> {
+    match event::read().ok()? {
+        Event::Key(key) if key.kind == KeyEventKind::Press => Some(key.code),
+        _ => None,
+    }
+}
+
+// plays until the player quits. against an engine if there's a seat for it, which plays the other side.
+pub fn run(start: Board, opponent: Option<(Color, EngineSeat)>, weights: &str) -> std::io::Result<()> {
+    let perspective = match &opponent {
+        Some((color, _)) => color.the_other(),
+        None => start.move_color,
+    };
+    let mut tui = Tui {
+        game: Game::new(start, GamePolicy::casual()),
+        opponent,
+        evaluator: Engine::load(weights).ok(),
+        cursor: Coordinate { file: File::E, rank: if perspective == Color::White { Rank::Second } else { Rank::Seventh } },
+        selected: None,
+        perspective,
+        message: String::new(),
+    };
+
+    let mut screen = Screen::enter()?;
+    loop {
+        tui.engine_turn(&mut screen.0)?;
+        tui.draw(&mut screen.0)?;
+        let Some(code) = key() else {
+            continue;
+        };
+        tui.message.clear();
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => tui.step(0, 1),
+            KeyCode::Down | KeyCode::Char('j') => tui.step(0, -1),
+            KeyCode::Left | KeyCode::Char('h') => tui.step(-1, 0),
+            KeyCode::Right | KeyCode::Char('l') => tui.step(1, 0),
+            KeyCode::Enter | KeyCode::Char(' ') => tui.pick(&mut screen.0)?,
+            KeyCode::Esc => tui.selected = None,
+            KeyCode::Char('u') => tui.takeback(),
+            KeyCode::Char('f') => tui.perspective = tui.perspective.the_other(),
+            KeyCode::Char('q') => break,
+            _ => {},
+        };
+    };
+    Ok(())
+}
@@ -1,125 +1,278 @@
 use std::sync::atomic::{AtomicI32, Ordering};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 use rayon::prelude::*;
+use tracing::{error, info};
 use engine::Engine;
-use ress::{Board, DrawReason, GameOutcome};
-use ress::piece::Color;
-
-
-fn battle(white: &Engine, black: &Engine) -> (i32, i32) {
-    [
-        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",  // starting
-        "rnbq1bnr/ppppkppp/8/4p3/4P3/8/PPPPKPPP/RNBQ1BNR w - - 2 3", // double bongcloud
-        "rnbqk2r/pppp1ppp/5n2/2b1p3/2B1P3/2N5/PPPP1PPP/R1BQK1NR w KQkq - 4 4", // vienna
-        "rnbqkb1r/ppp2ppp/3p4/8/3Pn3/5N2/PPP2PPP/RNBQKB1R b KQkq - 0 5",  // petrov's
-        "rnbqkb1r/pp3p1p/3p1np1/2pP4/4PP2/2N5/PP4PP/R1BQKBNR b KQkq f3 0 7", // "The Flick-Knife Attack"
-        "r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 4 4",  // four knights
-        "rnb1kbnr/ppp1pppp/8/q7/8/2N5/PPPP1PPP/R1BQKBNR w KQkq - 2 4",  // scandi
-        "rn1qkbnr/pp2pppp/2p5/3pPb2/3P4/8/PPP2PPP/RNBQKBNR w KQkq - 1 4",  // caro-kann advanced
-    ].into_par_iter().map(|pos| {
-        let mut score = (0, 0);
-        let mut board = Board::from_fen(pos).unwrap();
-
-        while board.game_outcome.is_none() {
-            if board.draw_pending.is_some() {
-                board.decline_draw();
-            };
+use engine::architecture::Architecture;
+use engine::checkpoint::Checkpoint;
+use engine::dashboard::Dashboard;
+use engine::options::DEFAULT_WEIGHTS;
+use engine::suite::OpeningSuite;
+use engine::training::Mutation;
+use ress::{Board, DrawReason, GameOutcome, WinReason};
+use ress::piece::{Color, ValueTable};
+
+
+
+// calls games before the rules would end them, bad engines can shuffle pieces for hundreds of plies otherwise
+#[derive(Debug, Clone, Copy)]
+struct Adjudication {
+    // a game still going after this many plies just stops, without an outcome
+    max_plies: usize,
+    // a draw once every position has been evaluated within `draw_below` of even for this many plies in a row
+    draw_plies: usize,
+    draw_below: f32,
+    // a win once one side has been this much material ahead (a pawn being 1) for this many plies in a row
+    win_material: i32,
+    win_plies: usize,
+}
+
+impl Default for Adjudication {
+    fn default() -> Self {
+        Self { max_plies: 400, draw_plies: 40, draw_below: 0.05, win_material: 10, win_plies: 6 }
+    }
+}
+
+
+// all the openings are played at once, so each engine evaluates every position it's to move in as one batch
+fn battle(white: &Engine, black: &Engine, openings: &[Board], adjudication: &Adjudication) -> (i32, i32) {
+    let mut boards = openings.to_vec();
+    // a game the engine can't continue stops there, without an outcome
+    let mut stuck = vec![false; boards.len()];
+    // the winner of a game adjudication called, none for a draw
+    let mut called = vec![None; boards.len()];
+    // plies in a row without much of an eval, and with the side ahead in material by enough
+    let mut quiet = vec![0; boards.len()];
+    let mut ahead = vec![(None, 0); boards.len()];
+
+    loop {
+        let mut playing = false;
+        for (color, engine) in [(Color::White, white), (Color::Black, black)] {
+            let to_move = (0..boards.len()).filter(|i| {
+                !stuck[*i] && called[*i].is_none() && boards[*i].game_outcome.is_none() && boards[*i].move_color == color &&
+                    boards[*i].grid_history.len() <= adjudication.max_plies
+            }).collect::<Vec<_>>();
+            let outputs = engine.evaluate_batch(&to_move.iter().map(|i| &boards[*i]).collect::<Vec<_>>());
+
+            for (i, output) in to_move.into_iter().zip(outputs) {
+                let board = &mut boards[i];
+                if Engine::choose_move_from(board, color, &output).is_some_and(|(r#move, _)| board.play_move(r#move).is_ok()) {
+                    playing = true;
+                } else {
+                    stuck[i] = true;
+                    continue;
+                };
+
+                quiet[i] = if output.value().abs() < adjudication.draw_below { quiet[i] + 1 } else { 0 };
+                let difference = board.material(Color::White, &ValueTable::CLASSICAL) - board.material(Color::Black, &ValueTable::CLASSICAL);
+                let leader = match difference {
+                    difference if difference >= adjudication.win_material => Some(Color::White),
+                    difference if difference <= -adjudication.win_material => Some(Color::Black),
+                    _ => None,
+                };
+                ahead[i] = (leader, if leader.is_some() && leader == ahead[i].0 { ahead[i].1 + 1 } else { 1 });
 
-            let engine = match board.move_color {
-                Color::White => white,
-                Color::Black => black,
+                if let Some(leader) = ahead[i].0.filter(|_| ahead[i].1 >= adjudication.win_plies) {
+                    called[i] = Some(Some(leader));
+                } else if quiet[i] >= adjudication.draw_plies {
+                    called[i] = Some(None);
+                };
             };
+        };
 
-            let r#move = engine.choose_move(&board, board.move_color).0;
-            let _ = board.play_move(r#move);
+        if !playing {
+            break;
         };
+    };
 
+    let mut score = (0, 0);
+    for (board, called) in boards.into_iter().zip(called) {
         let plies_count_score = board.grid_history.len() as i32;
         score.0 += plies_count_score;
         score.1 += plies_count_score;
 
-        match board.game_outcome.unwrap() {
-            GameOutcome::Decisive { won, .. } => {
+        // an adjudicated draw counts like one by the fifty move rule, for nothing
+        let outcome = match called {
+            Some(Some(won)) => Some(GameOutcome::Decisive { won, reason: WinReason::Resignation }),
+            Some(None) => Some(GameOutcome::Draw(DrawReason::NoAdvancement)),
+            None => board.game_outcome,
+        };
+        match outcome {
+            Some(GameOutcome::Decisive { won, .. }) => {
                 match won {
                     Color::White => score.0 += 500,
                     Color::Black => score.1 += 500,
                 };
             },
-            GameOutcome::Draw(DrawReason::InsufficientMaterial | DrawReason::Stalemate) => {
+            Some(GameOutcome::Draw(DrawReason::InsufficientMaterial | DrawReason::Stalemate)) => {
                 score.0 += 350;
                 score.1 += 350;
             },
             _ => {}
         };
-        score
-    }).reduce(|| (0, 0), |r#final, battle| (r#final.0 + battle.0, r#final.1 + battle.1))
+    };
+    score
 }
 
 
-fn find_best(pool: Vec<Engine>) -> Engine {
+// every engine's score from playing everyone else in the pool
+fn battle_all(pool: &[Engine], openings: &[Board], adjudication: &Adjudication) -> Vec<i32> {
     let score_atom = Vec::from_iter((0..pool.len()).map(|_| AtomicI32::new(0)));
     for (i, engine_a) in pool.iter().enumerate() {
         pool.par_iter().enumerate()
             .filter(|(j, _)| i != *j)
-            .map(|(j, engine_b)| (j, battle(engine_a, engine_b)))
+            .map(|(j, engine_b)| (j, battle(engine_a, engine_b, openings, adjudication)))
             .for_each(|(j, (a, b))| {
                 score_atom[i].fetch_add(a, Ordering::Relaxed);
                 score_atom[j].fetch_add(b, Ordering::Relaxed);
             });
     };
     
-    let score = score_atom.into_iter().map(|s| s.into_inner()).collect::<Vec<_>>();
+    score_atom.into_iter().map(|s| s.into_inner()).collect()
+}
+
+
+fn find_best(pool: Vec<Engine>, openings: &[Board], adjudication: &Adjudication) -> Engine {
+    let score = battle_all(&pool, openings, adjudication);
     pool.into_iter().enumerate().max_by_key(|(i, _)| score[*i]).unwrap().1
 }
 
 
-fn create_pool(engine: &Engine, mutation_coef: Option<f32>, size: usize) -> Vec<Engine> {
-    (0..size).map(|_| {
-        let mut engine = engine.clone();
-        engine.mutate(mutation_coef);
-        engine
+// without a mutation the pool is made of fresh random engines
+fn create_pool(engine: &Engine, mutation: Option<Mutation>, size: usize, rng: &mut StdRng) -> Vec<Engine> {
+    (0..size).map(|_| match mutation {
+        Some(mutation) => {
+            let mut engine = engine.clone();
+            engine.mutate_with(mutation, rng);
+            engine
+        },
+        None => Engine::new_random_architecture(engine.architecture(), rng),
     }).collect()
 }
 
 
-fn create_pools(engine: &Engine, mutation_coef: Option<f32>, size: usize, count: usize) -> Vec<Vec<Engine>> {
-    (0..count).into_par_iter().map(|_| create_pool(engine, mutation_coef, size)).collect()
+// the seeds are drawn up front, so the pools come out the same however rayon schedules them
+fn create_pools(engine: &Engine, mutation: Option<Mutation>, size: usize, count: usize, rng: &mut StdRng) -> Vec<Vec<Engine>> {
+    let seeds = (0..count).map(|_| rng.gen()).collect::<Vec<u64>>();
+    seeds.into_par_iter().map(|seed| create_pool(engine, mutation, size, &mut StdRng::seed_from_u64(seed))).collect()
 }
 
 
 const POOL_SIZE: usize = 15;
 const POOLS_COUNT: usize = 20;
 const HYPER_POOL_SIZE: usize = 10;
+const MUTATION: Mutation = Mutation { rate: 0.2, sigma: 0.4 };
+// every battle of an epoch is played from this many openings picked from the suite
+const OPENINGS_PER_EPOCH: usize = 8;
+
+
+const CHECKPOINT_PATH: &str = "evolve.rec";
 
 
+// `evolve [seed] [layers]`, the same seed and starting weights give the same run.
+// the layers (e.g. `features,64,32,planes`) only matter when there's no engine.rew to start from.
+// `evolve --resume` goes on from the checkpoint saved after every epoch, just like the run would have.
+// `--openings <file>` (before the rest, and again when resuming) has the battles start from a fen or epd per line instead of 8 varied ones.
+// `--dashboard <address>` (after the openings) serves a page there with the scores of every epoch, e.g. `--dashboard 127.0.0.1:8080`.
 fn main() {
+    engine::logging::init("info");
+    let mut args = std::env::args().skip(1).peekable();
+    let resume = args.next_if(|arg| arg == "--resume").is_some();
+    let suite = match args.next_if(|arg| arg == "--openings").map(|_| args.next().expect("the openings need a file")) {
+        Some(path) => match OpeningSuite::load(&path) {
+            Ok(suite) => suite,
+            Err(err) => {
+                error!(path, %err, "couldn't load the openings");
+                return;
+            },
+        },
+        None => OpeningSuite::default(),
+    };
+    let dashboard = match args.next_if(|arg| arg == "--dashboard").map(|_| args.next().expect("the dashboard needs an address")) {
+        Some(addr) => match Dashboard::serve(&addr, "evolve") {
+            Ok(dashboard) => {
+                info!(addr, "dashboard up");
+                Some(dashboard)
+            },
+            Err(err) => {
+                error!(addr, %err, "couldn't start the dashboard");
+                return;
+            },
+        },
+        None => None,
+    };
+
+    let adjudication = Adjudication::default();
+    let mut epoch_i;
+    let mut rng;
     let random;
     let mut engine;
-    if let Some(eng) = Engine::load("engine.rew") {
-        engine = eng;
-        random = false;
+    if resume {
+        let checkpoint = match Checkpoint::load(CHECKPOINT_PATH) {
+            Ok(checkpoint) => checkpoint,
+            Err(err) => {
+                error!(path = CHECKPOINT_PATH, %err, "couldn't load the checkpoint");
+                return;
+            },
+        };
+        let scores = checkpoint.population.iter().map(|(_, score)| *score).collect::<Vec<_>>();
+        info!(epoch = checkpoint.epoch, ?scores, "resuming");
+        epoch_i = checkpoint.epoch;
+        rng = StdRng::seed_from_u64(checkpoint.seed);
+        random = checkpoint.random;
+        engine = checkpoint.best;
     } else {
-        engine = Engine::new_random();
-        random = true;
+        let seed = args.next().map(|raw| raw.parse().expect("the seed has to be a number")).unwrap_or_else(|| rand::thread_rng().gen());
+        let architecture = args.next().map(|raw| Architecture::parse(&raw).expect("invalid layers")).unwrap_or_default();
+        info!(seed, "starting");
+        epoch_i = 0;
+        rng = StdRng::seed_from_u64(seed);
+
+        if let Ok(eng) = Engine::load(DEFAULT_WEIGHTS) {
+            engine = eng;
+            random = false;
+        } else {
+            engine = Engine::new_random_architecture(architecture, &mut rng);
+            random = true;
+        };
     };
     
-    let mut epoch_i = 0;
     loop {
-        engine.save(&format!("engine_epoch{epoch_i}.rew"));
+        let path = format!("engine_epoch{epoch_i}.rew");
+        if let Err(err) = engine.save(&path) {
+            error!(path, %err, "couldn't save the weights");
+        };
         epoch_i += 1;
-        println!("epoch {epoch_i}");
+        info!(epoch = epoch_i, "new epoch");
+        let openings = suite.choose(OPENINGS_PER_EPOCH, &mut rng);
         
-        let hyper_pool = (0..HYPER_POOL_SIZE).into_par_iter().map(|i| {
-            println!("generating pools (#{i})...");
-            let pools = create_pools(&engine, (epoch_i != 1 && !random).then_some(0.8), POOL_SIZE, POOLS_COUNT);
+        let seeds = (0..HYPER_POOL_SIZE).map(|_| rng.gen()).collect::<Vec<u64>>();
+        let hyper_pool = seeds.into_par_iter().enumerate().map(|(i, seed)| {
+            info!(hyper_pool = i, "generating pools");
+            let pools = create_pools(&engine, (epoch_i != 1 && !random).then_some(MUTATION), POOL_SIZE, POOLS_COUNT, &mut StdRng::seed_from_u64(seed));
             
-            println!("battling pools (#{i})...");
-            let super_pool = pools.into_par_iter().map(find_best).collect::<Vec<_>>();
+            info!(hyper_pool = i, "battling pools");
+            let super_pool = pools.into_par_iter().map(|pool| find_best(pool, &openings, &adjudication)).collect::<Vec<_>>();
             
-            println!("battling super pool (#{i})...");
-            find_best(super_pool)
+            info!(hyper_pool = i, "battling super pool");
+            find_best(super_pool, &openings, &adjudication)
         }).collect::<Vec<_>>();
         
-        println!("battling hyper pool...");
-        engine = find_best(hyper_pool);
+        info!("battling hyper pool");
+        let scores = battle_all(&hyper_pool, &openings, &adjudication);
+        let best = (0..hyper_pool.len()).max_by_key(|i| scores[*i]).unwrap();
+        if let Some(dashboard) = &dashboard {
+            dashboard.record_epoch(epoch_i, &scores);
+        };
+        engine = hyper_pool[best].clone();
+
+        // reseeding from itself makes the seed the rng's whole state, which is what the checkpoint keeps
+        let seed = rng.gen();
+        rng = StdRng::seed_from_u64(seed);
+        let checkpoint = Checkpoint { epoch: epoch_i, seed, random, best: engine.clone(), population: hyper_pool.into_iter().zip(scores).collect() };
+        if let Err(err) = checkpoint.save(CHECKPOINT_PATH) {
+            error!(path = CHECKPOINT_PATH, %err, "couldn't save the checkpoint");
+        };
     };
 }
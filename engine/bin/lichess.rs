@@ -0,0 +1,142 @@
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use engine::Engine;
+use engine::lichess::{board_after, to_uci, Event, GameEvent, GameState, Lichess, LichessError};
+use engine::options::DEFAULT_WEIGHTS;
+use engine::search::{search_parallel, SearchLimits};
+use engine::time::TimeManager;
+use engine::tt::TranspositionTable;
+use ress::piece::Color;
+
+const TOKEN_VARIABLE: &str = "LICHESS_TOKEN";
+
+
+// `lichess [--threads n] [weights]` with the bot account's token in LICHESS_TOKEN.
+// accepts standard challenges that aren't correspondence and plays every game on its own thread until it's stopped.
+fn main() {
+    engine::logging::init("info");
+    let mut threads = 1;
+    let mut weights = String::from(DEFAULT_WEIGHTS);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threads" => threads = args.next().and_then(|raw| raw.parse().ok()).expect("the threads have to be a number"),
+            _ => weights = arg,
+        };
+    };
+
+    let Ok(token) = std::env::var(TOKEN_VARIABLE) else {
+        error!("the bot account's token has to be in {TOKEN_VARIABLE}");
+        return;
+    };
+    let engine = match Engine::load(&weights) {
+        Ok(engine) => Arc::new(engine),
+        Err(err) => {
+            error!(path = weights, %err, "couldn't load the weights");
+            return;
+        },
+    };
+
+    let lichess = Arc::new(Lichess::new(&token));
+    let me = match lichess.account() {
+        Ok(me) => me,
+        Err(err) => {
+            error!(%err, "couldn't get the account");
+            return;
+        },
+    };
+    info!(me, "online");
+
+    let events = match lichess.events() {
+        Ok(events) => events,
+        Err(err) => {
+            error!(%err, "couldn't follow the account");
+            return;
+        },
+    };
+    for event in events {
+        let result = match event {
+            Ok(Event::Challenge(challenge)) if challenge.challenger == me => Ok(()),
+            Ok(Event::Challenge(challenge)) => {
+                info!(id = challenge.id, challenger = challenge.challenger, variant = challenge.variant, speed = challenge.speed, "challenged");
+                match (challenge.variant.as_str(), challenge.speed.as_str()) {
+                    (_, "correspondence") => lichess.decline(&challenge.id, "timeControl"),
+                    ("standard", _) => lichess.accept(&challenge.id),
+                    _ => lichess.decline(&challenge.id, "variant"),
+                }
+            },
+            Ok(Event::GameStart(id)) => {
+                let bot = Bot { lichess: Arc::clone(&lichess), engine: Arc::clone(&engine), me: me.clone(), threads };
+                std::thread::spawn(move || match bot.play(&id) {
+                    Ok(()) => info!(id, "game over"),
+                    Err(err) => error!(id, %err, "left the game"),
+                });
+                Ok(())
+            },
+            Ok(Event::GameFinish(_) | Event::Other) => Ok(()),
+            Err(err) => {
+                error!(%err, "lost the account's events");
+                return;
+            },
+        };
+        if let Err(err) = result {
+            warn!(%err, "couldn't answer");
+        };
+    };
+}
+
+// what every game's thread gets
+struct Bot {
+    lichess: Arc<Lichess>,
+    engine: Arc<Engine>,
+    me: String,
+    threads: usize,
+}
+
+impl Bot {
+    // follows the game and moves whenever it's the bot's turn, until it's over
+    fn play(&self, id: &str) -> Result<(), LichessError> {
+        let mut color = None;
+        let mut initial_fen = String::new();
+        // kept for the whole game
+        let tt = TranspositionTable::default();
+        for event in self.lichess.game(id)? {
+            let state = match event? {
+                GameEvent::Full { white, black, initial_fen: fen, state } => {
+                    color = match (white.as_deref() == Some(self.me.as_str()), black.as_deref() == Some(self.me.as_str())) {
+                        (true, _) => Some(Color::White),
+                        (_, true) => Some(Color::Black),
+                        _ => None,
+                    };
+                    initial_fen = fen;
+                    state
+                },
+                GameEvent::State(state) => state,
+                GameEvent::Other => continue,
+            };
+            if state.is_over() {
+                break;
+            };
+            self.respond(id, color, &initial_fen, &state, &tt)?;
+        };
+        Ok(())
+    }
+
+    fn respond(&self, id: &str, color: Option<Color>, initial_fen: &str, state: &GameState, tt: &TranspositionTable) -> Result<(), LichessError> {
+        let board = board_after(initial_fen, &state.moves)?;
+        if color != Some(board.move_color) || board.game_outcome.is_some() {
+            return Ok(());
+        };
+
+        let time = TimeManager::new(&state.time_control(), board.move_color);
+        let limits = SearchLimits { depth: u8::MAX, ..Default::default() };
+        let result = search_parallel(&board, &*self.engine, limits, &time, tt, &(), self.threads);
+        match result.best_move {
+            Some(r#move) => {
+                info!(id, r#move = board.san(r#move), depth = result.depth, score = result.score, "playing");
+                self.lichess.play(id, &to_uci(&board, r#move))
+            },
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use tracing::{error, info};
+use engine::Engine;
+use engine::architecture::Architecture;
+use engine::format::{self, VERSION};
+
+// `migrate [--layers sizes] <weights> <to>` rewrites weights of any format version, headerless ones included, in the newest one.
+// `migrate [--layers sizes] --in-place <weights>...` does it to every file given, e.g. a whole run's `engine_epoch*.rew`.
+// with the layers (like evolve's, e.g. `features,64,32,planes`) the hidden layers are resized as well: new neurons start at zero
+// and play no part until trained, dropped ones take what they contributed with them. the input and the output can't change.
+fn main() {
+    engine::logging::init("info");
+    let mut architecture = None;
+    let mut in_place = false;
+    let mut paths = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--layers" => architecture = Some(args.next().and_then(|raw| Architecture::parse(&raw)).expect("invalid layers")),
+            "--in-place" => in_place = true,
+            _ => paths.push(arg),
+        };
+    };
+
+    let jobs = match (in_place, &paths[..]) {
+        (true, paths) if !paths.is_empty() => paths.iter().map(|path| (path, path)).collect::<Vec<_>>(),
+        (false, [from, to]) => vec![(from, to)],
+        _ => {
+            error!("usage: migrate [--layers sizes] <weights> <to> or migrate [--layers sizes] --in-place <weights>...");
+            return;
+        },
+    };
+
+    for (from, to) in jobs {
+        let bytes = match std::fs::read(from) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                error!(path = from, %err, "couldn't read the weights");
+                continue;
+            },
+        };
+        let mut engine = match Engine::from_bytes(&bytes) {
+            Ok(engine) => engine,
+            Err(err) => {
+                error!(path = from, %err, "couldn't load the weights");
+                continue;
+            },
+        };
+        let from_architecture = engine.architecture();
+        if let Some(architecture) = &architecture {
+            engine = match engine.resized(architecture.clone()) {
+                Ok(engine) => engine,
+                Err(err) => {
+                    error!(path = from, from = %from_architecture, to = %architecture, %err, "couldn't resize the weights");
+                    continue;
+                },
+            };
+        };
+
+        match engine.save(to) {
+            Ok(()) => info!(from, to, from_version = format::version(&bytes), to_version = VERSION, from_architecture = %from_architecture, to_architecture = %engine.architecture(), "migrated"),
+            Err(err) => error!(path = to, %err, "couldn't save the weights"),
+        };
+    };
+}
@@ -0,0 +1,57 @@
+use tracing::error;
+use engine::Engine;
+use engine::odds::Calibration;
+use engine::search::SearchLimits;
+use engine::suite::OpeningSuite;
+use engine::tournament::TournamentOptions;
+
+
+// `odds [--depth n] [--rounds n] [--openings file] <weights> <reference weights>`, e.g. with an evolve epoch and a fixed reference.
+// prints how the engine did giving more and more material, and the most it could give and still score 50%.
+fn main() {
+    engine::logging::init("info");
+    let mut options = TournamentOptions::default();
+    let mut paths = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => options.limits = SearchLimits { depth: args.next().and_then(|raw| raw.parse().ok()).expect("the depth has to be a number"), ..options.limits },
+            "--rounds" => options.rounds = args.next().and_then(|raw| raw.parse().ok()).expect("the rounds have to be a number"),
+            "--openings" => {
+                let path = args.next().expect("the openings need a file");
+                match OpeningSuite::load(&path) {
+                    Ok(suite) => options.openings = suite,
+                    Err(err) => {
+                        error!(path, %err, "couldn't load the openings");
+                        return;
+                    },
+                };
+            },
+            _ => paths.push(arg),
+        };
+    };
+    let [path, reference_path] = &paths[..] else {
+        error!("usage: odds [--depth n] [--rounds n] [--openings file] <weights> <reference weights>");
+        return;
+    };
+
+    let mut engines = Vec::new();
+    for path in [path, reference_path] {
+        match Engine::load(path) {
+            Ok(engine) => engines.push(engine),
+            Err(err) => {
+                error!(path, %err, "couldn't load the weights");
+                return;
+            },
+        };
+    };
+
+    let calibration = Calibration::run(&engines[0], &engines[1], &options);
+    for rung in &calibration.rungs {
+        println!("{:<20} -{:<2}  {:>5.1}%  {}", rung.handicap.name, rung.handicap.material, rung.estimate.score()*100.0, rung.estimate);
+    };
+    match calibration.handicap() {
+        Some(handicap) => println!("{path} gives {reference_path} {} odds (worth {} in pawns) and still scores 50%", handicap.name, handicap.material),
+        None => println!("{path} doesn't score 50% against {reference_path} even without odds"),
+    };
+}
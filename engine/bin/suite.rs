@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+use tracing::error;
+use engine::Engine;
+use engine::pst::Pst;
+use engine::search::{Evaluator, SearchLimits};
+use engine::suite::TestSuite;
+
+
+// `suite [--depth n] [--time ms] <epd file> [weights]`, e.g. with wac.epd. without weights the piece-square tables evaluate.
+// prints whether each position was solved and how many were, so a change to the search can be measured against the last one.
+fn main() {
+    engine::logging::init("info");
+    let mut limits = SearchLimits { depth: u8::MAX, ..Default::default() };
+    let mut time = None;
+    let mut paths = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => limits.depth = args.next().and_then(|raw| raw.parse().ok()).expect("the depth has to be a number"),
+            "--time" => time = Some(Duration::from_millis(args.next().and_then(|raw| raw.parse().ok()).expect("the time has to be a number of milliseconds"))),
+            _ => paths.push(arg),
+        };
+    };
+    // something has to end the searches
+    if time.is_none() && limits.depth == u8::MAX {
+        limits.depth = SearchLimits::default().depth;
+    };
+    let (path, weights) = match &paths[..] {
+        [path] => (path, None),
+        [path, weights] => (path, Some(weights)),
+        _ => {
+            error!("usage: suite [--depth n] [--time ms] <epd file> [weights]");
+            return;
+        },
+    };
+
+    let suite = match TestSuite::load(path) {
+        Ok(suite) => suite,
+        Err(err) => {
+            error!(path, %err, "couldn't load the suite");
+            return;
+        },
+    };
+    match weights {
+        Some(weights) => match Engine::load(weights) {
+            Ok(engine) => run(&suite, &engine, limits, time),
+            Err(err) => error!(path = weights, %err, "couldn't load the weights"),
+        },
+        None => run(&suite, &Pst::default(), limits, time),
+    };
+}
+
+fn run(suite: &TestSuite, evaluator: &(impl Evaluator + Sync), limits: SearchLimits, time: Option<Duration>) {
+    let start = Instant::now();
+    let report = suite.run(evaluator, limits, time);
+    for (epd, result) in suite.positions.iter().zip(&report.results) {
+        let played = result.best_move.map_or_else(|| String::from("-"), |r#move| epd.board.san(r#move));
+        let expected = match epd.best_moves.is_empty() {
+            true => epd.avoid_moves.iter().map(|r#move| format!("not {}", epd.board.san(*r#move))).collect::<Vec<_>>().join(" "),
+            false => epd.best_moves.iter().map(|r#move| epd.board.san(*r#move)).collect::<Vec<_>>().join(" "),
+        };
+        println!("{}  {:<16} {:<8} {:<16} depth {:<3} {}", if result.solved { "ok" } else { "--" }, result.id.as_deref().unwrap_or("?"), played, expected, result.depth, result.nodes);
+    };
+    println!("{report} ({} nodes in {:.1}s)", report.nodes(), start.elapsed().as_secs_f64());
+}
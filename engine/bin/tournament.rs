@@ -0,0 +1,128 @@
+use std::io::Write;
+use tracing::{error, info};
+use engine::Engine;
+use engine::dashboard::Dashboard;
+use engine::search::SearchLimits;
+use engine::suite::OpeningSuite;
+use engine::tournament::{Entrant, Format, Sprt, Tournament, TournamentOptions};
+
+
+const PGN_PATH: &str = "tournament.pgn";
+// what the sprt between two entrants tells apart, no gain against a gain of this much
+const SPRT_ELO: (f64, f64) = (0.0, 10.0);
+// events the broadcast file can fall behind by, it's written as fast as the games go anyway
+const BROADCAST_BUFFER: usize = 1 << 16;
+
+
+// `tournament [--gauntlet] [--depth n] [--rounds n] [--openings file] [--broadcast file] [--dashboard address] <weights>...`, the games go to tournament.pgn.
+// a gauntlet has the first weights play all the others. the openings are a fen or epd per line, 8 varied ones otherwise.
+// with a broadcast every move of every game is appended to the file as a json line while it's played, to follow with `tail -f`.
+// with a dashboard the games and the standings can be followed in a browser, it stays up after the tournament until interrupted.
+fn main() {
+    engine::logging::init("info");
+    let mut options = TournamentOptions::default();
+    let mut paths = Vec::new();
+    let mut broadcast = None;
+    let mut dashboard = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--gauntlet" => options.format = Format::Gauntlet,
+            "--depth" => options.limits = SearchLimits { depth: args.next().and_then(|raw| raw.parse().ok()).expect("the depth has to be a number"), ..options.limits },
+            "--rounds" => options.rounds = args.next().and_then(|raw| raw.parse().ok()).expect("the rounds have to be a number"),
+            "--broadcast" => broadcast = Some(args.next().expect("the broadcast needs a file")),
+            "--dashboard" => dashboard = Some(args.next().expect("the dashboard needs an address, e.g. 127.0.0.1:8080")),
+            "--openings" => {
+                let path = args.next().expect("the openings need a file");
+                match OpeningSuite::load(&path) {
+                    Ok(suite) => options.openings = suite,
+                    Err(err) => {
+                        error!(path, %err, "couldn't load the openings");
+                        return;
+                    },
+                };
+            },
+            _ => paths.push(arg),
+        };
+    };
+    if paths.len() < 2 {
+        error!("usage: tournament [--gauntlet] [--depth n] [--rounds n] [--openings file] [--broadcast file] [--dashboard address] <weights> <weights>...");
+        return;
+    };
+
+    let mut entrants = Vec::new();
+    for path in paths {
+        match Engine::load(&path) {
+            Ok(engine) => entrants.push(Entrant { name: path, engine }),
+            Err(err) => {
+                error!(path, %err, "couldn't load the weights");
+                return;
+            },
+        };
+    };
+
+    let dashboard = match dashboard.map(|addr| (Dashboard::serve(&addr, "tournament"), addr)) {
+        Some((Ok(dashboard), addr)) => {
+            info!(addr, "dashboard up");
+            dashboard.follow(options.broadcast.get_or_insert_with(Default::default));
+            Some(dashboard)
+        },
+        Some((Err(err), addr)) => {
+            error!(addr, %err, "couldn't start the dashboard");
+            return;
+        },
+        None => None,
+    };
+
+    let spectator = match broadcast {
+        Some(path) => match std::fs::File::create(&path) {
+            Ok(mut file) => {
+                let events = options.broadcast.get_or_insert_with(Default::default).subscribe(None, BROADCAST_BUFFER);
+                Some(std::thread::spawn(move || {
+                    for event in events {
+                        if let Err(err) = writeln!(file, "{}", event.to_json()) {
+                            error!(path, %err, "couldn't broadcast");
+                            break;
+                        };
+                    };
+                }))
+            },
+            Err(err) => {
+                error!(path, %err, "couldn't create the broadcast");
+                return;
+            },
+        },
+        None => None,
+    };
+
+    let tournament = Tournament::run(&entrants, &options);
+    // the spectator is done once the broadcast is gone
+    drop(options);
+    if let Some(spectator) = spectator {
+        let _ = spectator.join();
+    };
+    if let Err(err) = std::fs::write(PGN_PATH, tournament.pgn()) {
+        error!(path = PGN_PATH, %err, "couldn't save the games");
+    };
+
+    println!("{} games", tournament.games.len());
+    for (rank, (entrant, estimate)) in tournament.standings().into_iter().enumerate() {
+        println!("{:>3}. {:<30} {estimate}", rank + 1, tournament.names[entrant]);
+    };
+    if entrants.len() == 2 {
+        let estimate = tournament.estimate(0, 1);
+        let verdict = match estimate.sprt(SPRT_ELO.0, SPRT_ELO.1) {
+            Sprt::Accept => "the first is stronger",
+            Sprt::Reject => "the first isn't stronger",
+            Sprt::Continue => "not enough games to tell",
+        };
+        info!(llr = estimate.llr(SPRT_ELO.0, SPRT_ELO.1), elo0 = SPRT_ELO.0, elo1 = SPRT_ELO.1, verdict, "sprt");
+    };
+
+    if dashboard.is_some() {
+        info!("the tournament is over, the dashboard stays up until interrupted");
+        loop {
+            std::thread::park();
+        };
+    };
+}
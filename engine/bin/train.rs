@@ -0,0 +1,55 @@
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use tracing::{error, info};
+use engine::Engine;
+use engine::options::DEFAULT_WEIGHTS as WEIGHTS_PATH;
+use engine::train::{read_pgn, TrainOptions};
+
+
+// how much of an eval annotation goes into a target, the rest comes from the game's result
+const EVAL_WEIGHT: f32 = 0.75;
+// every n-th sample is kept aside to see whether the net generalizes or just memorizes
+const VALIDATION_EVERY: usize = 10;
+
+
+// `train <pgn> [epochs] [seed]`, continues from engine.rew if there is one and saves after every epoch
+fn main() {
+    engine::logging::init("info");
+    let mut args = std::env::args().skip(1);
+    let Some(path) = args.next() else {
+        error!("usage: train <pgn> [epochs] [seed]");
+        return;
+    };
+    let epochs = args.next().map(|raw| raw.parse().expect("the epochs have to be a number")).unwrap_or(10);
+    let seed = args.next().map(|raw| raw.parse().expect("the seed has to be a number")).unwrap_or(0);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let raw = match std::fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(err) => {
+            error!(path, %err, "couldn't read the games");
+            return;
+        },
+    };
+    let (samples, summary) = read_pgn(&raw, EVAL_WEIGHT);
+    info!(games = summary.games, positions = summary.positions, unfinished = summary.unfinished, cut_short = summary.cut_short, "read the games");
+
+    let (mut training, validation) = samples.into_iter().enumerate().fold((Vec::new(), Vec::new()), |(mut training, mut validation), (i, sample)| {
+        if i % VALIDATION_EVERY == 0 { validation.push(sample) } else { training.push(sample) };
+        (training, validation)
+    });
+    if training.is_empty() {
+        error!("there's nothing to train on");
+        return;
+    };
+
+    let mut engine = Engine::load(WEIGHTS_PATH).unwrap_or_else(|_| Engine::new_random_with(&mut rng));
+    info!(validation_loss = engine.loss(&validation), "starting");
+    for epoch in 1..=epochs {
+        let loss = engine.train_epoch(&mut training, TrainOptions::default(), &mut rng);
+        info!(epoch, loss, validation_loss = engine.loss(&validation), "epoch done");
+        if let Err(err) = engine.save(WEIGHTS_PATH) {
+            error!(path = WEIGHTS_PATH, %err, "couldn't save the weights");
+        };
+    };
+}
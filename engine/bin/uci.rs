@@ -0,0 +1,181 @@
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+use std::time::Duration;
+use engine::Engine;
+use engine::options::UciOptions;
+use engine::search::{search_parallel, SearchHandle, SearchInfo, SearchLimits, SearchResult, Score};
+use engine::time::{TimeControl, TimeManager};
+use engine::tt::TranspositionTable;
+use ress::{Board, PlayerMove};
+use ress::clock::{render_clocks, ClockStyle};
+use ress::piece::Color;
+
+fn set_position(args: &[&str]) -> Option<Board> {
+    let (mut board, rest) = match *args.first()? {
+        "startpos" => (Board::default(), &args[1..]),
+        "fen" => {
+            let fen_end = args.iter().position(|arg| *arg == "moves").unwrap_or(args.len());
+            (Board::from_fen(&args[1..fen_end].join(" "))?, &args[fen_end..])
+        },
+        _ => None?,
+    };
+
+    if rest.first() == Some(&"moves") {
+        // guis claim repetition draws themselves, playing on declines them
+        let moves = rest[1..].iter().map(|raw| PlayerMove::parse(raw)).collect::<Option<Vec<_>>>()?;
+        board = board.apply_moves(&moves).ok()?;
+    };
+
+    Some(board)
+}
+
+fn parse_go(args: &[&str], options: &UciOptions) -> (SearchLimits, TimeControl) {
+    let mut limits = SearchLimits { multi_pv: options.multi_pv(), ..Default::default() };
+    let mut control = TimeControl { move_overhead: Some(options.move_overhead()), ..Default::default() };
+    let mut depth = None;
+
+    let millis = |raw: Option<&&str>| raw.and_then(|raw| raw.parse::<i64>().ok()).map(|ms| Duration::from_millis(ms.max(0) as u64));
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "depth" => depth = args.next().and_then(|raw| raw.parse().ok()),
+            "nodes" => limits.nodes = args.next().and_then(|raw| raw.parse().ok()),
+            "movetime" => control.movetime = millis(args.next()),
+            "wtime" => control.wtime = millis(args.next()),
+            "btime" => control.btime = millis(args.next()),
+            "winc" => control.winc = millis(args.next()),
+            "binc" => control.binc = millis(args.next()),
+            "movestogo" => control.moves_to_go = args.next().and_then(|raw| raw.parse().ok()),
+            // we don't advertise pondering, but if a gui does it anyway it's a search until `stop`
+            "infinite" | "ponder" => control.infinite = true,
+            _ => {},
+        };
+    };
+
+    // with a clock to watch the search deepens for as long as it's allowed to
+    let timed = control.infinite || control.movetime.is_some() || control.wtime.is_some() || control.btime.is_some();
+    limits.depth = depth.unwrap_or(if timed { u8::MAX } else { limits.depth });
+    (limits, control)
+}
+
+fn format_score(score: i32) -> String {
+    match Score::of(score) {
+        Score::Mate(moves) => format!("mate {moves}"),
+        Score::Centipawns(score) => format!("cp {score}"),
+    }
+}
+
+// the pv is played out for the side to move at each ply
+fn report_iteration(info: &SearchInfo, board: &Board) {
+    let mut line = format!("info depth {} multipv {} score {} nodes {}", info.depth, info.multipv, format_score(info.score), info.nodes);
+    if let (Some(nps), Some(time)) = (info.nps(), info.time) {
+        line.push_str(&format!(" nps {nps} time {}", time.as_millis()));
+    };
+    if !info.pv.is_empty() {
+        let mut color = board.move_color;
+        line.push_str(" pv");
+        for r#move in &info.pv {
+            line.push_str(&format!(" {}", r#move.to_long_algebraic(color)));
+            color = color.the_other();
+        };
+    };
+    println!("{line}");
+    std::io::stdout().flush().unwrap();
+}
+
+fn report(result: &SearchResult, time: &TimeManager, board: &Board) {
+    // the iterations already told everything but the nodes of the helper threads
+    println!("info nodes {} time {}", result.nodes, time.elapsed().as_millis());
+
+    match result.best_move {
+        Some(r#move) => println!("bestmove {}", r#move.to_long_algebraic(board.move_color)),
+        None => println!("bestmove 0000"),
+    };
+    std::io::stdout().flush().unwrap();
+}
+
+// dropping the running search stops it
+fn finish(search: &mut Option<SearchHandle>) {
+    search.take();
+}
+
+fn main() {
+    engine::logging::init("warn");
+    let mut stdout = std::io::stdout();
+    let mut engine = None;
+    let mut board = Board::default();
+    let mut search = None;
+    let options = UciOptions::new();
+    // kept between moves, that's most of its worth
+    let mut tt = Arc::new(TranspositionTable::with_megabytes(options.hash_megabytes()));
+
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let args = line.split_whitespace().collect::<Vec<_>>();
+
+        match args.first() {
+            Some(&"uci") => {
+                println!("id name ress");
+                println!("id author Spaceginner");
+                for declaration in options.declarations() {
+                    println!("{declaration}");
+                };
+                println!("uciok");
+            },
+            Some(&"isready") => {
+                if engine.is_none() {
+                    let weights = options.weights();
+                    engine = Some(Engine::load(&weights).unwrap_or_else(|err| {
+                        println!("info string couldn't load {weights} ({err}), playing with random weights");
+                        Engine::new_random()
+                    }));
+                };
+                println!("readyok");
+            },
+            Some(&"setoption") => match options.setoption(&args[1..]) {
+                // a running search keeps the table it has
+                Ok("Hash") => tt = Arc::new(TranspositionTable::with_megabytes(options.hash_megabytes())),
+                // loaded on the next `isready` or `go`
+                Ok("WeightsFile") => engine = None,
+                Ok(_) => {},
+                Err(err) => println!("info string {err}"),
+            },
+            Some(&"ucinewgame") => {
+                finish(&mut search);
+                board = Board::default();
+                tt = Arc::new(TranspositionTable::with_megabytes(options.hash_megabytes()));
+            },
+            Some(&"position") => match set_position(&args[1..]) {
+                Some(new_board) => board = new_board,
+                None => println!("info string invalid position: {line}"),
+            },
+            Some(&"go") => {
+                finish(&mut search);
+                let engine = engine.get_or_insert_with(|| Engine::load(&options.weights()).unwrap_or_else(|_| Engine::new_random())).clone();
+                let (limits, control) = parse_go(&args[1..], &options);
+                let threads = options.threads();
+                if let (Some(wtime), Some(btime)) = (control.wtime, control.btime) {
+                    let increment = match board.move_color { Color::White => control.winc, Color::Black => control.binc };
+                    println!("info string clock {}", render_clocks(wtime, btime, Some(board.move_color), increment, ClockStyle::Standard));
+                };
+                let time = TimeManager::new(&control, board.move_color);
+                let (board, tt) = (board.clone(), Arc::clone(&tt));
+                search = Some(SearchHandle::spawn(move |stop| {
+                    let result = search_parallel(&board, &engine, limits, &(&time, stop), &tt, &|info: &SearchInfo| report_iteration(info, &board), threads);
+                    report(&result, &time, &board);
+                    result
+                }));
+            },
+            Some(&"stop") => finish(&mut search),
+            Some(&"quit") => {
+                finish(&mut search);
+                break;
+            },
+            _ => {},
+        };
+
+        stdout.flush().unwrap();
+    };
+}
@@ -0,0 +1,95 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use ress::position::Position;
+use crate::{Engine, Weights};
+use crate::features::Features;
+
+// updates pile up rounding errors, every so often the sums are started over
+const REFRESH_EVERY: u32 = 1024;
+
+// the first layer's sums (offsets included) for one position. a move only changes a few inputs,
+// so instead of multiplying the whole layer again the columns of the ones that changed are added or subtracted.
+#[derive(Clone)]
+pub struct Accumulator {
+    // what it was computed with, so a reload in between can't mix two networks
+    pub(crate) weights: Arc<Weights>,
+    // ascending by index
+    pub(crate) inputs: Vec<(usize, f32)>,
+    pub(crate) sums: Vec<f32>,
+    updates: u32,
+}
+
+impl Weights {
+    fn add_input(&self, sums: &mut [f32], index: usize, value: f32) {
+        let column = &self.coefs[index*sums.len()..(index + 1)*sums.len()];
+        for (sum, weight) in sums.iter_mut().zip(column) {
+            *sum += value*weight;
+        };
+    }
+}
+
+impl Accumulator {
+    pub(crate) fn from_inputs(weights: Arc<Weights>, inputs: Vec<(usize, f32)>) -> Self {
+        let layers = weights.architecture.layers();
+        // a network without hidden layers has no offsets at all
+        let mut sums = match layers.len() {
+            2 => alloc::vec![0.0; layers[1]],
+            _ => weights.offsets[..layers[1]].to_vec(),
+        };
+        for (index, value) in &inputs {
+            weights.add_input(&mut sums, *index, *value);
+        };
+        Self { weights, inputs, sums, updates: 0 }
+    }
+
+    pub fn new(engine: &Engine, position: &Position) -> Self {
+        let weights = engine.weights();
+        let inputs = Features::of(position).inputs(weights.architecture.encoding());
+        Self::from_inputs(weights, inputs)
+    }
+
+    // brings it to another position, the closer that is (e.g. a move away) the cheaper
+    pub fn update(&mut self, position: &Position) {
+        let inputs = Features::of(position).inputs(self.weights.architecture.encoding());
+        self.updates += 1;
+        if self.updates >= REFRESH_EVERY {
+            *self = Self::from_inputs(Arc::clone(&self.weights), inputs);
+            return;
+        };
+
+        // both lists are ascending, walking them side by side finds every input that changed
+        let (mut old, mut new) = (self.inputs.iter().copied().peekable(), inputs.iter().copied().peekable());
+        loop {
+            let (index, change) = match (old.peek().copied(), new.peek().copied()) {
+                (None, None) => break,
+                (Some((o, old_value)), Some((n, new_value))) if o == n => {
+                    old.next();
+                    new.next();
+                    (o, new_value - old_value)
+                },
+                (Some((o, old_value)), Some((n, _))) if o < n => {
+                    old.next();
+                    (o, -old_value)
+                },
+                (Some((o, old_value)), None) => {
+                    old.next();
+                    (o, -old_value)
+                },
+                (_, Some((n, new_value))) => {
+                    new.next();
+                    (n, new_value)
+                },
+            };
+            if change != 0.0 {
+                self.weights.add_input(&mut self.sums, index, change);
+            };
+        };
+        self.inputs = inputs;
+    }
+
+    // the same as `Engine::evaluate` for the position it's at, unless that's over
+    pub fn evaluate(&self) -> f32 {
+        let state = Engine::propagate(&self.weights, core::slice::from_ref(self), true).swap_remove(0);
+        state[state.len() - 1]
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::{Arc, Mutex, PoisonError};
+use ress::Board;
+use ress::coordinate::Move;
+use crate::Engine;
+use crate::search::{search_parallel, SearchHandle, SearchInfo, SearchLimits, SearchResult};
+use crate::tt::TranspositionTable;
+
+// an engine looking at one position for as long as it's let to, e.g. for a gui's analysis pane.
+// what every finished depth concluded is kept until the position changes, so it can be shown how settled the engine is.
+pub struct AnalysisSession {
+    engine: Engine,
+    board: Board,
+    // kept between searches of the same position, the next one picks up where the last stopped
+    tt: Arc<TranspositionTable>,
+    history: Arc<Mutex<Vec<SearchInfo>>>,
+    search: Option<SearchHandle>,
+    pub threads: usize,
+}
+
+impl AnalysisSession {
+    pub fn new(engine: Engine, board: &Board) -> Self {
+        Self {
+            engine,
+            board: board.clone(),
+            tt: Arc::new(TranspositionTable::default()),
+            history: Arc::default(),
+            search: None,
+            threads: 1,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    // stops the search and forgets everything about the last position, unless it's the same one
+    pub fn set_position(&mut self, board: &Board) {
+        self.stop();
+        if board.to_fen() != self.board.to_fen() {
+            self.board = board.clone();
+            self.clear();
+        };
+    }
+
+    // searches in the background until `limits` are reached or it's stopped, `u8::MAX` deep for an infinite analysis.
+    // the history is started over, a new search goes through the shallow depths again.
+    pub fn start(&mut self, limits: SearchLimits) {
+        self.stop();
+        self.history.lock().unwrap_or_else(PoisonError::into_inner).clear();
+
+        let (board, engine, tt, history, threads) = (self.board.clone(), self.engine.clone(), Arc::clone(&self.tt), Arc::clone(&self.history), self.threads);
+        self.search = Some(SearchHandle::spawn(move |stop| {
+            let record = |info: &SearchInfo| history.lock().unwrap_or_else(PoisonError::into_inner).push(info.clone());
+            search_parallel(&board, &engine, limits, stop, &tt, &record, threads)
+        }));
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.search.as_ref().is_some_and(|search| !search.is_finished())
+    }
+
+    // ends the search and hands back what it found, none if nothing was searching
+    pub fn stop(&mut self) -> Option<SearchResult> {
+        let search = self.search.take()?;
+        search.stop();
+        search.join()
+    }
+
+    // waits for the search to reach its limits, never returns for an infinite one
+    pub fn wait(&mut self) -> Option<SearchResult> {
+        self.search.take()?.join()
+    }
+
+    // drops the history and the table, e.g. for a new game
+    pub fn clear(&mut self) {
+        self.stop();
+        self.history.lock().unwrap_or_else(PoisonError::into_inner).clear();
+        self.tt = Arc::new(TranspositionTable::default());
+    }
+
+    // every finished depth of the current search, shallowest first
+    pub fn history(&self) -> Vec<SearchInfo> {
+        self.history.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    // the deepest finished depth so far
+    pub fn best(&self) -> Option<SearchInfo> {
+        self.history.lock().unwrap_or_else(PoisonError::into_inner).last().cloned()
+    }
+
+    // the depths whose best move wasn't the one before them, with the move it changed to
+    pub fn changes(&self) -> Vec<(u8, Move)> {
+        self.history.lock().unwrap_or_else(PoisonError::into_inner)
+            .windows(2)
+            .filter_map(|pair| Some((pair[1].depth, pair[1].best_move()?)).filter(|_| pair[0].best_move() != pair[1].best_move()))
+            .collect()
+    }
+
+    // the most the score moved between two consecutive depths among the last `depths`,
+    // a big one means the evaluation isn't to be trusted yet
+    pub fn swing(&self, depths: usize) -> i32 {
+        let history = self.history.lock().unwrap_or_else(PoisonError::into_inner);
+        history[history.len().saturating_sub(depths)..]
+            .windows(2)
+            .map(|pair| (pair[1].score - pair[0].score).abs())
+            .max()
+            .unwrap_or(0)
+    }
+}
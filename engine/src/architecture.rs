@@ -0,0 +1,120 @@
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use crate::features::FEATURES;
+use crate::policy::POLICY_MOVES;
+
+// the board encoding from before the features: a piece id per square, the castling rights and the stale plies
+pub const LEGACY_INPUTS: usize = 69;
+// the square policy reads this many neurons from the start of the state
+const SQUARE_NEURONS: usize = 128;
+
+// how a position is fed to the first layer, told apart by its size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    // LEGACY_INPUTS floats, still loaded so old weights keep working
+    Legacy,
+    // FEATURES sparse (piece, square, king bucket) inputs, see `Features`
+    Features,
+}
+
+// how the moves are scored, told apart by the output's size. the last output is the value either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    // a move is worth its from square's neuron times its to square's one, from the start of the state
+    // (so the legacy inputs for those networks), and the value also picks the promotion piece
+    Squares,
+    // an output per move in `policy::move_index`, plus the value
+    Planes,
+}
+
+// layer sizes from the input to the output. every hidden layer has an offset per neuron, the output has none.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Architecture {
+    layers: Vec<usize>,
+}
+
+impl Default for Architecture {
+    fn default() -> Self {
+        Self { layers: alloc::vec![FEATURES, 32, 32, POLICY_MOVES + 1] }
+    }
+}
+
+impl Architecture {
+    // the one every .rew file had before the architecture was stored in it
+    pub fn legacy() -> Self {
+        Self { layers: alloc::vec![LEGACY_INPUTS, 120, 120, 60, 30, 30, 30, 30, 129] }
+    }
+
+    pub fn new(layers: Vec<usize>) -> Option<Self> {
+        let architecture = Self { layers };
+        let valid = architecture.layers.len() >= 2 && [LEGACY_INPUTS, FEATURES].contains(&architecture.layers[0]) &&
+            architecture.layers.iter().all(|size| *size > 0) &&
+            (architecture.policy() == Policy::Planes || architecture.state_neurons() > SQUARE_NEURONS);
+        valid.then_some(architecture)
+    }
+
+    // comma separated sizes, e.g. `3077,64,32,4673`. the first one can also be `features` or `legacy`, the last one `planes`.
+    pub fn parse(raw: &str) -> Option<Self> {
+        Self::new(raw.split(',').map(|size| match size.trim() {
+            "features" => Some(FEATURES),
+            "legacy" => Some(LEGACY_INPUTS),
+            "planes" => Some(POLICY_MOVES + 1),
+            size => size.parse().ok(),
+        }).collect::<Option<_>>()?)
+    }
+
+    pub fn layers(&self) -> &[usize] {
+        &self.layers
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        match self.layers[0] {
+            LEGACY_INPUTS => Encoding::Legacy,
+            _ => Encoding::Features,
+        }
+    }
+
+    pub fn policy(&self) -> Policy {
+        match self.layers[self.layers.len() - 1] {
+            size if size == POLICY_MOVES + 1 => Policy::Planes,
+            _ => Policy::Squares,
+        }
+    }
+
+    pub fn coefs(&self) -> usize {
+        self.layers.windows(2).map(|dims| dims[0]*dims[1]).sum()
+    }
+
+    pub fn offsets(&self) -> usize {
+        self.layers[1..self.layers.len() - 1].iter().sum()
+    }
+
+    pub fn neurons(&self) -> usize {
+        self.layers.iter().sum()
+    }
+
+    // how many of the inputs a forward pass keeps at the start of its state. the legacy ones are kept
+    // since that's where its move choice always read from, the sparse features are too many to be worth it.
+    pub(crate) fn state_inputs(&self) -> usize {
+        match self.encoding() {
+            Encoding::Legacy => LEGACY_INPUTS,
+            Encoding::Features => 0,
+        }
+    }
+
+    pub(crate) fn state_neurons(&self) -> usize {
+        self.neurons() - self.layers[0] + self.state_inputs()
+    }
+}
+
+impl Display for Architecture {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for (i, size) in self.layers.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            };
+            write!(f, "{size}")?;
+        };
+        Ok(())
+    }
+}
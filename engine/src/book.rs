@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Write};
+use ress::{Board, PlayerMove};
+use ress::coordinate::Move;
+use ress::piece::Color;
+use ress::zobrist::PositionKey;
+use crate::search::table_key;
+
+// an opening book is a text file with a position per line: the first four fields of its fen,
+// then the moves to play there in san with an optional weight, e.g. `... w KQkq - e4:3 d4:2 c4`.
+// empty lines and ones starting with `#` are skipped, a position on several lines gets all of their moves.
+//
+// what the engine learned from its games is kept next to the book, in `<book>.learn`, a line per move:
+// the position's zobrist key (what's in hand included), the move in internal notation, then the wins, draws and losses after playing it.
+
+#[derive(Debug)]
+pub enum BookError {
+    Io(std::io::Error),
+    // the line, counting from 1
+    Position(usize),
+    // the line and the move that isn't legal there
+    Move(usize, String),
+}
+
+impl Display for BookError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Position(line) => write!(f, "line {line}: that's not a position"),
+            Self::Move(line, r#move) => write!(f, "line {line}: {move} can't be played there"),
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+impl From<std::io::Error> for BookError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+// how the games went after the engine played a book move, from its point of view
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Learned {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Learned {
+    // what the book's weight is multiplied by: 1 for a move without games, towards 0 for one
+    // that keeps losing and towards 2 for one that keeps winning
+    pub fn factor(&self) -> f64 {
+        let games = (self.wins + self.draws + self.losses) as f64;
+        2.0*(self.wins as f64 + self.draws as f64/2.0 + 1.0)/(games + 2.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookMove {
+    pub r#move: Move,
+    pub weight: u32,
+    pub learned: Learned,
+}
+
+impl BookMove {
+    pub fn learned_weight(&self) -> f64 {
+        self.weight as f64*self.learned.factor()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Book {
+    positions: HashMap<PositionKey, Vec<BookMove>>,
+}
+
+impl Book {
+    pub fn parse(raw: &str) -> Result<Self, BookError> {
+        let mut book = Self::default();
+        for (i, line) in raw.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            };
+
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            if fields.len() < 4 {
+                return Err(BookError::Position(i));
+            };
+            let board = Board::from_fen(&format!("{} 0 1", fields[..4].join(" "))).ok_or(BookError::Position(i))?;
+            for raw in &fields[4..] {
+                let (san, weight) = match raw.split_once(':') {
+                    Some((san, weight)) => (san, weight.parse().map_err(|_| BookError::Move(i, raw.to_string()))?),
+                    None => (*raw, 1),
+                };
+                let r#move = board.parse_san(san).ok_or_else(|| BookError::Move(i, san.to_string()))?;
+                book.add(&board, r#move, weight);
+            };
+        };
+        Ok(book)
+    }
+
+    // the book along with what was learned with it so far, if anything
+    pub fn load(path: &str) -> Result<Self, BookError> {
+        let mut book = Self::parse(&std::fs::read_to_string(path)?)?;
+        match std::fs::read_to_string(Self::learning_path(path)) {
+            Ok(raw) => book.apply_learning(&raw),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {},
+            Err(err) => return Err(err.into()),
+        };
+        Ok(book)
+    }
+
+    pub fn learning_path(book: &str) -> String {
+        format!("{book}.learn")
+    }
+
+    // adds the weight to the move's if it's already in the book
+    pub fn add(&mut self, board: &Board, r#move: Move, weight: u32) {
+        let moves = self.positions.entry(table_key(board)).or_default();
+        match moves.iter_mut().find(|entry| entry.r#move == r#move) {
+            Some(entry) => entry.weight += weight,
+            None => moves.push(BookMove { r#move, weight, learned: Learned::default() }),
+        };
+    }
+
+    pub fn moves(&self, board: &Board) -> &[BookMove] {
+        self.positions.get(&table_key(board)).map_or(&[], Vec::as_slice)
+    }
+
+    // picks a move by the learned weights, `roll` is uniform in 0..1. none once out of book,
+    // or if every move there has lost enough to not be played anymore.
+    pub fn choose(&self, board: &Board, roll: f32) -> Option<Move> {
+        let moves = self.moves(board);
+        let total = moves.iter().map(BookMove::learned_weight).sum::<f64>();
+        if total <= 0.0 {
+            return None;
+        };
+
+        let mut left = roll as f64*total;
+        moves.iter()
+            .filter(|entry| entry.learned_weight() > 0.0)
+            .find(|entry| {
+                left -= entry.learned_weight();
+                left < 0.0
+            })
+            .or_else(|| moves.iter().rfind(|entry| entry.learned_weight() > 0.0))
+            .map(|entry| entry.r#move)
+    }
+
+    // credits every book move `color` played in the game with its result, the winner or none for a draw.
+    // returns whether any was, i.e. whether there's anything new to save.
+    pub fn learn(&mut self, start: &Board, moves: &[Move], color: Color, result: Option<Color>) -> bool {
+        let mut board = start.clone();
+        let mut learned = false;
+        for r#move in moves {
+            if board.move_color == color {
+                let entry = self.positions.get_mut(&table_key(&board))
+                    .and_then(|entries| entries.iter_mut().find(|entry| entry.r#move == *r#move));
+                if let Some(entry) = entry {
+                    match result {
+                        Some(won) if won == color => entry.learned.wins += 1,
+                        Some(_) => entry.learned.losses += 1,
+                        None => entry.learned.draws += 1,
+                    };
+                    learned = true;
+                };
+            };
+
+            if board.play_move(PlayerMove::Internal(*r#move)).is_err() {
+                break;
+            };
+        };
+        learned
+    }
+
+    // lines for moves that aren't in the book (anymore) are ignored
+    pub fn apply_learning(&mut self, raw: &str) {
+        for line in raw.lines() {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let [hash, r#move, wins, draws, losses] = fields[..] else {
+                continue;
+            };
+            let (Ok(hash), Ok(wins), Ok(draws), Ok(losses)) = (u64::from_str_radix(hash, 16), wins.parse(), draws.parse(), losses.parse()) else {
+                continue;
+            };
+            let entry = self.positions.get_mut(&PositionKey(hash))
+                .and_then(|entries| entries.iter_mut().find(|entry| entry.r#move.to_string() == r#move));
+            if let Some(entry) = entry {
+                entry.learned = Learned { wins, draws, losses };
+            };
+        };
+    }
+
+    pub fn learning(&self) -> String {
+        let mut raw = String::new();
+        for (PositionKey(hash), entries) in &self.positions {
+            for entry in entries.iter().filter(|entry| entry.learned != Learned::default()) {
+                let Learned { wins, draws, losses } = entry.learned;
+                writeln!(raw, "{hash:016x} {} {wins} {draws} {losses}", entry.r#move).unwrap();
+            };
+        };
+        raw
+    }
+
+    // `path` is the book's, the learning goes next to it
+    pub fn save_learning(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(Self::learning_path(path), self.learning())
+    }
+}
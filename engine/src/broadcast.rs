@@ -0,0 +1,113 @@
+use std::fmt::Write;
+use std::sync::{Mutex, PoisonError};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+
+// live games for spectators: the tournament sends every game's start, moves and end here, and whoever
+// subscribed gets them in order. there's no server in here, a front-end forwards what it receives.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    // the game is its index in the schedule, which it keeps in the finished tournament
+    Started { game: usize, white: String, black: String, fen: String },
+    // the position is the one after the move
+    Move { game: usize, ply: usize, san: String, fen: String },
+    Finished { game: usize, result: &'static str, termination: &'static str },
+}
+
+impl GameEvent {
+    pub fn game(&self) -> usize {
+        match self {
+            Self::Started { game, .. } | Self::Move { game, .. } | Self::Finished { game, .. } => *game,
+        }
+    }
+
+    // one object per event, e.g. `{"event":"move","game":3,"ply":12,"san":"Nf3","fen":"..."}`
+    pub fn to_json(&self) -> String {
+        match self {
+            Self::Started { game, white, black, fen } => format!(r#"{{"event":"started","game":{game},"white":{},"black":{},"fen":{}}}"#, json_string(white), json_string(black), json_string(fen)),
+            Self::Move { game, ply, san, fen } => format!(r#"{{"event":"move","game":{game},"ply":{ply},"san":{},"fen":{}}}"#, json_string(san), json_string(fen)),
+            Self::Finished { game, result, termination } => format!(r#"{{"event":"finished","game":{game},"result":"{result}","termination":"{termination}"}}"#),
+        }
+    }
+
+    // what it adds to the game's pgn: its tags when it starts, the move with its number, and the result
+    pub fn to_pgn(&self) -> String {
+        match self {
+            Self::Started { white, black, fen, .. } => format!("[White \"{white}\"]\n[Black \"{black}\"]\n[SetUp \"1\"]\n[FEN \"{fen}\"]\n\n"),
+            Self::Move { ply, san, fen, .. } => {
+                // the fen is after the move, so white just moved when black is to move
+                let white_moved = fen.split_whitespace().nth(1) == Some("b");
+                let number = fen.split_whitespace().nth(5).and_then(|raw| raw.parse::<usize>().ok()).unwrap_or(1);
+                match (white_moved, *ply == 0) {
+                    (true, _) => format!("{number}. {san} "),
+                    (false, true) => format!("{}... {san} ", number.saturating_sub(1)),
+                    (false, false) => format!("{san} "),
+                }
+            },
+            Self::Finished { result, .. } => format!("{result}\n\n"),
+        }
+    }
+}
+
+pub(crate) fn json_string(raw: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in raw.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        };
+    };
+    escaped.push('"');
+    escaped
+}
+
+#[derive(Debug)]
+struct Subscriber {
+    // none for every game
+    game: Option<usize>,
+    sender: SyncSender<GameEvent>,
+}
+
+// sends every event to every subscriber without ever waiting on one. each has a queue of its own,
+// and one that lets it fill up is dropped (its receiver disconnects), so a slow spectator can't slow the games down.
+#[derive(Debug, Default)]
+pub struct Broadcast {
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+impl Broadcast {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the events of one game, or of all of them, from now on. `capacity` is how many can wait to be received.
+    pub fn subscribe(&self, game: Option<usize>, capacity: usize) -> Receiver<GameEvent> {
+        let (sender, receiver) = sync_channel(capacity.max(1));
+        self.subscribers.lock().unwrap_or_else(PoisonError::into_inner).push(Subscriber { game, sender });
+        receiver
+    }
+
+    pub fn subscribers(&self) -> usize {
+        self.subscribers.lock().unwrap_or_else(PoisonError::into_inner).len()
+    }
+
+    pub fn send(&self, event: GameEvent) {
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(PoisonError::into_inner);
+        subscribers.retain(|subscriber| {
+            if subscriber.game.is_some_and(|game| game != event.game()) {
+                return true;
+            };
+            match subscriber.sender.try_send(event.clone()) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(game = subscriber.game, "a spectator fell behind and was dropped");
+                    false
+                },
+                Err(TrySendError::Disconnected(_)) => false,
+            }
+        });
+    }
+}
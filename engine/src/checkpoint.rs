@@ -0,0 +1,104 @@
+use crate::Engine;
+use crate::format::{crc32, WeightsError};
+
+// an evolve checkpoint is, all little endian:
+//   MAGIC, the format version and a crc-32 of everything after it as u32s,
+//   the epoch as a u32, the seed the rng continues from as a u64, whether the run started from random weights as a u8,
+//   the best engine, then the number of engines in the population as a u32 and each one's score as an i32 followed by it.
+// every engine is its size in bytes as a u32 followed by it as a .rew file.
+const MAGIC: [u8; 4] = *b"rec\0";
+pub const VERSION: u32 = 1;
+
+// everything an evolution run needs to go on exactly like it would have without stopping
+#[derive(Clone)]
+pub struct Checkpoint {
+    // the last finished one
+    pub epoch: u32,
+    // the rng gets reseeded from itself after every epoch, so this is all of its state
+    pub seed: u64,
+    // a run from random weights never mutates, every pool is fresh random engines
+    pub random: bool,
+    pub best: Engine,
+    // the last hyper pool with the scores it got battling itself, the best one included
+    pub population: Vec<(Engine, i32)>,
+}
+
+fn push_engine(bytes: &mut Vec<u8>, engine: &Engine) {
+    let weights = engine.to_bytes();
+    bytes.extend((weights.len() as u32).to_le_bytes());
+    bytes.extend(weights);
+}
+
+// reads the fields one at a time, anything cut short is an unknown format
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], WeightsError> {
+        if self.0.len() < count {
+            return Err(WeightsError::UnknownFormat);
+        };
+        let (taken, rest) = self.0.split_at(count);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32, WeightsError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn engine(&mut self) -> Result<Engine, WeightsError> {
+        let size = self.u32()? as usize;
+        Engine::from_bytes(self.take(size)?)
+    }
+}
+
+impl Checkpoint {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend(self.epoch.to_le_bytes());
+        data.extend(self.seed.to_le_bytes());
+        data.push(self.random as u8);
+        push_engine(&mut data, &self.best);
+        data.extend((self.population.len() as u32).to_le_bytes());
+        for (engine, score) in &self.population {
+            data.extend(score.to_le_bytes());
+            push_engine(&mut data, engine);
+        };
+
+        MAGIC.into_iter().chain(VERSION.to_le_bytes()).chain(crc32(&data).to_le_bytes()).chain(data).collect()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WeightsError> {
+        let mut reader = Reader(bytes.strip_prefix(&MAGIC).ok_or(WeightsError::UnknownFormat)?);
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(WeightsError::UnsupportedVersion(version));
+        };
+        let expected = reader.u32()?;
+        let found = crc32(reader.0);
+        if found != expected {
+            return Err(WeightsError::ChecksumMismatch { expected, found });
+        };
+
+        let epoch = reader.u32()?;
+        let seed = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+        let random = reader.take(1)?[0] != 0;
+        let best = reader.engine()?;
+        let population = (0..reader.u32()?).map(|_| {
+            let score = i32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+            Ok((reader.engine()?, score))
+        }).collect::<Result<_, WeightsError>>()?;
+        Ok(Self { epoch, seed, random, best, population })
+    }
+
+    // written next to it and renamed over it, so a crash while saving leaves the last checkpoint whole
+    pub fn save(&self, to: &str) -> std::io::Result<()> {
+        let temporary = format!("{to}.tmp");
+        std::fs::write(&temporary, self.to_bytes())?;
+        std::fs::rename(temporary, to)
+    }
+
+    pub fn load(from: &str) -> Result<Self, WeightsError> {
+        Self::from_bytes(&std::fs::read(from)?)
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, PoisonError};
+use crate::broadcast::{json_string, Broadcast, GameEvent};
+
+// a page to follow a long evolve or tournament run from a browser: the scores of every epoch as a graph,
+// the standings so far and the boards of the games being played. it's polled, there's nothing to set up.
+
+const PAGE: &str = include_str!("dashboard.html");
+// finished games stay on the page for a while, the newest ones
+const FINISHED_SHOWN: usize = 8;
+// how many events the dashboard can fall behind the games by before the broadcast drops it
+const EVENTS_BUFFER: usize = 1 << 16;
+
+#[derive(Debug, Clone, Default)]
+struct LiveGame {
+    white: String,
+    black: String,
+    fen: String,
+    moves: Vec<String>,
+    // none while it's being played
+    result: Option<&'static str>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    title: String,
+    // the best and the average score of every epoch
+    epochs: Vec<(u32, i32, f64)>,
+    // points and games per entrant, from the finished games
+    scores: BTreeMap<String, (f64, u32)>,
+    games: BTreeMap<usize, LiveGame>,
+}
+
+impl State {
+    fn apply(&mut self, event: GameEvent) {
+        match event {
+            GameEvent::Started { game, white, black, fen } => {
+                self.games.insert(game, LiveGame { white, black, fen, ..Default::default() });
+            },
+            GameEvent::Move { game, san, fen, .. } => if let Some(live) = self.games.get_mut(&game) {
+                live.fen = fen;
+                live.moves.push(san);
+            },
+            GameEvent::Finished { game, result, .. } => {
+                let Some(live) = self.games.get_mut(&game) else {
+                    return;
+                };
+                live.result = Some(result);
+                let points = match result {
+                    "1-0" => Some((1.0, 0.0)),
+                    "0-1" => Some((0.0, 1.0)),
+                    "1/2-1/2" => Some((0.5, 0.5)),
+                    _ => None,
+                };
+                if let Some((white, black)) = points {
+                    for (name, points) in [(live.white.clone(), white), (live.black.clone(), black)] {
+                        let score = self.scores.entry(name).or_default();
+                        *score = (score.0 + points, score.1 + 1);
+                    };
+                };
+
+                let finished = self.games.iter().filter(|(_, live)| live.result.is_some()).map(|(game, _)| *game).collect::<Vec<_>>();
+                for game in &finished[..finished.len().saturating_sub(FINISHED_SHOWN)] {
+                    self.games.remove(game);
+                };
+            },
+        };
+    }
+
+    fn to_json(&self) -> String {
+        let epochs = self.epochs.iter().map(|(epoch, best, mean)| format!(r#"{{"epoch":{epoch},"best":{best},"mean":{mean:.2}}}"#)).collect::<Vec<_>>();
+        let mut standings = self.scores.iter().collect::<Vec<_>>();
+        standings.sort_by(|(_, a), (_, b)| (b.0/b.1.max(1) as f64).total_cmp(&(a.0/a.1.max(1) as f64)));
+        let standings = standings.into_iter().map(|(name, (points, games))| format!(r#"{{"name":{},"points":{points},"games":{games}}}"#, json_string(name))).collect::<Vec<_>>();
+        let games = self.games.iter().map(|(game, live)| {
+            let mut json = format!(r#"{{"game":{game},"white":{},"black":{},"fen":{},"moves":{}"#,
+                json_string(&live.white), json_string(&live.black), json_string(&live.fen), json_string(&live.moves.join(" ")));
+            match live.result {
+                Some(result) => write!(json, r#","result":"{result}"}}"#).unwrap(),
+                None => json.push('}'),
+            };
+            json
+        }).collect::<Vec<_>>();
+
+        format!(r#"{{"title":{},"epochs":[{}],"standings":[{}],"games":[{}]}}"#, json_string(&self.title), epochs.join(","), standings.join(","), games.join(","))
+    }
+}
+
+// the page and what's on it, updated by the run while a thread serves it
+#[derive(Clone)]
+pub struct Dashboard {
+    state: Arc<Mutex<State>>,
+}
+
+impl Dashboard {
+    // binds right away, so a taken address is an error here and not a silent thread failing later
+    pub fn serve(addr: impl ToSocketAddrs, title: &str) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let dashboard = Self { state: Arc::new(Mutex::new(State { title: title.to_string(), ..Default::default() })) };
+
+        let served = dashboard.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let served = served.clone();
+                std::thread::spawn(move || {
+                    // a browser giving up mid-request isn't worth reporting
+                    let _ = served.respond(stream);
+                });
+            };
+        });
+        Ok(dashboard)
+    }
+
+    fn respond(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        let mut reader = BufReader::new(&stream);
+        let mut request = String::new();
+        reader.read_line(&mut request)?;
+        // the headers don't matter, but they have to be read before answering
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        };
+
+        let (status, kind, body) = match request.split_whitespace().nth(1) {
+            Some("/") => ("200 OK", "text/html; charset=utf-8", PAGE.to_string()),
+            Some("/state") => ("200 OK", "application/json", self.state.lock().unwrap_or_else(PoisonError::into_inner).to_json()),
+            _ => ("404 Not Found", "text/plain", String::from("not found")),
+        };
+        write!(stream, "HTTP/1.1 {status}\r\nContent-Type: {kind}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{body}", body.len())
+    }
+
+    pub fn record_epoch(&self, epoch: u32, scores: &[i32]) {
+        let best = scores.iter().copied().max().unwrap_or_default();
+        let mean = scores.iter().map(|score| *score as f64).sum::<f64>()/scores.len().max(1) as f64;
+        self.state.lock().unwrap_or_else(PoisonError::into_inner).epochs.push((epoch, best, mean));
+    }
+
+    // shows the broadcast's games as they're played, and tallies the standings from them
+    pub fn follow(&self, broadcast: &Broadcast) {
+        let events = broadcast.subscribe(None, EVENTS_BUFFER);
+        let state = Arc::clone(&self.state);
+        std::thread::spawn(move || {
+            for event in events {
+                state.lock().unwrap_or_else(PoisonError::into_inner).apply(event);
+            };
+        });
+    }
+}
@@ -0,0 +1,75 @@
+use alloc::vec::Vec;
+use ress::position::Position;
+use ress::coordinate::{Coordinate, File};
+use ress::piece::{Color, Piece, PieceKind};
+use crate::Engine;
+use crate::architecture::{Encoding, LEGACY_INPUTS};
+
+// where a piece's own king stands: on the queen or king half of the board, and at home (its first two ranks) or not
+pub const KING_BUCKETS: usize = 4;
+pub const PIECE_FEATURES: usize = KING_BUCKETS*12*64;
+// castling rights and the stale plies, the same as at the end of the legacy encoding
+pub const RIGHTS: usize = 5;
+pub const FEATURES: usize = PIECE_FEATURES + RIGHTS;
+
+fn king_bucket(king: Option<Coordinate>, color: Color) -> usize {
+    let Some(king) = king else {
+        // variants where the king is optional
+        return 0;
+    };
+    let at_home = (king.rank as i8 - color.home_rank() as i8).abs() < 2;
+    (!at_home as usize)*2 + (king.file >= File::E) as usize
+}
+
+fn piece_feature(piece: Piece, coord: Coordinate, bucket: usize) -> u16 {
+    let piece = (piece.color == Color::Black) as usize*6 + piece.kind as usize;
+    ((bucket*12 + piece)*64 + coord.rank as usize*8 + coord.file as usize) as u16
+}
+
+// a position as the network sees it. a move changes only a couple of the active features
+// (unless a king changes bucket), which is what lets `Accumulator` update instead of starting over.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Features {
+    // the active (piece, square, king bucket) features, ascending
+    pieces: Vec<u16>,
+    rights: [f32; RIGHTS],
+}
+
+impl Features {
+    pub fn of(position: &Position) -> Self {
+        let bucket = |color| king_bucket(position.pieces_of(color, PieceKind::King).next(), color);
+        let (white, black) = (bucket(Color::White), bucket(Color::Black));
+        let mut pieces = position.pieces(Color::White).map(|(piece, coord)| piece_feature(piece, coord, white))
+            .chain(position.pieces(Color::Black).map(|(piece, coord)| piece_feature(piece, coord, black)))
+            .collect::<Vec<_>>();
+        pieces.sort_unstable();
+
+        let rights = [
+            position.white_castle.0 as u8 as f32,
+            position.white_castle.1 as u8 as f32,
+            position.black_castle.0 as u8 as f32,
+            position.black_castle.1 as u8 as f32,
+            position.stale_plies as f32 / 50.0,
+        ];
+        Self { pieces, rights }
+    }
+
+    // every input that isn't zero as (index, value), ascending
+    pub(crate) fn inputs(&self, encoding: Encoding) -> Vec<(usize, f32)> {
+        let (mut inputs, rights_start) = match encoding {
+            Encoding::Features => (self.pieces.iter().map(|feature| (*feature as usize, 1.0)).collect::<Vec<_>>(), PIECE_FEATURES),
+            // every square holds its piece's id, negative for black
+            Encoding::Legacy => {
+                let mut inputs = self.pieces.iter().map(|feature| {
+                    let (piece, square) = (*feature as usize/64 % 12, *feature as usize % 64);
+                    let id = Engine::piece_id([PieceKind::Pawn, PieceKind::Knight, PieceKind::Bishop, PieceKind::Rook, PieceKind::Queen, PieceKind::King][piece % 6]);
+                    (square, if piece < 6 { id } else { -id })
+                }).collect::<Vec<_>>();
+                inputs.sort_unstable_by_key(|(square, _)| *square);
+                (inputs, LEGACY_INPUTS - RIGHTS)
+            },
+        };
+        inputs.extend(self.rights.iter().enumerate().filter(|(_, value)| **value != 0.0).map(|(i, value)| (rights_start + i, *value)));
+        inputs
+    }
+}
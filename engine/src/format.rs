@@ -0,0 +1,189 @@
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use crate::Weights;
+use crate::architecture::Architecture;
+
+// a .rew file is, all little endian:
+//   MAGIC, then the format version, the number of layers and every layer size as u32s,
+//   a crc-32 of everything after it as a u32, then every coefficient and every offset as f32s.
+// older files still load and get migrated by saving them again:
+//   version 1 is the same without the checksum,
+//   files from before the header are just the numbers for the legacy architecture.
+// weights can also be moved to an architecture with other hidden layer sizes, see `resize`.
+const MAGIC: [u8; 4] = *b"rew\0";
+pub const VERSION: u32 = 2;
+pub const LEGACY_WEIGHTS_BYTES: usize = (38250 + 420)*4;
+
+#[derive(Debug)]
+pub enum WeightsError {
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    // neither a header nor the size of a headerless file
+    UnknownFormat,
+    UnsupportedVersion(u32),
+    InvalidArchitecture,
+    // resizing can't add or remove layers, or change the encoding or the policy
+    IncompatibleArchitecture,
+    // the header promises a different number of weights than there are
+    WrongLength { expected: usize, found: usize },
+    ChecksumMismatch { expected: u32, found: u32 },
+}
+
+impl Display for WeightsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Self::Io(err) => write!(f, "{err}"),
+            Self::UnknownFormat => write!(f, "not a weights file"),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported format version {version}, the newest known is {VERSION}"),
+            Self::InvalidArchitecture => write!(f, "invalid architecture"),
+            Self::IncompatibleArchitecture => write!(f, "only the sizes of the hidden layers can change"),
+            Self::WrongLength { expected, found } => write!(f, "expected {expected} weights, found {found}"),
+            Self::ChecksumMismatch { expected, found } => write!(f, "checksum is {found:08x} instead of {expected:08x}, the file is damaged"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WeightsError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for WeightsError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { crc >> 1 ^ 0xedb88320 } else { crc >> 1 };
+            bit += 1;
+        };
+        table[i] = crc;
+        i += 1;
+    };
+    table
+};
+
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, byte| crc >> 8 ^ CRC_TABLE[((crc ^ *byte as u32) & 0xff) as usize])
+}
+
+fn floats(bytes: &[u8]) -> Vec<f32> {
+    bytes.chunks_exact(4).map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap())).collect()
+}
+
+// the file has every layer a row per neuron, in memory the first one is a column per input instead (see `Weights`)
+fn transpose(matrix: &mut [f32], rows: usize, columns: usize) {
+    let copy = matrix.to_vec();
+    for row in 0..rows {
+        for column in 0..columns {
+            matrix[column*rows + row] = copy[row*columns + column];
+        };
+    };
+}
+
+fn split(architecture: Architecture, data: &[u8]) -> Result<Weights, WeightsError> {
+    let expected = architecture.coefs() + architecture.offsets();
+    if data.len() != expected*4 {
+        return Err(WeightsError::WrongLength { expected, found: data.len()/4 });
+    };
+    let data = floats(data);
+    let (coefs, offsets) = data.split_at(architecture.coefs());
+    let mut coefs = coefs.to_vec();
+    let (inputs, neurons) = (architecture.layers()[0], architecture.layers()[1]);
+    transpose(&mut coefs[..inputs*neurons], neurons, inputs);
+    Ok(Weights { coefs, offsets: offsets.to_vec(), architecture })
+}
+
+pub(crate) fn parse(bytes: &[u8]) -> Result<Weights, WeightsError> {
+    let Some(header) = bytes.strip_prefix(&MAGIC) else {
+        // a short headerless file is hard to tell from something else entirely
+        let data = bytes.get(..LEGACY_WEIGHTS_BYTES).ok_or(WeightsError::UnknownFormat)?;
+        return split(Architecture::legacy(), data);
+    };
+
+    let mut words = header.chunks_exact(4).map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()));
+    let version = words.next().ok_or(WeightsError::UnknownFormat)?;
+    if !(1..=VERSION).contains(&version) {
+        return Err(WeightsError::UnsupportedVersion(version));
+    };
+
+    let count = words.next().ok_or(WeightsError::UnknownFormat)? as usize;
+    let layers = words.by_ref().take(count).map(|size| size as usize).collect::<Vec<_>>();
+    if layers.len() != count {
+        return Err(WeightsError::UnknownFormat);
+    };
+    let architecture = Architecture::new(layers).ok_or(WeightsError::InvalidArchitecture)?;
+
+    let checksum = if version >= 2 { Some(words.next().ok_or(WeightsError::UnknownFormat)?) } else { None };
+    let data = &header[(2 + count + checksum.is_some() as usize)*4..];
+    // a truncated file is better told apart from a damaged one
+    let weights = split(architecture, data)?;
+    if let Some(expected) = checksum {
+        let found = crc32(data);
+        if found != expected {
+            return Err(WeightsError::ChecksumMismatch { expected, found });
+        };
+    };
+    Ok(weights)
+}
+
+// the version a file was written in, 0 for a headerless one. none if it isn't a weights file at all.
+pub fn version(bytes: &[u8]) -> Option<u32> {
+    match bytes.strip_prefix(&MAGIC) {
+        Some(header) => header.get(..4).map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap())),
+        None => (bytes.len() >= LEGACY_WEIGHTS_BYTES).then_some(0),
+    }
+}
+
+// the same network with other hidden layer sizes. a neuron keeps its coefficients for the inputs that are still there:
+// new neurons and new inputs get zeros, so growing a layer computes the same as before (a new neuron gets nothing
+// and passes nothing on), while shrinking one drops its last neurons along with what they contributed.
+// the square policy reads the first neurons, so shrinking below those changes which moves it picks.
+pub(crate) fn resize(weights: &Weights, architecture: Architecture) -> Result<Weights, WeightsError> {
+    let (from, to) = (weights.architecture.layers(), architecture.layers());
+    if from.len() != to.len() || from[0] != to[0] || from[from.len() - 1] != to[to.len() - 1] {
+        return Err(WeightsError::IncompatibleArchitecture);
+    };
+
+    let mut coefs = Vec::with_capacity(architecture.coefs());
+    let mut offsets = Vec::with_capacity(architecture.offsets());
+    let (mut coefs_at, mut offsets_at) = (0, 0);
+    for (layer, (old, new)) in from.windows(2).zip(to.windows(2)).enumerate() {
+        // in memory, like everywhere else in the engine
+        let at = |input: usize, neuron: usize, dims: &[usize]| if layer == 0 { input*dims[1] + neuron } else { neuron*dims[0] + input };
+        let mut matrix = alloc::vec![0.0; new[0]*new[1]];
+        for input in 0..old[0].min(new[0]) {
+            for neuron in 0..old[1].min(new[1]) {
+                matrix[at(input, neuron, new)] = weights.coefs[coefs_at + at(input, neuron, old)];
+            };
+        };
+        coefs.extend(matrix);
+        coefs_at += old[0]*old[1];
+
+        // the output has no offsets
+        if layer + 2 < from.len() {
+            offsets.extend((0..new[1]).map(|neuron| if neuron < old[1] { weights.offsets[offsets_at + neuron] } else { 0.0 }));
+            offsets_at += old[1];
+        };
+    };
+    Ok(Weights { architecture, coefs, offsets })
+}
+
+pub(crate) fn serialize(weights: &Weights) -> Vec<u8> {
+    let layers = weights.architecture.layers();
+    let mut coefs = weights.coefs.clone();
+    transpose(&mut coefs[..layers[0]*layers[1]], layers[0], layers[1]);
+    let data = coefs.iter().chain(weights.offsets.iter()).flat_map(|w| w.to_le_bytes()).collect::<Vec<_>>();
+    let header = [VERSION, layers.len() as u32].into_iter()
+        .chain(layers.iter().map(|size| *size as u32))
+        .chain([crc32(&data)]);
+
+    MAGIC.into_iter().chain(header.flat_map(u32::to_le_bytes)).chain(data).collect()
+}
@@ -1,70 +1,179 @@
-#![feature(iter_array_chunks)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use std::io::{Read, Write};
-use rand::Rng;
-use ress::{Board, PlayerMove};
-use ress::coordinate::{Coordinate, File, Rank};
+extern crate alloc;
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+#[cfg(feature = "std")]
+use std::sync::{PoisonError, RwLock};
+use ress::{Board, GameOutcome, PlayerMove};
+use ress::coordinate::{Coordinate, Move};
 use ress::piece::{Color, PieceKind};
+use ress::position::Position;
+use accumulator::Accumulator;
+use architecture::{Architecture, Policy};
+use features::Features;
+use format::WeightsError;
+
+pub mod accumulator;
+#[cfg(feature = "std")]
+pub mod analysis;
+pub mod architecture;
+#[cfg(feature = "std")]
+pub mod book;
+#[cfg(feature = "training")]
+pub mod broadcast;
+#[cfg(feature = "training")]
+pub mod checkpoint;
+#[cfg(feature = "training")]
+pub mod dashboard;
+pub mod features;
+pub mod format;
+#[cfg(feature = "lichess")]
+pub mod lichess;
+#[cfg(feature = "training")]
+pub mod logging;
+#[cfg(feature = "training")]
+pub mod odds;
+#[cfg(feature = "std")]
+pub mod options;
+pub mod ordering;
+pub mod pst;
+#[cfg(feature = "std")]
+pub mod pool;
+pub mod policy;
+pub mod search;
+#[cfg(feature = "training")]
+pub mod suite;
+pub mod tablebase;
+#[cfg(feature = "std")]
+pub mod time;
+#[cfg(feature = "training")]
+pub mod tournament;
+pub mod tt;
+#[cfg(feature = "training")]
+pub mod train;
+#[cfg(feature = "training")]
+pub mod training;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+// every layer is a row of coefficients per neuron, except for the first one which is a column per input,
+// so an accumulator adds up contiguous columns. files keep it as rows, see `format`.
+#[derive(Clone)]
+pub(crate) struct Weights {
+    pub(crate) architecture: Architecture,
+    pub(crate) coefs: Vec<f32>,
+    pub(crate) offsets: Vec<f32>,
+}
+
+#[cfg(feature = "std")]
+fn pow9(x: f32) -> f32 {
+    9.0f32.powf(x)
+}
+
+#[cfg(not(feature = "std"))]
+fn pow9(x: f32) -> f32 {
+    libm::powf(9.0, x)
+}
+
+// every neuron of the network for one position
+#[derive(Debug, Clone)]
+pub struct Output {
+    state: Vec<f32>,
+    policy: Policy,
+    // where the output layer starts in the state
+    output_start: usize,
+}
+
+impl Output {
+    // see `Engine::evaluate`
+    pub fn value(&self) -> f32 {
+        self.state[self.state.len() - 1]
+    }
+
+    // a score for every legal move of `by`, the higher the better. none once the game is over.
+    pub fn move_scores(&self, board: &Board, by: Color) -> Vec<(Move, f32)> {
+        if board.game_outcome.is_some() {
+            return Vec::new();
+        };
+        let moves = board.possible_moves_iter(by);
+        match self.policy {
+            Policy::Planes => moves.filter_map(|r#move| {
+                Some((r#move, self.state[self.output_start + policy::move_index(r#move, by)?]))
+            }).collect(),
+            Policy::Squares => {
+                // only promotions to the piece whose id is closest to the value are considered
+                let value = self.value();
+                let promote_to = [PieceKind::Knight, PieceKind::Bishop, PieceKind::Rook, PieceKind::Queen].into_iter()
+                    .min_by(|a, b| (Engine::piece_id(*a) - value).abs().total_cmp(&(Engine::piece_id(*b) - value).abs()))
+                    .unwrap_or(PieceKind::Queen);
+                moves.filter(|r#move| !matches!(r#move, Move::Promotion { piece, .. } if *piece != promote_to)).map(|r#move| {
+                    let square = |coord: Coordinate| coord.rank as usize*8 + coord.file as usize;
+                    (r#move, self.state[square(r#move.resolve_from(by))].abs()*self.state[64 + square(r#move.resolve_to(by))].abs())
+                }).collect()
+            },
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Engine {
-    // input 69 -> 2×120 -> 60 -> 4×30 -> output 129
-    weights: (Box<[f32; 38250]>, Box<[f32; 420]>),
+    // by default the sparse features -> 2×32 -> a move policy and the value, see `Architecture`
+    // shared between clones, so handing an engine to another thread doesn't copy the network
+    // and a reload reaches every clone. mutating detaches the engine from the others.
+    #[cfg(feature = "std")]
+    weights: Arc<RwLock<Arc<Weights>>>,
+    #[cfg(not(feature = "std"))]
+    weights: Arc<Weights>,
 }
 
 impl Engine {
-    pub fn save(&self, to: &str) {
-        std::fs::File::create(to).unwrap().write_all(&self.weights.0.iter().chain(self.weights.1.iter()).map(|w| w.to_le_bytes()).collect::<Vec<_>>().concat()).unwrap();
-    }
-    
-    pub fn load(from: &str) -> Option<Self> {
-        let mut file = std::fs::File::open(from).ok()?;
-        let mut buf = vec![0; 38250*4+420*4];
-        file.read_exact(&mut buf).unwrap();
-        let data = buf.into_iter().array_chunks::<4>().map(f32::from_le_bytes).collect::<Vec<_>>();
-        Some(Self {
-            weights: (
-                Box::new(data[0..38250].try_into().unwrap()),
-                Box::new(data[38250..38250 + 420].try_into().unwrap()),
-            )
-        })
+    #[cfg(feature = "std")]
+    pub(crate) fn from_weights(weights: Weights) -> Self {
+        Self { weights: Arc::new(RwLock::new(Arc::new(weights))) }
     }
-    
-    pub fn new_random() -> Self {
-        let mut rng = rand::thread_rng();
 
-        let mut coefs = vec![0.0; 38250];
-        let mut offsets = vec![0.0; 420];
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn from_weights(weights: Weights) -> Self {
+        Self { weights: Arc::new(weights) }
+    }
 
-        coefs.iter_mut().chain(offsets.iter_mut()).for_each(|w| *w = rng.gen::<f32>()*2.0-1.0);
-        
-        Self {
-            weights: (coefs.into_boxed_slice().try_into().unwrap(), offsets.into_boxed_slice().try_into().unwrap())
-        }
+    // a snapshot, so a forward pass keeps its weights even if they're swapped midway
+    #[cfg(feature = "std")]
+    pub(crate) fn weights(&self) -> Arc<Weights> {
+        // the lock only guards swapping one arc for another, a panic while holding it can't leave anything half done
+        Arc::clone(&self.weights.read().unwrap_or_else(PoisonError::into_inner))
     }
 
-    pub fn variate(&mut self, with: &Self) {
-        todo!()
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn weights(&self) -> Arc<Weights> {
+        Arc::clone(&self.weights)
     }
 
-    pub fn mutate(&mut self, coef: Option<f32>) {
-        let mut rng = rand::thread_rng();
-        self.weights.0.iter_mut().chain(self.weights.1.iter_mut()).for_each(|w| {
-            if let Some(coef) = coef {
-                let portion = *w*rng.gen::<f32>().powi(2)/2.0*coef;
-                if rng.gen() {
-                    *w += portion;
-                } else {
-                    *w -= portion;
-                };
-                *w += (rng.gen::<f32>()*2.0-1.0)*coef;
-            } else {
-                *w = rng.gen();
-            };
-        });
+    pub fn architecture(&self) -> Architecture {
+        self.weights().architecture.clone()
+    }
+
+    // for builds without a filesystem, e.g. with the weights baked in through `include_bytes!`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WeightsError> {
+        format::parse(bytes).map(Self::from_weights)
+    }
+
+    // always in the newest format, whatever it was loaded from
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format::serialize(&self.weights())
+    }
+
+    // a new engine with the weights moved to other hidden layer sizes, see `format::resize`
+    pub fn resized(&self, architecture: Architecture) -> Result<Self, WeightsError> {
+        format::resize(&self.weights(), architecture).map(Self::from_weights)
     }
 
-    fn piece_id(piece: PieceKind) -> f32 {
+    // how the legacy encoding feeds a piece to the network. it looks like material, but it's what the
+    // weights were trained on, so it stays fixed whatever `ValueTable` anything else counts with.
+    pub(crate) fn piece_id(piece: PieceKind) -> f32 {
         match piece {
             PieceKind::Pawn => 1.0/12.0,     // 1/12
             PieceKind::Knight => 3.0/12.0,   // 3/12
@@ -75,95 +184,205 @@ impl Engine {
         }
     }
 
-    fn prepare_input(board: &Board) -> [f32; 69] {
-        let mut buf = [0.0; 69];
-
-        for (i, (piece, _)) in board.grid().iter_coord().enumerate() {
-            buf[i] = match piece {
-                None => 0.0,
-                Some(piece) => {
-                    let id = Self::piece_id(piece.kind);
-                    match piece.color {
-                        Color::White => id,
-                        Color::Black => -id,
-                    }
-                }
-            }
-        }
-
-        buf[64] = board.white_castle.0 as u8 as f32;
-        buf[65] = board.white_castle.1 as u8 as f32;
-        buf[66] = board.black_castle.0 as u8 as f32;
-        buf[67] = board.black_castle.1 as u8 as f32;
-        buf[68] = board.stale_plies as f32 / 50.0;
+    // one row of the weight matrix against the previous layer. summing into 8 separate lanes breaks
+    // the dependency between additions, which lets the compiler vectorize it (a single sum can't be reordered).
+    fn dot(row: &[f32], source: &[f32]) -> f32 {
+        let mut lanes = [0.0f32; 8];
+        let (rows, sources) = (row.chunks_exact(8), source.chunks_exact(8));
+        let tail = rows.remainder().iter().zip(sources.remainder()).map(|(w, x)| w*x).sum::<f32>();
+        for (row, source) in rows.zip(sources) {
+            for lane in 0..8 {
+                lanes[lane] += row[lane]*source[lane];
+            };
+        };
+        lanes.iter().sum::<f32>() + tail
+    }
 
-        buf
+    fn activate(sum: f32) -> f32 {
+        2.0 / (1.0 + pow9(-sum)) - 1.0
     }
 
-    fn feed(weights: &[f32], offset: usize, state: &mut [f32], source: (usize, usize), layer: (usize, usize)) {
-        for i in 0..layer.1 {
-            for j in 0..source.1 {
-                state[layer.0+i] += state[source.0+j] * weights[offset+i*source.1+j];
+    // every neuron after the first layer's sums for all of the positions at once, the legacy encoding's inputs first.
+    // each layer is a matrix (a row per neuron) multiplied with every position's previous layer,
+    // going row by row so a row is loaded once for the whole batch. the neurons start out holding their offsets.
+    // for just the value the output's other neurons are left at 0.
+    pub(crate) fn propagate(weights: &Weights, accumulators: &[Accumulator], value_only: bool) -> Vec<Vec<f32>> {
+        let layers = weights.architecture.layers();
+        let inputs = weights.architecture.state_inputs();
+        let mut states = accumulators.iter().map(|accumulator| {
+            let mut state = alloc::vec![0.0; weights.architecture.state_neurons()];
+            for (index, value) in accumulator.inputs.iter().filter(|(index, _)| *index < inputs) {
+                state[*index] = *value;
             };
-            state[layer.0+i] = 2.0 / (1.0 + 9.0f32.powf(-state[layer.0+i])) - 1.0;
+            state[inputs..inputs + weights.offsets.len()].copy_from_slice(&weights.offsets);
+            for (neuron, sum) in state[inputs..].iter_mut().zip(&accumulator.sums) {
+                *neuron = Self::activate(*sum);
+            };
+            state
+        }).collect::<Vec<_>>();
+
+        let mut of = layers[0]*layers[1];
+        let mut source_start = inputs;
+        for (n, dims) in layers[1..].windows(2).enumerate() {
+            let layer_start = source_start + dims[0];
+            let output = n + 2 == layers.len() - 1;
+            for (i, row) in weights.coefs[of..of + dims[0]*dims[1]].chunks_exact(dims[0]).enumerate() {
+                if value_only && output && i != dims[1] - 1 {
+                    continue;
+                };
+                for state in &mut states {
+                    let (source, layer) = state.split_at_mut(layer_start);
+                    layer[i] = Self::activate(layer[i] + Self::dot(row, &source[source_start..]));
+                };
+            };
+            of += dims[0]*dims[1];
+            source_start = layer_start;
         };
+
+        states
     }
 
-    pub fn choose_move(&self, board: &Board, by: Color) -> (PlayerMove, f32) {
-        let legal_moves = board.possible_moves(by);
+    // one forward pass over many positions, cheaper than evaluating them one by one.
+    // takes boards as well as references to them, so they don't have to be moved into one slice.
+    pub fn evaluate_batch<B: Borrow<Board>>(&self, boards: &[B]) -> Vec<Output> {
+        let weights = self.weights();
+        let architecture = &weights.architecture;
+        let accumulators = boards.iter()
+            .map(|board| Accumulator::from_inputs(Arc::clone(&weights), Features::of(&board.borrow().position()).inputs(architecture.encoding())))
+            .collect::<Vec<_>>();
+        let (policy, output_start) = (architecture.policy(), architecture.state_neurons() - architecture.layers()[architecture.layers().len() - 1]);
+        Self::propagate(&weights, &accumulators, false).into_iter().map(|state| Output { state, policy, output_start }).collect()
+    }
 
-        if legal_moves.is_empty() {
-            panic!();
+    // the value of a position that's over: 1 if white has won, -1 if black has, 0 for a draw.
+    // none while there are moves to play.
+    pub fn terminal_value(board: &Board) -> Option<f32> {
+        let value = |won: Color| match won {
+            Color::White => 1.0,
+            Color::Black => -1.0,
+        };
+        match board.game_outcome {
+            Some(GameOutcome::Decisive { won, .. }) => return Some(value(won)),
+            Some(GameOutcome::Draw(_)) => return Some(0.0),
+            None => {},
+        };
+        if board.has_at_least(board.move_color, 1) {
+            return None;
         };
+        let king = board.pieces_of(board.move_color, PieceKind::King).next();
+        match king.is_some_and(|king| board.is_under_attack(board.move_color.the_other(), king, None)) {
+            true => Some(value(board.move_color.the_other())),
+            // stalemate
+            false => Some(0.0),
+        }
+    }
+
+    // the position value in -1..1 from white's point of view: exact for a position that's over,
+    // otherwise the network's last output
+    pub fn evaluate(&self, board: &Board) -> f32 {
+        Self::terminal_value(board).unwrap_or_else(|| self.network_value(&board.position()))
+    }
+
+    // what the network makes of the position, whether it's over or not
+    #[cfg(feature = "std")]
+    pub(crate) fn network_value(&self, position: &Position) -> f32 {
+        std::thread_local! {
+            // the position this thread evaluated last. a search evaluates positions a move or two apart
+            // one after the other, so updating from it beats starting over.
+            static LAST: core::cell::RefCell<Option<Accumulator>> = const { core::cell::RefCell::new(None) };
+        }
+
+        let weights = self.weights();
+        LAST.with_borrow_mut(|last| match last {
+            Some(accumulator) if Arc::ptr_eq(&accumulator.weights, &weights) => {
+                accumulator.update(position);
+                accumulator.evaluate()
+            },
+            _ => last.insert(Accumulator::from_inputs(Arc::clone(&weights), Features::of(position).inputs(weights.architecture.encoding()))).evaluate(),
+        })
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn network_value(&self, position: &Position) -> f32 {
+        Accumulator::new(self, position).evaluate()
+    }
 
-        if legal_moves.len() == 1 {
-            return (PlayerMove::Internal(legal_moves[0]), 1.0);
+    // spelled out as squares, the way players enter them
+    fn player_move(r#move: Move, by: Color) -> PlayerMove {
+        let promotion = match r#move {
+            Move::Promotion { piece, .. } => Some(piece),
+            // no squares to spell it with
+            Move::Drop { .. } => return PlayerMove::Internal(r#move),
+            _ => None,
         };
+        PlayerMove::Long { from: r#move.resolve_from(by), to: r#move.resolve_to(by), promotion }
+    }
 
-        let input = Self::prepare_input(board);
-        let mut state = [0.0; 618];
-        state[0..69].copy_from_slice(&input);
-        state[69..489].copy_from_slice(&*self.weights.1);
+    // none if there's no legal move to choose from
+    pub fn choose_move(&self, board: &Board, by: Color) -> Option<(PlayerMove, f32)> {
+        Self::choose_move_from(board, by, &self.evaluate_batch(core::slice::from_ref(board)).swap_remove(0))
+    }
 
-        let mut of = 0;
-        let mut source_start = 0;
-        for dims in [69, 120, 120, 60, 30, 30, 30, 30, 129].windows(2) {
-            let layer_start = source_start + dims[0];
-            Self::feed(&*self.weights.0, of, &mut state, (source_start, dims[0]), (layer_start, dims[1]));
-            of += dims[0]*dims[1];
-            source_start = layer_start;
+    // the move choice for an output from `evaluate_batch`: the best scored legal move
+    pub fn choose_move_from(board: &Board, by: Color, output: &Output) -> Option<(PlayerMove, f32)> {
+        let scores = output.move_scores(board, by);
+        if scores.is_empty() {
+            return None;
         };
-        
-        let mut best_move = (PlayerMove::Internal(legal_moves[0]), 0.0);
-        for from_file in 0..8 {
-            for from_rank in 0..8 {
-                for to_file in 0..8 {
-                    for to_rank in 0..8 {
-                        let eval = state[from_rank*8+from_file].abs() * state[to_rank*8+to_file+64].abs();
-                        if eval > best_move.1 {
-                            let mut promote_to = (PieceKind::Queen, 1.0);
-                            for piece in [PieceKind::Knight, PieceKind::Bishop, PieceKind::Rook, PieceKind::Queen] {
-                                let dist = (Self::piece_id(piece) - state[617]).abs();
-                                if dist < promote_to.1 {
-                                    promote_to = (piece, dist);
-                                };
-                            };
-                            
-                            let chosen_move = (
-                                Coordinate { file: File::try_from(from_file as i8).unwrap(), rank: Rank::try_from(from_rank as i8).unwrap() },
-                                Coordinate { file: File::try_from(to_file as i8).unwrap(), rank: Rank::try_from(to_rank as i8).unwrap() },
-                                Some(promote_to.0)
-                            );
-                            
-                            if legal_moves.iter().any(|m| m.resolve_from(by) == chosen_move.0 && m.resolve_to(by) == chosen_move.1) {
-                                best_move = (PlayerMove::Long { from: chosen_move.0, to: chosen_move.1, promotion: chosen_move.2 }, eval);
-                            };
-                        };
-                    };
-                };
+        if scores.len() == 1 {
+            return Some((Self::player_move(scores[0].0, by), 1.0));
+        };
+        scores.into_iter().fold(None, |best: Option<(Move, f32)>, (r#move, score)| match best {
+            Some(best) if best.1 >= score => Some(best),
+            _ => Some((r#move, score)),
+        }).map(|(r#move, score)| (Self::player_move(r#move, by), score))
+    }
+
+    // picks a legal move at random, weighted by the softmax of the scores over `temperature` (at 0 it's
+    // `choose_move_from`). `roll` is uniform in 0..1, so the caller decides where the randomness comes from.
+    // returns the move's probability.
+    pub fn sample_move_from(board: &Board, by: Color, output: &Output, temperature: f32, roll: f32) -> Option<(PlayerMove, f32)> {
+        if temperature <= 0.0 {
+            return Self::choose_move_from(board, by, output).map(|(r#move, _)| (r#move, 1.0));
+        };
+        let scores = output.move_scores(board, by);
+        let max = scores.iter().map(|(_, score)| *score).fold(f32::NEG_INFINITY, f32::max);
+        // subtracting the maximum keeps the exponentials from overflowing
+        let weights = scores.iter().map(|(_, score)| pow9((score - max)/temperature)).collect::<Vec<_>>();
+        let total = weights.iter().sum::<f32>();
+        let mut left = roll.clamp(0.0, 1.0)*total;
+        for ((r#move, _), weight) in scores.iter().zip(&weights) {
+            if left < *weight {
+                return Some((Self::player_move(*r#move, by), weight/total));
             };
+            left -= weight;
         };
+        // rounding can leave a sliver at the end
+        scores.last().zip(weights.last()).map(|((r#move, _), weight)| (Self::player_move(*r#move, by), weight/total))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Engine {
+    pub fn save(&self, to: &str) -> std::io::Result<()> {
+        std::fs::write(to, self.to_bytes())
+    }
+
+    pub fn load(from: &str) -> Result<Self, WeightsError> {
+        let engine = Self::from_bytes(&std::fs::read(from)?);
+        #[cfg(feature = "tracing")]
+        match &engine {
+            Ok(engine) => tracing::debug!(from, architecture = %engine.architecture(), "loaded weights"),
+            Err(err) => tracing::warn!(from, %err, "couldn't load weights"),
+        };
+        engine
+    }
 
-        best_move
+    // swaps the weights in place for this engine and all of its clones.
+    // searches already running finish on the old ones, a broken file leaves them untouched.
+    pub fn reload(&self, from: &str) -> Result<(), WeightsError> {
+        let weights = format::parse(&std::fs::read(from)?)?;
+        *self.weights.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(weights);
+        Ok(())
     }
 }
@@ -0,0 +1,242 @@
+use std::fmt::{Display, Formatter};
+use std::io::{BufRead, BufReader, Read};
+use std::time::Duration;
+use serde_json::Value;
+use ress::{Board, PlayerMove};
+use ress::coordinate::Move;
+use ress::fen::{self, Dialect};
+use crate::time::TimeControl;
+
+// the bot api of lichess: a stream of events for the account (challenges, games starting), one for every game
+// with its moves and clocks, and plain posts to answer challenges and play moves. moves go both ways as uci strings.
+
+pub const LICHESS: &str = "https://lichess.org";
+
+#[derive(Debug)]
+pub enum LichessError {
+    // the status lichess answered with and what it said
+    Status(u16, String),
+    Transport(String),
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    // a move lichess sent that can't be played in the game so far
+    Move(String),
+    Position(fen::FenError),
+}
+
+impl Display for LichessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Status(status, body) => write!(f, "lichess answered {status}: {body}"),
+            Self::Transport(err) => write!(f, "couldn't reach lichess: {err}"),
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Json(err) => write!(f, "lichess sent something that isn't json: {err}"),
+            Self::Move(raw) => write!(f, "lichess sent {raw}, which isn't a legal move"),
+            Self::Position(err) => write!(f, "lichess sent an invalid fen: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for LichessError {}
+
+impl From<ureq::Error> for LichessError {
+    fn from(err: ureq::Error) -> Self {
+        match err {
+            ureq::Error::Status(status, response) => Self::Status(status, response.into_string().unwrap_or_default()),
+            ureq::Error::Transport(err) => Self::Transport(err.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for LichessError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for LichessError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Challenge {
+    pub id: String,
+    pub challenger: String,
+    // `standard`, `chess960`, ...
+    pub variant: String,
+    // `bullet`, `blitz`, ..., `correspondence`
+    pub speed: String,
+    pub rated: bool,
+}
+
+// what happens to the account
+#[derive(Debug, Clone)]
+pub enum Event {
+    Challenge(Challenge),
+    // by the game's id
+    GameStart(String),
+    GameFinish(String),
+    // anything a bot doesn't have to act on, e.g. a challenge taken back
+    Other,
+}
+
+// a game's moves and clocks, in milliseconds
+#[derive(Debug, Clone, Default)]
+pub struct GameState {
+    // every move so far as uci, separated by spaces
+    pub moves: String,
+    pub wtime: u64,
+    pub btime: u64,
+    pub winc: u64,
+    pub binc: u64,
+    // `started` while it's being played, then why it ended (`mate`, `resign`, `outoftime`, ...)
+    pub status: String,
+}
+
+impl GameState {
+    pub fn is_over(&self) -> bool {
+        self.status != "started" && self.status != "created"
+    }
+
+    // the clocks as a uci `go` would give them
+    pub fn time_control(&self) -> TimeControl {
+        let millis = |ms: u64| Some(Duration::from_millis(ms));
+        TimeControl { wtime: millis(self.wtime), btime: millis(self.btime), winc: millis(self.winc), binc: millis(self.binc), ..Default::default() }
+    }
+}
+
+// what happens in a game
+#[derive(Debug, Clone)]
+pub enum GameEvent {
+    // always the first: the players' ids (none for lichess' own ai), where the game started and how it stands
+    Full { white: Option<String>, black: Option<String>, initial_fen: String, state: GameState },
+    State(GameState),
+    Other,
+}
+
+fn text(value: &Value, key: &str) -> String {
+    value[key].as_str().unwrap_or_default().to_string()
+}
+
+fn parse_state(value: &Value) -> GameState {
+    let millis = |key: &str| value[key].as_u64().unwrap_or_default();
+    GameState { moves: text(value, "moves"), wtime: millis("wtime"), btime: millis("btime"), winc: millis("winc"), binc: millis("binc"), status: text(value, "status") }
+}
+
+fn parse_event(value: &Value) -> Event {
+    match value["type"].as_str() {
+        Some("challenge") => {
+            let challenge = &value["challenge"];
+            Event::Challenge(Challenge {
+                id: text(challenge, "id"),
+                challenger: text(&challenge["challenger"], "id"),
+                variant: text(&challenge["variant"], "key"),
+                speed: text(challenge, "speed"),
+                rated: challenge["rated"].as_bool().unwrap_or_default(),
+            })
+        },
+        Some("gameStart") => Event::GameStart(text(&value["game"], "gameId")),
+        Some("gameFinish") => Event::GameFinish(text(&value["game"], "gameId")),
+        _ => Event::Other,
+    }
+}
+
+fn parse_game_event(value: &Value) -> GameEvent {
+    match value["type"].as_str() {
+        Some("gameFull") => GameEvent::Full {
+            white: value["white"]["id"].as_str().map(ToString::to_string),
+            black: value["black"]["id"].as_str().map(ToString::to_string),
+            initial_fen: text(value, "initialFen"),
+            state: parse_state(&value["state"]),
+        },
+        Some("gameState") => GameEvent::State(parse_state(value)),
+        _ => GameEvent::Other,
+    }
+}
+
+// newline delimited json, the empty lines lichess keeps the connection alive with are skipped
+fn ndjson<T>(reader: impl Read, parse: impl Fn(&Value) -> T) -> impl Iterator<Item = Result<T, LichessError>> {
+    BufReader::new(reader).lines()
+        .filter(|line| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+        .map(move |line| Ok(parse(&serde_json::from_str::<Value>(&line?)?)))
+}
+
+// the position a game is in: where it started (`startpos` or a fen) and its moves the way lichess sends them
+pub fn board_after(initial_fen: &str, moves: &str) -> Result<Board, LichessError> {
+    let mut board = match initial_fen {
+        "" | "startpos" => Board::default(),
+        fen => fen::parse(fen, Dialect::Standard).map_err(LichessError::Position)?.0,
+    };
+    for raw in moves.split_whitespace() {
+        let r#move = PlayerMove::parse(raw).ok_or_else(|| LichessError::Move(raw.to_string()))?;
+        board.play_move(r#move).map_err(|_| LichessError::Move(raw.to_string()))?;
+    };
+    Ok(board)
+}
+
+// how lichess wants a move of the side to move, e.g. `e1g1` for castling
+pub fn to_uci(board: &Board, r#move: Move) -> String {
+    r#move.to_long_algebraic(board.move_color)
+}
+
+pub struct Lichess {
+    agent: ureq::Agent,
+    token: String,
+    base: String,
+}
+
+impl Lichess {
+    // the token has to be one of a bot account with the `bot:play` scope
+    pub fn new(token: &str) -> Self {
+        Self::with_base(token, LICHESS)
+    }
+
+    // e.g. a local lila for testing
+    pub fn with_base(token: &str, base: &str) -> Self {
+        Self { agent: ureq::AgentBuilder::new().build(), token: token.to_string(), base: base.trim_end_matches('/').to_string() }
+    }
+
+    fn get(&self, path: &str) -> Result<ureq::Response, LichessError> {
+        Ok(self.agent.get(&format!("{}{path}", self.base)).set("Authorization", &format!("Bearer {}", self.token)).call()?)
+    }
+
+    fn post(&self, path: &str, form: &[(&str, &str)]) -> Result<(), LichessError> {
+        self.agent.post(&format!("{}{path}", self.base)).set("Authorization", &format!("Bearer {}", self.token)).send_form(form)?;
+        Ok(())
+    }
+
+    // the account's id, for telling which side the bot plays
+    pub fn account(&self) -> Result<String, LichessError> {
+        let account = serde_json::from_reader::<_, Value>(self.get("/api/account")?.into_reader())?;
+        Ok(text(&account, "id"))
+    }
+
+    // blocks until lichess has something to say, it never ends on its own
+    pub fn events(&self) -> Result<impl Iterator<Item = Result<Event, LichessError>>, LichessError> {
+        Ok(ndjson(self.get("/api/stream/event")?.into_reader(), parse_event))
+    }
+
+    // starts with the whole game, then a state for every move, and ends with the game
+    pub fn game(&self, id: &str) -> Result<impl Iterator<Item = Result<GameEvent, LichessError>>, LichessError> {
+        Ok(ndjson(self.get(&format!("/api/bot/game/stream/{id}"))?.into_reader(), parse_game_event))
+    }
+
+    pub fn accept(&self, challenge: &str) -> Result<(), LichessError> {
+        self.post(&format!("/api/challenge/{challenge}/accept"), &[])
+    }
+
+    // `reason` is one of lichess' keys, e.g. `variant` or `timeControl`
+    pub fn decline(&self, challenge: &str, reason: &str) -> Result<(), LichessError> {
+        self.post(&format!("/api/challenge/{challenge}/decline"), &[("reason", reason)])
+    }
+
+    pub fn play(&self, game: &str, r#move: &str) -> Result<(), LichessError> {
+        self.post(&format!("/api/bot/game/{game}/move/{}", r#move), &[])
+    }
+
+    pub fn resign(&self, game: &str) -> Result<(), LichessError> {
+        self.post(&format!("/api/bot/game/{game}/resign"), &[])
+    }
+}
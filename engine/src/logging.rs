@@ -0,0 +1,12 @@
+use tracing_subscriber::EnvFilter;
+
+// logs to stderr (stdout is the protocol for uci), filtered by RUST_LOG, e.g. `RUST_LOG=engine=debug,ress=trace`.
+// span closes are logged too, that's where the timings are.
+pub fn init(default_filter: &str) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .with_writer(std::io::stderr)
+        .try_init();
+}
@@ -0,0 +1,108 @@
+use ress::Board;
+use ress::coordinate::{Coordinate, File};
+use ress::editor::BoardEditor;
+use ress::piece::{Color, Piece, PieceKind};
+use crate::Engine;
+use crate::suite;
+use crate::tournament::{play_game, Estimate, TournamentOptions};
+
+// how strong an engine is as the most material it can give a reference opponent and still score 50% against it.
+// unlike elo against the last epoch, it means the same thing over a whole evolve run.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Handicap {
+    pub name: &'static str,
+    // in pawns
+    pub material: u8,
+    pub pieces: &'static [PieceKind],
+}
+
+// from the smallest, each at least as much material as the one before
+pub const HANDICAPS: [Handicap; 8] = [
+    Handicap { name: "even", material: 0, pieces: &[] },
+    Handicap { name: "pawn", material: 1, pieces: &[PieceKind::Pawn] },
+    Handicap { name: "knight", material: 3, pieces: &[PieceKind::Knight] },
+    Handicap { name: "rook", material: 5, pieces: &[PieceKind::Rook] },
+    Handicap { name: "rook and knight", material: 8, pieces: &[PieceKind::Rook, PieceKind::Knight] },
+    Handicap { name: "queen", material: 9, pieces: &[PieceKind::Queen] },
+    Handicap { name: "queen and knight", material: 12, pieces: &[PieceKind::Queen, PieceKind::Knight] },
+    Handicap { name: "queen and rook", material: 14, pieces: &[PieceKind::Queen, PieceKind::Rook] },
+];
+
+impl Handicap {
+    // `color` without the pieces, the classical ones (the f-pawn, the queen's knight and rook) when they're still there.
+    // castling with a rook that's gone isn't allowed anymore. none if `color` doesn't have them or what's left isn't playable.
+    pub fn apply(&self, board: &Board, color: Color) -> Option<Board> {
+        let mut editor = BoardEditor::from_board(board);
+        for kind in self.pieces {
+            let preferred = match kind {
+                PieceKind::Pawn => File::F,
+                PieceKind::Knight => File::B,
+                PieceKind::Bishop => File::C,
+                PieceKind::Rook => File::A,
+                PieceKind::Queen | PieceKind::King => File::D,
+            };
+            let piece = Piece { color, kind: *kind };
+            let squares = Coordinate::iter().filter(|coord| editor.grid()[*coord] == Some(piece)).collect::<Vec<_>>();
+            let coord = squares.iter().find(|coord| coord.file == preferred).or(squares.first())?;
+            editor.put(*coord, None);
+        };
+
+        let rank = color.home_rank();
+        let rook_at = |file: File| editor.grid()[Coordinate { file, rank }] == Some(Piece { color, kind: PieceKind::Rook });
+        let (kingside, queenside) = (rook_at(File::H), rook_at(File::A));
+        let castle = editor.castle_mut(color);
+        *castle = (castle.0 && kingside, castle.1 && queenside);
+        editor.build().ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Rung {
+    pub handicap: Handicap,
+    // the engine's, giving the odds
+    pub estimate: Estimate,
+}
+
+#[derive(Debug, Clone)]
+pub struct Calibration {
+    // every handicap played, from the smallest
+    pub rungs: Vec<Rung>,
+}
+
+impl Calibration {
+    // plays every opening of the suite with both colors and the handicap applied to the engine, from the even game up
+    // until the engine scores less than 50%. the games of a handicap are spread over rayon's threads.
+    pub fn run(engine: &Engine, reference: &Engine, options: &TournamentOptions) -> Self {
+        let entrants = [engine, reference];
+        let schedule = suite::schedule(&[(0, 1)], options.openings.len(), options.rounds);
+
+        let mut rungs = Vec::new();
+        for handicap in HANDICAPS {
+            let scores = suite::run(&schedule, |_, suite::Pairing { white, black, opening }| {
+                let color = if white == 0 { Color::White } else { Color::Black };
+                let board = handicap.apply(&options.openings.openings[opening], color)?;
+                let (_, result, _) = play_game(entrants[white], entrants[black], &board, options, &|_, _, _| {});
+                match result? {
+                    Some(won) if won == color => Some(1.0),
+                    Some(_) => Some(0.0),
+                    None => Some(0.5),
+                }
+            });
+            let estimate = Estimate::from_scores(scores.into_iter().flatten());
+
+            #[cfg(feature = "tracing")]
+            tracing::info!(handicap = handicap.name, games = estimate.games(), score = estimate.score(), "handicap played");
+            rungs.push(Rung { handicap, estimate });
+            if estimate.games() == 0 || estimate.score() < 0.5 {
+                break;
+            };
+        };
+        Self { rungs }
+    }
+
+    // the largest handicap the engine still scored 50% with, none if it didn't even in an even game
+    pub fn handicap(&self) -> Option<Handicap> {
+        self.rungs.iter().take_while(|rung| rung.estimate.games() > 0 && rung.estimate.score() >= 0.5).last().map(|rung| rung.handicap)
+    }
+}
@@ -0,0 +1,175 @@
+use std::fmt::{Display, Formatter};
+use std::sync::{PoisonError, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+// where the weights are looked for when nobody says otherwise
+pub const DEFAULT_WEIGHTS: &str = "engine.rew";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OptionKind {
+    Spin { default: u64, min: u64, max: u64 },
+    String { default: &'static str },
+}
+
+// an option as a gui is told about it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UciOption {
+    pub name: &'static str,
+    pub kind: OptionKind,
+}
+
+impl Display for UciOption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            OptionKind::Spin { default, min, max } => write!(f, "option name {} type spin default {default} min {min} max {max}", self.name),
+            OptionKind::String { default } => write!(f, "option name {} type string default {default}", self.name),
+        }
+    }
+}
+
+pub const HASH: UciOption = UciOption { name: "Hash", kind: OptionKind::Spin { default: 16, min: 1, max: 4096 } };
+pub const THREADS: UciOption = UciOption { name: "Threads", kind: OptionKind::Spin { default: 1, min: 1, max: 64 } };
+pub const MULTI_PV: UciOption = UciOption { name: "MultiPV", kind: OptionKind::Spin { default: 1, min: 1, max: 64 } };
+pub const WEIGHTS_FILE: UciOption = UciOption { name: "WeightsFile", kind: OptionKind::String { default: DEFAULT_WEIGHTS } };
+pub const MOVE_OVERHEAD: UciOption = UciOption { name: "MoveOverhead", kind: OptionKind::Spin { default: 30, min: 0, max: 5000 } };
+
+// every option, in the order they're declared to a gui
+pub const OPTIONS: [UciOption; 5] = [HASH, THREADS, MULTI_PV, WEIGHTS_FILE, MOVE_OVERHEAD];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionError {
+    Unknown(String),
+    // the option and the value it can't take
+    Invalid(&'static str, String),
+    // a `setoption` that isn't `name <name> [value <value>]`
+    Malformed,
+}
+
+impl Display for OptionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unknown(name) => write!(f, "unknown option: {name}"),
+            Self::Invalid(name, value) => write!(f, "invalid {name}: {value}"),
+            Self::Malformed => write!(f, "expected `setoption name <name> value <value>`"),
+        }
+    }
+}
+
+impl std::error::Error for OptionError {}
+
+// the options a gui can set, shared with whatever reads them: the numbers are atomics and the path is behind a lock,
+// so a search can pick them up while `setoption` comes in on another thread.
+#[derive(Debug)]
+pub struct UciOptions {
+    // in megabytes
+    hash: AtomicU64,
+    threads: AtomicU64,
+    multi_pv: AtomicU64,
+    weights: RwLock<String>,
+    // in milliseconds
+    move_overhead: AtomicU64,
+}
+
+impl Default for UciOptions {
+    fn default() -> Self {
+        let spin = |option: UciOption| match option.kind {
+            OptionKind::Spin { default, .. } => AtomicU64::new(default),
+            OptionKind::String { .. } => unreachable!(),
+        };
+        Self {
+            hash: spin(HASH),
+            threads: spin(THREADS),
+            multi_pv: spin(MULTI_PV),
+            weights: RwLock::new(DEFAULT_WEIGHTS.to_string()),
+            move_overhead: spin(MOVE_OVERHEAD),
+        }
+    }
+}
+
+impl UciOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the `option` lines answering `uci`
+    pub fn declarations(&self) -> impl Iterator<Item = String> {
+        OPTIONS.iter().map(ToString::to_string)
+    }
+
+    // names are matched regardless of case, as uci has it
+    pub fn set(&self, name: &str, value: &str) -> Result<(), OptionError> {
+        let option = OPTIONS.iter().find(|option| option.name.eq_ignore_ascii_case(name)).ok_or_else(|| OptionError::Unknown(name.to_string()))?;
+        match option.kind {
+            OptionKind::Spin { min, max, .. } => {
+                let number = value.trim().parse::<u64>().ok().filter(|number| (min..=max).contains(number))
+                    .ok_or_else(|| OptionError::Invalid(option.name, value.to_string()))?;
+                let atomic = match option.name {
+                    "Hash" => &self.hash,
+                    "Threads" => &self.threads,
+                    "MultiPV" => &self.multi_pv,
+                    "MoveOverhead" => &self.move_overhead,
+                    // declared but not stored anywhere, as good as unknown
+                    _ => return Err(OptionError::Unknown(option.name.to_string())),
+                };
+                atomic.store(number, Ordering::Relaxed);
+            },
+            OptionKind::String { .. } => self.set_weights(value.trim()),
+        };
+        Ok(())
+    }
+
+    // the arguments of a `setoption`, e.g. `name Hash value 64`. names and values may have spaces in them.
+    // returns the option's name as declared.
+    pub fn setoption(&self, args: &[&str]) -> Result<&'static str, OptionError> {
+        let ["name", rest @ ..] = args else {
+            return Err(OptionError::Malformed);
+        };
+        let split = rest.iter().position(|arg| *arg == "value").unwrap_or(rest.len());
+        let name = rest[..split].join(" ");
+        let value = rest.get(split + 1..).map(|value| value.join(" ")).unwrap_or_default();
+        self.set(&name, &value)?;
+        Ok(OPTIONS.iter().find(|option| option.name.eq_ignore_ascii_case(&name)).map_or("", |option| option.name))
+    }
+
+    pub fn hash_megabytes(&self) -> usize {
+        self.hash.load(Ordering::Relaxed) as usize
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads.load(Ordering::Relaxed) as usize
+    }
+
+    pub fn multi_pv(&self) -> u8 {
+        self.multi_pv.load(Ordering::Relaxed) as u8
+    }
+
+    pub fn weights(&self) -> String {
+        self.weights.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    pub fn move_overhead(&self) -> Duration {
+        Duration::from_millis(self.move_overhead.load(Ordering::Relaxed))
+    }
+
+    pub fn set_weights(&self, path: &str) {
+        *self.weights.write().unwrap_or_else(PoisonError::into_inner) = path.to_string();
+    }
+
+    // the numbers have to be in range like they do for a gui, see `OPTIONS`
+    pub fn set_hash_megabytes(&self, megabytes: usize) -> Result<(), OptionError> {
+        self.set(HASH.name, &megabytes.to_string())
+    }
+
+    pub fn set_threads(&self, threads: usize) -> Result<(), OptionError> {
+        self.set(THREADS.name, &threads.to_string())
+    }
+
+    pub fn set_multi_pv(&self, lines: u8) -> Result<(), OptionError> {
+        self.set(MULTI_PV.name, &lines.to_string())
+    }
+
+    pub fn set_move_overhead(&self, overhead: Duration) -> Result<(), OptionError> {
+        self.set(MOVE_OVERHEAD.name, &overhead.as_millis().to_string())
+    }
+}
@@ -0,0 +1,109 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use ress::coordinate::{Coordinate, Move};
+use ress::piece::{Color, PieceKind};
+use ress::position::Position;
+
+const HINT: i32 = i32::MAX;
+// captures and promotions, by mvv-lva on top
+const TACTICAL: i32 = 1 << 30;
+const KILLER: i32 = 1 << 29;
+// history scores are halved once one gets here, so they stay below the killers
+const HISTORY_LIMIT: i32 = 1 << 20;
+
+fn square(coord: Coordinate) -> usize {
+    coord.rank as usize*8 + coord.file as usize
+}
+
+// what decides the order moves are searched in, the sooner the best one comes the more the rest get cut off:
+// the table's move, then captures (most valuable victim by the least valuable attacker), then moves that cut
+// off at the same ply before ("killers"), then quiet moves by how often they cut off anywhere ("history").
+// one per search thread, it learns from the cutoffs the search reports back.
+pub struct MoveOrderer {
+    // two per ply, the most recent first
+    killers: Vec<[Option<Move>; 2]>,
+    // by color, then from and to square
+    history: Vec<i32>,
+}
+
+impl Default for MoveOrderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MoveOrderer {
+    pub fn new() -> Self {
+        Self { killers: Vec::new(), history: vec![0; 2*64*64] }
+    }
+
+    fn history_index(color: Color, r#move: Move) -> usize {
+        let side = match color {
+            Color::White => 0,
+            Color::Black => 1,
+        };
+        (side*64 + square(r#move.resolve_from(color)))*64 + square(r#move.resolve_to(color))
+    }
+
+    // the victim and attacker of a capture, none for anything else
+    fn capture(position: &Position, r#move: Move) -> Option<(PieceKind, PieceKind)> {
+        let color = position.move_color;
+        match r#move {
+            Move::EnPassant { .. } => Some((PieceKind::Pawn, PieceKind::Pawn)),
+            Move::Castling { .. } | Move::Drop { .. } => None,
+            Move::Simple { .. } | Move::Promotion { .. } => position.grid[r#move.resolve_to(color)]
+                .filter(|victim| victim.color != color)
+                .zip(position.grid[r#move.resolve_from(color)])
+                .map(|(victim, attacker)| (victim.kind, attacker.kind)),
+        }
+    }
+
+    pub fn is_quiet(position: &Position, r#move: Move) -> bool {
+        !matches!(r#move, Move::Promotion { .. }) && Self::capture(position, r#move).is_none()
+    }
+
+    fn score(&self, position: &Position, r#move: Move, ply: u8, hint: Option<Move>) -> i32 {
+        if hint == Some(r#move) {
+            return HINT;
+        };
+
+        let promotion = match r#move {
+            Move::Promotion { piece, .. } => piece as i32,
+            _ => 0,
+        };
+        if let Some((victim, attacker)) = Self::capture(position, r#move) {
+            return TACTICAL + (victim as i32*8 - attacker as i32)*8 + promotion;
+        };
+        if promotion > 0 {
+            return TACTICAL + promotion;
+        };
+
+        match self.killers.get(ply as usize) {
+            Some([Some(first), _]) if *first == r#move => KILLER + 1,
+            Some([_, Some(second)]) if *second == r#move => KILLER,
+            _ => self.history[Self::history_index(position.move_color, r#move)],
+        }
+    }
+
+    // sorts `moves` of the side to move in `position` best first, `hint` (e.g. from the table) goes before everything
+    pub fn order(&self, position: &Position, moves: &mut [Move], ply: u8, hint: Option<Move>) {
+        moves.sort_by_cached_key(|r#move| core::cmp::Reverse(self.score(position, *r#move, ply, hint)));
+    }
+
+    // a quiet move that made the search fail high at `ply` with `depth` left to go
+    pub fn cutoff(&mut self, color: Color, r#move: Move, ply: u8, depth: u8) {
+        if self.killers.len() <= ply as usize {
+            self.killers.resize(ply as usize + 1, [None; 2]);
+        };
+        let killers = &mut self.killers[ply as usize];
+        if killers[0] != Some(r#move) {
+            *killers = [Some(r#move), killers[0]];
+        };
+
+        let index = Self::history_index(color, r#move);
+        self.history[index] += depth as i32*depth as i32;
+        if self.history[index] >= HISTORY_LIMIT {
+            self.history.iter_mut().for_each(|score| *score /= 2);
+        };
+    }
+}
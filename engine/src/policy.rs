@@ -0,0 +1,38 @@
+use ress::coordinate::Move;
+use ress::piece::{Color, PieceKind};
+
+// every move as seen by the side making it (black's board is flipped), told apart by where it starts and a plane:
+// 8 directions × 7 distances for queen-like moves (castling is the king's two steps, promoting to a queen is a pawn step),
+// 8 knight jumps and 3 directions × 3 pieces for promoting to anything but a queen
+pub const PLANES: usize = 73;
+pub const POLICY_MOVES: usize = 64*PLANES;
+
+const DIRECTIONS: [(i8, i8); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+const KNIGHT_JUMPS: [(i8, i8); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+const UNDERPROMOTIONS: [PieceKind; 3] = [PieceKind::Knight, PieceKind::Bishop, PieceKind::Rook];
+
+// where the move's logit is in the policy, none for moves it can't express (e.g. promoting to a king)
+pub fn move_index(r#move: Move, color: Color) -> Option<usize> {
+    let (from, to) = (r#move.resolve_from(color), r#move.resolve_to(color));
+    let rank = |rank: i8| if color == Color::White { rank } else { 7 - rank };
+    let (from_rank, to_rank) = (rank(from.rank as i8), rank(to.rank as i8));
+    let (files, ranks) = (to.file as i8 - from.file as i8, to_rank - from_rank);
+
+    let plane = match r#move {
+        Move::Promotion { piece, .. } if piece != PieceKind::Queen => {
+            64 + UNDERPROMOTIONS.iter().position(|kind| *kind == piece)?*3 + (files + 1) as usize
+        },
+        _ => match KNIGHT_JUMPS.iter().position(|jump| *jump == (files, ranks)) {
+            Some(jump) => 56 + jump,
+            None => {
+                let distance = files.abs().max(ranks.abs());
+                if distance == 0 || (files != 0 && ranks != 0 && files.abs() != ranks.abs()) {
+                    return None;
+                };
+                let direction = DIRECTIONS.iter().position(|of| *of == (files.signum(), ranks.signum()))?;
+                direction*7 + distance as usize - 1
+            },
+        },
+    };
+    Some((from_rank as usize*8 + from.file as usize)*PLANES + plane)
+}
@@ -0,0 +1,97 @@
+use std::sync::{mpsc, Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use ress::{Board, PlayerMove};
+use ress::piece::Color;
+use crate::Engine;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    // how long a request may wait in the queue before it's dropped as stale
+    pub max_wait: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PoolError {
+    TimedOut,
+    ShutDown,
+    NoLegalMoves,
+}
+
+pub type PoolResult = Result<(PlayerMove, f32), PoolError>;
+
+struct Request {
+    board: Board,
+    color: Color,
+    limits: Limits,
+    queued_at: Instant,
+    reply: mpsc::Sender<PoolResult>,
+}
+
+// a fixed set of worker threads sharing one copy of the weights and a single request queue
+pub struct EnginePool {
+    queue: Option<mpsc::Sender<Request>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl EnginePool {
+    pub fn new(engine: Engine, size: usize) -> Self {
+        let (queue, requests) = mpsc::channel::<Request>();
+        let requests = Arc::new(Mutex::new(requests));
+
+        let workers = (0..size.max(1)).map(|_| {
+            let engine = engine.clone();
+            let requests = Arc::clone(&requests);
+            std::thread::spawn(move || loop {
+                // a worker that panicked while waiting can't have left the receiver in a bad state
+                let Ok(request) = requests.lock().unwrap_or_else(PoisonError::into_inner).recv() else {
+                    break;
+                };
+
+                let result = if request.limits.max_wait.is_some_and(|max_wait| request.queued_at.elapsed() > max_wait) {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(waited = ?request.queued_at.elapsed(), "dropping a stale request");
+                    Err(PoolError::TimedOut)
+                } else {
+                    engine.choose_move(&request.board, request.color).ok_or(PoolError::NoLegalMoves)
+                };
+
+                // the requester may have stopped waiting, that's fine
+                let _ = request.reply.send(result);
+            })
+        }).collect();
+
+        Self { queue: Some(queue), workers }
+    }
+
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub fn submit(&self, board: Board, color: Color, limits: Limits) -> mpsc::Receiver<PoolResult> {
+        let (reply, result) = mpsc::channel();
+        let request = Request { board, color, limits, queued_at: Instant::now(), reply };
+        let Some(queue) = &self.queue else {
+            let _ = request.reply.send(Err(PoolError::ShutDown));
+            return result;
+        };
+        if let Err(mpsc::SendError(request)) = queue.send(request) {
+            let _ = request.reply.send(Err(PoolError::ShutDown));
+        };
+        result
+    }
+
+    pub fn choose_move(&self, board: Board, color: Color, limits: Limits) -> PoolResult {
+        self.submit(board, color, limits).recv().unwrap_or(Err(PoolError::ShutDown))
+    }
+}
+
+impl Drop for EnginePool {
+    fn drop(&mut self) {
+        // closing the queue lets the workers finish what's already queued and exit
+        drop(self.queue.take());
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        };
+    }
+}
@@ -0,0 +1,252 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{BufRead, BufReader, Write};
+use ress::position::Position;
+use ress::coordinate::Coordinate;
+use ress::piece::{Color, Piece, PieceKind};
+
+pub const MAX_PHASE: i32 = 24;
+pub const PARAMS_COUNT: usize = 6*2*65;
+
+// indexed by `PieceKind as usize`
+#[cfg(feature = "std")]
+const KIND_NAMES: [&str; 6] = ["pawn", "knight", "bishop", "rook", "queen", "king"];
+
+// tables are written as seen from white's side (a8 top left), black uses them mirrored
+const PAWN: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+const PAWN_ENDGAME: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    80, 80, 80, 80, 80, 80, 80, 80,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    30, 30, 30, 30, 30, 30, 30, 30,
+    20, 20, 20, 20, 20, 20, 20, 20,
+    10, 10, 10, 10, 10, 10, 10, 10,
+     0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+const KNIGHT: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+const BISHOP: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+const ROOK: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+const QUEEN: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+const KING: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+const KING_ENDGAME: [i32; 64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Table {
+    pub material: i32,
+    pub squares: [i32; 64],
+}
+
+// middlegame and endgame tables per piece kind, interpolated by the amount of material left
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pst {
+    pub middlegame: [Table; 6],
+    pub endgame: [Table; 6],
+}
+
+impl Default for Pst {
+    fn default() -> Self {
+        let table = |material, squares| Table { material, squares };
+        Self {
+            middlegame: [
+                table(82, PAWN),
+                table(337, KNIGHT),
+                table(365, BISHOP),
+                table(477, ROOK),
+                table(1025, QUEEN),
+                table(0, KING),
+            ],
+            endgame: [
+                table(94, PAWN_ENDGAME),
+                table(281, KNIGHT),
+                table(297, BISHOP),
+                table(512, ROOK),
+                table(936, QUEEN),
+                table(0, KING_ENDGAME),
+            ],
+        }
+    }
+}
+
+impl Pst {
+    fn index(piece: Piece, coord: Coordinate) -> usize {
+        let rank = match piece.color {
+            Color::White => 7 - coord.rank as usize,
+            Color::Black => coord.rank as usize,
+        };
+        rank*8 + coord.file as usize
+    }
+
+    // MAX_PHASE with all the pieces on the board, 0 with only kings and pawns
+    pub fn phase(position: &Position) -> i32 {
+        let phase = position.pieces(Color::White).chain(position.pieces(Color::Black))
+            .map(|(piece, _)| match piece.kind {
+                PieceKind::Knight | PieceKind::Bishop => 1,
+                PieceKind::Rook => 2,
+                PieceKind::Queen => 4,
+                PieceKind::Pawn | PieceKind::King => 0,
+            })
+            .sum::<i32>();
+        phase.min(MAX_PHASE)
+    }
+
+    pub fn value(&self, piece: Piece, coord: Coordinate, phase: i32) -> i32 {
+        let i = Self::index(piece, coord);
+        let middlegame = &self.middlegame[piece.kind as usize];
+        let endgame = &self.endgame[piece.kind as usize];
+        let middlegame = middlegame.material + middlegame.squares[i];
+        let endgame = endgame.material + endgame.squares[i];
+        (middlegame*phase + endgame*(MAX_PHASE - phase)) / MAX_PHASE
+    }
+
+    // in centipawns from white's point of view
+    pub fn evaluate(&self, position: &Position) -> i32 {
+        let phase = Self::phase(position);
+        position.pieces(Color::White).map(|(piece, coord)| self.value(piece, coord, phase)).sum::<i32>()
+            - position.pieces(Color::Black).map(|(piece, coord)| self.value(piece, coord, phase)).sum::<i32>()
+    }
+
+    // flat view of every tunable value, in the same order as the params file
+    pub fn params(&self) -> Vec<i32> {
+        let mut params = Vec::with_capacity(PARAMS_COUNT);
+        for i in 0..6 {
+            for table in [&self.middlegame[i], &self.endgame[i]] {
+                params.push(table.material);
+                params.extend_from_slice(&table.squares);
+            };
+        };
+        params
+    }
+
+    // none (and nothing changed) if there aren't exactly PARAMS_COUNT of them
+    pub fn set_params(&mut self, params: &[i32]) -> Option<()> {
+        if params.len() != PARAMS_COUNT {
+            return None;
+        };
+
+        let tables = self.middlegame.iter_mut().zip(self.endgame.iter_mut()).flat_map(|(middlegame, endgame)| [middlegame, endgame]);
+        for (table, chunk) in tables.zip(params.chunks_exact(65)) {
+            table.material = chunk[0];
+            table.squares.copy_from_slice(&chunk[1..]);
+        };
+        Some(())
+    }
+
+    // one line per table: `<piece> <mg|eg> <material> <64 square values>`
+    #[cfg(feature = "std")]
+    pub fn save(&self, to: &str) -> std::io::Result<()> {
+        let mut file = std::fs::File::create(to)?;
+        for (i, name) in KIND_NAMES.iter().enumerate() {
+            for (stage, table) in [("mg", &self.middlegame[i]), ("eg", &self.endgame[i])] {
+                write!(file, "{name} {stage} {}", table.material)?;
+                for value in table.squares {
+                    write!(file, " {value}")?;
+                };
+                writeln!(file)?;
+            };
+        };
+        Ok(())
+    }
+
+    // tables missing from the file keep their default values
+    #[cfg(feature = "std")]
+    pub fn load(from: &str) -> std::io::Result<Self> {
+        let invalid = |line: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("malformed pst line: {line}"));
+
+        let mut pst = Self::default();
+        for line in BufReader::new(std::fs::File::open(from)?).lines() {
+            let line = line?;
+            let mut words = line.split_whitespace();
+            let Some(name) = words.next() else {
+                continue;
+            };
+
+            let i = KIND_NAMES.iter().position(|kind_name| *kind_name == name).ok_or_else(|| invalid(&line))?;
+            let table = match words.next() {
+                Some("mg") => &mut pst.middlegame[i],
+                Some("eg") => &mut pst.endgame[i],
+                _ => return Err(invalid(&line)),
+            };
+
+            let values = words.map(str::parse).collect::<Result<Vec<i32>, _>>().map_err(|_| invalid(&line))?;
+            if values.len() != 65 {
+                return Err(invalid(&line));
+            };
+
+            table.material = values[0];
+            table.squares.copy_from_slice(&values[1..]);
+        };
+
+        Ok(pst)
+    }
+}
@@ -0,0 +1,658 @@
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::fmt::{Display, Formatter};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::mpsc::Sender;
+#[cfg(feature = "std")]
+use std::thread::JoinHandle;
+use ress::{Board, GameOutcome, PlayerMove, CLAIMABLE_DRAW_PLIES};
+use ress::coordinate::Move;
+use ress::piece::{Color, PieceKind};
+use ress::position::Position;
+use ress::zobrist::PositionKey;
+use crate::Engine;
+use crate::ordering::MoveOrderer;
+use crate::pst::Pst;
+use crate::tt::{Bound, Entry, TranspositionTable};
+
+// anything above `MATE - u8::MAX` is a forced mate, closer mates score higher
+pub const MATE: i32 = 1_000_000;
+
+// a search score as people read it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i32),
+    // in moves, negative when it's the side to move getting mated (0 if it already is)
+    Mate(i32),
+}
+
+impl Score {
+    pub fn of(score: i32) -> Self {
+        if score.abs() > MATE - u8::MAX as i32 {
+            let moves = (MATE - score.abs() + 1)/2;
+            Self::Mate(if score > 0 { moves } else { -moves })
+        } else {
+            Self::Centipawns(score)
+        }
+    }
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Centipawns(score) => write!(f, "{score:+}cp"),
+            Self::Mate(moves) => write!(f, "#{moves}"),
+        }
+    }
+}
+
+// leaf evaluation in centipawns from white's point of view. the search handles positions that are over itself,
+// so those never get here.
+pub trait Evaluator {
+    fn evaluate(&self, position: &Position) -> i32;
+}
+
+impl Evaluator for Pst {
+    fn evaluate(&self, position: &Position) -> i32 {
+        Pst::evaluate(self, position)
+    }
+}
+
+impl Evaluator for Engine {
+    fn evaluate(&self, position: &Position) -> i32 {
+        (self.network_value(position)*1000.0) as i32
+    }
+}
+
+// how hard the search cuts corners, trading the odd missed move for searching deeper in the same time
+#[derive(Debug, Clone, Copy)]
+pub struct Pruning {
+    // depth taken off a null move's search on top of the ply it passes, 0 turns null moves off.
+    // if passing the turn still fails high, an actual move would too (unless it's zugzwang).
+    pub null_move_reduction: u8,
+    // late move reductions: quiet moves after this many get searched shallower first, and again at full depth only
+    // if they beat alpha. the ordering puts the likely good ones first, so the rest rarely do.
+    pub full_depth_moves: u8,
+    // no reductions below this depth
+    pub reduction_depth: u8,
+    // plies taken off a late move, 0 turns the reductions off
+    pub late_move_reduction: u8,
+}
+
+impl Pruning {
+    // every move at full depth
+    pub const NONE: Self = Self { null_move_reduction: 0, full_depth_moves: 0, reduction_depth: 0, late_move_reduction: 0 };
+}
+
+impl Default for Pruning {
+    fn default() -> Self {
+        Self { null_move_reduction: 2, full_depth_moves: 3, reduction_depth: 3, late_move_reduction: 1 }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SearchLimits {
+    pub depth: u8,
+    pub nodes: Option<u64>,
+    pub pruning: Pruning,
+    // how many of the best lines to report every iteration, the ones after the first only for watching
+    pub multi_pv: u8,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        Self { depth: 4, nodes: None, pruning: Pruning::default(), multi_pv: 1 }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SearchResult {
+    // none if the game is already over
+    pub best_move: Option<Move>,
+    // from the point of view of the side to move
+    pub score: i32,
+    // last fully searched depth
+    pub depth: u8,
+    pub nodes: u64,
+}
+
+// anything besides the depth and node limits that can end a search, like a clock or a `stop` from the gui
+pub trait Deadline {
+    // checked at every node, once true the search unwinds and answers with the last finished depth
+    fn hard_stop(&self) -> bool;
+
+    // checked between iterations, there's no point in starting a depth that won't finish
+    fn soft_stop(&self) -> bool {
+        self.hard_stop()
+    }
+
+    // how long the search has been running, if whatever stops it keeps a clock
+    fn elapsed(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl Deadline for () {
+    fn hard_stop(&self) -> bool {
+        false
+    }
+}
+
+// a plain flag someone else sets
+impl Deadline for AtomicBool {
+    fn hard_stop(&self) -> bool {
+        self.load(Ordering::Relaxed)
+    }
+}
+
+impl<D: Deadline + ?Sized> Deadline for &D {
+    fn hard_stop(&self) -> bool {
+        (**self).hard_stop()
+    }
+
+    fn soft_stop(&self) -> bool {
+        (**self).soft_stop()
+    }
+
+    fn elapsed(&self) -> Option<Duration> {
+        (**self).elapsed()
+    }
+}
+
+// whichever of the two stops first
+impl<A: Deadline, B: Deadline> Deadline for (A, B) {
+    fn hard_stop(&self) -> bool {
+        self.0.hard_stop() || self.1.hard_stop()
+    }
+
+    fn soft_stop(&self) -> bool {
+        self.0.soft_stop() || self.1.soft_stop()
+    }
+
+    fn elapsed(&self) -> Option<Duration> {
+        self.0.elapsed().or_else(|| self.1.elapsed())
+    }
+}
+
+// sent after every finished iteration
+#[derive(Debug, Clone)]
+pub struct SearchInfo {
+    pub depth: u8,
+    // from the point of view of the side to move
+    pub score: i32,
+    // searched by the reporting thread, helpers aren't counted
+    pub nodes: u64,
+    pub time: Option<Duration>,
+    // the best line as far as the table remembers it, starting with the best move
+    pub pv: Vec<Move>,
+    // which of the best lines it is, counting from 1, see `SearchLimits::multi_pv`
+    pub multipv: u8,
+}
+
+impl SearchInfo {
+    pub fn best_move(&self) -> Option<Move> {
+        self.pv.first().copied()
+    }
+
+    pub fn nps(&self) -> Option<u64> {
+        self.time.map(|time| (self.nodes as u128*1000/time.as_millis().max(1)) as u64)
+    }
+}
+
+// whoever wants to watch a search while it runs, e.g. a gui showing `info` lines
+pub trait Progress {
+    fn iteration(&self, info: &SearchInfo);
+}
+
+impl Progress for () {
+    fn iteration(&self, _: &SearchInfo) {}
+}
+
+impl<F: Fn(&SearchInfo)> Progress for F {
+    fn iteration(&self, info: &SearchInfo) {
+        self(info)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Progress for Sender<SearchInfo> {
+    fn iteration(&self, info: &SearchInfo) {
+        // nobody listening anymore isn't a reason to stop searching
+        let _ = self.send(info.clone());
+    }
+}
+
+// scores are relative to the root, entries to the node they're stored at, so mates are shifted by the ply on the way
+fn to_tt(score: i32, ply: u8) -> i32 {
+    match score {
+        score if score > MATE - u8::MAX as i32 => score + ply as i32,
+        score if score < -(MATE - u8::MAX as i32) => score - ply as i32,
+        score => score,
+    }
+}
+
+fn from_tt(score: i32, ply: u8) -> i32 {
+    match score {
+        score if score > MATE - u8::MAX as i32 => score - ply as i32,
+        score if score < -(MATE - u8::MAX as i32) => score + ply as i32,
+        score => score,
+    }
+}
+
+// the zobrist key, which tells apart what the fnv hash doesn't (the en passant square, what's in hand)
+pub(crate) fn table_key(board: &Board) -> PositionKey {
+    board.position_key().unwrap_or_else(|| board.position().key())
+}
+
+struct Searcher<'a, E, D> {
+    evaluator: &'a E,
+    deadline: &'a D,
+    tt: &'a TranspositionTable,
+    orderer: MoveOrderer,
+    limits: SearchLimits,
+    nodes: u64,
+    aborted: bool,
+    // set right before searching a null move, so its node doesn't pass right back
+    after_null: bool,
+    // root moves left out, for finding the lines after the best one
+    excluded: Vec<Move>,
+    // the keys of the game so far and of the line being searched, for telling repetitions
+    keys: Vec<PositionKey>,
+}
+
+impl<E: Evaluator, D: Deadline> Searcher<'_, E, D> {
+    // counts the node, true if it's one too many
+    fn exhausted(&mut self) -> bool {
+        self.nodes += 1;
+        if self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes) || self.deadline.hard_stop() {
+            self.aborted = true;
+        };
+        self.aborted
+    }
+
+    // whether the side to move has anything but pawns (and the king). without, zugzwang is too likely to pass the turn.
+    fn has_pieces(position: &Position) -> bool {
+        position.pieces(position.move_color).any(|(piece, _)| !matches!(piece.kind, PieceKind::Pawn | PieceKind::King))
+    }
+
+    // how a move scores when it ends the game, none if it doesn't. the position is the one after the move.
+    fn terminal(&self, position: &Position, ply: u8) -> Option<i32> {
+        match position.outcome() {
+            // not always for the mover, e.g. leaving antichess' opponent without moves wins it for them
+            Some(GameOutcome::Decisive { won, .. }) if won != position.move_color => Some(MATE - ply as i32 - 1),
+            Some(GameOutcome::Decisive { .. }) => Some(-(MATE - ply as i32 - 1)),
+            Some(GameOutcome::Draw(_)) => Some(0),
+            None => None,
+        }
+    }
+
+    // whether the position after a move could be claimed a draw: 50 moves, or a third time there.
+    // only the plies since the last capture or pawn move can repeat it.
+    fn claimable(&self, position: &Position, key: PositionKey) -> bool {
+        position.stale_plies >= CLAIMABLE_DRAW_PLIES ||
+            self.keys.iter().rev().take(position.stale_plies as usize).filter(|earlier| **earlier == key).count() >= 2
+    }
+
+    // moves are made and unmade on the position in place, it's as it was when this returns
+    fn negamax(&mut self, position: &mut Position, depth: u8, ply: u8, mut alpha: i32, beta: i32, hint: Option<Move>) -> (i32, Option<Move>) {
+        // two null moves in a row would just be the same position searched shallower
+        let after_null = core::mem::replace(&mut self.after_null, false);
+        if self.exhausted() {
+            return (0, None);
+        };
+
+        if depth == 0 {
+            return (self.quiescence(position, ply, alpha, beta), None);
+        };
+
+        let key = position.key();
+        let entry = self.tt.probe(key);
+        // the root always searches, it has to come up with a move
+        if let Some(entry) = entry.filter(|entry| ply > 0 && entry.depth >= depth) {
+            let score = from_tt(entry.score, ply);
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if cutoff {
+                return (score, entry.best_move);
+            };
+        };
+        let hint = hint.or(entry.and_then(|entry| entry.best_move));
+        let original_alpha = alpha;
+        let in_check = position.in_check();
+        let pruning = self.limits.pruning;
+
+        let reduction = pruning.null_move_reduction;
+        if !after_null && ply > 0 && reduction > 0 && depth > reduction && !in_check && beta.abs() < MATE - u8::MAX as i32 && Self::has_pieces(position) {
+            let null = position.make_null_move();
+            self.after_null = true;
+            let score = -self.negamax(position, depth - 1 - reduction, ply + 1, -beta, -beta + 1, None).0;
+            position.unmake_null_move(null);
+            if self.aborted {
+                return (0, None);
+            };
+            if score >= beta {
+                return (beta, None);
+            };
+        };
+
+        let mover = position.move_color;
+        let mut moves = position.legal_moves();
+        self.orderer.order(position, &mut moves, ply, hint);
+
+        let mut best = (-MATE, None);
+        for (i, r#move) in moves.into_iter().enumerate() {
+            if ply == 0 && self.excluded.contains(&r#move) {
+                continue;
+            };
+            let quiet = MoveOrderer::is_quiet(position, r#move);
+            let unmake = position.make(r#move);
+            let child = position.key();
+            let score = match self.terminal(position, ply) {
+                Some(score) => score,
+                // a repetition or 50 moves the opponent can claim are as good as a draw
+                None if self.claimable(position, child) => 0,
+                None => {
+                    self.keys.push(child);
+                    let late = pruning.late_move_reduction > 0 && i >= pruning.full_depth_moves as usize && depth >= pruning.reduction_depth &&
+                        quiet && !in_check && !position.in_check();
+                    let reduced = late.then(|| {
+                        let depth = (depth - 1).saturating_sub(pruning.late_move_reduction);
+                        -self.negamax(position, depth, ply + 1, -alpha - 1, -alpha, None).0
+                    });
+                    let score = match reduced {
+                        Some(score) if score <= alpha => score,
+                        _ => -self.negamax(position, depth - 1, ply + 1, -beta, -alpha, None).0,
+                    };
+                    self.keys.pop();
+                    score
+                },
+            };
+            position.unmake(r#move, unmake);
+
+            if self.aborted {
+                return best;
+            };
+
+            if score > best.0 || best.1.is_none() {
+                best = (score, Some(r#move));
+            };
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                if quiet {
+                    self.orderer.cutoff(mover, r#move, ply, depth);
+                };
+                break;
+            };
+        };
+
+        // a root without its best moves doesn't score like the position does
+        if best.1.is_some() && (ply > 0 || self.excluded.is_empty()) {
+            let bound = if best.0 <= original_alpha {
+                Bound::Upper
+            } else if best.0 >= beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            self.tt.store(key, Entry { score: to_tt(best.0, ply), depth, bound, best_move: best.1 });
+        };
+
+        best
+    }
+
+    fn evaluate(&self, position: &Position) -> i32 {
+        let eval = self.evaluator.evaluate(position);
+        match position.move_color {
+            Color::White => eval,
+            Color::Black => -eval,
+        }
+    }
+
+    // keeps playing out captures until the position is quiet, so a leaf isn't evaluated mid exchange.
+    // the side to move may always stop capturing, hence the static eval is a lower bound ("stand pat").
+    // a capture never repeats a position, so there are no keys to keep here.
+    fn quiescence(&mut self, position: &mut Position, ply: u8, mut alpha: i32, beta: i32) -> i32 {
+        let stand_pat = self.evaluate(position);
+        if stand_pat >= beta {
+            return stand_pat;
+        };
+        alpha = alpha.max(stand_pat);
+
+        let mut best = stand_pat;
+        let mut moves = position.capture_moves();
+        self.orderer.order(position, &mut moves, ply, None);
+        for r#move in moves {
+            if self.exhausted() {
+                return best;
+            };
+
+            let unmake = position.make(r#move);
+            let score = match self.terminal(position, ply) {
+                Some(score) => score,
+                None if position.stale_plies >= CLAIMABLE_DRAW_PLIES => 0,
+                None => -self.quiescence(position, ply.saturating_add(1), -beta, -alpha),
+            };
+            position.unmake(r#move, unmake);
+
+            if self.aborted {
+                return best;
+            };
+
+            best = best.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            };
+        };
+
+        best
+    }
+}
+
+pub fn search(board: &Board, evaluator: &impl Evaluator, limits: SearchLimits) -> SearchResult {
+    search_until(board, evaluator, limits, &())
+}
+
+pub fn search_until(board: &Board, evaluator: &impl Evaluator, limits: SearchLimits, deadline: &impl Deadline) -> SearchResult {
+    search_with(board, evaluator, limits, deadline, &TranspositionTable::new(1 << 16), &())
+}
+
+// the position to search from, or the result right away if there's nothing to search
+fn root(board: &Board) -> Result<Board, SearchResult> {
+    let mut result = SearchResult { best_move: None, score: 0, depth: 0, nodes: 0 };
+    if let Some(GameOutcome::Decisive { won, .. }) = board.game_outcome {
+        result.score = if won == board.move_color { MATE } else { -MATE };
+    };
+    if board.game_outcome.is_some() {
+        return Err(result);
+    };
+
+    let board = board.clone();
+    if !board.has_at_least(board.move_color, 1) {
+        let king = board.pieces_of(board.move_color, PieceKind::King).next();
+        if king.is_some_and(|king| board.is_under_attack(board.move_color.the_other(), king, None)) {
+            result.score = -MATE;
+        };
+        return Err(result);
+    };
+
+    Ok(board)
+}
+
+// follows the best moves the table has stored, as long as they're legal, at most `depth` of them
+fn principal_variation(board: &Board, tt: &TranspositionTable, best_move: Move, depth: u8) -> Vec<Move> {
+    let mut pv = Vec::new();
+    let mut board = board.clone();
+    let mut next = Some(best_move);
+    while let Some(r#move) = next {
+        if pv.len() >= depth as usize || board.play_move(PlayerMove::Internal(r#move)).is_err() {
+            break;
+        };
+        pv.push(r#move);
+        next = tt.probe(table_key(&board)).and_then(|entry| entry.best_move);
+    };
+    pv
+}
+
+// iterative deepening, so running out of nodes or time still leaves the last completed depth to answer with
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(first_depth = first_depth)))]
+fn iterate(board: &Board, evaluator: &impl Evaluator, limits: SearchLimits, deadline: &impl Deadline, tt: &TranspositionTable, progress: &impl Progress, first_depth: u8) -> SearchResult {
+    let mut result = SearchResult { best_move: None, score: 0, depth: 0, nodes: 0 };
+    let keys = board.position_keys().to_vec();
+    let mut searcher = Searcher { evaluator, deadline, tt, orderer: MoveOrderer::new(), limits, nodes: 0, aborted: false, after_null: false, excluded: Vec::new(), keys };
+    // the board only has the game so far, the search makes its moves on the position
+    let mut root = board.position();
+    for depth in first_depth.min(limits.depth).max(1)..=limits.depth.max(1) {
+        if depth > first_depth && deadline.soft_stop() {
+            break;
+        };
+
+        let (score, best_move) = searcher.negamax(&mut root, depth, 0, -MATE, MATE, result.best_move);
+        if searcher.aborted {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(depth, nodes = searcher.nodes, "iteration aborted");
+            // a partial iteration is only worth anything if nothing finished before it
+            if result.best_move.is_none() {
+                result.best_move = best_move;
+                result.score = score;
+            };
+            break;
+        };
+
+        result.best_move = best_move;
+        result.score = score;
+        result.depth = depth;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(depth, score, nodes = searcher.nodes, "iteration finished");
+        let pv = best_move.map(|best_move| principal_variation(board, tt, best_move, depth)).unwrap_or_default();
+        progress.iteration(&SearchInfo { depth, score, nodes: searcher.nodes, time: deadline.elapsed(), pv, multipv: 1 });
+
+        // every next line is the best one without the moves the lines before it start with
+        searcher.excluded.extend(best_move);
+        for multipv in 2..=limits.multi_pv {
+            let (score, best_move) = searcher.negamax(&mut root, depth, 0, -MATE, MATE, None);
+            let Some(best_move) = best_move.filter(|_| !searcher.aborted) else {
+                break;
+            };
+            searcher.excluded.push(best_move);
+            let pv = principal_variation(board, tt, best_move, depth);
+            progress.iteration(&SearchInfo { depth, score, nodes: searcher.nodes, time: deadline.elapsed(), pv, multipv });
+        };
+        searcher.excluded.clear();
+        if searcher.aborted {
+            break;
+        };
+    };
+    result.nodes = searcher.nodes;
+
+    result
+}
+
+// searching with a table that outlives the search, e.g. kept between the moves of a game
+pub fn search_with(board: &Board, evaluator: &impl Evaluator, limits: SearchLimits, deadline: &impl Deadline, tt: &TranspositionTable, progress: &impl Progress) -> SearchResult {
+    match root(board) {
+        Ok(board) => iterate(&board, evaluator, limits, deadline, tt, progress, 1),
+        Err(result) => result,
+    }
+}
+
+// stops the helper threads as soon as the main one is done
+#[cfg(feature = "std")]
+struct Helper<'a, D> {
+    deadline: &'a D,
+    done: &'a AtomicBool,
+}
+
+#[cfg(feature = "std")]
+impl<D: Deadline> Deadline for Helper<'_, D> {
+    fn hard_stop(&self) -> bool {
+        self.done.load(Ordering::Relaxed) || self.deadline.hard_stop()
+    }
+
+    fn soft_stop(&self) -> bool {
+        self.done.load(Ordering::Relaxed) || self.deadline.soft_stop()
+    }
+
+    fn elapsed(&self) -> Option<Duration> {
+        self.deadline.elapsed()
+    }
+}
+
+// lazy smp: every thread runs the same search, only sharing the table. the helpers fill it
+// with results the main thread then finds ready, they start on alternating depths so they don't all walk in lockstep.
+// only the main thread reports progress, and it does so from the calling thread.
+#[cfg(feature = "std")]
+pub fn search_parallel<E: Evaluator + Sync, D: Deadline + Sync>(board: &Board, evaluator: &E, limits: SearchLimits, deadline: &D, tt: &TranspositionTable, progress: &impl Progress, threads: usize) -> SearchResult {
+    let board = match root(board) {
+        Ok(board) => board,
+        Err(result) => return result,
+    };
+    if threads <= 1 {
+        return iterate(&board, evaluator, limits, deadline, tt, progress, 1);
+    };
+
+    let done = AtomicBool::new(false);
+    let helper = Helper { deadline, done: &done };
+    std::thread::scope(|scope| {
+        let helpers = (1..threads).map(|i| {
+            let (board, helper) = (&board, &helper);
+            // the lines after the best one are only for watching, which nobody does with a helper
+            let limits = SearchLimits { multi_pv: 1, ..limits };
+            scope.spawn(move || iterate(board, evaluator, limits, helper, tt, &(), 1 + (i % 2) as u8).nodes)
+        }).collect::<Vec<_>>();
+
+        let mut result = iterate(&board, evaluator, limits, deadline, tt, progress, 1);
+        done.store(true, Ordering::Relaxed);
+        // a helper that panicked only takes its node count with it, the table it filled is still fine
+        result.nodes += helpers.into_iter().filter_map(|helper| helper.join().ok()).sum::<u64>();
+        result
+    })
+}
+
+// a search running on its own thread. dropping the handle stops it and waits for the thread,
+// so nothing keeps burning cpu for a game that's gone.
+#[cfg(feature = "std")]
+pub struct SearchHandle {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<SearchResult>>,
+}
+
+#[cfg(feature = "std")]
+impl SearchHandle {
+    // `search` is handed a flag it has to give up on once it's set, e.g. by using it as (part of) its deadline
+    pub fn spawn(search: impl FnOnce(&AtomicBool) -> SearchResult + Send + 'static) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&stop);
+        Self { stop, thread: Some(std::thread::spawn(move || search(&flag))) }
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    // waits for the search to end on its own, none if it panicked
+    pub fn join(mut self) -> Option<SearchResult> {
+        self.thread.take()?.join().ok()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for SearchHandle {
+    fn drop(&mut self) {
+        self.stop();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        };
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+use rand::Rng;
+use rand::seq::index;
+use rayon::prelude::*;
+use ress::Board;
+use ress::coordinate::Move;
+use ress::epd::{Epd, EpdError};
+use ress::fen::{self, Dialect, FenError};
+use crate::search::{search_until, Evaluator, SearchLimits};
+use crate::time::{TimeControl, TimeManager};
+
+// the suite used when none is given, every game is played from these with both colors,
+// otherwise two deterministic engines just repeat one game
+pub const OPENINGS: [&str; 8] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",  // starting
+    "rnbq1bnr/ppppkppp/8/4p3/4P3/8/PPPPKPPP/RNBQ1BNR w - - 2 3", // double bongcloud
+    "rnbqk2r/pppp1ppp/5n2/2b1p3/2B1P3/2N5/PPPP1PPP/R1BQK1NR w KQkq - 4 4", // vienna
+    "rnbqkb1r/ppp2ppp/3p4/8/3Pn3/5N2/PPP2PPP/RNBQKB1R b KQkq - 0 5",  // petrov's
+    "rnbqkb1r/pp3p1p/3p1np1/2pP4/4PP2/2N5/PP4PP/R1BQKBNR b KQkq f3 0 7", // "The Flick-Knife Attack"
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 4 4",  // four knights
+    "rnb1kbnr/ppp1pppp/8/q7/8/2N5/PPPP1PPP/R1BQKBNR w KQkq - 2 4",  // scandi
+    "rn1qkbnr/pp2pppp/2p5/3pPb2/3P4/8/PPP2PPP/RNBQKBNR w KQkq - 1 4",  // caro-kann advanced
+];
+
+#[derive(Debug)]
+pub enum SuiteError {
+    Io(std::io::Error),
+    // the line, counting from 1, and why it isn't a position
+    Position(usize, FenError),
+    // the same for a test suite's records
+    Record(usize, EpdError),
+    Empty,
+}
+
+impl Display for SuiteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Position(line, err) => write!(f, "line {line}: {err}"),
+            Self::Record(line, err) => write!(f, "line {line}: {err}"),
+            Self::Empty => write!(f, "there are no positions in the suite"),
+        }
+    }
+}
+
+impl std::error::Error for SuiteError {}
+
+impl From<std::io::Error> for SuiteError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OpeningSuite {
+    pub openings: Vec<Board>,
+}
+
+impl Default for OpeningSuite {
+    fn default() -> Self {
+        Self { openings: OPENINGS.iter().map(|fen| Board::from_fen(fen).unwrap()).collect() }
+    }
+}
+
+impl OpeningSuite {
+    // one position per line, as a fen or an epd (whose operations are ignored).
+    // empty lines and ones starting with `#` are skipped.
+    pub fn parse(raw: &str) -> Result<Self, SuiteError> {
+        let openings = records(raw)
+            .map(|(i, line)| {
+                // a fen has its clocks as the 5th and 6th field, an epd has operations there if anything
+                let is_fen = line.split_whitespace().nth(4).is_some_and(|field| field.parse::<u8>().is_ok());
+                match is_fen {
+                    true => fen::parse(line, Dialect::Standard).map(|(board, _)| board),
+                    false => fen::parse_epd(line).map(|(board, _)| board),
+                }.map_err(|err| SuiteError::Position(i, err))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if openings.is_empty() {
+            return Err(SuiteError::Empty);
+        };
+        Ok(Self { openings })
+    }
+
+    pub fn load(path: &str) -> Result<Self, SuiteError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.openings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.openings.is_empty()
+    }
+
+    // `count` different openings picked at random, all of them when there aren't more.
+    // a big suite is sampled like this for every round of fitness evaluation instead of played whole.
+    pub fn choose(&self, count: usize, rng: &mut impl Rng) -> Vec<Board> {
+        if count >= self.openings.len() {
+            return self.openings.clone();
+        };
+        index::sample(rng, self.openings.len(), count).into_iter().map(|i| self.openings[i].clone()).collect()
+    }
+}
+
+// the lines of a suite that are worth reading, counting from 1. empty lines and ones starting with `#` are skipped.
+fn records(raw: &str) -> impl Iterator<Item = (usize, &str)> {
+    raw.lines().enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+}
+
+// a test suite like wac or sts, one epd per line with what solves it in `bm` or `am`
+#[derive(Debug, Clone)]
+pub struct TestSuite {
+    pub positions: Vec<Epd>,
+}
+
+impl TestSuite {
+    pub fn parse(raw: &str) -> Result<Self, SuiteError> {
+        let positions = records(raw)
+            .map(|(i, line)| Epd::parse(line).map_err(|err| SuiteError::Record(i, err)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if positions.is_empty() {
+            return Err(SuiteError::Empty);
+        };
+        Ok(Self { positions })
+    }
+
+    pub fn load(path: &str) -> Result<Self, SuiteError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    // searches every position with the limits, and for at most `time` each if there's one.
+    // the positions are spread over rayon's threads with a single-threaded search each, so the times hold per position.
+    pub fn run(&self, evaluator: &(impl Evaluator + Sync), limits: SearchLimits, time: Option<Duration>) -> SuiteReport {
+        let results = self.positions.par_iter().map(|epd| {
+            let control = TimeControl { movetime: time, ..Default::default() };
+            let deadline = TimeManager::new(&control, epd.board.move_color);
+            let result = search_until(&epd.board, evaluator, limits, &deadline);
+            TestResult {
+                id: epd.id.clone(),
+                best_move: result.best_move,
+                solved: result.best_move.is_some_and(|r#move| epd.is_solved_by(r#move)),
+                score: result.score,
+                depth: result.depth,
+                nodes: result.nodes,
+            }
+        }).collect();
+        SuiteReport { results }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub id: Option<String>,
+    // none if the game was already over
+    pub best_move: Option<Move>,
+    pub solved: bool,
+    pub score: i32,
+    pub depth: u8,
+    pub nodes: u64,
+}
+
+// the results in the suite's order
+#[derive(Debug, Clone)]
+pub struct SuiteReport {
+    pub results: Vec<TestResult>,
+}
+
+impl SuiteReport {
+    pub fn solved(&self) -> usize {
+        self.results.iter().filter(|result| result.solved).count()
+    }
+
+    pub fn total(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn nodes(&self) -> u64 {
+        self.results.iter().map(|result| result.nodes).sum()
+    }
+}
+
+impl Display for SuiteReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{} solved", self.solved(), self.total())
+    }
+}
+
+// one game to play, the engines and the opening by their indices
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pairing {
+    pub white: usize,
+    pub black: usize,
+    pub opening: usize,
+}
+
+// every game between each pair of engines, `rounds` times over every opening with both colors
+pub fn schedule(pairs: &[(usize, usize)], openings: usize, rounds: usize) -> Vec<Pairing> {
+    pairs.iter()
+        .flat_map(|(a, b)| (0..rounds).flat_map(move |_| [(*a, *b), (*b, *a)]))
+        .flat_map(|(white, black)| (0..openings).map(move |opening| Pairing { white, black, opening }))
+        .collect()
+}
+
+// plays the games spread over rayon's threads, each handed its index in the schedule.
+// the results come back in the schedule's order.
+pub fn run<R: Send>(schedule: &[Pairing], play: impl Fn(usize, Pairing) -> R + Sync) -> Vec<R> {
+    schedule.par_iter().enumerate().map(|(i, pairing)| play(i, *pairing)).collect()
+}
@@ -0,0 +1,348 @@
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use ress::Board;
+use ress::coordinate::{Coordinate, File, Move, Rank};
+use ress::editor::BoardEditor;
+use ress::piece::{Color, Piece, PieceKind};
+use ress::PlayerMove;
+
+// perfect play for the endings of a king and one piece against a lone king. they're small enough
+// (64³ squares, two sides to move) to generate in well under a second instead of shipping files:
+// starting from the mates, every position gets the number of plies to mate with the best play from both sides.
+
+// the piece the stronger side has next to its king
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ending {
+    Queen,
+    Rook,
+    Pawn,
+}
+
+impl Ending {
+    fn of(kind: PieceKind) -> Option<Self> {
+        match kind {
+            PieceKind::Queen => Some(Self::Queen),
+            PieceKind::Rook => Some(Self::Rook),
+            PieceKind::Pawn => Some(Self::Pawn),
+            _ => None,
+        }
+    }
+
+    fn kind(self) -> PieceKind {
+        match self {
+            Self::Queen => PieceKind::Queen,
+            Self::Rook => PieceKind::Rook,
+            Self::Pawn => PieceKind::Pawn,
+        }
+    }
+}
+
+// for the side to move, with the plies until mate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Win(u8),
+    Draw,
+    Loss(u8),
+}
+
+impl Outcome {
+    // the outcome of the position before, for the other side
+    fn back(self) -> Self {
+        match self {
+            Self::Win(plies) => Self::Loss(plies + 1),
+            Self::Draw => Self::Draw,
+            Self::Loss(plies) => Self::Win(plies + 1),
+        }
+    }
+}
+
+impl PartialOrd for Outcome {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// better is greater: a quicker win, then a draw, then a slower loss
+impl Ord for Outcome {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let rank = |outcome: &Self| match outcome {
+            Self::Win(plies) => (2, -(*plies as i16)),
+            Self::Draw => (1, 0),
+            Self::Loss(plies) => (0, *plies as i16),
+        };
+        rank(self).cmp(&rank(other))
+    }
+}
+
+// plies to mate, both for the stronger side to move (a win) and the lone king to move (a loss)
+const UNKNOWN: u8 = u8::MAX;
+const SIZE: usize = 2*64*64*64;
+const KING_STEPS: [(i8, i8); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+const ROOK_STEPS: [(i8, i8); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+fn index(strong_to_move: bool, strong_king: u8, piece: u8, weak_king: u8) -> usize {
+    (((!strong_to_move as usize)*64 + strong_king as usize)*64 + piece as usize)*64 + weak_king as usize
+}
+
+fn step(square: u8, (files, ranks): (i8, i8)) -> Option<u8> {
+    let (file, rank) = ((square % 8) as i8 + files, (square / 8) as i8 + ranks);
+    ((0..8).contains(&file) && (0..8).contains(&rank)).then_some((rank*8 + file) as u8)
+}
+
+fn adjacent(a: u8, b: u8) -> bool {
+    a != b && (a % 8).abs_diff(b % 8) <= 1 && (a / 8).abs_diff(b / 8) <= 1
+}
+
+fn slides(kind: PieceKind) -> &'static [(i8, i8)] {
+    match kind {
+        PieceKind::Queen => &KING_STEPS,
+        PieceKind::Rook => &ROOK_STEPS,
+        _ => &[],
+    }
+}
+
+// whether the piece attacks `target` with `blocker` in the way, the stronger side is always white (moving up)
+fn attacks(kind: PieceKind, piece: u8, target: u8, blocker: u8) -> bool {
+    if kind == PieceKind::Pawn {
+        return [(-1, 1), (1, 1)].iter().any(|of| step(piece, *of) == Some(target));
+    };
+    slides(kind).iter().any(|of| {
+        let mut square = piece;
+        while let Some(next) = step(square, *of) {
+            if next == target {
+                return true;
+            };
+            if next == blocker {
+                return false;
+            };
+            square = next;
+        };
+        false
+    })
+}
+
+// where a position can go
+enum Child {
+    Here(usize),
+    // the lone king took the piece
+    Drawn,
+    // the pawn promoted, to a position in another table with the lone king to move
+    Promoted(Ending, usize),
+}
+
+pub struct Tablebase {
+    ending: Ending,
+    plies: Vec<u8>,
+}
+
+impl Tablebase {
+    fn legal(&self, strong_to_move: bool, strong_king: u8, piece: u8, weak_king: u8) -> bool {
+        let kind = self.ending.kind();
+        strong_king != piece && piece != weak_king && strong_king != weak_king && !adjacent(strong_king, weak_king) &&
+            (kind != PieceKind::Pawn || (8..56).contains(&piece)) &&
+            // the lone king can't be in check with the other side to move
+            !(strong_to_move && attacks(kind, piece, weak_king, strong_king))
+    }
+
+    fn children(&self, strong_to_move: bool, strong_king: u8, piece: u8, weak_king: u8, children: &mut Vec<Child>) {
+        children.clear();
+        let kind = self.ending.kind();
+        if !strong_to_move {
+            for to in KING_STEPS.iter().filter_map(|of| step(weak_king, *of)) {
+                if adjacent(to, strong_king) || to == strong_king || (to != piece && attacks(kind, piece, to, strong_king)) {
+                    continue;
+                };
+                children.push(if to == piece { Child::Drawn } else { Child::Here(index(true, strong_king, piece, to)) });
+            };
+            return;
+        };
+
+        for to in KING_STEPS.iter().filter_map(|of| step(strong_king, *of)) {
+            if to != piece && !adjacent(to, weak_king) {
+                children.push(Child::Here(index(false, to, piece, weak_king)));
+            };
+        };
+
+        if kind == PieceKind::Pawn {
+            let free = |square: u8| square != strong_king && square != weak_king;
+            let mut pushes = Vec::new();
+            if let Some(one) = step(piece, (0, 1)).filter(|square| free(*square)) {
+                pushes.push(one);
+                if let Some(two) = step(one, (0, 1)).filter(|square| piece / 8 == 1 && free(*square)) {
+                    pushes.push(two);
+                };
+            };
+            for to in pushes {
+                // knights and bishops can't mate alone, so only the queen and the rook are worth it
+                children.extend(match to >= 56 {
+                    true => [Ending::Queen, Ending::Rook].map(|ending| Child::Promoted(ending, index(false, strong_king, to, weak_king))).into_iter().collect::<Vec<_>>(),
+                    false => alloc::vec![Child::Here(index(false, strong_king, to, weak_king))],
+                });
+            };
+            return;
+        };
+
+        for of in slides(kind) {
+            let mut square = piece;
+            while let Some(next) = step(square, *of) {
+                if next == strong_king || next == weak_king {
+                    break;
+                };
+                children.push(Child::Here(index(false, strong_king, next, weak_king)));
+                square = next;
+            };
+        };
+    }
+
+    // `promoted` has to hold the queen and rook tables for the pawn one
+    fn generate(ending: Ending, promoted: &[&Tablebase]) -> Self {
+        let mut table = Self { ending, plies: alloc::vec![UNKNOWN; SIZE] };
+        // every position's children are worked out once: those in this table by index, the others
+        // (a capture or a promotion) by how many plies they take, offset by SIZE
+        let promoted_plies = |ending: Ending, i: usize| promoted.iter().find(|table| table.ending == ending).map_or(UNKNOWN, |table| table.plies[i]);
+        let (mut children, mut edges) = (Vec::new(), Vec::new());
+        // the positions still unknown as (index, edges start, edges end), split by the side to move
+        let (mut strong, mut weak) = (Vec::new(), Vec::new());
+        for i in 0..SIZE {
+            let (strong_to_move, strong_king, piece, weak_king) = (i / (64*64*64) == 0, (i / (64*64) % 64) as u8, (i / 64 % 64) as u8, (i % 64) as u8);
+            if !table.legal(strong_to_move, strong_king, piece, weak_king) {
+                continue;
+            };
+            table.children(strong_to_move, strong_king, piece, weak_king, &mut children);
+            let start = edges.len() as u32;
+            edges.extend(children.iter().map(|child| match child {
+                Child::Here(i) => *i as u32,
+                Child::Drawn => (SIZE + UNKNOWN as usize) as u32,
+                Child::Promoted(ending, i) => (SIZE + promoted_plies(*ending, *i) as usize) as u32,
+            }));
+            if strong_to_move { &mut strong } else { &mut weak }.push((i, start, edges.len() as u32));
+        };
+
+        weak.retain(|&(i, start, end)| {
+            let (strong_king, piece, weak_king) = ((i / (64*64) % 64) as u8, (i / 64 % 64) as u8, (i % 64) as u8);
+            let mated = start == end && attacks(ending.kind(), piece, weak_king, strong_king);
+            if mated {
+                table.plies[i] = 0;
+            };
+            !mated
+        });
+
+        // the promotions lead to positions that take this long, nothing can be done before them
+        let floor = promoted.iter().flat_map(|table| table.plies.iter()).filter(|plies| **plies != UNKNOWN).max().copied().unwrap_or(0);
+        for plies in 1..UNKNOWN {
+            // the stronger side mates in odd plies, the lone king gets mated in even ones.
+            // either only looks at the other's positions, so they can all be set once the pass is done.
+            let (strong_to_move, pending) = if plies % 2 == 1 { (true, &mut strong) } else { (false, &mut weak) };
+            let mut mated = Vec::new();
+            pending.retain(|&(i, start, end)| {
+                let mut known = edges[start as usize..end as usize].iter().map(|edge| match (*edge as usize).checked_sub(SIZE) {
+                    Some(plies) => plies as u8,
+                    None => table.plies[*edge as usize],
+                });
+                let done = match strong_to_move {
+                    true => known.any(|child| child < plies),
+                    false => start != end && known.all(|child| child < plies),
+                };
+                if done {
+                    mated.push(i);
+                };
+                !done
+            });
+            let changed = !mated.is_empty();
+            for i in mated {
+                table.plies[i] = plies;
+            };
+            if !changed && plies % 2 == 0 && plies > floor {
+                break;
+            };
+        };
+        table
+    }
+}
+
+// the queen, rook and pawn endings
+pub struct Tablebases {
+    tables: [Tablebase; 3],
+}
+
+impl Default for Tablebases {
+    fn default() -> Self {
+        Self::generate()
+    }
+}
+
+impl Tablebases {
+    pub fn generate() -> Self {
+        let queen = Tablebase::generate(Ending::Queen, &[]);
+        let rook = Tablebase::generate(Ending::Rook, &[]);
+        let pawn = Tablebase::generate(Ending::Pawn, &[&queen, &rook]);
+        Self { tables: [queen, rook, pawn] }
+    }
+
+    fn table(&self, ending: Ending) -> &Tablebase {
+        &self.tables[ending as usize]
+    }
+
+    // none unless it's one of the endings, or bare kings and a minor piece which are simply drawn
+    pub fn probe(&self, board: &Board) -> Option<Outcome> {
+        if board.game_outcome.is_some() {
+            return None;
+        };
+        let pieces = board.pieces(Color::White).chain(board.pieces(Color::Black)).filter(|(piece, _)| piece.kind != PieceKind::King).collect::<Vec<_>>();
+        let (piece, coord) = match pieces.as_slice() {
+            [] => return Some(Outcome::Draw),
+            [(piece, coord)] => (*piece, *coord),
+            _ => return None,
+        };
+        let Some(ending) = Ending::of(piece.kind) else {
+            return matches!(piece.kind, PieceKind::Knight | PieceKind::Bishop).then_some(Outcome::Draw);
+        };
+
+        // the tables have the stronger side as white, so a black one gets the board mirrored
+        let square = |coord: Coordinate| {
+            let rank = if piece.color == Color::White { coord.rank as u8 } else { 7 - coord.rank as u8 };
+            rank*8 + coord.file as u8
+        };
+        let king = |color| board.pieces_of(color, PieceKind::King).next();
+        let (strong_king, weak_king) = (king(piece.color)?, king(piece.color.the_other())?);
+        let strong_to_move = board.move_color == piece.color;
+        let plies = self.table(ending).plies[index(strong_to_move, square(strong_king), square(coord), square(weak_king))];
+
+        Some(match (plies, strong_to_move) {
+            (UNKNOWN, _) => Outcome::Draw,
+            (plies, true) => Outcome::Win(plies),
+            (plies, false) => Outcome::Loss(plies),
+        })
+    }
+
+    // the best move for the side to move and where it leads, none if the position isn't in the tables or is over
+    pub fn best_move(&self, board: &Board) -> Option<(Move, Outcome)> {
+        self.probe(board)?;
+        board.possible_moves(board.move_color).into_iter().filter_map(|r#move| {
+            let mut child = board.clone();
+            child.play_move(PlayerMove::Internal(r#move)).ok()?;
+            let outcome = match child.game_outcome {
+                Some(ress::GameOutcome::Decisive { .. }) => Outcome::Win(1),
+                Some(ress::GameOutcome::Draw(_)) => Outcome::Draw,
+                None => self.probe(&child)?.back(),
+            };
+            Some((r#move, outcome))
+        }).max_by_key(|(_, outcome)| *outcome)
+    }
+
+    // a position with white to move and mate in at least `min_plies` (which it can't be if that's too many),
+    // the first one found after `seed`
+    pub fn position(&self, ending: Ending, min_plies: u8, seed: u64) -> Option<Board> {
+        let table = self.table(ending);
+        let half = SIZE/2;
+        let start = (seed % half as u64) as usize;
+        let i = (start..half).chain(0..start).find(|i| table.plies[*i] != UNKNOWN && table.plies[*i] >= min_plies)?;
+
+        let coord = |square: usize| Some(Coordinate { file: File::try_from((square % 8) as i8).ok()?, rank: Rank::try_from((square / 8) as i8).ok()? });
+        let mut editor = BoardEditor::default();
+        editor.put(coord(i / (64*64) % 64)?, Some(Piece { kind: PieceKind::King, color: Color::White }));
+        editor.put(coord(i / 64 % 64)?, Some(Piece { kind: ending.kind(), color: Color::White }));
+        editor.put(coord(i % 64)?, Some(Piece { kind: PieceKind::King, color: Color::Black }));
+        editor.build().ok()
+    }
+}
@@ -0,0 +1,88 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use ress::piece::Color;
+use crate::search::Deadline;
+
+// kept back from every allocation for the gui/transport lag
+const OVERHEAD: Duration = Duration::from_millis(30);
+// how many moves the remaining time is split over when the gui doesn't say
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+// the clock part of a uci `go`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeControl {
+    pub movetime: Option<Duration>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    pub moves_to_go: Option<u32>,
+    // search until told to stop (`go infinite`, `go ponder`)
+    pub infinite: bool,
+    // what to keep back instead of the usual 30ms, e.g. for a slow connection
+    pub move_overhead: Option<Duration>,
+}
+
+pub struct TimeManager {
+    start: Instant,
+    // don't start another iteration after this
+    soft: Option<Duration>,
+    // abort whatever is running after this
+    hard: Option<Duration>,
+    stop: Arc<AtomicBool>,
+}
+
+impl TimeManager {
+    pub fn new(control: &TimeControl, color: Color) -> Self {
+        let (time, inc) = match color {
+            Color::White => (control.wtime, control.winc),
+            Color::Black => (control.btime, control.binc),
+        };
+
+        let overhead = control.move_overhead.unwrap_or(OVERHEAD);
+        let (soft, hard) = if control.infinite {
+            (None, None)
+        } else if let Some(movetime) = control.movetime {
+            let movetime = movetime.saturating_sub(overhead);
+            (Some(movetime), Some(movetime))
+        } else if let Some(time) = time {
+            let left = time.saturating_sub(overhead);
+            let inc = inc.unwrap_or_default();
+            let soft = (left/control.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1) + inc*3/4).min(left);
+            // a deeper iteration may overrun the plan a bit, but never eat more than half of the clock
+            (Some(soft), Some((soft*3).min(left/2).max(soft)))
+        } else {
+            (None, None)
+        };
+
+        Self { start: Instant::now(), soft, hard, stop: Arc::new(AtomicBool::new(false)) }
+    }
+
+    // lets another thread end the search early, e.g. on `stop` or when a ponder miss comes in
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+impl Deadline for TimeManager {
+    fn hard_stop(&self) -> bool {
+        self.stop.load(Ordering::Relaxed) || self.hard.is_some_and(|hard| self.elapsed() >= hard)
+    }
+
+    fn soft_stop(&self) -> bool {
+        self.stop.load(Ordering::Relaxed) || self.soft.is_some_and(|soft| self.elapsed() >= soft)
+    }
+
+    fn elapsed(&self) -> Option<Duration> {
+        Some(TimeManager::elapsed(self))
+    }
+}
@@ -0,0 +1,290 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use ress::{Board, GameOutcome};
+use ress::piece::Color;
+use crate::Engine;
+use crate::broadcast::{Broadcast, GameEvent};
+use crate::suite::{self, OpeningSuite};
+use crate::search::{search_with, SearchLimits};
+use crate::tt::TranspositionTable;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Format {
+    // everyone plays everyone
+    #[default]
+    RoundRobin,
+    // the first entrant plays everyone else, who don't play each other
+    Gauntlet,
+}
+
+#[derive(Debug, Clone)]
+pub struct TournamentOptions {
+    pub format: Format,
+    pub limits: SearchLimits,
+    // each pairing plays every opening with both colors this many times
+    pub rounds: usize,
+    // a game still going after this many plies is called a draw
+    pub max_plies: usize,
+    pub openings: OpeningSuite,
+    // where the games are sent move by move while they're played, for spectators
+    pub broadcast: Option<Arc<Broadcast>>,
+}
+
+impl Default for TournamentOptions {
+    fn default() -> Self {
+        Self {
+            format: Format::default(),
+            limits: SearchLimits { depth: 2, ..Default::default() },
+            rounds: 1,
+            max_plies: 300,
+            openings: OpeningSuite::default(),
+            broadcast: None,
+        }
+    }
+}
+
+pub struct Entrant {
+    pub name: String,
+    pub engine: Engine,
+}
+
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    // indices into the entrants
+    pub white: usize,
+    pub black: usize,
+    pub opening: String,
+    pub moves: Vec<String>,
+    // the winner, none for a draw
+    pub result: Option<Option<Color>>,
+    // why it ended, as the pgn's termination tag has it
+    pub termination: &'static str,
+}
+
+impl GameRecord {
+    fn result_tag(&self) -> &'static str {
+        match self.result {
+            Some(Some(Color::White)) => "1-0",
+            Some(Some(Color::Black)) => "0-1",
+            Some(None) => "1/2-1/2",
+            None => "*",
+        }
+    }
+
+    // what the entrant scored, none if they didn't play
+    pub fn score_of(&self, entrant: usize) -> Option<f64> {
+        let color = if entrant == self.white { Color::White } else if entrant == self.black { Color::Black } else { return None };
+        match self.result? {
+            Some(won) if won == color => Some(1.0),
+            Some(_) => Some(0.0),
+            None => Some(0.5),
+        }
+    }
+}
+
+// `on_move` is handed the ply, the move's san and the position after it
+pub(crate) fn play_game(white: &Engine, black: &Engine, opening: &Board, options: &TournamentOptions, on_move: &dyn Fn(usize, &str, &Board)) -> (Vec<String>, Option<Option<Color>>, &'static str) {
+    let mut board = opening.clone();
+    // each engine keeps its table for the whole game
+    let tables = [TranspositionTable::new(1 << 16), TranspositionTable::new(1 << 16)];
+    let mut moves = Vec::new();
+
+    loop {
+        let ply = moves.len();
+        if let Some(outcome) = board.game_outcome {
+            let result = match outcome {
+                GameOutcome::Decisive { won, .. } => Some(won),
+                GameOutcome::Draw(_) => None,
+            };
+            return (moves, Some(result), "normal");
+        };
+        // a repetition or 50 moves the rules let the side to move claim are claimed, engines don't avoid them anyway
+        if board.can_claim_draw().is_some() {
+            return (moves, Some(None), "normal");
+        };
+        if ply >= options.max_plies {
+            return (moves, Some(None), "adjudication");
+        };
+
+        let color = board.move_color;
+        let (engine, tt) = match color {
+            Color::White => (white, &tables[0]),
+            Color::Black => (black, &tables[1]),
+        };
+        let Some(best_move) = search_with(&board, engine, options.limits, &(), tt, &()).best_move else {
+            return (moves, None, "unterminated");
+        };
+
+        let san = board.san(best_move);
+        moves.push(match color {
+            Color::White => format!("{}. {san}", board.move_number()),
+            Color::Black if ply == 0 => format!("{}... {san}", board.move_number()),
+            Color::Black => san.clone(),
+        });
+        if board.play_move(Engine::player_move(best_move, color)).is_err() {
+            return (moves, None, "rules infraction");
+        };
+        on_move(ply, &san, &board);
+    }
+}
+
+pub struct Tournament {
+    pub names: Vec<String>,
+    pub games: Vec<GameRecord>,
+}
+
+impl Tournament {
+    // the games are spread over rayon's threads, each one searching single threaded
+    pub fn run(entrants: &[Entrant], options: &TournamentOptions) -> Self {
+        let pairings = match options.format {
+            Format::RoundRobin => (0..entrants.len()).flat_map(|a| (a + 1..entrants.len()).map(move |b| (a, b))).collect::<Vec<_>>(),
+            Format::Gauntlet => (1..entrants.len()).map(|b| (0, b)).collect(),
+        };
+        let schedule = suite::schedule(&pairings, options.openings.len(), options.rounds);
+
+        let games = suite::run(&schedule, |game, suite::Pairing { white, black, opening }| {
+            let opening = &options.openings.openings[opening];
+            let send = |event| if let Some(broadcast) = &options.broadcast {
+                broadcast.send(event);
+            };
+            send(GameEvent::Started { game, white: entrants[white].name.clone(), black: entrants[black].name.clone(), fen: opening.to_fen() });
+
+            let on_move = |ply, san: &str, board: &Board| send(GameEvent::Move { game, ply, san: san.to_string(), fen: board.to_fen() });
+            let (moves, result, termination) = play_game(&entrants[white].engine, &entrants[black].engine, opening, options, &on_move);
+            #[cfg(feature = "tracing")]
+            tracing::info!(white = entrants[white].name, black = entrants[black].name, moves = moves.len(), termination, "game finished");
+            let record = GameRecord { white, black, opening: opening.to_fen(), moves, result, termination };
+            send(GameEvent::Finished { game, result: record.result_tag(), termination });
+            record
+        });
+
+        Self { names: entrants.iter().map(|entrant| entrant.name.clone()).collect(), games }
+    }
+
+    pub fn pgn(&self) -> String {
+        let mut pgn = String::new();
+        for (round, game) in self.games.iter().enumerate() {
+            let tags = [
+                ("Event", String::from("engine tournament")),
+                ("Round", (round + 1).to_string()),
+                ("White", self.names[game.white].clone()),
+                ("Black", self.names[game.black].clone()),
+                ("Result", game.result_tag().to_string()),
+                ("SetUp", String::from("1")),
+                ("FEN", game.opening.clone()),
+                ("Termination", game.termination.to_string()),
+            ];
+            for (name, value) in tags {
+                pgn += &format!("[{name} \"{value}\"]\n");
+            };
+            pgn += &format!("\n{} {}\n\n", game.moves.join(" "), game.result_tag());
+        };
+        pgn
+    }
+
+    // how `a` did against `b` over their games with each other
+    pub fn estimate(&self, a: usize, b: usize) -> Estimate {
+        let scores = self.games.iter()
+            .filter(|game| (game.white, game.black) == (a, b) || (game.white, game.black) == (b, a))
+            .filter_map(|game| game.score_of(a));
+        Estimate::from_scores(scores)
+    }
+
+    // how every entrant did against everyone they played, best first
+    pub fn standings(&self) -> Vec<(usize, Estimate)> {
+        let mut standings = (0..self.names.len()).map(|entrant| {
+            (entrant, Estimate::from_scores(self.games.iter().filter_map(|game| game.score_of(entrant))))
+        }).filter(|(_, estimate)| estimate.games() > 0).collect::<Vec<_>>();
+        standings.sort_by(|(_, a), (_, b)| b.elo().total_cmp(&a.elo()));
+        standings
+    }
+}
+
+fn elo_of(score: f64) -> f64 {
+    -400.0*(1.0/score.clamp(1e-6, 1.0 - 1e-6) - 1.0).log10()
+}
+
+fn expected_score(elo: f64) -> f64 {
+    1.0/(1.0 + 10f64.powf(-elo/400.0))
+}
+
+// wins, draws and losses from one side's point of view
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Estimate {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+// what a sprt concluded so far
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sprt {
+    // the difference is at least elo1
+    Accept,
+    // it's at most elo0
+    Reject,
+    Continue,
+}
+
+impl Estimate {
+    pub(crate) fn from_scores(scores: impl Iterator<Item = f64>) -> Self {
+        scores.fold(Self::default(), |mut estimate, score| {
+            match score {
+                1.0 => estimate.wins += 1,
+                0.0 => estimate.losses += 1,
+                _ => estimate.draws += 1,
+            };
+            estimate
+        })
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64/2.0)/self.games().max(1) as f64
+    }
+
+    // the variance of a single game's score
+    fn variance(&self) -> f64 {
+        let (score, games) = (self.score(), self.games().max(1) as f64);
+        (self.wins as f64*(1.0 - score).powi(2) + self.draws as f64*(0.5 - score).powi(2) + self.losses as f64*score.powi(2))/games
+    }
+
+    pub fn elo(&self) -> f64 {
+        elo_of(self.score())
+    }
+
+    // the 95% confidence interval around `elo`, as the distance to either end
+    pub fn error(&self) -> f64 {
+        let margin = 1.96*(self.variance()/self.games().max(1) as f64).sqrt();
+        (elo_of(self.score() + margin) - elo_of(self.score() - margin))/2.0
+    }
+
+    // the log-likelihood ratio of the difference being elo1 rather than elo0, with a normal approximation
+    pub fn llr(&self, elo0: f64, elo1: f64) -> f64 {
+        let variance = self.variance();
+        if variance == 0.0 {
+            return 0.0;
+        };
+        let (score0, score1) = (expected_score(elo0), expected_score(elo1));
+        self.games() as f64*(score1 - score0)*(2.0*self.score() - score0 - score1)/(2.0*variance)
+    }
+
+    // with both error rates at 5%
+    pub fn sprt(&self, elo0: f64, elo1: f64) -> Sprt {
+        let bound = (0.95f64/0.05).ln();
+        match self.llr(elo0, elo1) {
+            llr if llr >= bound => Sprt::Accept,
+            llr if llr <= -bound => Sprt::Reject,
+            _ => Sprt::Continue,
+        }
+    }
+}
+
+impl Display for Estimate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:+.0} ± {:.0} elo (+{} ={} -{})", self.elo(), self.error(), self.wins, self.draws, self.losses)
+    }
+}
@@ -0,0 +1,303 @@
+use std::sync::Arc;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use rayon::prelude::*;
+use ress::Board;
+use crate::{Engine, Weights};
+use crate::accumulator::Accumulator;
+use crate::features::Features;
+
+// the activation is 2/(1 + 9^-x) - 1, its derivative in terms of its output y is ln(9)/2*(1 - y²)
+const SLOPE: f32 = 1.0986123;
+// samples per task when a batch is split across threads
+const CHUNK: usize = 32;
+
+// one position to learn the value of, already encoded for the network
+#[derive(Debug, Clone)]
+pub struct Sample {
+    features: Features,
+    // -1..1 from white's point of view, like `Engine::evaluate`
+    pub target: f32,
+}
+
+impl Sample {
+    pub fn new(board: &Board, target: f32) -> Self {
+        Self { features: Features::of(&board.position()), target }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TrainOptions {
+    pub learning_rate: f32,
+    pub batch_size: usize,
+}
+
+impl Default for TrainOptions {
+    fn default() -> Self {
+        Self { learning_rate: 0.05, batch_size: 256 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PgnSummary {
+    pub games: usize,
+    pub positions: usize,
+    // games without a result can't tell anything about their positions
+    pub unfinished: usize,
+    // games with a move that couldn't be played, only the positions before it are used
+    pub cut_short: usize,
+}
+
+enum Token {
+    Move(String),
+    Comment(String),
+    Result(f32),
+}
+
+fn result(raw: &str) -> Option<Option<f32>> {
+    match raw {
+        "1-0" => Some(Some(1.0)),
+        "0-1" => Some(Some(-1.0)),
+        "1/2-1/2" => Some(Some(0.0)),
+        "*" => Some(None),
+        _ => None,
+    }
+}
+
+// the main line only, variations and nags are skipped
+fn tokens(movetext: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut depth = 0usize;
+    let mut chars = movetext.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | ';' => {
+                let end = if c == '{' { '}' } else { '\n' };
+                let text = chars.by_ref().take_while(|c| *c != end).collect::<String>();
+                if depth == 0 {
+                    tokens.push(Token::Comment(text));
+                };
+            },
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if c.is_whitespace() => {},
+            c => {
+                let mut word = String::from(c);
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"{};()".contains(*c)) {
+                    word.push(c);
+                };
+                if depth > 0 || word.starts_with('$') {
+                    continue;
+                };
+
+                match result(&word) {
+                    Some(Some(score)) => tokens.push(Token::Result(score)),
+                    Some(None) => {},
+                    // `12.`, `12...` and `12.e4`
+                    None => match word.rsplit('.').next() {
+                        Some(san) if !san.is_empty() => tokens.push(Token::Move(san.to_string())),
+                        _ => {},
+                    },
+                };
+            },
+        };
+    };
+    tokens
+}
+
+// lichess puts the engine's opinion in comments as `[%eval 0.35]` or `[%eval #-3]`, in pawns for white.
+// the target is what the search would read back as that many centipawns.
+fn eval(comment: &str) -> Option<f32> {
+    let raw = comment.split("[%eval ").nth(1)?.split(']').next()?.trim();
+    match raw.strip_prefix('#') {
+        Some(mate) => Some(if mate.starts_with('-') { -1.0 } else { 1.0 }),
+        None => Some((raw.parse::<f32>().ok()?*100.0/1000.0).clamp(-1.0, 1.0)),
+    }
+}
+
+fn play_san(board: &mut Board, san: &str) -> Option<()> {
+    let r#move = board.parse_san(san)?;
+    board.play_move(ress::PlayerMove::Internal(r#move)).ok().map(|_| ())
+}
+
+// the positions of one game, each with the eval given after the move leading to it
+fn samples_of(tags: &[(String, String)], movetext: &str, eval_weight: f32, summary: &mut PgnSummary) -> Vec<Sample> {
+    let tag = |name: &str| tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str());
+    let tokens = tokens(movetext);
+    let outcome = tag("Result").and_then(result).flatten().or_else(|| tokens.iter().find_map(|token| match token {
+        Token::Result(score) => Some(*score),
+        _ => None,
+    }));
+    let Some(outcome) = outcome else {
+        summary.unfinished += 1;
+        return Vec::new();
+    };
+
+    let Some(mut board) = tag("FEN").map_or(Some(Board::default()), Board::from_fen) else {
+        summary.cut_short += 1;
+        return Vec::new();
+    };
+    let mut positions = vec![(board.clone(), None)];
+    for token in tokens {
+        match token {
+            Token::Move(san) => {
+                if play_san(&mut board, &san).is_none() {
+                    summary.cut_short += 1;
+                    break;
+                };
+                positions.push((board.clone(), None));
+            },
+            Token::Comment(text) => if let Some(eval) = eval(&text) {
+                positions.last_mut().unwrap().1 = Some(eval);
+            },
+            Token::Result(_) => break,
+        };
+    };
+
+    summary.positions += positions.len();
+    positions.into_iter().map(|(board, eval)| {
+        let target = eval.map_or(outcome, |eval| eval_weight*eval + (1.0 - eval_weight)*outcome);
+        Sample::new(&board, target)
+    }).collect()
+}
+
+// samples for every position of every finished game. where there's an eval, the target mixes
+// `eval_weight` of it with the rest of the game's result, otherwise it's just the result.
+pub fn read_pgn(raw: &str, eval_weight: f32) -> (Vec<Sample>, PgnSummary) {
+    let mut summary = PgnSummary::default();
+    let mut samples = Vec::new();
+    let mut tags = Vec::new();
+    let mut movetext = String::new();
+
+    let mut finish = |tags: &mut Vec<(String, String)>, movetext: &mut String, summary: &mut PgnSummary| {
+        if !tags.is_empty() || !movetext.trim().is_empty() {
+            summary.games += 1;
+            samples.extend(samples_of(tags, movetext, eval_weight, summary));
+        };
+        tags.clear();
+        movetext.clear();
+    };
+
+    for line in raw.lines() {
+        let line = line.trim();
+        if let Some(pair) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            // tags after movetext start the next game
+            if !movetext.trim().is_empty() {
+                finish(&mut tags, &mut movetext, &mut summary);
+            };
+            if let Some((name, value)) = pair.split_once(' ') {
+                tags.push((name.to_string(), value.trim().trim_matches('"').to_string()));
+            };
+        } else {
+            movetext.push_str(line);
+            movetext.push('\n');
+        };
+    };
+    finish(&mut tags, &mut movetext, &mut summary);
+
+    (samples, summary)
+}
+
+// the squared error of the value summed over the samples, and its gradient for every coefficient then every offset
+fn accumulators(weights: &Arc<Weights>, samples: &[Sample]) -> Vec<Accumulator> {
+    let encoding = weights.architecture.encoding();
+    samples.iter().map(|sample| Accumulator::from_inputs(Arc::clone(weights), sample.features.inputs(encoding))).collect()
+}
+
+fn gradient(weights: &Arc<Weights>, samples: &[Sample]) -> (f32, Vec<f32>) {
+    let layers = weights.architecture.layers();
+    let inputs_kept = weights.architecture.state_inputs();
+    // where each layer starts in the state, the input only appears in it for the legacy encoding
+    let starts = core::iter::once(0).chain(layers[1..].iter().scan(inputs_kept, |start, size| {
+        let layer_start = *start;
+        *start += size;
+        Some(layer_start)
+    })).collect::<Vec<_>>();
+
+    let mut loss = 0.0;
+    let mut grads = vec![0.0; weights.coefs.len() + weights.offsets.len()];
+    let accumulators = accumulators(weights, samples);
+    let states = Engine::propagate(weights, &accumulators, true);
+    for ((sample, accumulator), state) in samples.iter().zip(&accumulators).zip(states) {
+        // how the loss changes with each neuron's sum before the activation
+        let mut deltas = vec![0.0; state.len()];
+        let value = state[state.len() - 1];
+        loss += (value - sample.target).powi(2);
+        deltas[state.len() - 1] = 2.0*(value - sample.target)*SLOPE*(1.0 - value*value);
+
+        let mut of = weights.coefs.len();
+        for layer in (0..layers.len() - 1).rev() {
+            let (inputs, outputs) = (layers[layer], layers[layer + 1]);
+            let (source, target) = (starts[layer], starts[layer + 1]);
+            of -= inputs*outputs;
+
+            for i in 0..outputs {
+                let delta = deltas[target + i];
+                if delta == 0.0 {
+                    continue;
+                };
+                // only the hidden layers have offsets, laid out like their neurons
+                if layer + 1 < layers.len() - 1 {
+                    grads[weights.coefs.len() + target + i - inputs_kept] += delta;
+                };
+
+                let row = of + i*inputs;
+                // nothing is learned from the input, only the weights it's multiplied with
+                if layer == 0 {
+                    for (j, value) in &accumulator.inputs {
+                        grads[j*outputs + i] += delta*value;
+                    };
+                    continue;
+                };
+                for j in 0..inputs {
+                    grads[row + j] += delta*state[source + j];
+                    deltas[source + j] += delta*weights.coefs[row + j];
+                };
+            };
+            if layer > 0 {
+                for j in 0..inputs {
+                    deltas[source + j] *= SLOPE*(1.0 - state[source + j]*state[source + j]);
+                };
+            };
+        };
+    };
+
+    (loss, grads)
+}
+
+impl Engine {
+    // the mean squared error of the value
+    pub fn loss(&self, samples: &[Sample]) -> f32 {
+        let weights = self.weights();
+        let loss = samples.par_chunks(CHUNK).map(|chunk| {
+            Engine::propagate(&weights, &accumulators(&weights, chunk), true).iter().zip(chunk)
+                .map(|(state, sample)| (state[state.len() - 1] - sample.target).powi(2)).sum::<f32>()
+        }).sum::<f32>();
+        loss/samples.len().max(1) as f32
+    }
+
+    // one pass of minibatch gradient descent over the samples in a random order,
+    // returns the mean loss seen along the way. only the value is trained, the move choice is left alone.
+    pub fn train_epoch(&mut self, samples: &mut [Sample], options: TrainOptions, rng: &mut impl Rng) -> f32 {
+        samples.shuffle(rng);
+        let mut weights = Arc::new(Weights::clone(&self.weights()));
+        let mut total = 0.0;
+        for batch in samples.chunks(options.batch_size.max(1)) {
+            let (loss, grads) = batch.par_chunks(CHUNK)
+                .map(|chunk| gradient(&weights, chunk))
+                .reduce_with(|(loss_a, mut grads_a), (loss_b, grads_b)| {
+                    grads_a.iter_mut().zip(grads_b).for_each(|(a, b)| *a += b);
+                    (loss_a + loss_b, grads_a)
+                })
+                .unwrap();
+
+            let step = options.learning_rate/batch.len() as f32;
+            // every accumulator is gone by now, so this doesn't copy the weights
+            let weights = Arc::make_mut(&mut weights);
+            weights.coefs.iter_mut().chain(weights.offsets.iter_mut()).zip(grads).for_each(|(w, grad)| *w -= step*grad);
+            total += loss;
+        };
+        *self = Self::from_weights(Arc::unwrap_or_clone(weights));
+        total/samples.len().max(1) as f32
+    }
+}
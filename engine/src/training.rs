@@ -0,0 +1,105 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use crate::{Engine, Weights};
+use crate::architecture::Architecture;
+
+// every weight is nudged with probability `rate` by gaussian noise with a standard deviation of `sigma`
+#[derive(Debug, Clone, Copy)]
+pub struct Mutation {
+    pub rate: f32,
+    pub sigma: f32,
+}
+
+impl Default for Mutation {
+    fn default() -> Self {
+        Self { rate: 0.1, sigma: 0.2 }
+    }
+}
+
+// how `variate` combines two parents
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Crossover {
+    // every weight comes from either parent with even odds
+    #[default]
+    Uniform,
+    // weights up to a random point come from one parent, the rest from the other
+    SinglePoint,
+}
+
+// box-muller, rand_distr isn't worth a dependency for one distribution
+fn gaussian(rng: &mut impl Rng) -> f32 {
+    let u = 1.0 - rng.gen::<f32>();
+    let v = rng.gen::<f32>();
+    (-2.0*u.ln()).sqrt()*(core::f32::consts::TAU*v).cos()
+}
+
+// everything the evolution needs on top of inference, kept apart so inference-only builds don't pull in rand.
+// the `_with` variants take the rng, so a seeded one makes a whole run reproducible. the `_seeded` ones
+// are the same with a `StdRng` from the seed, which comes out the same on every machine.
+impl Engine {
+    pub fn new_random() -> Self {
+        Self::new_random_with(&mut rand::thread_rng())
+    }
+
+    pub fn new_seeded(seed: u64) -> Self {
+        Self::new_random_with(&mut StdRng::seed_from_u64(seed))
+    }
+
+    pub fn new_random_with(rng: &mut impl Rng) -> Self {
+        Self::new_random_architecture(Architecture::default(), rng)
+    }
+
+    pub fn new_random_architecture(architecture: Architecture, rng: &mut impl Rng) -> Self {
+        let mut random = |count| (0..count).map(|_| rng.gen::<f32>()*2.0-1.0).collect();
+        Self::from_weights(Weights { coefs: random(architecture.coefs()), offsets: random(architecture.offsets()), architecture })
+    }
+
+    // none if the two don't share an architecture
+    pub fn variate(&mut self, with: &Self, crossover: Crossover) -> Option<()> {
+        self.variate_with(with, crossover, &mut rand::thread_rng())
+    }
+
+    pub fn variate_seeded(&mut self, with: &Self, crossover: Crossover, seed: u64) -> Option<()> {
+        self.variate_with(with, crossover, &mut StdRng::seed_from_u64(seed))
+    }
+
+    pub fn variate_with(&mut self, with: &Self, crossover: Crossover, rng: &mut impl Rng) -> Option<()> {
+        let mut weights = Weights::clone(&self.weights());
+        let other = with.weights();
+        if weights.architecture != other.architecture {
+            return None;
+        };
+        let point = rng.gen_range(0..=weights.coefs.len() + weights.offsets.len());
+
+        let pairs = weights.coefs.iter_mut().chain(weights.offsets.iter_mut()).zip(other.coefs.iter().chain(other.offsets.iter()));
+        for (i, (w, other)) in pairs.enumerate() {
+            let take = match crossover {
+                Crossover::Uniform => rng.gen(),
+                Crossover::SinglePoint => i >= point,
+            };
+            if take {
+                *w = *other;
+            };
+        };
+        *self = Self::from_weights(weights);
+        Some(())
+    }
+
+    pub fn mutate(&mut self, mutation: Mutation) {
+        self.mutate_with(mutation, &mut rand::thread_rng());
+    }
+
+    pub fn mutate_seeded(&mut self, mutation: Mutation, seed: u64) {
+        self.mutate_with(mutation, &mut StdRng::seed_from_u64(seed));
+    }
+
+    pub fn mutate_with(&mut self, mutation: Mutation, rng: &mut impl Rng) {
+        let mut weights = Weights::clone(&self.weights());
+        weights.coefs.iter_mut().chain(weights.offsets.iter_mut()).for_each(|w| {
+            if rng.gen::<f32>() < mutation.rate {
+                *w += gaussian(rng)*mutation.sigma;
+            };
+        });
+        *self = Self::from_weights(weights);
+    }
+}
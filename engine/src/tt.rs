@@ -0,0 +1,193 @@
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+use ress::coordinate::{Coordinate, File, Move, Rank, Side};
+use ress::piece::PieceKind;
+use ress::zobrist::PositionKey;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    Exact,
+    // the score is at least this (the search failed high)
+    Lower,
+    // the score is at most this (nothing beat alpha)
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Entry {
+    pub score: i32,
+    pub depth: u8,
+    pub bound: Bound,
+    pub best_move: Option<Move>,
+}
+
+// the king only for antichess
+const PROMOTIONS: [PieceKind; 5] = [PieceKind::Knight, PieceKind::Bishop, PieceKind::Rook, PieceKind::Queen, PieceKind::King];
+const DROPS: [PieceKind; 5] = [PieceKind::Pawn, PieceKind::Knight, PieceKind::Bishop, PieceKind::Rook, PieceKind::Queen];
+
+// 2 bits of variant, then up to 14 bits of payload. castling and drops share the last one, told apart by the 14th bit.
+fn encode_move(r#move: Move) -> u16 {
+    let square = |coord: Coordinate| coord.rank as u16*8 + coord.file as u16;
+    match r#move {
+        Move::Simple { from, to } => square(from) << 6 | square(to),
+        Move::Promotion { from, to, piece } => 1 << 14 | (from as u16) << 6 | (to as u16) << 3 | PROMOTIONS.iter().position(|kind| *kind == piece).unwrap() as u16,
+        Move::EnPassant { from, to } => 2 << 14 | (from as u16) << 3 | to as u16,
+        Move::Castling { side } => 3 << 14 | matches!(side, Side::Queen) as u16,
+        Move::Drop { piece, to } => 3 << 14 | 1 << 13 | (DROPS.iter().position(|kind| *kind == piece).unwrap() as u16) << 6 | square(to),
+    }
+}
+
+fn decode_move(raw: u16) -> Move {
+    let file = |raw: u16| File::try_from((raw & 7) as i8).unwrap();
+    let square = |raw: u16| Coordinate { file: file(raw), rank: Rank::try_from((raw >> 3 & 7) as i8).unwrap() };
+    match raw >> 14 {
+        0 => Move::Simple { from: square(raw >> 6), to: square(raw) },
+        1 => Move::Promotion { from: file(raw >> 6), to: file(raw >> 3), piece: PROMOTIONS[(raw & 7) as usize] },
+        2 => Move::EnPassant { from: file(raw >> 3), to: file(raw) },
+        _ if raw >> 13 & 1 == 1 => Move::Drop { piece: DROPS[(raw >> 6 & 7) as usize], to: square(raw) },
+        _ => Move::Castling { side: if raw & 1 == 0 { Side::King } else { Side::Queen } },
+    }
+}
+
+// score (32) | depth (8) | bound (2) | has move (1) | move (16)
+fn pack(entry: Entry) -> u64 {
+    let bound = match entry.bound {
+        Bound::Exact => 0,
+        Bound::Lower => 1,
+        Bound::Upper => 2,
+    };
+    let r#move = entry.best_move.map_or(0, |r#move| 1 << 16 | encode_move(r#move) as u64);
+    (entry.score as u32 as u64) << 32 | (entry.depth as u64) << 24 | bound << 22 | r#move
+}
+
+fn unpack(data: u64) -> Entry {
+    Entry {
+        score: (data >> 32) as u32 as i32,
+        depth: (data >> 24) as u8,
+        bound: match data >> 22 & 3 {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        },
+        best_move: (data >> 16 & 1 == 1).then(|| decode_move(data as u16)),
+    }
+}
+
+// shared between search threads without locking. every slot keeps the key xor-ed with the data,
+// so a slot torn by two threads writing at once just looks like a miss instead of a wrong entry.
+pub struct TranspositionTable {
+    slots: Vec<(AtomicU64, AtomicU64)>,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(1 << 18)
+    }
+}
+
+impl TranspositionTable {
+    // as many 16 byte entries as fit, rounded down to a power of two so the table is never bigger than asked for
+    pub fn with_megabytes(megabytes: usize) -> Self {
+        let entries = (megabytes << 20)/size_of::<(AtomicU64, AtomicU64)>();
+        Self::new(1 << entries.max(1).ilog2())
+    }
+
+    pub fn new(entries: usize) -> Self {
+        Self { slots: (0..entries.max(1).next_power_of_two()).map(|_| (AtomicU64::new(0), AtomicU64::new(0))).collect() }
+    }
+
+    fn slot(&self, key: u64) -> &(AtomicU64, AtomicU64) {
+        &self.slots[key as usize & (self.slots.len() - 1)]
+    }
+
+    pub fn probe(&self, PositionKey(key): PositionKey) -> Option<Entry> {
+        let (check, data) = self.slot(key);
+        let data = data.load(Ordering::Relaxed);
+        (check.load(Ordering::Relaxed) ^ data == key && data != 0).then(|| unpack(data))
+    }
+
+    // always replaces, deeper entries surviving would need an age to not go stale between moves
+    pub fn store(&self, PositionKey(key): PositionKey, entry: Entry) {
+        let (check, data) = self.slot(key);
+        let packed = pack(entry);
+        check.store(key ^ packed, Ordering::Relaxed);
+        data.store(packed, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for (check, data) in &self.slots {
+            check.store(0, Ordering::Relaxed);
+            data.store(0, Ordering::Relaxed);
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use ress::coordinate::{Coordinate, File, Move, Rank, Side};
+    use ress::zobrist::PositionKey;
+    use crate::search::MATE;
+    use super::{decode_move, encode_move, pack, unpack, Bound, Entry, TranspositionTable, DROPS, PROMOTIONS};
+
+    fn squares() -> impl Iterator<Item = Coordinate> {
+        (0..64i8).map(|i| Coordinate { file: File::try_from(i % 8).unwrap(), rank: Rank::try_from(i/8).unwrap() })
+    }
+
+    fn files() -> impl Iterator<Item = File> {
+        (0..8i8).map(|i| File::try_from(i).unwrap())
+    }
+
+    // every move there is a way to write, not only the legal ones somewhere
+    fn every_move() -> Vec<Move> {
+        let mut moves = Vec::new();
+        for from in squares() {
+            moves.extend(squares().map(|to| Move::Simple { from, to }));
+        };
+        for (from, to) in files().flat_map(|from| files().map(move |to| (from, to))) {
+            moves.extend(PROMOTIONS.map(|piece| Move::Promotion { from, to, piece }));
+            moves.push(Move::EnPassant { from, to });
+        };
+        moves.extend([Move::Castling { side: Side::King }, Move::Castling { side: Side::Queen }]);
+        for to in squares() {
+            moves.extend(DROPS.map(|piece| Move::Drop { piece, to }));
+        };
+        moves
+    }
+
+    #[test]
+    fn moves_survive_encoding() {
+        let moves = every_move();
+        for r#move in &moves {
+            assert_eq!(decode_move(encode_move(*r#move)), *r#move);
+        };
+        // and no two share an encoding, a drop included
+        let mut encoded = moves.iter().map(|r#move| encode_move(*r#move)).collect::<Vec<_>>();
+        encoded.sort_unstable();
+        encoded.dedup();
+        assert_eq!(encoded.len(), moves.len());
+    }
+
+    #[test]
+    fn entries_survive_packing() {
+        let drop = Move::Drop { piece: DROPS[0], to: Coordinate::parse("e4").unwrap() };
+        for (score, best_move) in [(0, None), (-42, Some(drop)), (MATE - 3, Some(Move::Castling { side: Side::Queen })), (i32::MIN, None)] {
+            for bound in [Bound::Exact, Bound::Lower, Bound::Upper] {
+                let entry = unpack(pack(Entry { score, depth: 7, bound, best_move }));
+                assert_eq!((entry.score, entry.depth, entry.bound, entry.best_move), (score, 7, bound, best_move));
+            };
+        };
+    }
+
+    #[test]
+    fn probing() {
+        let tt = TranspositionTable::new(16);
+        let entry = Entry { score: 15, depth: 3, bound: Bound::Lower, best_move: None };
+        tt.store(PositionKey(0xabcd), entry);
+        assert_eq!(tt.probe(PositionKey(0xabcd)).map(|entry| entry.score), Some(15));
+        // the same slot, but another position
+        assert!(tt.probe(PositionKey(0xabcd + 16)).is_none());
+        tt.clear();
+        assert!(tt.probe(PositionKey(0xabcd)).is_none());
+    }
+}
@@ -0,0 +1,117 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use wasm_bindgen::prelude::*;
+use ress::{Board, GameOutcome, PlayerMove};
+use ress::fen::{self, Dialect};
+use ress::piece::Color;
+use crate::Engine;
+
+// the board and the engine for javascript, e.g. a gui in the browser. positions go in and out as fens,
+// moves as uci strings like `e2e4`, `e1g1` or `e7e8q`, and sides as `white` and `black`.
+
+#[wasm_bindgen(js_name = Board)]
+#[derive(Default)]
+pub struct WasmBoard {
+    board: Board,
+}
+
+#[wasm_bindgen(js_class = Board)]
+impl WasmBoard {
+    // the starting position
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[wasm_bindgen(js_name = fromFen)]
+    pub fn from_fen(fen: &str) -> Result<WasmBoard, JsError> {
+        let (board, _) = fen::parse(fen, Dialect::Standard).map_err(|err| JsError::new(&err.to_string()))?;
+        Ok(Self { board })
+    }
+
+    pub fn fen(&self) -> String {
+        self.board.to_fen()
+    }
+
+    #[wasm_bindgen(js_name = sideToMove)]
+    pub fn side_to_move(&self) -> String {
+        self.board.move_color.to_string()
+    }
+
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self) -> Vec<String> {
+        let color = self.board.move_color;
+        self.board.possible_moves(color).into_iter().map(|r#move| r#move.to_long_algebraic(color)).collect()
+    }
+
+    #[wasm_bindgen(js_name = isLegal)]
+    pub fn is_legal(&self, uci: &str) -> bool {
+        PlayerMove::parse(uci).is_some_and(|r#move| self.board.is_legal(&r#move).is_ok())
+    }
+
+    // the move in standard algebraic notation, undefined if it isn't legal
+    pub fn san(&self, uci: &str) -> Option<String> {
+        let r#move = self.board.resolve_move(&PlayerMove::parse(uci)?)?;
+        Some(self.board.san(r#move))
+    }
+
+    // throws with why when the move can't be played
+    pub fn play(&mut self, uci: &str) -> Result<(), JsError> {
+        let r#move = PlayerMove::parse(uci).ok_or_else(|| JsError::new(&alloc::format!("{uci} isn't a uci move")))?;
+        self.board.play_move(r#move).map_err(|err| JsError::new(&err.to_string()))?;
+        Ok(())
+    }
+
+    // takes the last move back, false when there's none
+    pub fn undo(&mut self) -> bool {
+        self.board.undo_move()
+    }
+
+    #[wasm_bindgen(js_name = inCheck)]
+    pub fn in_check(&self) -> bool {
+        self.board.checked_king().is_some()
+    }
+
+    // `1-0`, `0-1` or `1/2-1/2` once the game is over
+    pub fn result(&self) -> Option<String> {
+        let result = match self.board.game_outcome? {
+            GameOutcome::Decisive { won, .. } => match won {
+                Color::White => "1-0",
+                Color::Black => "0-1",
+            },
+            GameOutcome::Draw(_) => "1/2-1/2",
+        };
+        Some(result.to_string())
+    }
+
+    // why the game is over, e.g. `checkmate` or `stalemate`
+    pub fn reason(&self) -> Option<String> {
+        Some(match self.board.game_outcome? {
+            GameOutcome::Decisive { reason, .. } => reason.to_string(),
+            GameOutcome::Draw(reason) => reason.to_string(),
+        })
+    }
+}
+
+#[wasm_bindgen(js_name = Engine)]
+pub struct WasmEngine {
+    engine: Engine,
+}
+
+#[wasm_bindgen(js_class = Engine)]
+impl WasmEngine {
+    // from the bytes of a weights file, e.g. a fetched `engine.rew`
+    #[wasm_bindgen(constructor)]
+    pub fn new(weights: &[u8]) -> Result<WasmEngine, JsError> {
+        let engine = Engine::from_bytes(weights).map_err(|err| JsError::new(&err.to_string()))?;
+        Ok(Self { engine })
+    }
+
+    // the move the network likes best for the side to move, undefined if there's none
+    #[wasm_bindgen(js_name = chooseMove)]
+    pub fn choose_move(&self, board: &WasmBoard) -> Option<String> {
+        let board = &board.board;
+        let (r#move, _) = self.engine.choose_move(board, board.move_color)?;
+        Some(board.resolve_move(&r#move)?.to_long_algebraic(board.move_color))
+    }
+}
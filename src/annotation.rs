@@ -0,0 +1,118 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use crate::coordinate::{Coordinate, Move};
+use crate::piece::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MarkColor {
+    Red,
+    Green,
+    Blue,
+    Yellow,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Mark {
+    Highlight(MarkColor),
+    Comment(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Arrow {
+    pub from: Coordinate,
+    pub to: Coordinate,
+    pub color: MarkColor,
+}
+
+// user data hanging off the board. square annotations stay where they are,
+// piece annotations travel with the piece and are dropped once it's captured.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Annotations<T = Mark> {
+    pub squares: BTreeMap<Coordinate, Vec<T>>,
+    pub pieces: BTreeMap<Coordinate, Vec<T>>,
+    pub arrows: Vec<Arrow>,
+}
+
+impl<T> Default for Annotations<T> {
+    fn default() -> Self {
+        Self {
+            squares: BTreeMap::new(),
+            pieces: BTreeMap::new(),
+            arrows: Vec::new(),
+        }
+    }
+}
+
+impl<T> Annotations<T> {
+    pub fn annotate_square(&mut self, coord: Coordinate, data: T) {
+        self.squares.entry(coord).or_default().push(data);
+    }
+
+    pub fn annotate_piece(&mut self, coord: Coordinate, data: T) {
+        self.pieces.entry(coord).or_default().push(data);
+    }
+
+    pub fn square(&self, coord: Coordinate) -> &[T] {
+        self.squares.get(&coord).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn piece(&self, coord: Coordinate) -> &[T] {
+        self.pieces.get(&coord).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn arrow(&mut self, from: Coordinate, to: Coordinate, color: MarkColor) {
+        self.arrows.push(Arrow { from, to, color });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.squares.is_empty() && self.pieces.is_empty() && self.arrows.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.squares.clear();
+        self.pieces.clear();
+        self.arrows.clear();
+    }
+
+    pub(crate) fn follow(&mut self, r#move: Move, color: Color) {
+        if self.pieces.is_empty() {
+            return;
+        };
+
+        let from = r#move.resolve_from(color);
+        let to = r#move.resolve_to(color);
+
+        self.pieces.remove(&to);
+        if let Move::EnPassant { to, .. } = r#move {
+            self.pieces.remove(&Coordinate { file: to, rank: color.en_passant_rank() });
+        };
+
+        if let Some(data) = self.pieces.remove(&from) {
+            self.pieces.insert(to, data);
+        };
+
+        if let Move::Castling { side } = r#move {
+            let rank = color.home_rank();
+            if let Some(data) = self.pieces.remove(&Coordinate { file: side.rook_home_file(), rank }) {
+                self.pieces.insert(Coordinate { file: side.rook_castled_file(), rank }, data);
+            };
+        };
+    }
+}
+
+impl Annotations<Mark> {
+    pub fn highlight(&mut self, coord: Coordinate, color: MarkColor) {
+        self.annotate_square(coord, Mark::Highlight(color));
+    }
+
+    pub fn comment(&mut self, coord: Coordinate, text: impl Into<String>) {
+        self.annotate_square(coord, Mark::Comment(text.into()));
+    }
+}
@@ -0,0 +1,374 @@
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use ress::{Board, GameOutcome, PlayerMove};
+use ress::fen::{self, Dialect};
+use ress::piece::Color;
+use ress::store::GameRecord;
+
+
+// records read before they're handed out to the threads, so the input is never read whole
+const BATCH: usize = 4096;
+// pgn wants its lines no longer than this
+const LINE_WIDTH: usize = 79;
+// the tags every pgn game should have, in the order they should come in
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"), ("Site", "?"), ("Date", "????.??.??"), ("Round", "?"), ("White", "?"), ("Black", "?"), ("Result", "*"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Pgn,
+    // one fen per line
+    Fen,
+    // the first four fields of a fen followed by operations, `... - bm Nf3; id "test 1";`
+    Epd,
+    // the store's format, games separated by empty lines
+    Rgf,
+}
+
+impl Format {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.to_ascii_lowercase().as_str() {
+            "pgn" => Some(Self::Pgn),
+            "fen" => Some(Self::Fen),
+            "epd" => Some(Self::Epd),
+            "rgf" => Some(Self::Rgf),
+            _ => None,
+        }
+    }
+
+    fn of_path(path: &str) -> Option<Self> {
+        Path::new(path).extension()?.to_str().and_then(Self::parse)
+    }
+}
+
+// splits the input into one chunk per game or position as it's read
+struct Records<R> {
+    lines: io::Lines<R>,
+    format: Format,
+    // the first line of the next record, read while looking for the end of the last one
+    next: Option<String>,
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = self.next.take().unwrap_or_default();
+        let mut movetext = false;
+        for line in self.lines.by_ref() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err)),
+            };
+            let trimmed = line.trim();
+
+            match self.format {
+                Format::Fen | Format::Epd => {
+                    if trimmed.is_empty() || trimmed.starts_with('#') {
+                        continue;
+                    };
+                    return Some(Ok(trimmed.to_string()));
+                },
+                // tags after movetext start the next game, `[%eval ...]` and the like are comments though
+                Format::Pgn => if trimmed.starts_with('[') && !trimmed.starts_with("[%") {
+                    if movetext {
+                        self.next = Some(line + "\n");
+                        return Some(Ok(record));
+                    };
+                } else if !trimmed.is_empty() {
+                    movetext = true;
+                },
+                Format::Rgf => {
+                    if !record.is_empty() && (trimmed.is_empty() || trimmed.starts_with("fen ")) {
+                        self.next = trimmed.starts_with("fen ").then(|| line + "\n");
+                        return Some(Ok(record));
+                    };
+                    if trimmed.is_empty() {
+                        continue;
+                    };
+                },
+            };
+            record.push_str(&line);
+            record.push('\n');
+        };
+        (!record.trim().is_empty()).then_some(Ok(record))
+    }
+}
+
+struct Game {
+    tags: Vec<(String, String)>,
+    record: GameRecord,
+    result: String,
+}
+
+enum Item {
+    Game(Game),
+    Position {
+        board: Board,
+        // epd's, as opcode and operand
+        operations: Vec<(String, String)>,
+    },
+}
+
+fn result_of(board: &Board) -> &'static str {
+    match board.game_outcome {
+        Some(GameOutcome::Decisive { won: Color::White, .. }) => "1-0",
+        Some(GameOutcome::Decisive { won: Color::Black, .. }) => "0-1",
+        Some(GameOutcome::Draw(_)) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+// the moves of the main line and the result after them, comments, variations and nags are skipped
+fn movetext_tokens(movetext: &str) -> (Vec<String>, Option<String>) {
+    let (mut moves, mut result) = (Vec::new(), None);
+    let mut depth = 0usize;
+    let mut chars = movetext.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => chars.by_ref().take_while(|c| *c != '}').for_each(drop),
+            ';' => chars.by_ref().take_while(|c| *c != '\n').for_each(drop),
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if c.is_whitespace() => {},
+            c => {
+                let mut word = String::from(c);
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"{};()".contains(*c)) {
+                    word.push(c);
+                };
+                if depth > 0 || word.starts_with('$') {
+                    continue;
+                };
+                match word.as_str() {
+                    "1-0" | "0-1" | "1/2-1/2" | "*" => result = Some(word),
+                    // `12.`, `12...` and `12.e4`
+                    _ => match word.rsplit('.').next() {
+                        Some(san) if !san.is_empty() => moves.push(san.to_string()),
+                        _ => {},
+                    },
+                };
+            },
+        };
+    };
+    (moves, result)
+}
+
+fn parse_pgn(raw: &str) -> Result<Game, String> {
+    let mut tags = Vec::new();
+    let mut movetext = String::new();
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        match trimmed.strip_prefix('[').and_then(|line| line.strip_suffix(']')).filter(|_| !trimmed.starts_with("[%")) {
+            Some(pair) => if let Some((name, value)) = pair.split_once(' ') {
+                tags.push((name.to_string(), value.trim().trim_matches('"').replace("\\\"", "\"")));
+            },
+            None => {
+                movetext.push_str(line);
+                movetext.push('\n');
+            },
+        };
+    };
+    let tag = |name: &str| tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.clone());
+
+    let fen = tag("FEN");
+    let mut board = match &fen {
+        Some(fen) => fen::parse(fen, Dialect::Standard).map_err(|err| format!("the fen doesn't parse, {err}"))?.0,
+        None => Board::default(),
+    };
+    let (sans, result) = movetext_tokens(&movetext);
+    let mut moves = Vec::new();
+    for san in sans {
+        let r#move = board.parse_san(&san).ok_or(format!("`{san}` isn't a legal move after {} plies", moves.len()))?;
+        board.play_move(PlayerMove::Internal(r#move)).map_err(|err| format!("`{san}` can't be played: {err:?}"))?;
+        moves.push(r#move);
+    };
+
+    let result = tag("Result").or(result).unwrap_or_else(|| result_of(&board).to_string());
+    tags.retain(|(name, _)| !["FEN", "SetUp", "Result"].contains(&name.as_str()));
+    Ok(Game { tags, record: GameRecord { fen, moves }, result })
+}
+
+fn parse(raw: &str, format: Format) -> Result<Item, String> {
+    match format {
+        Format::Pgn => parse_pgn(raw).map(Item::Game),
+        Format::Fen => fen::parse(raw, Dialect::Standard).map(|(board, _)| Item::Position { board, operations: Vec::new() }).map_err(|err| err.to_string()),
+        Format::Epd => fen::parse_epd(raw).map(|(board, operations)| Item::Position { board, operations }).map_err(|err| err.to_string()),
+        Format::Rgf => {
+            let record = GameRecord::from_rgf(raw).ok_or("a move can't be played or the fen doesn't parse")?;
+            let result = record.board().map_or("*", |board| result_of(&board)).to_string();
+            Ok(Item::Game(Game { tags: Vec::new(), record, result }))
+        },
+    }
+}
+
+// the position before every move and the one after the last
+fn positions(record: &GameRecord) -> Result<Vec<Board>, String> {
+    let mut board = match &record.fen {
+        Some(fen) => Board::from_fen(fen).ok_or("the fen doesn't parse")?,
+        None => Board::default(),
+    };
+    let mut positions = vec![board.clone()];
+    for r#move in &record.moves {
+        board.play_move(PlayerMove::Internal(*r#move)).map_err(|err| format!("{move} can't be played: {err:?}"))?;
+        positions.push(board.clone());
+    };
+    Ok(positions)
+}
+
+fn write_pgn(game: &Game) -> Result<String, String> {
+    let mut tags = SEVEN_TAG_ROSTER.iter().map(|(name, default)| {
+        let value = match *name {
+            "Result" => game.result.clone(),
+            _ => game.tags.iter().find(|(tag, _)| tag == name).map_or(default.to_string(), |(_, value)| value.clone()),
+        };
+        (name.to_string(), value)
+    }).collect::<Vec<_>>();
+    tags.extend(game.tags.iter().filter(|(name, _)| SEVEN_TAG_ROSTER.iter().all(|(roster, _)| roster != name)).cloned());
+    if let Some(fen) = &game.record.fen {
+        tags.push((String::from("SetUp"), String::from("1")));
+        tags.push((String::from("FEN"), fen.clone()));
+    };
+
+    let mut pgn = tags.into_iter().map(|(name, value)| format!("[{name} \"{}\"]\n", value.replace('"', "\\\""))).collect::<String>();
+    pgn.push('\n');
+
+    let mut tokens = Vec::new();
+    let positions = positions(&game.record)?;
+    for (ply, (board, r#move)) in positions.iter().zip(&game.record.moves).enumerate() {
+        let number = board.move_number();
+        match board.move_color {
+            Color::White => tokens.push(format!("{number}.")),
+            Color::Black if ply == 0 => tokens.push(format!("{number}...")),
+            Color::Black => {},
+        };
+        tokens.push(board.san(*r#move));
+    };
+    tokens.push(game.result.clone());
+
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+            pgn += &line;
+            pgn.push('\n');
+            line.clear();
+        };
+        if !line.is_empty() {
+            line.push(' ');
+        };
+        line += &token;
+    };
+    Ok(pgn + &line + "\n\n")
+}
+
+fn write(item: Item, format: Format) -> Result<String, String> {
+    match (item, format) {
+        (Item::Game(game), Format::Pgn) => write_pgn(&game),
+        (Item::Game(game), Format::Rgf) => Ok(game.record.to_rgf() + "\n"),
+        // a game turns into every position it went through
+        (Item::Game(game), Format::Fen) => Ok(positions(&game.record)?.iter().map(|board| board.to_fen() + "\n").collect()),
+        (Item::Game(game), Format::Epd) => Ok(positions(&game.record)?.iter().map(|board| fen::write_epd(board, &[]) + "\n").collect()),
+        // and a position into a game without moves
+        (Item::Position { board, .. }, Format::Pgn | Format::Rgf) => {
+            let result = result_of(&board).to_string();
+            write(Item::Game(Game { tags: Vec::new(), record: GameRecord { fen: Some(board.to_fen()), moves: Vec::new() }, result }), format)
+        },
+        (Item::Position { board, .. }, Format::Fen) => Ok(board.to_fen() + "\n"),
+        (Item::Position { board, operations }, Format::Epd) => Ok(fen::write_epd(&board, &operations) + "\n"),
+    }
+}
+
+// converts a batch split evenly over the threads, keeping the order
+fn convert(records: &[String], from: Format, to: Format, threads: usize) -> Vec<Result<String, String>> {
+    let chunk = records.len().div_ceil(threads).max(1);
+    std::thread::scope(|scope| {
+        let handles = records.chunks(chunk).map(|records| scope.spawn(move || {
+            records.iter().map(|raw| parse(raw, from).and_then(|item| write(item, to))).collect::<Vec<_>>()
+        })).collect::<Vec<_>>();
+        handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+    })
+}
+
+
+// `convert [--from format] [--to format] [--threads n] [input] [output]`, converts between pgn, fen lists, epd and .rgf.
+// the formats are guessed from the extensions when not given, a missing path or `-` is stdin or stdout.
+// records that can't be converted are reported and skipped.
+fn main() -> ExitCode {
+    let (mut from, mut to) = (None, None);
+    let mut threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    let mut paths = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => from = args.next().as_deref().and_then(Format::parse),
+            "--to" => to = args.next().as_deref().and_then(Format::parse),
+            "--threads" => threads = args.next().and_then(|raw| raw.parse().ok()).unwrap_or(threads).max(1),
+            _ => paths.push(arg),
+        };
+    };
+    let input = paths.first().filter(|path| *path != "-");
+    let output = paths.get(1).filter(|path| *path != "-");
+    let (Some(from), Some(to)) = (from.or(input.and_then(|path| Format::of_path(path))), to.or(output.and_then(|path| Format::of_path(path)))) else {
+        eprintln!("usage: convert [--from pgn|fen|epd|rgf] [--to pgn|fen|epd|rgf] [--threads n] [input] [output]");
+        return ExitCode::FAILURE;
+    };
+
+    let reader: Box<dyn BufRead> = match input {
+        Some(path) => match std::fs::File::open(path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(err) => {
+                eprintln!("couldn't open {path}: {err}");
+                return ExitCode::FAILURE;
+            },
+        },
+        None => Box::new(io::stdin().lock()),
+    };
+    let mut writer: BufWriter<Box<dyn Write>> = BufWriter::new(match output {
+        Some(path) => match std::fs::File::create(path) {
+            Ok(file) => Box::new(file),
+            Err(err) => {
+                eprintln!("couldn't create {path}: {err}");
+                return ExitCode::FAILURE;
+            },
+        },
+        None => Box::new(io::stdout().lock()),
+    });
+
+    let mut records = Records { lines: reader.lines(), format: from, next: None };
+    let (mut converted, mut skipped) = (0, 0);
+    loop {
+        let batch = records.by_ref().take(BATCH).collect::<io::Result<Vec<_>>>();
+        let batch = match batch {
+            Ok(batch) if batch.is_empty() => break,
+            Ok(batch) => batch,
+            Err(err) => {
+                eprintln!("couldn't read the input: {err}");
+                return ExitCode::FAILURE;
+            },
+        };
+
+        for result in convert(&batch, from, to, threads) {
+            match result {
+                Ok(text) => {
+                    if let Err(err) = writer.write_all(text.as_bytes()) {
+                        eprintln!("couldn't write the output: {err}");
+                        return ExitCode::FAILURE;
+                    };
+                    converted += 1;
+                },
+                Err(why) => {
+                    skipped += 1;
+                    eprintln!("record {}: {why}", converted + skipped);
+                },
+            };
+        };
+    };
+    if let Err(err) = writer.flush() {
+        eprintln!("couldn't write the output: {err}");
+        return ExitCode::FAILURE;
+    };
+
+    eprintln!("{converted} converted, {skipped} skipped");
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,130 @@
+use std::process::ExitCode;
+use std::time::Instant;
+use ress::{Board, GameOutcome, DrawReason, PlayerMove, WinReason};
+use ress::movegen::MoveGen;
+use ress::position::Position;
+
+
+// what it covers, the position and the number of move sequences it has for every depth from 1 on
+const PERFT: [(&str, &str, &[u64]); 20] = [
+    ("starting position", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8902, 197281]),
+    ("kiwipete", "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862]),
+    ("rook endgame with en passant pins", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238, 674624]),
+    ("promotions and castling under fire", "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467]),
+    ("promotion with capture and check", "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1486, 62379]),
+    ("middlegame", "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P3/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", &[47, 1845, 81467]),
+    ("illegal en passant exposing the king", "3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", &[18, 92, 1670, 10138, 185429]),
+    ("illegal en passant along a diagonal", "8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1", &[13, 102, 1266, 10276, 135655]),
+    ("en passant giving check", "8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", &[15, 126, 1928, 13931, 206379]),
+    ("kingside castling giving check", "5k2/8/8/8/8/8/8/4K2R w K - 0 1", &[15, 66, 1198, 6399, 120330]),
+    ("queenside castling giving check", "3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", &[16, 71, 1286, 7418, 141077]),
+    ("losing castling rights", "r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1", &[26, 1141, 27826]),
+    ("castling prevented", "r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", &[44, 1494, 50509]),
+    ("promoting out of check", "2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", &[11, 133, 1442, 19174, 266199]),
+    ("discovered check", "8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1", &[29, 165, 5160, 31961]),
+    ("promoting to give check", "4k3/1P6/8/8/8/8/K7/8 w - - 0 1", &[9, 40, 472, 2661, 38983, 217342]),
+    ("underpromoting to give check", "8/P1k5/K7/8/8/8/8/8 w - - 0 1", &[6, 27, 273, 1329, 18135, 92683]),
+    ("self stalemate", "K1k5/8/P7/8/8/8/8/8 w - - 0 1", &[2, 6, 13, 63, 382, 2217]),
+    ("stalemate and checkmate with a pawn", "8/k1P5/8/1K6/8/8/8/8 w - - 0 1", &[10, 25, 268, 926, 10857, 43261]),
+    ("stalemate and checkmate with a queen", "8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1", &[37, 183, 6559, 23527]),
+];
+
+enum Expect {
+    Legal,
+    Illegal,
+    Outcome(GameOutcome),
+}
+
+// what it covers, the position, the moves played from it and what the last one should do
+const RULES: [(&str, &str, &[&str], Expect); 12] = [
+    ("castling out of check", "4r1k1/8/8/8/8/8/8/4K2R w K - 0 1", &["e1g1"], Expect::Illegal),
+    ("castling through an attacked square", "5rk1/8/8/8/8/8/8/4K2R w K - 0 1", &["e1g1"], Expect::Illegal),
+    ("castling with an attacked rook", "7r/4k3/8/8/8/8/8/4K2R w K - 0 1", &["e1g1"], Expect::Legal),
+    ("castling queenside past an attacked b1", "1r2k3/8/8/8/8/8/8/R3K3 w Q - 0 1", &["e1c1"], Expect::Legal),
+    ("castling with a captured rook", "r3k2r/8/8/8/8/8/6B1/R3K2R w KQkq - 0 1", &["g2a8", "e8c8"], Expect::Illegal),
+    ("castling after the king went back", "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", &["e1f1", "e8d8", "f1e1", "d8e8", "e1g1"], Expect::Illegal),
+    ("en passant", "4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1", &["e2e4", "d4e3"], Expect::Legal),
+    ("en passant a move too late", "4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1", &["e2e4", "e8e7", "e1e2", "d4e3"], Expect::Illegal),
+    ("en passant pinned along the rank", "8/8/8/K2pP2r/8/8/8/4k3 w - d6 0 1", &["e5d6"], Expect::Illegal),
+    ("en passant out of check", "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1", &["e4d3"], Expect::Legal),
+    ("underpromotion mate", "6nr/5Ppk/6pp/8/8/8/8/K7 w - - 0 1", &["f7f8n"], Expect::Outcome(GameOutcome::Decisive { won: ress::piece::Color::White, reason: WinReason::Checkmate })),
+    ("stalemate", "k7/8/8/2Q5/8/8/8/K7 w - - 0 1", &["c5b6"], Expect::Outcome(GameOutcome::Draw(DrawReason::Stalemate))),
+];
+
+
+// made and unmade in place, a position doesn't end the game on draws the rules declare (e.g. insufficient material),
+// so only running out of moves stops the counting
+fn perft(position: &mut Position, depth: usize, movegen: &mut MoveGen) -> u64 {
+    let mut moves = Vec::new();
+    movegen.generate_position(position, &mut moves);
+    if depth <= 1 {
+        return moves.len() as u64;
+    };
+
+    moves.into_iter().map(|r#move| {
+        let unmake = position.make(r#move);
+        let count = perft(position, depth - 1, movegen);
+        position.unmake(r#move, unmake);
+        count
+    }).sum()
+}
+
+fn check_perft(fen: &str, expected: &[u64]) -> Result<String, String> {
+    let mut position = Board::from_fen(fen).ok_or("the fen doesn't parse")?.position();
+    let start = position.clone();
+    let mut movegen = MoveGen::new();
+    for (depth, expected) in (1..).zip(expected) {
+        let found = perft(&mut position, depth, &mut movegen);
+        if found != *expected {
+            return Err(format!("{found} instead of {expected} at depth {depth}"));
+        };
+        if position != start {
+            return Err(format!("unmaking the moves didn't restore the position at depth {depth}"));
+        };
+    };
+    Ok(format!("{} at depth {}", expected.last().unwrap_or(&0), expected.len()))
+}
+
+fn check_rule(fen: &str, moves: &[&str], expect: &Expect) -> Result<String, String> {
+    let mut board = Board::from_fen(fen).ok_or("the fen doesn't parse")?;
+    let (last, before) = moves.split_last().ok_or("there's no move to check")?;
+    for raw in before {
+        let r#move = PlayerMove::parse(raw).ok_or(format!("{raw} doesn't parse"))?;
+        board.play_move(r#move).map_err(|err| format!("{raw} can't be played: {err:?}"))?;
+    };
+
+    let r#move = PlayerMove::parse(last).ok_or(format!("{last} doesn't parse"))?;
+    match (board.play_move(r#move), expect) {
+        (Ok(_), Expect::Legal) | (Err(_), Expect::Illegal) => Ok(String::new()),
+        (Ok(outcome), Expect::Outcome(expected)) if outcome == Some(*expected) => Ok(format!("{expected:?}")),
+        (Ok(outcome), Expect::Outcome(expected)) => Err(format!("{last} ends in {outcome:?} instead of {expected:?}")),
+        (Ok(_), _) => Err(format!("{last} was played")),
+        (Err(err), _) => Err(format!("{last} was refused: {err:?}")),
+    }
+}
+
+
+// `verify`, runs the move generator through a battery of tricky positions and prints what passed.
+// fails with a non-zero code, so it can be scripted after building with other features or for another target.
+fn main() -> ExitCode {
+    let start = Instant::now();
+    let mut failed = 0;
+    let mut report = |kind, name, result: Result<String, String>| match result {
+        Ok(detail) => println!("ok    {kind} {name}{}", if detail.is_empty() { detail } else { format!(" ({detail})") }),
+        Err(why) => {
+            failed += 1;
+            println!("FAIL  {kind} {name}: {why}");
+        },
+    };
+
+    for (name, fen, expected) in PERFT {
+        report("perft", name, check_perft(fen, expected));
+    };
+    for (name, fen, moves, expect) in &RULES {
+        report("rule ", name, check_rule(fen, moves, expect));
+    };
+
+    let total = PERFT.len() + RULES.len();
+    println!("{} of {total} passed in {:.1}s", total - failed, start.elapsed().as_secs_f32());
+    if failed == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
@@ -0,0 +1,137 @@
+use std::time::{Duration, Instant};
+use crate::{Board, GameOutcome, MoveError, PlayerMove, WinReason};
+use crate::hand::Hand;
+use crate::piece::{Color, PieceKind};
+use crate::position::captured;
+use crate::variant::Variant;
+
+// two boards played by two teams: white on the first board partners black on the second one,
+// and everything captured on one board ends up in the partner's hand on the other.
+// the boards are crazyhouse ones, so what's in hand is in their pockets and can be dropped by their rules.
+#[derive(Debug, Clone)]
+pub struct LinkedGames {
+    pub boards: [Board; 2],
+    pub clocks: [(Duration, Duration); 2],
+    last_tick: Option<Instant>,
+}
+
+impl LinkedGames {
+    pub fn new(time: Duration) -> Self {
+        let mut board = Board::default();
+        board.set_variant(Variant::Crazyhouse);
+        Self {
+            boards: [board.clone(), board],
+            clocks: [(time, time); 2],
+            last_tick: None,
+        }
+    }
+
+    pub fn partner(board: usize) -> usize {
+        1 - board
+    }
+
+    pub fn hand(&self, board: usize, color: Color) -> Hand {
+        *self.boards[board].pockets().hand(color)
+    }
+
+    pub fn clock(&self, board: usize, color: Color) -> Duration {
+        match color {
+            Color::White => self.clocks[board].0,
+            Color::Black => self.clocks[board].1,
+        }
+    }
+
+    fn clock_mut(&mut self, board: usize, color: Color) -> &mut Duration {
+        match color {
+            Color::White => &mut self.clocks[board].0,
+            Color::Black => &mut self.clocks[board].1,
+        }
+    }
+
+    // both boards share the wall clock, so every tick is charged to whoever is to move on each of them.
+    // returns the first (board, color) that has run out of time.
+    pub fn tick(&mut self, now: Instant) -> Option<(usize, Color)> {
+        let elapsed = self.last_tick.map(|last| now.saturating_duration_since(last)).unwrap_or_default();
+        self.last_tick = Some(now);
+
+        if self.outcome().is_some() {
+            return None;
+        };
+
+        let mut flagged = None;
+        for board in 0..2 {
+            let color = self.boards[board].move_color;
+            let clock = self.clock_mut(board, color);
+            *clock = clock.saturating_sub(elapsed);
+            if clock.is_zero() && flagged.is_none() {
+                flagged = Some((board, color));
+            };
+        };
+
+        flagged
+    }
+
+    pub fn outcome(&self) -> Option<(usize, GameOutcome)> {
+        self.boards.iter().enumerate().find_map(|(i, board)| board.game_outcome.map(|outcome| (i, outcome)))
+    }
+
+    pub fn play_move(&mut self, board: usize, r#move: PlayerMove) -> Result<Option<GameOutcome>, MoveError> {
+        if let Some((_, outcome)) = self.outcome() {
+            return Err(MoveError::GameHasOutcome(outcome));
+        };
+
+        if let Some((flagged_board, color)) = self.tick(Instant::now()) {
+            let outcome = GameOutcome::Decisive { won: color.the_other(), reason: WinReason::Timeout };
+            self.boards[flagged_board].game_outcome = Some(outcome);
+            return Err(MoveError::GameHasOutcome(outcome));
+        };
+
+        let color = self.boards[board].move_color;
+        let resolved = self.boards[board].is_legal(&r#move)?;
+        // a promoted piece goes back as the pawn it was
+        let taken = captured(self.boards[board].grid(), color, resolved)
+            .map(|(piece, at)| if self.boards[board].pockets().is_promoted(at) { PieceKind::Pawn } else { piece.kind });
+        let outcome = self.boards[board].play_move(r#move)?;
+
+        // crazyhouse put it in the capturer's hand, but it's the partner's: the other color on the other board
+        if let Some(kind) = taken {
+            self.boards[board].update_hand(color, |hand| {
+                hand.take(kind);
+            });
+            self.boards[Self::partner(board)].update_hand(color.the_other(), |hand| hand.add(kind));
+        };
+
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::PlayerMove;
+    use crate::piece::{Color, PieceKind};
+    use super::LinkedGames;
+
+    fn play(games: &mut LinkedGames, board: usize, raw: &str) {
+        let r#move = games.boards[board].parse_move(raw).expect("a move");
+        games.play_move(board, r#move).expect("a legal move");
+    }
+
+    #[test]
+    fn captures_go_to_the_partner() {
+        let mut games = LinkedGames::new(Duration::from_secs(60));
+        play(&mut games, 0, "e4");
+        play(&mut games, 0, "d5");
+        play(&mut games, 0, "exd5");
+        // white took on the first board, its partner is black on the second
+        assert!(games.hand(0, Color::White).is_empty());
+        assert_eq!(games.hand(1, Color::Black).count(PieceKind::Pawn), 1);
+        assert!(games.hand(1, Color::White).is_empty());
+
+        // and can drop it there
+        play(&mut games, 1, "e4");
+        play(&mut games, 1, "P@d3");
+        assert!(games.hand(1, Color::Black).is_empty());
+        assert!(games.play_move(1, PlayerMove::parse("P@d6").expect("a drop")).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+use alloc::format;
+use alloc::string::String;
+use core::fmt::{Display, Formatter};
+use core::time::Duration;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use crate::piece::Color;
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum ClockStyle {
+    // `1:02:03`, `4:05`, tenths once it's under 10 seconds: `0:09.4`
+    #[default]
+    Standard,
+    // always with tenths, e.g. for spectators or logs
+    Precise,
+}
+
+// how a clock shows the time left (or spent)
+pub fn format_clock(time: Duration, style: ClockStyle) -> String {
+    let tenths = time.as_millis()/100;
+    let (hours, minutes, seconds) = (tenths/36000, tenths/600 % 60, tenths/10 % 60);
+
+    let mut clock = if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    };
+    if style == ClockStyle::Precise || time < Duration::from_secs(10) {
+        clock.push_str(&format!(".{}", tenths % 10));
+    };
+    clock
+}
+
+// `+3`, `+0.5`
+pub fn format_increment(increment: Duration) -> String {
+    let millis = increment.as_millis();
+    if millis.is_multiple_of(1000) {
+        format!("+{}", millis/1000)
+    } else {
+        format!("+{}", increment.as_secs_f32())
+    }
+}
+
+// `5+3` for whole minutes, `45s+0` otherwise
+pub fn format_time_control(base: Duration, increment: Duration) -> String {
+    let seconds = base.as_secs();
+    if seconds.is_multiple_of(60) && seconds > 0 {
+        format!("{}{}", seconds/60, format_increment(increment))
+    } else {
+        format!("{seconds}s{}", format_increment(increment))
+    }
+}
+
+// both clocks on one line, the running one marked: `white 4:59.3 * | black 5:00 (+3)`
+pub fn render_clocks(white: Duration, black: Duration, running: Option<Color>, increment: Option<Duration>, style: ClockStyle) -> String {
+    let side = |color: Color, time: Duration| {
+        let mut side = format!("{color} {}", format_clock(time, style));
+        if running == Some(color) {
+            side.push_str(" *");
+        };
+        side
+    };
+
+    let mut line = format!("{} | {}", side(Color::White, white), side(Color::Black, black));
+    if let Some(increment) = increment {
+        line.push_str(&format!(" ({})", format_increment(increment)));
+    };
+    line
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ClockError {
+    // not minutes or seconds, with the increment after a `+` and the delay after a `d`
+    Format,
+    // a game has to start with some time on the clocks
+    NoTime,
+}
+
+impl Display for ClockError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Format => write!(f, "a time control is the minutes (or seconds with an s), then +increment and d delay in seconds if any"),
+            Self::NoTime => write!(f, "a time control can't start the clocks at zero"),
+        }
+    }
+}
+
+impl core::error::Error for ClockError {}
+
+// how much time a game gives each side
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TimeControl {
+    pub base: Duration,
+    // added after every move
+    pub increment: Duration,
+    // every move gets this long before the clock starts running (a simple, us-style delay)
+    pub delay: Duration,
+}
+
+impl TimeControl {
+    pub fn sudden_death(base: Duration) -> Self {
+        Self { base, increment: Duration::ZERO, delay: Duration::ZERO }
+    }
+
+    // the base in minutes or in seconds with an `s`, then optionally the increment and the delay in seconds:
+    // `5` (sudden death), `3+2`, `90s+0`, `15d5`, `25+10d2`
+    pub fn parse(raw: &str) -> Result<Self, ClockError> {
+        // negative, not finite or too long for a duration, e.g. `1e30`, is just as malformed
+        let seconds = |raw: &str| raw.parse::<f32>().ok().and_then(|seconds| Duration::try_from_secs_f32(seconds).ok()).ok_or(ClockError::Format);
+        let (raw, delay) = match raw.trim().split_once('d') {
+            Some((raw, delay)) => (raw, seconds(delay)?),
+            None => (raw.trim(), Duration::ZERO),
+        };
+        let (base, increment) = match raw.split_once('+') {
+            Some((base, increment)) => (base, seconds(increment)?),
+            None => (raw, Duration::ZERO),
+        };
+        let base = match base.strip_suffix('s') {
+            Some(base) => seconds(base)?,
+            None => seconds(base)?.checked_mul(60).ok_or(ClockError::Format)?,
+        };
+
+        match base.is_zero() {
+            true => Err(ClockError::NoTime),
+            false => Ok(Self { base, increment, delay }),
+        }
+    }
+}
+
+impl Display for TimeControl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", format_time_control(self.base, self.increment))?;
+        if !self.delay.is_zero() {
+            write!(f, "d{}", &format_increment(self.delay)[1..])?;
+        };
+        Ok(())
+    }
+}
+
+// what both sides have left. it doesn't tick by itself (there's no clock to read without std),
+// whoever runs the game says how long each move took.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Clock {
+    pub control: TimeControl,
+    // indexed by color
+    remaining: [Duration; 2],
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        Self { control, remaining: [control.base; 2] }
+    }
+
+    pub fn remaining(&self, color: Color) -> Duration {
+        self.remaining[color as usize]
+    }
+
+    // what `color` has left after thinking for `thinking` on the current move, the delay used up first
+    pub fn remaining_after(&self, color: Color, thinking: Duration) -> Duration {
+        self.remaining(color).saturating_sub(thinking.saturating_sub(self.control.delay))
+    }
+
+    pub fn has_flagged(&self, color: Color, thinking: Duration) -> bool {
+        self.remaining_after(color, thinking).is_zero()
+    }
+
+    // takes `thinking` off `color`'s time without ending the move, e.g. when a game is put aside
+    pub fn charge(&mut self, color: Color, thinking: Duration) {
+        self.remaining[color as usize] = self.remaining_after(color, thinking);
+    }
+
+    // `color` made a move after `thinking` on it, which is charged before the increment is added.
+    // false if its flag fell before that, the clock is left at zero then.
+    pub fn press(&mut self, color: Color, thinking: Duration) -> bool {
+        self.charge(color, thinking);
+        if self.remaining(color).is_zero() {
+            return false;
+        };
+        self.remaining[color as usize] += self.control.increment;
+        true
+    }
+}
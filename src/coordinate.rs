@@ -1,8 +1,13 @@
-use std::fmt::{Display, Formatter};
-use std::ops::{Add, Sub};
-use crate::piece::{Color, Piece, PieceKind};
-
-#[derive(Clone, Debug, Copy, PartialEq)]
+use alloc::format;
+use alloc::string::String;
+use core::fmt::{Display, Formatter};
+use core::ops::{Add, Sub};
+use crate::piece::{Color, PieceKind};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Rank {
     First = 0,
     Second = 1,
@@ -65,12 +70,13 @@ impl Sub<i8> for Rank {
 }
 
 impl Display for Rank {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", *self as i8 + 1)
     }
 }
 
-#[derive(Clone, Debug, Copy, PartialEq)]
+#[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum File {
     A = 0,
     B = 1,
@@ -133,7 +139,7 @@ impl Sub<i8> for File {
 }
 
 impl Display for File {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", match *self {
             File::A => "a",
             File::B => "b",
@@ -147,14 +153,15 @@ impl Display for File {
     }
 }
 
-#[derive(Clone, Debug, Copy, PartialEq)]
+#[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Coordinate {
     pub file: File,
     pub rank: Rank,
 }
 
 impl Display for Coordinate {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}{}", self.file, self.rank)
     }
 }
@@ -204,8 +211,8 @@ impl Coordinate {
     
     pub fn parse(raw: &str) -> Option<Self> {
         Some(Self {
-            file: File::parse(&raw[0..1])?,
-            rank: Rank::parse(&raw[1..2])?,
+            file: File::parse(raw.get(0..1)?)?,
+            rank: Rank::parse(raw.get(1..2)?)?,
         })
     }
 }
@@ -222,6 +229,7 @@ impl Sub<Self> for Coordinate {
 }
 
 #[derive(Clone, Debug, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Side {
     King,
     Queen,
@@ -251,7 +259,7 @@ impl Side {
 }
 
 impl Display for Side {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::King => write!(f, "o-o"),
             Self::Queen => write!(f, "o-o-o"),
@@ -260,6 +268,7 @@ impl Display for Side {
 }
 
 #[derive(Clone, Debug, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Move {
     Simple {
         from: Coordinate,
@@ -276,7 +285,12 @@ pub enum Move {
     },
     Castling {
         side: Side,
-    }
+    },
+    // a piece from the hand put on an empty square, in crazyhouse
+    Drop {
+        piece: PieceKind,
+        to: Coordinate,
+    },
 }
 
 impl Move {
@@ -287,6 +301,8 @@ impl Move {
             Self::Promotion { from, .. } => Coordinate { file: from, rank: color.prepromotion_rank() },
             Self::EnPassant { from, .. } => Coordinate { file: from, rank: color.en_passant_rank() },
             Self::Castling { .. } => Coordinate { file: File::E, rank: color.home_rank() },
+            // it comes from the hand, the square it lands on is the closest thing to where from
+            Self::Drop { to, .. } => to,
         }
     }
 
@@ -297,17 +313,31 @@ impl Move {
             Self::Promotion { to, .. } => Coordinate { file: to, rank: color.promotion_rank() },
             Self::EnPassant { to, .. } => Coordinate { file: to, rank: color.unpassable_rank() },
             Self::Castling { side } => Coordinate { file: side.king_safespot_file(), rank: color.home_rank() },
+            Self::Drop { to, .. } => to,
         }
     }
+
+    // long algebraic notation as used by uci, e.g. `e1g1` for castling, `e7e8q` or `N@f3` for a drop
+    pub fn to_long_algebraic(self, color: Color) -> String {
+        if let Self::Drop { piece, to } = self {
+            return format!("{}@{to}", piece.letter().to_ascii_uppercase());
+        };
+        let mut raw = format!("{}{}", self.resolve_from(color), self.resolve_to(color));
+        if let Self::Promotion { piece, .. } = self {
+            raw.push(piece.letter());
+        };
+        raw
+    }
 }
 
 impl Display for Move {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Move::Simple { from, to } => write!(f, "{from}{to}"),
-            Move::Promotion { from, to, piece } => write!(f, "={from}{to}{piece}"),
+            Move::Promotion { from, to, piece } => write!(f, "={from}{to}{}", piece.letter()),
             Move::EnPassant { from, to } => write!(f, "~{from}{to}"),
             Move::Castling { side } => write!(f, "c{side}"),
+            Move::Drop { piece, to } => write!(f, "@{}{to}", piece.letter()),
         }
     }
 }
@@ -326,6 +356,12 @@ impl Iter {
     }
 }
 
+impl Default for Iter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Iterator for Iter {
     type Item = Coordinate;
 
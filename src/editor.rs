@@ -0,0 +1,127 @@
+use core::fmt::{Display, Formatter};
+use crate::{Board, Rights};
+use crate::coordinate::{Coordinate, File, Side};
+use crate::grid::Grid;
+use crate::piece::{Color, Piece, PieceKind};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum EditError {
+    // there has to be exactly one of each
+    KingCount(Color),
+    PawnOnBackRank(Coordinate),
+    // the side that isn't to move can't be in check, it would have had to leave it
+    WaitingSideInCheck(Color),
+    // the king or the rook the right needs isn't on its home square
+    CastlingWithoutPieces(Color, Side),
+}
+
+impl Display for EditError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::KingCount(color) => write!(f, "{color} has to have exactly one king"),
+            Self::PawnOnBackRank(coord) => write!(f, "there can't be a pawn on {coord}"),
+            Self::WaitingSideInCheck(color) => write!(f, "{color} is in check, but it isn't their turn"),
+            Self::CastlingWithoutPieces(color, side) => write!(f, "{color} can't castle {}, the king or the rook has moved", match side {
+                Side::King => "kingside",
+                Side::Queen => "queenside",
+            }),
+        }
+    }
+}
+
+impl core::error::Error for EditError {}
+
+// a position set up by hand. anything goes while editing, it's only checked once it becomes a board.
+#[derive(Debug, Clone)]
+pub struct BoardEditor {
+    grid: Grid,
+    pub move_color: Color,
+    // (kingside, queenside), like on the board
+    pub white_castle: (bool, bool),
+    pub black_castle: (bool, bool),
+}
+
+impl Default for BoardEditor {
+    // an empty board
+    fn default() -> Self {
+        Self { grid: Grid::default(), move_color: Color::White, white_castle: (false, false), black_castle: (false, false) }
+    }
+}
+
+impl BoardEditor {
+    pub fn from_board(board: &Board) -> Self {
+        Self { grid: board.grid().clone(), move_color: board.move_color, white_castle: board.white_castle, black_castle: board.black_castle }
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    // returns whatever stood there before
+    pub fn put(&mut self, coord: Coordinate, piece: Option<Piece>) -> Option<Piece> {
+        core::mem::replace(&mut self.grid[coord], piece)
+    }
+
+    pub fn clear(&mut self) {
+        self.grid = Grid::default();
+    }
+
+    pub fn castle_mut(&mut self, color: Color) -> &mut (bool, bool) {
+        match color {
+            Color::White => &mut self.white_castle,
+            Color::Black => &mut self.black_castle,
+        }
+    }
+
+    fn unchecked_board(&self) -> Board {
+        let mut board = Board {
+            grid_history: alloc::vec![self.grid.clone()],
+            rights_history: alloc::vec![Rights { move_color: self.move_color, white_castle: self.white_castle, black_castle: self.black_castle, en_passant: None }],
+            white_castle: self.white_castle,
+            black_castle: self.black_castle,
+            move_color: self.move_color,
+            ..Default::default()
+        };
+        board.refresh_piece_list();
+        board.refresh_keys();
+        board
+    }
+
+    // the first thing that makes the position unplayable
+    pub fn validate(&self) -> Result<(), EditError> {
+        let board = self.unchecked_board();
+        for color in [Color::White, Color::Black] {
+            if board.pieces_of(color, PieceKind::King).count() != 1 {
+                return Err(EditError::KingCount(color));
+            };
+        };
+
+        if let Some((_, coord)) = board.pieces(Color::White).chain(board.pieces(Color::Black))
+            .find(|(piece, coord)| piece.kind == PieceKind::Pawn && (coord.rank == Color::White.home_rank() || coord.rank == Color::Black.home_rank())) {
+            return Err(EditError::PawnOnBackRank(coord));
+        };
+
+        let waiting = self.move_color.the_other();
+        if board.pieces_of(waiting, PieceKind::King).any(|king| board.is_under_attack(self.move_color, king, None)) {
+            return Err(EditError::WaitingSideInCheck(waiting));
+        };
+
+        for (color, castle) in [(Color::White, self.white_castle), (Color::Black, self.black_castle)] {
+            let rank = color.home_rank();
+            let at_home = |file: File, kind: PieceKind| self.grid[Coordinate { file, rank }] == Some(Piece { color, kind });
+            for (side, allowed) in [(Side::King, castle.0), (Side::Queen, castle.1)] {
+                if allowed && !(at_home(File::E, PieceKind::King) && at_home(side.rook_home_file(), PieceKind::Rook)) {
+                    return Err(EditError::CastlingWithoutPieces(color, side));
+                };
+            };
+        };
+
+        Ok(())
+    }
+
+    pub fn build(&self) -> Result<Board, EditError> {
+        self.validate()?;
+        Ok(self.unchecked_board())
+    }
+}
@@ -0,0 +1,102 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use crate::Board;
+use crate::coordinate::Move;
+use crate::fen::{self, FenError};
+
+// an epd record as test suites like wac or sts write them: a position, the moves that solve it (`bm`) or
+// the ones that don't (`am`), its name (`id`) and maybe what it's worth (`ce`, in centipawns for the side to move).
+#[derive(Debug, Clone)]
+pub struct Epd {
+    pub board: Board,
+    pub id: Option<String>,
+    pub best_moves: Vec<Move>,
+    pub avoid_moves: Vec<Move>,
+    pub evaluation: Option<i32>,
+    // every other operation as opcode and operand, e.g. `c0` comments
+    pub operations: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum EpdError {
+    Position(FenError),
+    // the opcode and the san in its operand that isn't a legal move
+    Move(String, String),
+    // a `ce` that isn't a number
+    Evaluation(String),
+}
+
+impl Display for EpdError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Position(err) => write!(f, "{err}"),
+            Self::Move(opcode, san) => write!(f, "{san} in `{opcode}` isn't a legal move"),
+            Self::Evaluation(raw) => write!(f, "the evaluation {raw} isn't a number of centipawns"),
+        }
+    }
+}
+
+impl core::error::Error for EpdError {}
+
+impl From<FenError> for EpdError {
+    fn from(err: FenError) -> Self {
+        Self::Position(err)
+    }
+}
+
+// string operands may be quoted, and usually are
+fn unquote(operand: &str) -> &str {
+    operand.strip_prefix('"').and_then(|operand| operand.strip_suffix('"')).unwrap_or(operand)
+}
+
+impl Epd {
+    pub fn parse(raw: &str) -> Result<Self, EpdError> {
+        let (board, operations) = fen::parse_epd(raw)?;
+        let mut epd = Self { board, id: None, best_moves: Vec::new(), avoid_moves: Vec::new(), evaluation: None, operations: Vec::new() };
+        for (opcode, operand) in operations {
+            match opcode.as_str() {
+                "bm" | "am" => {
+                    let moves = operand.split_whitespace()
+                        .map(|san| epd.board.parse_san(san).ok_or_else(|| EpdError::Move(opcode.clone(), san.to_string())))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    match opcode.as_str() {
+                        "bm" => epd.best_moves.extend(moves),
+                        _ => epd.avoid_moves.extend(moves),
+                    };
+                },
+                "id" => epd.id = Some(unquote(&operand).to_string()),
+                "ce" => epd.evaluation = Some(operand.parse().map_err(|_| EpdError::Evaluation(operand.clone()))?),
+                _ => epd.operations.push((opcode, operand)),
+            };
+        };
+        Ok(epd)
+    }
+
+    // whether playing the move solves the position: it's one of the best moves if there are any, and none to avoid
+    pub fn is_solved_by(&self, r#move: Move) -> bool {
+        (self.best_moves.is_empty() || self.best_moves.contains(&r#move)) && !self.avoid_moves.contains(&r#move)
+    }
+}
+
+impl Display for Epd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let sans = |moves: &[Move]| moves.iter().map(|r#move| self.board.san(*r#move)).collect::<Vec<_>>().join(" ");
+        let mut operations = Vec::new();
+        if !self.best_moves.is_empty() {
+            operations.push(("bm".to_string(), sans(&self.best_moves)));
+        };
+        if !self.avoid_moves.is_empty() {
+            operations.push(("am".to_string(), sans(&self.avoid_moves)));
+        };
+        if let Some(evaluation) = self.evaluation {
+            operations.push(("ce".to_string(), evaluation.to_string()));
+        };
+        if let Some(id) = &self.id {
+            operations.push(("id".to_string(), alloc::format!("\"{id}\"")));
+        };
+        operations.extend(self.operations.iter().cloned());
+        write!(f, "{}", fen::write_epd(&self.board, &operations))
+    }
+}
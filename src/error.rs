@@ -0,0 +1,85 @@
+use core::fmt::{Display, Formatter};
+use crate::{MoveError, MoveGenError};
+use crate::clock::ClockError;
+use crate::editor::EditError;
+use crate::epd::EpdError;
+use crate::fen::FenError;
+use crate::game::PolicyError;
+use crate::indexed::IndexError;
+#[cfg(feature = "std")]
+use crate::store::StoreError;
+use crate::validate::Corruption;
+
+// every error the crate can return, for applications that handle them all the same way with `?`.
+// the modules' own errors stay what their functions return, so specific cases can still be matched on.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    Fen(FenError),
+    Epd(EpdError),
+    Move(MoveError),
+    MoveGen(MoveGenError),
+    // a position set up by hand that can't be played
+    Position(EditError),
+    Policy(PolicyError),
+    Index(IndexError),
+    Clock(ClockError),
+    Corruption(Corruption),
+    #[cfg(feature = "std")]
+    Store(StoreError),
+}
+
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Fen(err) => write!(f, "invalid fen: {err}"),
+            Self::Epd(err) => write!(f, "invalid epd: {err}"),
+            Self::Move(err) => write!(f, "{err}"),
+            Self::MoveGen(err) => write!(f, "{err}"),
+            Self::Position(err) => write!(f, "invalid position: {err}"),
+            Self::Policy(err) => write!(f, "{err}"),
+            Self::Index(err) => write!(f, "{err}"),
+            Self::Clock(err) => write!(f, "{err}"),
+            Self::Corruption(err) => write!(f, "corrupt board: {err}"),
+            #[cfg(feature = "std")]
+            Self::Store(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Fen(err) => Some(err),
+            Self::Epd(err) => Some(err),
+            Self::Move(err) => Some(err),
+            Self::MoveGen(err) => Some(err),
+            Self::Position(err) => Some(err),
+            Self::Policy(err) => Some(err),
+            Self::Index(err) => Some(err),
+            Self::Clock(err) => Some(err),
+            Self::Corruption(err) => Some(err),
+            #[cfg(feature = "std")]
+            Self::Store(err) => Some(err),
+        }
+    }
+}
+
+macro_rules! from_errors {
+    ($($variant:ident($err:ty)),* $(,)?) => {
+        $(
+            impl From<$err> for Error {
+                fn from(err: $err) -> Self {
+                    Self::$variant(err)
+                }
+            }
+        )*
+    };
+}
+
+from_errors!(Fen(FenError), Epd(EpdError), Move(MoveError), MoveGen(MoveGenError), Position(EditError), Policy(PolicyError), Index(IndexError), Clock(ClockError), Corruption(Corruption));
+
+#[cfg(feature = "std")]
+from_errors!(Store(StoreError));
@@ -0,0 +1,400 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use crate::{Board, KingRule, Rights};
+use crate::coordinate::{Coordinate, File, Offset, Rank};
+use crate::grid::Grid;
+use crate::hand::{Hand, Pockets};
+use crate::piece::{Color, Piece, PieceKind};
+use crate::variant::Variant;
+
+// whose conventions (lichess') a fen is read and written with, picked by the variant being played.
+// three-check's counters and horde's kingless side come with those variants, there's nothing to play them by yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    #[default]
+    Standard,
+    // the pockets follow the placement, `.../RNBQKBNR[QRbn] w ...`
+    Crazyhouse,
+}
+
+// what a variant keeps beside the board
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct VariantState {
+    // what white and black hold, for crazyhouse
+    pub pockets: Option<(Hand, Hand)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum FenError {
+    Placement,
+    Color,
+    Castling,
+    EnPassant,
+    Clock,
+    Pockets,
+    // not as many kings of the color as the variant wants
+    Kings(Color),
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FenError::Placement => write!(f, "the pieces aren't placed on 8 ranks of 8 squares"),
+            FenError::Color => write!(f, "the side to move is neither w nor b"),
+            FenError::Castling => write!(f, "the castling rights aren't like KQkq or -"),
+            FenError::EnPassant => write!(f, "the en passant square isn't one a pawn could have skipped"),
+            FenError::Clock => write!(f, "the move counters aren't numbers, or too big ones"),
+            FenError::Pockets => write!(f, "the pockets hold something that isn't a piece"),
+            FenError::Kings(color) => write!(f, "{color} doesn't have the number of kings the variant needs"),
+        }
+    }
+}
+
+impl core::error::Error for FenError {}
+
+// the strongest first, as lichess writes them
+fn pocket_letters(hand: &Hand, color: Color) -> String {
+    hand.iter().collect::<Vec<_>>().into_iter().rev().flat_map(|(kind, count)| {
+        let letter = match color {
+            Color::White => kind.letter().to_ascii_uppercase(),
+            Color::Black => kind.letter(),
+        };
+        core::iter::repeat_n(letter, count as usize)
+    }).collect()
+}
+
+// the grid and the promoted pieces on it
+fn parse_placement(raw: &str) -> Result<(Grid, u64), FenError> {
+    let ranks = raw.split('/').collect::<Vec<_>>();
+    if ranks.len() != 8 {
+        return Err(FenError::Placement);
+    };
+
+    let mut grid = Grid::default();
+    let mut promoted = 0;
+    for (rank, row) in (0..8).rev().zip(ranks) {
+        let mut file = 0;
+        for c in row.chars() {
+            match c {
+                // crazyhouse marks promoted pieces, which only matters for what a capture puts in hand
+                '~' if file > 0 => promoted |= 1u64 << (rank*8 + file - 1),
+                '~' => return Err(FenError::Placement),
+                '1'..='8' => file += c as i8 - '0' as i8,
+                c => {
+                    let piece = Piece::parse(c.encode_utf8(&mut [0; 4])).ok_or(FenError::Placement)?;
+                    let coord = Coordinate {
+                        file: File::try_from(file).map_err(|_| FenError::Placement)?,
+                        rank: Rank::try_from(rank).map_err(|_| FenError::Placement)?,
+                    };
+                    grid[coord] = Some(piece);
+                    file += 1;
+                },
+            };
+        };
+        if file != 8 {
+            return Err(FenError::Placement);
+        };
+    };
+    Ok((grid, promoted))
+}
+
+fn parse_pockets(raw: &str) -> Result<(Hand, Hand), FenError> {
+    let (mut white, mut black) = (Hand::default(), Hand::default());
+    for c in raw.chars() {
+        match Piece::parse(c.encode_utf8(&mut [0; 4])) {
+            Some(Piece { kind: PieceKind::King, .. }) | None => return Err(FenError::Pockets),
+            Some(Piece { kind, color: Color::White }) => white.add(kind),
+            Some(Piece { kind, color: Color::Black }) => black.add(kind),
+        };
+    };
+    Ok((white, black))
+}
+
+// a board of the standard variant, or of crazyhouse for its dialect
+pub fn parse(raw: &str, dialect: Dialect) -> Result<(Board, VariantState), FenError> {
+    let variant = match dialect {
+        Dialect::Standard => Variant::Standard,
+        Dialect::Crazyhouse => Variant::Crazyhouse,
+    };
+    parse_variant(raw, variant)
+}
+
+// in the variant's dialect, and with as many kings as it plays with: any number when the king isn't royal (antichess)
+pub fn parse_variant(raw: &str, variant: Variant) -> Result<(Board, VariantState), FenError> {
+    let dialect = variant.dialect();
+    let fields = raw.split_whitespace().collect::<Vec<_>>();
+    let mut state = VariantState::default();
+
+    let mut placement = fields.first().copied().unwrap_or_default();
+    if dialect == Dialect::Crazyhouse {
+        // lichess writes the pockets in brackets, some others as a ninth rank
+        let pockets = if let Some((board, pockets)) = placement.strip_suffix(']').and_then(|placement| placement.split_once('[')) {
+            placement = board;
+            pockets
+        } else if placement.matches('/').count() == 8 {
+            let (board, pockets) = placement.rsplit_once('/').unwrap_or((placement, ""));
+            placement = board;
+            pockets
+        } else {
+            ""
+        };
+        state.pockets = Some(parse_pockets(pockets)?);
+    };
+    let (grid, promoted) = parse_placement(placement)?;
+
+    let move_color = match fields.get(1).copied().unwrap_or("w") {
+        "w" => Color::White,
+        "b" => Color::Black,
+        _ => return Err(FenError::Color),
+    };
+
+    // x-fen and shredder-fen name the rook's file instead, which for the standard setup is the h or a one
+    let (mut white_castle, mut black_castle) = ((false, false), (false, false));
+    match fields.get(2).copied().unwrap_or("-") {
+        "-" => {},
+        rights => for c in rights.chars() {
+            match c {
+                'K' | 'H' => white_castle.0 = true,
+                'Q' | 'A' => white_castle.1 = true,
+                'k' | 'h' => black_castle.0 = true,
+                'q' | 'a' => black_castle.1 = true,
+                _ => return Err(FenError::Castling),
+            };
+        },
+    };
+
+    // the square the pawn skipped, behind the one that just moved two squares
+    let en_passant = match fields.get(3).copied().unwrap_or("-") {
+        "-" => None,
+        raw => {
+            let square = (raw.len() == 2 && raw.is_ascii()).then(|| Coordinate::parse(raw)).flatten().ok_or(FenError::EnPassant)?;
+            let pushed = move_color.the_other();
+            if square.rank != (pushed.pawn_rank() + pushed.direction()).ok_or(FenError::EnPassant)? {
+                return Err(FenError::EnPassant);
+            };
+            Some(square.file)
+        },
+    };
+
+    let stale_plies = fields.get(4).map_or(Ok(0), |raw| raw.parse::<u8>()).map_err(|_| FenError::Clock)?;
+    let move_number = fields.get(5).map_or(Ok(1), |raw| raw.parse::<u16>()).map_err(|_| FenError::Clock)?;
+    // a move number past what the plies played can count up to is as malformed as one that isn't a number
+    let first_ply = u16::try_from((move_number.max(1) as u32 - 1)*2 + (move_color == Color::Black) as u32).map_err(|_| FenError::Clock)?;
+
+    let kings = |color| grid.0.iter().flatten().filter(|piece| **piece == Some(Piece { kind: PieceKind::King, color })).count();
+    for color in [Color::White, Color::Black] {
+        if variant.king_rule() == KingRule::Royal && kings(color) != 1 {
+            return Err(FenError::Kings(color));
+        };
+    };
+
+    // only kept when it can actually be taken, like after a move
+    let capturable = en_passant.filter(|file| [1, -1].into_iter()
+        .filter_map(|of| Coordinate { file: *file, rank: move_color.en_passant_rank() }.checked_add_offset(Offset { vertical: 0, horizontal: of }))
+        .any(|coord| grid[coord] == Some(Piece { color: move_color, kind: PieceKind::Pawn })));
+
+    let mut board = Board {
+        grid_history: vec![grid],
+        rights_history: vec![Rights { move_color, white_castle, black_castle, en_passant: capturable }],
+        stale_plies,
+        first_stale_plies: stale_plies,
+        white_castle,
+        black_castle,
+        move_color,
+        first_ply,
+        ..Default::default()
+    };
+    board.set_variant(variant);
+    if let Some((white, black)) = state.pockets {
+        board.pockets_history = vec![Pockets { white, black, promoted }];
+    };
+    board.refresh_piece_list();
+    board.refresh_keys();
+    Ok((board, state))
+}
+
+// the pockets are the state's if it has them, the board's otherwise
+pub fn write(board: &Board, state: &VariantState, dialect: Dialect) -> String {
+    let pockets = match state.pockets {
+        Some((white, black)) => Pockets { white, black, ..board.pockets() },
+        None => board.pockets(),
+    };
+    let mut placement = String::new();
+    for rank in (0..8i8).rev() {
+        let mut empty = 0;
+        for file in 0..8i8 {
+            let (Ok(file), Ok(rank)) = (File::try_from(file), Rank::try_from(rank)) else {
+                continue;
+            };
+            match board.grid()[Coordinate { file, rank }] {
+                None => empty += 1,
+                Some(piece) => {
+                    if empty > 0 {
+                        placement.push_str(&empty.to_string());
+                        empty = 0;
+                    };
+                    placement.push(match piece.color {
+                        Color::White => piece.kind.letter().to_ascii_uppercase(),
+                        Color::Black => piece.kind.letter(),
+                    });
+                    if dialect == Dialect::Crazyhouse && pockets.is_promoted(Coordinate { file, rank }) {
+                        placement.push('~');
+                    };
+                },
+            };
+        };
+        if empty > 0 {
+            placement.push_str(&empty.to_string());
+        };
+        if rank > 0 {
+            placement.push('/');
+        };
+    };
+    if dialect == Dialect::Crazyhouse {
+        placement = format!("{placement}[{}{}]", pocket_letters(&pockets.white, Color::White), pocket_letters(&pockets.black, Color::Black));
+    };
+
+    let side = match board.move_color {
+        Color::White => 'w',
+        Color::Black => 'b',
+    };
+
+    let castling = [(board.white_castle.0, 'K'), (board.white_castle.1, 'Q'), (board.black_castle.0, 'k'), (board.black_castle.1, 'q')]
+        .into_iter().filter(|(right, _)| *right).map(|(_, letter)| letter).collect::<String>();
+    let castling = if castling.is_empty() { String::from("-") } else { castling };
+
+    let en_passant = board.rights_history.last().and_then(|rights| rights.en_passant).and_then(|file| {
+        let pushed = board.move_color.the_other();
+        Some(Coordinate { file, rank: (pushed.pawn_rank() + pushed.direction())? }.to_string())
+    }).unwrap_or_else(|| String::from("-"));
+
+    format!("{placement} {side} {castling} {en_passant} {} {}", board.stale_plies, board.move_number())
+}
+
+// an epd line, the first four fields of a standard fen followed by operations like `bm Nf3; id "kiwipete";`.
+// the clocks are taken from `hmvc` and `fmvn` when they're there, the other operations come back as opcode and operand.
+pub fn parse_epd(raw: &str) -> Result<(Board, Vec<(String, String)>), FenError> {
+    let mut rest = raw.trim();
+    let mut fields = Vec::new();
+    for _ in 0..4 {
+        let (field, after) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        fields.push(field);
+        rest = after.trim_start();
+    };
+
+    let operations = rest.split(';').map(str::trim).filter(|operation| !operation.is_empty()).map(|operation| {
+        let (opcode, operand) = operation.split_once(char::is_whitespace).unwrap_or((operation, ""));
+        (opcode.to_string(), operand.trim().to_string())
+    }).collect::<Vec<_>>();
+    let operand = |opcode: &str| operations.iter().find(|(name, _)| name == opcode).map(|(_, operand)| operand.as_str());
+
+    let fen = format!("{} {} {}", fields.join(" "), operand("hmvc").unwrap_or("0"), operand("fmvn").unwrap_or("1"));
+    let (board, _) = parse(&fen, Dialect::Standard)?;
+    Ok((board, operations.into_iter().filter(|(opcode, _)| opcode != "hmvc" && opcode != "fmvn").collect()))
+}
+
+// the clocks are written as operations, so nothing is lost going back to a fen
+pub fn write_epd(board: &Board, operations: &[(String, String)]) -> String {
+    let fen = board.to_fen();
+    let fields = fen.split(' ').collect::<Vec<_>>();
+    let mut epd = format!("{} hmvc {}; fmvn {};", fields[..4].join(" "), fields[4], fields[5]);
+    for (opcode, operand) in operations {
+        epd += &match operand.is_empty() {
+            true => format!(" {opcode};"),
+            false => format!(" {opcode} {operand};"),
+        };
+    };
+    epd
+}
+
+impl Board {
+    // a fen of the current position, in the dialect of the variant (a standard one for most)
+    pub fn to_fen(&self) -> String {
+        write(self, &VariantState::default(), self.variant.dialect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use crate::coordinate::Coordinate;
+    use crate::piece::{Color, PieceKind};
+    use crate::variant::Variant;
+    use super::{parse, parse_variant, write, Dialect, FenError, VariantState};
+
+    fn round_trip(raw: &str, dialect: Dialect) -> String {
+        let (board, state) = parse(raw, dialect).expect("a valid fen");
+        write(&board, &state, dialect)
+    }
+
+    #[test]
+    fn standard_round_trip() {
+        for raw in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            // en passant is only kept when a pawn can take
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 13 48",
+        ] {
+            assert_eq!(round_trip(raw, Dialect::Standard), raw);
+        };
+        assert_eq!(round_trip("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1", Dialect::Standard),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+    }
+
+    #[test]
+    fn crazyhouse_pockets() {
+        let raw = "r1bqk2r/pppp1ppp/2n5/4p3/1b2P3/5N2/PPPP1PPP/RNBQK2R~[QRbn] b KQkq - 2 5";
+        assert_eq!(round_trip(raw, Dialect::Crazyhouse), raw);
+        // the pockets as a ninth rank come out in brackets
+        assert_eq!(round_trip("r1bqk2r/pppp1ppp/2n5/4p3/1b2P3/5N2/PPPP1PPP/RNBQK2R/QRbn b KQkq - 2 5", Dialect::Crazyhouse),
+            "r1bqk2r/pppp1ppp/2n5/4p3/1b2P3/5N2/PPPP1PPP/RNBQK2R[QRbn] b KQkq - 2 5");
+
+        let (board, state) = parse(raw, Dialect::Crazyhouse).expect("a valid fen");
+        let (white, black) = state.pockets.expect("pockets");
+        assert_eq!((white.count(PieceKind::Queen), white.count(PieceKind::Rook), white.count(PieceKind::Knight)), (1, 1, 0));
+        assert_eq!((black.count(PieceKind::Bishop), black.count(PieceKind::Knight), black.count(PieceKind::Queen)), (1, 1, 0));
+        assert_eq!(*board.pockets().hand(Color::White), white);
+        assert!(board.pockets().is_promoted(Coordinate::parse("h1").unwrap()));
+
+        assert_eq!(parse("8/8/8/4k3/8/8/8/4K3[Z] w - - 0 1", Dialect::Crazyhouse).err(), Some(FenError::Pockets));
+    }
+
+    #[test]
+    fn move_numbers() {
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 40000", Dialect::Standard).err(), Some(FenError::Clock));
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 32769", Dialect::Standard).err(), Some(FenError::Clock));
+        // the last ply a u16 counts
+        let (board, _) = parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 32768", Dialect::Standard).expect("a valid fen");
+        assert_eq!(board.move_number(), 32768);
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 300 1", Dialect::Standard).err(), Some(FenError::Clock));
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 one", Dialect::Standard).err(), Some(FenError::Clock));
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1", Dialect::Standard).err(), Some(FenError::Placement));
+        assert_eq!(parse("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", Dialect::Standard).err(), Some(FenError::Placement));
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1", Dialect::Standard).err(), Some(FenError::Color));
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KX - 0 1", Dialect::Standard).err(), Some(FenError::Castling));
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1", Dialect::Standard).err(), Some(FenError::EnPassant));
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq é6 0 1", Dialect::Standard).err(), Some(FenError::EnPassant));
+        assert_eq!(parse("", Dialect::Standard).err(), Some(FenError::Placement));
+    }
+
+    #[test]
+    fn kings_per_variant() {
+        let kingless = "rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1BNR w - - 0 1";
+        assert_eq!(parse(kingless, Dialect::Standard).err(), Some(FenError::Kings(Color::White)));
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKKNR w - - 0 1", Dialect::Standard).err(), Some(FenError::Kings(Color::White)));
+        // antichess' king isn't royal, any number of them will do
+        let (board, state) = parse_variant(kingless, Variant::Antichess).expect("a valid antichess fen");
+        assert_eq!(board.variant, Variant::Antichess);
+        assert_eq!(state, VariantState::default());
+        assert_eq!(board.to_fen(), kingless);
+    }
+}
@@ -0,0 +1,490 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use core::time::Duration;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use crate::{Board, DrawReason, GameOutcome, MoveError, PlayerMove, WinReason};
+use crate::clock::Clock;
+use crate::coordinate::Move;
+use crate::piece::{Color, PieceKind};
+
+// what a game lets its players do besides moving, kept here so every front-end permits the same things
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GamePolicy {
+    pub takebacks: bool,
+    // offering a draw before this move is refused, claiming one the rules give never is
+    pub draw_offers_from: usize,
+    // a long move of a pawn onto the last rank without a piece promotes to a queen
+    pub auto_queen: bool,
+    // whether a front-end may ask an engine for the best move on a player's behalf
+    pub hints: bool,
+}
+
+impl GamePolicy {
+    pub fn casual() -> Self {
+        Self { takebacks: true, draw_offers_from: 0, auto_queen: false, hints: true }
+    }
+
+    pub fn rated() -> Self {
+        Self { takebacks: false, draw_offers_from: 30, auto_queen: false, hints: false }
+    }
+}
+
+impl Default for GamePolicy {
+    fn default() -> Self {
+        Self::casual()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum PolicyError {
+    TakebacksNotAllowed,
+    NothingToTakeBack,
+    // the move draws can be offered from
+    DrawOfferTooEarly(usize),
+    GameHasOutcome(GameOutcome),
+    // the agent doesn't play in this game
+    NotSeated,
+    // the side has an agent, only it can act for it
+    Seated(Color),
+    // there's no draw offer to decline, or it was made by the one declining it
+    NoDrawOffer,
+}
+
+impl Display for PolicyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PolicyError::TakebacksNotAllowed => write!(f, "takebacks aren't allowed in this game"),
+            PolicyError::NothingToTakeBack => write!(f, "there is no move to take back"),
+            PolicyError::DrawOfferTooEarly(from) => write!(f, "draws can't be offered before move {from}"),
+            PolicyError::GameHasOutcome(_) => write!(f, "the game is already over"),
+            PolicyError::NotSeated => write!(f, "only the players of this game can do that"),
+            PolicyError::Seated(color) => write!(f, "only the player seated as {color} can do that"),
+            PolicyError::NoDrawOffer => write!(f, "there is no draw offer to decline"),
+        }
+    }
+}
+
+impl core::error::Error for PolicyError {}
+
+// what a player did besides moving, see `Game::journal`
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Action {
+    Resigned,
+    OfferedDraw,
+    // the opponent's offer, or a draw claimed
+    AcceptedDraw,
+    DeclinedDraw,
+    // ran out of time, which whoever keeps the clock reports
+    Flagged,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct JournalEntry {
+    // how many moves had been played
+    pub ply: usize,
+    pub by: Color,
+    // none when the side has no agent, or the action wasn't the player's own
+    pub agent: Option<String>,
+    pub action: Action,
+}
+
+// a line branching off the game after `ply` of its moves
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Variation {
+    pub ply: usize,
+    pub moves: Vec<Move>,
+}
+
+// numbered san of `moves` played from `start`
+fn numbered(start: &Board, moves: &[Move]) -> String {
+    let mut board = start.clone();
+    let mut tokens = Vec::new();
+    for (ply, r#move) in moves.iter().enumerate() {
+        match board.move_color {
+            Color::White => tokens.push(format!("{}.", board.move_number())),
+            Color::Black if ply == 0 => tokens.push(format!("{}...", board.move_number())),
+            Color::Black => {},
+        };
+        tokens.push(board.san(*r#move));
+
+        if board.play_move(PlayerMove::Internal(*r#move)).is_err() {
+            break;
+        };
+    };
+    tokens.join(" ")
+}
+
+// a board played on under a policy. the board is only handed out to read, so nothing gets around it.
+// the board keeps to the rules, everything between the players is kept here: draw offers, resignations,
+// the clock and how long each move took.
+//
+// sides can be given agents (a server's users, engines...), after which resigning and draws are only
+// taken from them, through the `_as` methods. sides without one are acted for by color, e.g. at a shared keyboard.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Game {
+    pub policy: GamePolicy,
+    start: Board,
+    board: Board,
+    moves: Vec<Move>,
+    // indexed by color
+    #[cfg_attr(feature = "serde", serde(default))]
+    seats: [Option<String>; 2],
+    #[cfg_attr(feature = "serde", serde(default))]
+    journal: Vec<JournalEntry>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    variations: Vec<Variation>,
+    // the side that offered a draw the other has yet to answer
+    #[cfg_attr(feature = "serde", serde(default))]
+    draw_offer: Option<Color>,
+    // how the game ended away from the board: a resignation, an agreed draw or a flag fall
+    #[cfg_attr(feature = "serde", serde(default))]
+    outcome: Option<GameOutcome>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    clock: Option<Clock>,
+    // how long each move took, aligned with the moves. none for the ones played without saying, see `play_timed`
+    #[cfg_attr(feature = "serde", serde(default))]
+    times: Vec<Option<Duration>>,
+}
+
+impl Game {
+    pub fn new(start: Board, policy: GamePolicy) -> Self {
+        Self {
+            policy, board: start.clone(), start, moves: Vec::new(), seats: [None, None], journal: Vec::new(), variations: Vec::new(),
+            draw_offer: None, outcome: None, clock: None, times: Vec::new(),
+        }
+    }
+
+    // none to play without one. the time already spent on the game isn't charged to a new clock.
+    pub fn set_clock(&mut self, clock: Option<Clock>) {
+        self.clock = clock;
+    }
+
+    // the clock as of the last move, the side to move's thinking since isn't charged yet
+    pub fn clock(&self) -> Option<Clock> {
+        self.clock
+    }
+
+    pub fn times(&self) -> &[Option<Duration>] {
+        &self.times
+    }
+
+    // how the game ended, on the board or away from it
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        self.outcome.or(self.board.game_outcome)
+    }
+
+    // the side whose draw offer is waiting for an answer
+    pub fn draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
+
+    // gives `color` to `agent`, or back to whoever acts for it by color with none
+    pub fn seat(&mut self, color: Color, agent: Option<&str>) {
+        self.seats[color as usize] = agent.map(String::from);
+    }
+
+    pub fn agent(&self, color: Color) -> Option<&str> {
+        self.seats[color as usize].as_deref()
+    }
+
+    // the side `agent` plays
+    pub fn color_of(&self, agent: &str) -> Result<Color, PolicyError> {
+        [Color::White, Color::Black].into_iter().find(|color| self.agent(*color) == Some(agent)).ok_or(PolicyError::NotSeated)
+    }
+
+    // every resignation, draw offer and answer, and flag fall, in order
+    pub fn journal(&self) -> &[JournalEntry] {
+        &self.journal
+    }
+
+    fn unseated(&self, color: Color) -> Result<(), PolicyError> {
+        match self.agent(color) {
+            Some(_) => Err(PolicyError::Seated(color)),
+            None => Ok(()),
+        }
+    }
+
+    fn note(&mut self, by: Color, action: Action) {
+        let agent = match action {
+            Action::Flagged => None,
+            _ => self.seats[by as usize].clone(),
+        };
+        self.journal.push(JournalEntry { ply: self.moves.len(), by, agent, action });
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    // every move played since the start, in internal notation
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    // the moves so far in san with their numbers, e.g. `1. e4 e5 2. Nf3`, or `12... Qd7 13. O-O` when black moved first
+    pub fn history(&self) -> String {
+        numbered(&self.start, &self.moves)
+    }
+
+    // a scratch board to try moves on from the current position, the game is left as it is
+    pub fn explore(&self) -> Exploration {
+        Exploration { ply: self.moves.len(), board: self.board.clone(), moves: Vec::new() }
+    }
+
+    // the same from the position after `ply` moves, none if fewer were played
+    pub fn explore_from(&self, ply: usize) -> Option<Exploration> {
+        let mut board = self.board.clone();
+        for _ in ply..self.moves.len() {
+            board.undo_move();
+        };
+        (ply <= self.moves.len()).then_some(Exploration { ply, board, moves: Vec::new() })
+    }
+
+    // the lines kept from explorations, in the order they were kept
+    pub fn variations(&self) -> &[Variation] {
+        &self.variations
+    }
+
+    // an exploration that went somewhere, kept alongside the moves actually played
+    pub fn keep(&mut self, exploration: Exploration) {
+        if !exploration.moves.is_empty() {
+            self.variations.push(Variation { ply: exploration.ply, moves: exploration.moves });
+        };
+    }
+
+    pub fn play_move(&mut self, r#move: PlayerMove) -> Result<Option<GameOutcome>, MoveError> {
+        self.play(r#move, None)
+    }
+
+    // a move `thinking` was spent on, which is charged to the clock. when the mover's flag fell before it,
+    // the move isn't played and the flag fall's outcome is returned instead.
+    pub fn play_timed(&mut self, r#move: PlayerMove, thinking: Duration) -> Result<Option<GameOutcome>, MoveError> {
+        let color = self.board.move_color;
+        if self.outcome().is_none() && self.clock.is_some_and(|clock| clock.has_flagged(color, thinking)) {
+            self.flag(color);
+            return Ok(self.outcome());
+        };
+        self.play(r#move, Some(thinking))
+    }
+
+    fn play(&mut self, r#move: PlayerMove, thinking: Option<Duration>) -> Result<Option<GameOutcome>, MoveError> {
+        if let Some(outcome) = self.outcome {
+            return Err(MoveError::GameHasOutcome(outcome));
+        };
+        if self.draw_offer.is_some() {
+            return Err(MoveError::DrawPending);
+        };
+
+        // without the policy the board refuses it with `PromotionRequired`
+        let r#move = match r#move {
+            PlayerMove::Long { from, to, promotion: None } if self.policy.auto_queen => {
+                let queen = PlayerMove::Long { from, to, promotion: Some(PieceKind::Queen) };
+                if self.board.resolve_move(&queen).is_some() { queen } else { PlayerMove::Long { from, to, promotion: None } }
+            },
+            r#move => r#move,
+        };
+
+        let color = self.board.move_color;
+        let resolved = self.board.resolve_move(&r#move);
+        let outcome = self.board.play_move(r#move)?;
+        if let Some(resolved) = resolved {
+            self.moves.push(resolved);
+            self.times.push(thinking);
+        };
+        if let (Some(clock), Some(thinking)) = (&mut self.clock, thinking) {
+            clock.press(color, thinking);
+        };
+        Ok(outcome)
+    }
+
+    // the time spent on the move stays spent
+    pub fn takeback(&mut self) -> Result<(), PolicyError> {
+        if !self.policy.takebacks {
+            return Err(PolicyError::TakebacksNotAllowed);
+        };
+        self.moves.pop().ok_or(PolicyError::NothingToTakeBack)?;
+        self.times.pop();
+        self.board.undo_move();
+        self.draw_offer = None;
+        self.outcome = None;
+        Ok(())
+    }
+
+    // accepts the opponent's offer or claims a draw the rules give as well, which the policy doesn't limit
+    pub fn propose_draw(&mut self, by: Color) -> Result<(), PolicyError> {
+        self.unseated(by)?;
+        self.draw(by)
+    }
+
+    pub fn propose_draw_as(&mut self, agent: &str) -> Result<(), PolicyError> {
+        self.draw(self.color_of(agent)?)
+    }
+
+    fn draw(&mut self, by: Color) -> Result<(), PolicyError> {
+        if let Some(outcome) = self.outcome() {
+            return Err(PolicyError::GameHasOutcome(outcome));
+        };
+        let accepted = self.result_if_draw(by).is_some();
+        if !accepted && self.board.move_number() < self.policy.draw_offers_from {
+            return Err(PolicyError::DrawOfferTooEarly(self.policy.draw_offers_from));
+        };
+        if let Some(claim) = self.board.can_claim_draw() {
+            self.board.claim_draw(claim);
+        } else if accepted {
+            self.outcome = Some(GameOutcome::Draw(DrawReason::Agreement));
+        } else {
+            self.draw_offer = Some(by);
+        };
+        self.note(by, if accepted { Action::AcceptedDraw } else { Action::OfferedDraw });
+        Ok(())
+    }
+
+    // by the side the offer was made to
+    pub fn decline_draw(&mut self) -> Result<(), PolicyError> {
+        let by = self.offered_to()?;
+        self.unseated(by)?;
+        self.decline(by)
+    }
+
+    pub fn decline_draw_as(&mut self, agent: &str) -> Result<(), PolicyError> {
+        let by = self.color_of(agent)?;
+        if self.offered_to()? != by {
+            return Err(PolicyError::NoDrawOffer);
+        };
+        self.decline(by)
+    }
+
+    fn offered_to(&self) -> Result<Color, PolicyError> {
+        self.draw_offer.map(|by| by.the_other()).ok_or(PolicyError::NoDrawOffer)
+    }
+
+    fn decline(&mut self, by: Color) -> Result<(), PolicyError> {
+        self.draw_offer = None;
+        self.note(by, Action::DeclinedDraw);
+        Ok(())
+    }
+
+    // `by` ran out of time, which only loses if the opponent could still mate.
+    // it's the clock's doing, so it's taken whoever keeps the time and whether or not the side has an agent.
+    pub fn flag(&mut self, by: Color) {
+        if let Some(outcome) = self.result_if_flag(by) {
+            self.outcome = Some(outcome);
+            self.note(by, Action::Flagged);
+        };
+    }
+
+    pub fn resign(&mut self, by: Color) -> Result<(), PolicyError> {
+        self.unseated(by)?;
+        self.give_up(by)
+    }
+
+    pub fn resign_as(&mut self, agent: &str) -> Result<(), PolicyError> {
+        self.give_up(self.color_of(agent)?)
+    }
+
+    fn give_up(&mut self, by: Color) -> Result<(), PolicyError> {
+        if let Some(outcome) = self.outcome() {
+            return Err(PolicyError::GameHasOutcome(outcome));
+        };
+        self.outcome = self.result_if_resign(by);
+        self.note(by, Action::Resigned);
+        Ok(())
+    }
+
+    // the following answer "what would happen if ... right now" without touching the game,
+    // returning none when the action wouldn't end it (or it has already ended)
+
+    pub fn result_if_draw(&self, by: Color) -> Option<GameOutcome> {
+        if self.outcome().is_some() {
+            return None;
+        };
+
+        // a claim doesn't need the opponent's agreement
+        if let Some(claim) = self.board.can_claim_draw() {
+            return Some(GameOutcome::Draw(claim.into()));
+        };
+        match self.draw_offer {
+            Some(color) if color.the_other() == by => Some(GameOutcome::Draw(DrawReason::Agreement)),
+            _ => None,
+        }
+    }
+
+    pub fn result_if_resign(&self, by: Color) -> Option<GameOutcome> {
+        self.outcome().is_none().then_some(GameOutcome::Decisive { won: by.the_other(), reason: WinReason::Resignation })
+    }
+
+    // running out of time only loses if the opponent could still mate, and is a draw otherwise
+    pub fn result_if_flag(&self, by: Color) -> Option<GameOutcome> {
+        if self.outcome().is_some() {
+            return None;
+        };
+
+        if self.board.has_mating_material(by.the_other()) {
+            Some(GameOutcome::Decisive { won: by.the_other(), reason: WinReason::Timeout })
+        } else {
+            Some(GameOutcome::Draw(DrawReason::InsufficientMaterial))
+        }
+    }
+}
+
+// what-ifs played from one of a game's positions, see `Game::explore`. no policy applies, anything legal goes
+// and takebacks are always allowed. dropping it returns to the game, `Game::keep` saves the line as a variation.
+#[derive(Debug, Clone)]
+pub struct Exploration {
+    ply: usize,
+    // where it started, kept in the board's history
+    board: Board,
+    moves: Vec<Move>,
+}
+
+impl Exploration {
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    // how many of the game's moves were played before it branched off
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn play_move(&mut self, r#move: PlayerMove) -> Result<Option<GameOutcome>, MoveError> {
+        let resolved = self.board.resolve_move(&r#move);
+        let outcome = self.board.play_move(r#move)?;
+        if let Some(resolved) = resolved {
+            self.moves.push(resolved);
+        };
+        Ok(outcome)
+    }
+
+    // false at the position it started from, which isn't the exploration's to take back
+    pub fn undo(&mut self) -> bool {
+        self.moves.pop().is_some() && self.board.undo_move()
+    }
+
+    // the line in numbered san, e.g. `12... Nxe4 13. Bxf7+`
+    pub fn line(&self) -> String {
+        let mut start = self.board.clone();
+        for _ in &self.moves {
+            start.undo_move();
+        };
+        numbered(&start, &self.moves)
+    }
+}
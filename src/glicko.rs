@@ -0,0 +1,64 @@
+use core::f64::consts::{LN_10, PI};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// glicko-1 (http://www.glicko.net/glicko/glicko.pdf), every game is treated as its own rating period
+const Q: f64 = LN_10/400.0;
+pub const INITIAL_RATING: f64 = 1500.0;
+pub const INITIAL_DEVIATION: f64 = 350.0;
+// how much the deviation grows per period without games, ~100 periods take a settled rating back to unknown
+const DEVIATION_GROWTH: f64 = 34.6;
+// a rating is never trusted more than this
+const MIN_DEVIATION: f64 = 30.0;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rating {
+    pub rating: f64,
+    pub deviation: f64,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self { rating: INITIAL_RATING, deviation: INITIAL_DEVIATION }
+    }
+}
+
+fn g(deviation: f64) -> f64 {
+    1.0/(1.0 + 3.0*Q*Q*deviation*deviation/(PI*PI)).sqrt()
+}
+
+impl Rating {
+    pub fn new(rating: f64, deviation: f64) -> Self {
+        Self { rating, deviation }
+    }
+
+    // the score (1 a win, 0.5 a draw, 0 a loss) expected against `opponent`
+    pub fn expected_score(&self, opponent: &Rating) -> f64 {
+        1.0/(1.0 + 10f64.powf(-g(opponent.deviation)*(self.rating - opponent.rating)/400.0))
+    }
+
+    // the rating after `periods` without any games
+    pub fn aged(&self, periods: u32) -> Self {
+        let deviation = (self.deviation*self.deviation + DEVIATION_GROWTH*DEVIATION_GROWTH*periods as f64).sqrt();
+        Self { rating: self.rating, deviation: deviation.min(INITIAL_DEVIATION) }
+    }
+
+    // the rating after a period with these results, as pairs of the opponent's rating and the score against them
+    pub fn updated(&self, results: &[(Rating, f64)]) -> Self {
+        if results.is_empty() {
+            return *self;
+        };
+
+        let (variance_inv, improvement) = results.iter().fold((0.0, 0.0), |(variance_inv, improvement), (opponent, score)| {
+            let (g, expected) = (g(opponent.deviation), self.expected_score(opponent));
+            (variance_inv + Q*Q*g*g*expected*(1.0 - expected), improvement + g*(score - expected))
+        });
+
+        let precision = 1.0/(self.deviation*self.deviation) + variance_inv;
+        Self {
+            rating: self.rating + Q/precision*improvement,
+            deviation: (1.0/precision).sqrt().max(MIN_DEVIATION),
+        }
+    }
+}
@@ -1,8 +1,11 @@
-use std::ops::{Index, IndexMut};
-use crate::coordinate::{Coordinate, File, Move, Rank, Side};
+use core::ops::{Index, IndexMut};
+use crate::coordinate::{Coordinate, File, Move, Rank};
 use crate::piece::{Color, Piece, PieceKind};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Grid(pub [[Option<Piece>; 8]; 8]);
 
 impl Index<Coordinate> for Grid {
@@ -45,10 +48,15 @@ impl Grid {
                 };
                 false
             },
+            Move::Drop { piece, to } => {
+                // only a pawn, like lichess has it
+                self[to] = Some(Piece { color, kind: piece });
+                piece == PieceKind::Pawn
+            },
         }
     }
     
-    pub fn iter_coord(&self) -> Iter {
+    pub fn iter_coord(&self) -> Iter<'_> {
         Iter {
             grid: self,
             next: Some(Coordinate { file: File::A, rank: Rank::First }),
@@ -0,0 +1,172 @@
+use crate::coordinate::{Coordinate, Move};
+use crate::movegen::bit;
+use crate::piece::{Color, PieceKind};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Hand([u8; 5]);
+
+impl Hand {
+    pub fn count(&self, kind: PieceKind) -> u8 {
+        match kind {
+            PieceKind::King => 0,
+            kind => self.0[kind as usize],
+        }
+    }
+
+    pub fn add(&mut self, kind: PieceKind) {
+        debug_assert!(kind != PieceKind::King, "kings can't be held in hand");
+        if kind != PieceKind::King {
+            self.0[kind as usize] += 1;
+        };
+    }
+
+    pub fn take(&mut self, kind: PieceKind) -> bool {
+        if self.count(kind) == 0 {
+            return false;
+        };
+
+        self.0[kind as usize] -= 1;
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|count| *count == 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (PieceKind, u8)> + '_ {
+        [PieceKind::Pawn, PieceKind::Knight, PieceKind::Bishop, PieceKind::Rook, PieceKind::Queen]
+            .into_iter()
+            .map(|kind| (kind, self.count(kind)))
+            .filter(|(_, count)| *count > 0)
+    }
+}
+
+// what a board with drops keeps beside the grid: what each side holds, and which pieces on the grid were promoted
+// (those go back to the hand as pawns when they're captured)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Pockets {
+    pub white: Hand,
+    pub black: Hand,
+    // a bit per square, a1 first
+    pub promoted: u64,
+}
+
+impl Pockets {
+    pub fn hand(&self, color: Color) -> &Hand {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+
+    pub fn hand_mut(&mut self, color: Color) -> &mut Hand {
+        match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        }
+    }
+
+    pub fn is_promoted(&self, coord: Coordinate) -> bool {
+        self.promoted & bit(coord) != 0
+    }
+
+    // follows the move `color` made, after whatever it captured was dealt with:
+    // a dropped piece leaves the hand, and promoted pieces are followed to where they went
+    pub(crate) fn play(&mut self, r#move: Move, color: Color) {
+        let (from, to) = (bit(r#move.resolve_from(color)), bit(r#move.resolve_to(color)));
+        match r#move {
+            Move::Drop { piece, .. } => {
+                self.hand_mut(color).take(piece);
+            },
+            Move::Promotion { .. } => self.promoted = self.promoted & !from | to,
+            Move::Simple { .. } => {
+                let moved = self.promoted & from != 0;
+                self.promoted &= !(from | to);
+                if moved {
+                    self.promoted |= to;
+                };
+            },
+            // neither a pawn nor a king and a rook that can still castle were ever promoted
+            Move::EnPassant { .. } | Move::Castling { .. } => {},
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use crate::{fen, Board};
+    use crate::coordinate::Coordinate;
+    use crate::piece::{Color, Piece, PieceKind};
+    use super::Hand;
+
+    fn crazyhouse(raw: &str) -> Board {
+        fen::parse(raw, fen::Dialect::Crazyhouse).expect("a valid fen").0
+    }
+
+    fn play(board: &mut Board, raw: &str) {
+        let r#move = board.parse_move(raw).expect("a move");
+        board.play_move(r#move).expect("a legal move");
+    }
+
+    fn square(raw: &str) -> Coordinate {
+        Coordinate::parse(raw).expect("a square")
+    }
+
+    #[test]
+    fn hand() {
+        let mut hand = Hand::default();
+        assert!(hand.is_empty());
+        hand.add(PieceKind::Knight);
+        hand.add(PieceKind::Pawn);
+        hand.add(PieceKind::Knight);
+        assert_eq!(hand.iter().collect::<Vec<_>>(), [(PieceKind::Pawn, 1), (PieceKind::Knight, 2)]);
+        assert!(hand.take(PieceKind::Knight));
+        assert!(!hand.take(PieceKind::Queen));
+        // a king is never held
+        assert!(!hand.take(PieceKind::King));
+        assert_eq!(hand.count(PieceKind::Knight), 1);
+    }
+
+    #[test]
+    fn promoted_goes_back_as_a_pawn() {
+        let mut board = crazyhouse("3q~k3/8/8/8/8/8/8/3QK3[] w - - 0 1");
+        play(&mut board, "Qxd8+");
+        assert_eq!(board.pockets().white.iter().collect::<Vec<_>>(), [(PieceKind::Pawn, 1)]);
+        assert!(!board.pockets().is_promoted(square("d8")));
+        // the queen that took wasn't promoted, it goes in whole
+        play(&mut board, "Kxd8");
+        assert_eq!(board.pockets().black.count(PieceKind::Queen), 1);
+    }
+
+    #[test]
+    fn promotions_are_followed() {
+        let mut board = crazyhouse("4k3/P7/8/8/8/8/8/4K3[] w - - 0 1");
+        play(&mut board, "a8=Q+");
+        assert!(board.pockets().is_promoted(square("a8")));
+        play(&mut board, "Kd7");
+        play(&mut board, "Qa4+");
+        assert!(!board.pockets().is_promoted(square("a8")));
+        assert!(board.pockets().is_promoted(square("a4")));
+    }
+
+    #[test]
+    fn drops_take_from_the_hand() {
+        let mut board = crazyhouse("4k3/8/8/8/8/8/8/4K3[PPn] w - - 0 1");
+        // no pawns on the first or last rank, and nothing that isn't held
+        assert!(board.parse_move("P@a1").and_then(|r#move| board.resolve_move(&r#move)).is_none());
+        assert!(board.parse_move("N@e4").and_then(|r#move| board.resolve_move(&r#move)).is_none());
+
+        play(&mut board, "P@e4");
+        assert_eq!(board.grid()[square("e4")], Some(Piece { kind: PieceKind::Pawn, color: Color::White }));
+        assert_eq!(board.pockets().white.count(PieceKind::Pawn), 1);
+        play(&mut board, "N@c3");
+        assert!(board.pockets().black.is_empty());
+        // a pawn drop resets the fifty-move count, any other doesn't
+        assert_eq!(board.stale_plies, 1);
+    }
+}
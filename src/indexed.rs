@@ -0,0 +1,79 @@
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use crate::Board;
+use crate::coordinate::{Coordinate, Move};
+use crate::piece::Color;
+
+// moves as small numbers, for protocols and front-ends that would rather not send notation back and forth.
+// a move's index is its place among the legal moves sorted by from square, to square and promotion piece,
+// so it depends on the position alone, not on how it was reached or on the order moves are generated in.
+
+// an index along with the position it's meant for, so it can't be played somewhere else by mistake
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MoveIndex {
+    // `Board::position_hash`
+    pub position: u64,
+    pub index: u16,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum IndexError {
+    // the hash of the position the index was meant for
+    WrongPosition(u64),
+    // how many legal moves there are
+    OutOfRange(usize),
+}
+
+impl Display for IndexError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::WrongPosition(hash) => write!(f, "the move was meant for another position ({hash:016x})"),
+            Self::OutOfRange(count) => write!(f, "there are only {count} legal moves"),
+        }
+    }
+}
+
+impl core::error::Error for IndexError {}
+
+fn key(r#move: Move, color: Color) -> u16 {
+    let square = |coord: Coordinate| coord.rank as u16*8 + coord.file as u16;
+    let promotion = match r#move {
+        // a drop starts and ends on the same square, which no other move does
+        Move::Promotion { piece, .. } | Move::Drop { piece, .. } => 1 + piece as u16,
+        _ => 0,
+    };
+    (square(r#move.resolve_from(color))*64 + square(r#move.resolve_to(color)))*8 + promotion
+}
+
+impl Board {
+    // every legal move for the side to move, in index order. it's double-ended, so the last ones are as easy to get.
+    pub fn indexed_moves(&self) -> impl DoubleEndedIterator<Item = Move> + ExactSizeIterator {
+        let color = self.move_color;
+        let mut moves = self.possible_moves(color);
+        moves.sort_unstable_by_key(|r#move| key(*r#move, color));
+        moves.into_iter()
+    }
+
+    pub fn legal_move_at(&self, index: usize) -> Option<Move> {
+        self.indexed_moves().nth(index)
+    }
+
+    // none if the move isn't legal here
+    pub fn move_index(&self, r#move: Move) -> Option<MoveIndex> {
+        let index = self.indexed_moves().position(|legal| legal == r#move)?;
+        Some(MoveIndex { position: self.position_hash(), index: index as u16 })
+    }
+
+    // the move an index stands for, as long as it was taken in this position
+    pub fn resolve_index(&self, index: MoveIndex) -> Result<Move, IndexError> {
+        if index.position != self.position_hash() {
+            return Err(IndexError::WrongPosition(index.position));
+        };
+        let moves = self.indexed_moves().collect::<Vec<_>>();
+        moves.get(index.index as usize).copied().ok_or(IndexError::OutOfRange(moves.len()))
+    }
+}
@@ -1,77 +1,269 @@
-#![feature(try_blocks)]
-#![feature(let_chains)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use std::fmt::{Display, Formatter};
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use core::ops::ControlFlow;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use grid::Grid;
-use piece::{Color, Piece, PieceKind};
-use crate::coordinate::{Coordinate, File, Move, Offset, Rank, Side};
+use annotation::Annotations;
+use movegen::{LegalMoves, MoveInfo, Setup};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use position::{captured, castling_after, en_passant_after, follow_move};
+use zobrist::PositionKey;
+use variant::Variant;
+use piece::{Color, Piece, PieceKind, ValueTable};
+use hand::{Hand, Pockets};
+use crate::coordinate::{Coordinate, File, Move, Rank, Side};
 
 pub mod coordinate;
 pub mod piece;
+pub mod hand;
+#[cfg(feature = "std")]
+pub mod bughouse;
+pub mod annotation;
+pub mod teach;
+pub mod editor;
+pub mod clock;
+pub mod render;
+pub mod fen;
+pub mod epd;
+pub mod game;
+pub mod indexed;
+#[cfg(feature = "oracle")]
+pub mod oracle;
+#[cfg(feature = "std")]
+pub mod store;
+#[cfg(feature = "std")]
+pub mod glicko;
 mod grid;
-
-#[derive(Debug, Copy, Clone)]
+pub mod movegen;
+pub mod position;
+mod san;
+pub mod validate;
+pub mod zobrist;
+pub mod variant;
+pub mod error;
+
+pub use error::{Error, Result};
+
+// plies without a capture or a pawn move: a draw may be claimed after 50 moves, and the game ends by itself after 75
+pub const CLAIMABLE_DRAW_PLIES: u8 = 100;
+pub const AUTOMATIC_DRAW_PLIES: u8 = 150;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum GameOutcome {
     Decisive { won: Color, reason: WinReason },
     Draw(DrawReason),
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum WinReason {
     Checkmate,
     Resignation,
+    Timeout,
+    // the king reached the center, see `Variant::KingOfTheHill`
+    KingOfTheHill,
+    // the winner had no legal moves left, see `Variant::Antichess`
+    OutOfMoves,
 }
 
 impl Display for WinReason {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Resignation => write!(f, "resignation"),
             Self::Checkmate => write!(f, "checkmate"),
+            Self::Timeout => write!(f, "timeout"),
+            Self::KingOfTheHill => write!(f, "the king reaching the center"),
+            Self::OutOfMoves => write!(f, "running out of moves"),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DrawReason {
     Agreement,
     Stalemate,
     ThreefoldRepetition,
     FivefoldRepetition,
+    // 75 moves without a capture or a pawn move, which ends the game by itself
     NoAdvancement,
     InsufficientMaterial,
+    // 50 of them, after which either player may claim a draw
+    FiftyMoveClaim,
 }
 
 impl Display for DrawReason {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             DrawReason::Agreement => write!(f, "agreement"),
             DrawReason::Stalemate => write!(f, "stalemate"),
             DrawReason::ThreefoldRepetition => write!(f, "threefold repetition"),
             DrawReason::FivefoldRepetition => write!(f, "fivefold repetition"),
-            DrawReason::NoAdvancement => write!(f, "lack of advancement in the position (50-move rule)"),
+            DrawReason::NoAdvancement => write!(f, "lack of advancement in the position (75-move rule)"),
             DrawReason::InsufficientMaterial => write!(f, "there is no sufficient material to checkmate"),
+            DrawReason::FiftyMoveClaim => write!(f, "a claim under the 50-move rule"),
+        }
+    }
+}
+
+// a draw the rules give either player for the asking, see `Board::can_claim_draw`
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DrawClaim {
+    // the position has occurred three times, with the same side to move and the same rights
+    ThreefoldRepetition,
+    // 50 moves without a capture or a pawn move
+    FiftyMoves,
+}
+
+impl From<DrawClaim> for DrawReason {
+    fn from(claim: DrawClaim) -> Self {
+        match claim {
+            DrawClaim::ThreefoldRepetition => DrawReason::ThreefoldRepetition,
+            DrawClaim::FiftyMoves => DrawReason::FiftyMoveClaim,
         }
     }
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
 pub enum MoveError {
     GameHasOutcome(GameOutcome),
     IllegalMove,
     AmbiguousMove,
+    // only from a `Game`, the board itself knows nothing of draw offers
     DrawPending,
+    // a pawn reaching the last rank without the piece it becomes
+    PromotionRequired,
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::GameHasOutcome(_) => write!(f, "the game is already over"),
+            Self::IllegalMove => write!(f, "the move is illegal"),
+            Self::AmbiguousMove => write!(f, "the move is ambiguous"),
+            Self::DrawPending => write!(f, "a draw has been offered, it has to be accepted or declined first"),
+            Self::PromotionRequired => write!(f, "the piece the pawn becomes has to be named"),
+        }
+    }
+}
+
+impl core::error::Error for MoveError {}
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum MoveGenError {
+    MissingKing(Color),
 }
 
+impl Display for MoveGenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::MissingKing(color) => write!(f, "{color} has no king"),
+        }
+    }
+}
+
+impl core::error::Error for MoveGenError {}
+
+// whether the king is the piece the game is about, set by the variant being played
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum KingRule {
+    // it can't be left in check, and a position without one can't be played
+    #[default]
+    Royal,
+    // just another piece (e.g. antichess), moves aren't filtered for check at all
+    Ordinary,
+}
+
+// everything besides the grid that makes two positions the same one for repetitions (fide 9.2.3)
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rights {
+    pub move_color: Color,
+    pub white_castle: (bool, bool),
+    pub black_castle: (bool, bool),
+    // the file of a pawn that was just pushed two squares, only if an enemy pawn stands next to it
+    pub en_passant: Option<File>,
+}
+
+// what `Board::make_null_move` replaced, for putting it back
+#[derive(Debug, Copy, Clone)]
+pub struct NullMove {
+    rights: Rights,
+    key: PositionKey,
+}
+
+// the piece list and the position keys are rebuilt on deserialization instead of being stored, see the impls below
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(remote = "Self"))]
 pub struct Board {
     pub grid_history: Vec<Grid>,
+    // one per grid, aligned from the end. boards saved before it existed have none, and those positions just never repeat
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rights_history: Vec<Rights>,
+    // every move played on the board and who played it, aligned with the grids from the end like the rights.
+    // boards saved before it existed have none, and start their history where they were loaded.
+    #[cfg_attr(feature = "serde", serde(default))]
+    moves: Vec<(Move, Color)>,
+    // what's in hand and which pieces were promoted, aligned with the grids from the end like the rights.
+    // it starts being kept with the first move of a variant dropping pieces (or a fen with pockets), grids before it held nothing.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pockets_history: Vec<Pockets>,
     pub last_move: Option<Move>,
     pub stale_plies: u8,
     pub white_castle: (bool, bool),
     pub black_castle: (bool, bool),
     pub move_color: Color,
     pub game_outcome: Option<GameOutcome>,
-    pub draw_pending: Option<(bool, Color)>,
+    pub annotations: Annotations,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub king_rule: KingRule,
+    // the rules it's played by, see `set_variant`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub variant: Variant,
+    // plies played before the first grid, when the board starts from a fen's move number
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub first_ply: u16,
+    // what `stale_plies` was at the first grid, for undoing back to it
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub first_stale_plies: u8,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    piece_list: Vec<(Piece, Coordinate)>,
+    // one per rights entry, for telling repetitions apart. rebuilt on deserialization like the piece list.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    keys: Vec<PositionKey>,
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Board {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Self::serialize(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut board = Self::deserialize(deserializer)?;
+        board.refresh_piece_list();
+        board.refresh_keys();
+        #[cfg(feature = "validate")]
+        validate::validate(&board).map_err(serde::de::Error::custom)?;
+        Ok(board)
+    }
 }
 
 macro_rules! row {
@@ -82,7 +274,7 @@ macro_rules! row {
 
 impl Default for Board {
     fn default() -> Self {
-        Self {
+        let mut board = Self {
             grid_history: vec![Grid([
                 row!(R N B Q K B N R),
                 row!(P P P P P P P P),
@@ -93,99 +285,85 @@ impl Default for Board {
                 row!(p p p p p p p p),
                 row!(r n b q k b n r),
             ])],
+            rights_history: vec![Rights { move_color: Color::White, white_castle: (true, true), black_castle: (true, true), en_passant: None }],
+            moves: Vec::new(),
+            pockets_history: Vec::new(),
             last_move: None,
             stale_plies: 0,
             white_castle: (true, true),
             black_castle: (true, true),
             move_color: Color::White,
             game_outcome: None,
-            draw_pending: None,
+            annotations: Annotations::default(),
+            king_rule: KingRule::Royal,
+            variant: Variant::Standard,
+            first_ply: 0,
+            first_stale_plies: 0,
+            piece_list: Vec::new(),
+            keys: Vec::new(),
+        };
+        board.refresh_piece_list();
+        board.refresh_keys();
+        board
+    }
+}
+
+impl Setup for Board {
+    fn grid(&self) -> &Grid {
+        Board::grid(self)
+    }
+
+    fn castling(&self, color: Color) -> (bool, bool) {
+        match color {
+            Color::White => self.white_castle,
+            Color::Black => self.black_castle,
         }
     }
+
+    fn en_passant(&self, color: Color) -> Option<File> {
+        self.rights_history.last().filter(|rights| rights.move_color == color).and_then(|rights| rights.en_passant)
+    }
+
+    fn king_rule(&self) -> KingRule {
+        self.king_rule
+    }
+
+    fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    fn hand(&self, color: Color) -> Hand {
+        *self.pockets().hand(color)
+    }
+
+    fn pieces(&self, color: Color) -> impl Iterator<Item = (Piece, Coordinate)> + '_ {
+        Board::pieces(self, color)
+    }
 }
 
 impl Board {
-    // fixme proper handling of incorrect FENs and / (separators)
+    // a standard fen, see `fen::parse` for the other dialects and why one is rejected
     pub fn from_fen(raw: &str) -> Option<Self> {
-        let mut grid = Grid::default();
-        let mut white_castle = (false, false);
-        let mut black_castle = (false, false);
-        let mut move_color = Color::White;
-        let mut stale_plies = 0;
-
-        #[derive(Debug, Clone, Copy)]
-        enum ParsingState {
-            Position, ColorToMove, Useless, Castling, StalePlies
-        }
-
-        impl ParsingState {
-            pub fn next(self) -> Option<Self> {
-                Some(match self {
-                    Self::Position => Self::ColorToMove,
-                    Self::ColorToMove => Self::Castling,
-                    Self::Castling => Self::Useless,
-                    Self::Useless => Self::StalePlies,
-                    Self::StalePlies => None?,
-                })
-            }
-        }
+        fen::parse(raw, fen::Dialect::Standard).ok().map(|(board, _)| board)
+    }
 
-        let mut state = ParsingState::Position;
-        let mut coord_iter = Coordinate::iter().rev();
-        let mut last_buf = String::new();
-        for c in (0..raw.len()).map(|i| &raw[i..=i]) {
-            if c == " " {
-                if let Some(next) = state.next() {
-                    state = next;
-                    continue;
-                } else {
-                    break;
-                };
-            };
+    // plays the board by the variant's rules from now on, the king rule included
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+        self.king_rule = variant.king_rule();
+    }
 
-            match state {
-                ParsingState::Position => {
-                    if let Ok(skip) = c.parse::<usize>() && skip > 0 && skip < 9 {
-                        for _ in 0..skip {
-                            let _ = coord_iter.next();
-                        };
-                    } else if let Some(piece) = Piece::parse(c) {
-                        grid[coord_iter.next().unwrap()] = Some(piece);
-                    };
-                },
-                ParsingState::ColorToMove => {
-                    // todo move to Color::parse
-                    move_color = match c {
-                        "b" => Color::Black,
-                        _ => Color::White,
-                    };
-                },
-                ParsingState::Castling => {
-                    match c {
-                        "K" => white_castle.1 = true,
-                        "k" => black_castle.1 = true,
-                        "Q" => white_castle.0 = true,
-                        "q" => black_castle.0 = true,
-                        _ => {},
-                    };
-                },
-                ParsingState::StalePlies => {
-                    last_buf.push_str(c);
-                },
-                ParsingState::Useless => {}
-            };
-        };
-        
-        stale_plies = last_buf.parse().unwrap();
-
-        Some(Self {
-            grid_history: vec![grid],
-            white_castle,
-            black_castle,
-            move_color,
-            stale_plies,
-            ..Default::default()
-        })
+    // fnv-1a over the squares, side to move and castling rights.
+    // it gets persisted (e.g. by the sqlite store), so it has to stay the same between builds, unlike std's hasher.
+    pub fn position_hash(&self) -> u64 {
+        let squares = Coordinate::iter().map(|coord| self.grid()[coord].map_or(0, |piece| 1 + piece.kind as u8*2 + piece.color as u8));
+        let state = [
+            self.move_color as u8,
+            self.white_castle.0 as u8, self.white_castle.1 as u8,
+            self.black_castle.0 as u8, self.black_castle.1 as u8,
+        ];
+
+        squares.chain(state).fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
     }
 
     pub fn grid(&self) -> &Grid {
@@ -196,375 +374,295 @@ impl Board {
         unsafe { self.grid_history.last_mut().unwrap_unchecked() }
     }
 
-    fn unchecked_for_check_possible_moves(&self, for_color: Color) -> Vec<Move> {
-        let mut possible_moves = Vec::new();
+    // has to be called after the grid is changed so the piece list doesn't go stale
+    fn refresh_piece_list(&mut self) {
+        let piece_list = self.grid().iter_coord().filter_map(|(piece, coord)| piece.map(|piece| (piece, coord))).collect();
+        self.piece_list = piece_list;
+    }
 
-        for (piece, coord) in self.grid().iter_coord()
-            .filter_map(|(piece, coord)| piece.map(|piece| (piece, coord)))
-            .filter_map(|(piece, coord)| (piece.color == for_color).then_some((piece.kind, coord))) {
-            let _: Option<_> = try {
-                match piece {
-                    PieceKind::Pawn => {
-                        // first move
-                        {
-                            if coord.rank == for_color.pawn_rank() {
-                                let path = coord.checked_add_offset(Offset { vertical: for_color.direction(), horizontal: 0 }).unwrap();
-                                let to = coord.checked_add_offset(Offset { vertical: for_color.direction()*2, horizontal: 0 }).unwrap();
-                                if self.grid()[to].is_none() && self.grid()[path].is_none() {
-                                    possible_moves.push(Move::Simple { from: coord, to });
-                                };
-                            };
-                        }
-
-                        // move forward
-                        {
-                            let _: Option<_> = try {
-                                let to = coord.checked_add_offset(Offset { vertical: for_color.direction(), horizontal: 0 })?;
-                                if self.grid()[to].is_none() {
-                                    if to.rank != for_color.promotion_rank() {
-                                        possible_moves.push(Move::Simple { from: coord, to });
-                                    } else {
-                                        for piece_kind in [PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight] {
-                                            possible_moves.push(Move::Promotion { from: coord.file, to: to.file, piece: piece_kind });
-                                        };
-                                    };
-                                };
-                            };
-                        };
-
-                        // move diagonally
-                        {
-                            for to in [1, -1].map(|of| coord.checked_add_offset(Offset { vertical: for_color.direction(), horizontal: of })) {
-                                let _: Option<_> = try {
-                                    let to = to?;
-
-                                    if let Some(Piece { color, .. }) = self.grid()[to] && color == for_color.the_other() {
-                                        if to.rank != for_color.promotion_rank() {
-                                            possible_moves.push(Move::Simple { from: coord, to });
-                                        } else {
-                                            for piece_kind in [PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight] {
-                                                possible_moves.push(Move::Promotion { from: coord.file, to: to.file, piece: piece_kind });
-                                            };
-                                        };
-                                    };
-                                };
-                            }
-                        };
-
-                        if coord.rank == for_color.en_passant_rank() &&
-                            let Some(Move::Simple { from, to }) = self.last_move &&
-                            self.grid_history[self.grid_history.len()-2][from].unwrap().kind == PieceKind::Pawn &&
-                            to.rank == for_color.en_passant_rank() && (coord.file as i8 - to.file as i8).abs() == 1 {
-                            possible_moves.push(Move::EnPassant { from: from.file, to: to.file });
-                        };
-                    },
-                    PieceKind::Knight => {
-                        for to in [
-                            (2, 1), (2, -1),
-                            (-2, 1), (-2, -1),
-                            (1, 2), (-1, 2),
-                            (1, -2), (-1, -2),
-                        ].map(|of| coord.checked_add_offset(of.into())) {
-                            let _: Option<_> = try {
-                                let to = to?;
-                                let square = self.grid()[to];
-                                if square.is_none() || matches!(square, Some(Piece { color, .. }) if color == for_color.the_other()) {
-                                    possible_moves.push(Move::Simple { from: coord, to });
-                                };
-                            };
-                        };
-                    },
-                    PieceKind::Bishop => {
-                        for of in [
-                            (1, 1), (1, -1),
-                            (-1, 1), (-1, -1),
-                        ] {
-                            let _: Option<_> = try {
-                                let mut check_coord = coord.checked_add_offset(of.into())?;
-
-                                while self.grid()[check_coord].is_none() {
-                                    possible_moves.push(Move::Simple { from: coord, to: check_coord });
-                                    check_coord = check_coord.checked_add_offset(of.into())?;
-                                };
-
-                                if let Some(Piece { color, .. }) = self.grid()[check_coord] && color == for_color.the_other() {
-                                    possible_moves.push(Move::Simple { from: coord, to: check_coord });
-                                };
-                            };
-                        }
-                    },
-                    PieceKind::Rook => {
-                        for of in [
-                            (0, 1), (0, -1),
-                            (1, 0), (-1, 0),
-                        ] {
-                            let _: Option<_> = try {
-                                let mut check_coord = coord.checked_add_offset(of.into())?;
-
-                                while self.grid()[check_coord].is_none() {
-                                    possible_moves.push(Move::Simple { from: coord, to: check_coord });
-                                    check_coord = check_coord.checked_add_offset(of.into())?;
-                                };
-
-                                if let Some(Piece { color, .. }) = self.grid()[check_coord] && color == for_color.the_other() {
-                                    possible_moves.push(Move::Simple { from: coord, to: check_coord });
-                                };
-                            };
-                        }
-                    },
-                    PieceKind::Queen => {
-                        for of in [
-                            (0, 1), (0, -1),
-                            (1, 0), (-1, 0),
-                            (1, 1), (1, -1),
-                            (-1, 1), (-1, -1),
-                        ] {
-                            let _: Option<_> = try {
-                                let mut check_coord = coord.checked_add_offset(of.into())?;
-
-                                while self.grid()[check_coord].is_none() {
-                                    possible_moves.push(Move::Simple { from: coord, to: check_coord });
-                                    check_coord = check_coord.checked_add_offset(of.into())?;
-                                };
-
-                                if let Some(Piece { color, .. }) = self.grid()[check_coord] && color == for_color.the_other() {
-                                    possible_moves.push(Move::Simple { from: coord, to: check_coord });
-                                };
-                            };
-                        }
-                    },
-                    PieceKind::King => {
-                        for of in [
-                            (0, 1), (0, -1),
-                            (1, 0), (-1, 0),
-                            (1, 1), (1, -1),
-                            (-1, 1), (-1, -1),
-                        ] {
-                            let _: Option<_> = try {
-                                let check_coord = coord.checked_add_offset(of.into())?;
-
-                                let piece = self.grid()[check_coord];
-
-                                if piece.is_none() || matches!(piece, Some(Piece { color, .. }) if color == for_color.the_other()) {
-                                    possible_moves.push(Move::Simple { from: coord, to: check_coord });
-                                };
-                            };
-                        };
-
-                        let castle_perm = match for_color {
-                            Color::White => self.white_castle,
-                            Color::Black => self.black_castle,
-                        };
-
-                        if castle_perm.0 &&
-                            !self.is_under_attack(for_color.the_other(), Coordinate { file: File::F, rank: for_color.home_rank() }, None) &&
-                            [File::F, File::G].into_iter().all(|file| self.grid()[Coordinate { file, rank: for_color.home_rank() }].is_none()) {
-                            possible_moves.push(Move::Castling { side: Side::King });
-                        };
-
-                        if castle_perm.1 &&
-                            !self.is_under_attack(for_color.the_other(), Coordinate { file: File::D, rank: for_color.home_rank() }, None) &&
-                            [File::D, File::C, File::B].into_iter().all(|file| self.grid()[Coordinate { file, rank: for_color.home_rank() }].is_none()) {
-                            possible_moves.push(Move::Castling { side: Side::Queen });
-                        };
-                    },
-                };
-            };
-        };
+    // has to be called after the histories are replaced, e.g. when a board is set up
+    pub(crate) fn refresh_keys(&mut self) {
+        let pockets = self.pockets_history.iter().rev().copied().chain(core::iter::repeat(Pockets::default()));
+        let keys = self.grid_history.iter().rev().zip(self.rights_history.iter().rev()).zip(pockets)
+            .map(|((grid, rights), pockets)| zobrist::key(grid, rights, &pockets))
+            .collect::<Vec<_>>();
+        self.keys = keys.into_iter().rev().collect();
+    }
 
-        possible_moves
+    // what's in hand right now, nothing without drops
+    pub fn pockets(&self) -> Pockets {
+        self.pockets_history.last().copied().unwrap_or_default()
     }
 
-    pub fn is_under_attack(&self, by: Color, mut coord: Coordinate, after: Option<(Color, Move, bool)>) -> bool {
-        // todo optional check if attacking piece is pinned
-        
-        let mut grid = self.grid().clone();
-        
-        if let Some((color, r#move, adapt)) = after {
-            grid.r#move(r#move, color);
-            if adapt && r#move.resolve_from(color) == coord {
-                coord = r#move.resolve_to(color);
-            };
+    // changes what `color` holds right now without a move, e.g. a bughouse partner passing a piece on
+    #[cfg(feature = "std")]
+    pub(crate) fn update_hand(&mut self, color: Color, update: impl FnOnce(&mut Hand)) {
+        if self.pockets_history.is_empty() {
+            self.pockets_history.push(Pockets::default());
         };
-
-        // check for pawn attacks
-        for coord in [-1, 1].map(|file_of| coord.checked_add_offset(Offset { vertical: -by.direction(), horizontal: file_of })) {
-            let _: Option<_> = try {
-                if let Some(Piece { kind: PieceKind::Pawn, color }) = grid[coord?] && color == by {
-                    return true;
-                };
-            };
+        if let Some(pockets) = self.pockets_history.last_mut() {
+            update(pockets.hand_mut(color));
         };
-
-        // check for knight attacks
-        for coord in [
-            (2, 1), (2, -1),
-            (-2, 1), (-2, -1),
-            (1, 2), (-1, 2),
-            (1, -2), (-1, -2),
-        ].map(|of| coord.checked_add_offset(of.into())) {
-            let _: Option<_> = try {
-                if let Some(Piece { kind: PieceKind::Knight, color }) = grid[coord?] && color == by {
-                    return true;
-                };
-            };
+        let key = self.rights_history.last().map(|rights| zobrist::key(self.grid(), rights, &self.pockets()));
+        if let Some(key) = key && let Some(last) = self.keys.last_mut() {
+            *last = key;
         };
+    }
 
-        // check for rook/queen attacks
-        for of in [
-            (0, 1), (0, -1),
-            (1, 0), (-1, 0),
-        ] {
-            let _: Option<_> = try {
-                let mut check_coord = coord.checked_add_offset(of.into())?;
+    // the current position's identity for the repetition rules, see `PositionKey`
+    pub fn position_key(&self) -> Option<PositionKey> {
+        self.keys.last().copied()
+    }
 
-                while grid[check_coord].is_none() {
-                    check_coord = check_coord.checked_add_offset(of.into())?;
-                };
+    // every position's key since the history is kept, oldest first: what repetitions are counted in, and a search goes on from
+    pub fn position_keys(&self) -> &[PositionKey] {
+        &self.keys
+    }
 
-                if let Some(Piece { kind: PieceKind::Rook | PieceKind::Queen, color }) = grid[check_coord] && color == by {
-                    return true;
-                };
-            };
-        };
+    pub fn pieces(&self, color: Color) -> impl Iterator<Item = (Piece, Coordinate)> + '_ {
+        self.piece_list.iter().copied().filter(move |(piece, _)| piece.color == color)
+    }
 
-        // check for bishop/queen attacks
-        for of in [
-            (1, 1), (1, -1),
-            (-1, 1), (-1, -1),
-        ] {
-            let _: Option<_> = try {
-                let mut check_coord = coord.checked_add_offset(of.into())?;
+    // what `color`'s pieces add up to
+    pub fn material(&self, color: Color, table: &ValueTable) -> i32 {
+        self.pieces(color).map(|(piece, _)| piece.kind.value(table)).sum()
+    }
 
-                while grid[check_coord].is_none() {
-                    check_coord = check_coord.checked_add_offset(of.into())?;
-                };
+    pub fn pieces_of(&self, color: Color, kind: PieceKind) -> impl Iterator<Item = Coordinate> + '_ {
+        self.pieces(color).filter(move |(piece, _)| piece.kind == kind).map(|(_, coord)| coord)
+    }
 
-                if let Some(Piece { kind: PieceKind::Bishop | PieceKind::Queen, color }) = grid[check_coord] && color == by {
-                    return true;
-                };
-            };
-        };
+    pub fn attackers(&self, by: Color, coord: Coordinate) -> Vec<(Piece, Coordinate)> {
+        movegen::attackers(self.grid(), by, coord)
+    }
 
-        // check for king attacks
-        for coord in [
-            (0, 1), (0, -1),
-            (1, 0), (-1, 0),
-            (1, 1), (1, -1),
-            (-1, 1), (-1, -1),
-        ].map(|of| coord.checked_add_offset(of.into())) {
-            let _: Option<_> = try {
-                if let Some(Piece { kind: PieceKind::King, color }) = grid[coord?] && color == by {
-                    return true;
-                };
-            };
+    pub fn is_under_attack(&self, by: Color, coord: Coordinate, after: Option<(Color, Move, bool)>) -> bool {
+        movegen::is_under_attack(self.grid(), by, coord, after)
+    }
+
+    fn find_piece(&self, piece: Piece) -> Option<Coordinate> {
+        self.pieces_of(piece.color, piece.kind).next()
+    }
+
+    // where the side to move's king is, if it's in check. none when kings aren't royal.
+    pub fn checked_king(&self) -> Option<Coordinate> {
+        if self.king_rule != KingRule::Royal {
+            return None;
         };
+        let color = self.move_color;
+        self.find_piece(Piece { kind: PieceKind::King, color }).filter(|king| self.is_under_attack(color.the_other(), *king, None))
+    }
 
-        false
+    // calls `visit` for every legal move until it breaks.
+    // without a (royal) king there's nothing to keep out of check, so every pseudo-legal move goes.
+    pub fn visit_legal_moves(&self, color: Color, visit: impl FnMut(Move) -> ControlFlow<()>) {
+        movegen::visit_legal_moves(self, color, &mut None, visit);
     }
 
-    fn find_piece(&self, piece: Piece) -> Option<Coordinate> {
-        self.grid().iter_coord().filter_map(|(piece, coord)| piece.map(|p| (p, coord)))
-            .find(|(cpiece, _)| *cpiece == piece).map(|(_, coord)| coord)
+    // the legal moves generated a piece at a time while they're asked for, so stopping at the first one
+    // that will do doesn't generate the rest. nothing gets allocated for the moves either.
+    pub fn possible_moves_iter(&self, color: Color) -> LegalMoves<'_> {
+        LegalMoves::new(self, color)
     }
 
+    // the same with the pieces' moves generated in parallel
+    #[cfg(feature = "rayon")]
+    pub fn possible_moves_par_iter(&self, color: Color) -> impl ParallelIterator<Item = Move> + '_ {
+        let mut restrictions = None;
+        let guard = movegen::guard(self, color, &mut restrictions);
+        let captures_only = movegen::captures_only(self, color, guard);
+        let king = guard.map(|(king, _)| king);
+        let guard = king.zip(restrictions);
+        self.piece_list.par_iter().filter(move |(piece, _)| piece.color == color).flat_map_iter(move |&(piece, coord)| {
+            movegen::PieceMoves::new(self, color, guard.as_ref().map(|(king, restrictions)| (*king, restrictions)), captures_only, piece, coord)
+        })
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
     pub fn possible_moves(&self, color: Color) -> Vec<Move> {
-        let king_coord = self.find_piece(Piece { kind: PieceKind::King, color }).unwrap_or_else(|| {
-            eprintln!("{self}");
-            eprintln!("states before:");
-            for grid in self.grid_history.iter().rev().skip(1) {
-                eprintln!("{grid}");
-            };
-            panic!("KING HAS GONE WILD");
+        let mut possible_moves = Vec::new();
+        self.visit_legal_moves(color, |r#move| {
+            possible_moves.push(r#move);
+            ControlFlow::Continue(())
         });
-        self.unchecked_for_check_possible_moves(color)
-            .into_iter()
-            .filter(|r#move| !self.is_under_attack(color.the_other(), king_coord, Some((color, *r#move, true))))
-            .collect()
+        possible_moves
     }
 
-    fn is_material_sufficient_for_checkmate(&self) -> bool {
-        let mut white_bishop_found = false;
-        let mut black_bishop_found = false;
-        let mut white_knight_found = false;
-        let mut black_knight_found = false;
-        for piece in self.grid().iter_coord().filter_map(|(p, _)| p).filter(|p| p.kind != PieceKind::King) {
-            match piece {
-                Piece { kind: PieceKind::Bishop, color: Color::White } => white_bishop_found = true,
-                Piece { kind: PieceKind::Bishop, color: Color::Black } => black_bishop_found = true,
-                Piece { kind: PieceKind::Knight, color: Color::White } => white_knight_found = true,
-                Piece { kind: PieceKind::Knight, color: Color::Black } => black_knight_found = true,
-                Piece { color: Color::White, .. } => return true,
-                Piece { color: Color::Black, .. } => return true,
+    pub fn is_capture(&self, r#move: Move, color: Color) -> bool {
+        movegen::is_capture(self.grid(), color, r#move)
+    }
+
+    // whether the (legal) move of the side to move checks the opponent. the board stays as it is, not even the grid gets copied.
+    pub fn gives_check(&self, r#move: Move) -> bool {
+        movegen::gives_check(self, self.move_color, r#move)
+    }
+
+    // a legal move of the side to move tagged as a capture, promotion, check or mate, see `Position::move_info`
+    pub fn move_info(&self, r#move: Move) -> MoveInfo {
+        self.position().move_info(r#move)
+    }
+
+    // every legal move of the side to move tagged like that
+    pub fn tagged_moves(&self) -> Vec<MoveInfo> {
+        let position = self.position();
+        position.legal_moves().into_iter().map(|r#move| position.move_info(r#move)).collect()
+    }
+
+    // only the moves changing material (captures and promotions), for searches settling exchanges
+    pub fn capture_moves(&self, color: Color) -> Vec<Move> {
+        let mut capture_moves = Vec::new();
+        self.visit_legal_moves(color, |r#move| {
+            if matches!(r#move, Move::Promotion { .. }) || self.is_capture(r#move, color) {
+                capture_moves.push(r#move);
             };
+            ControlFlow::Continue(())
+        });
+        capture_moves
+    }
+
+    // like `possible_moves`, but refuses positions the king rule says can't be played
+    pub fn try_possible_moves(&self, color: Color) -> Result<Vec<Move>, MoveGenError> {
+        if self.king_rule == KingRule::Royal && self.find_piece(Piece { kind: PieceKind::King, color }).is_none() {
+            return Err(MoveGenError::MissingKing(color));
         };
+        Ok(self.possible_moves(color))
+    }
 
-        (white_knight_found && white_bishop_found) || (black_knight_found && black_bishop_found)
+    pub fn count_legal_moves(&self, color: Color) -> usize {
+        let mut count = 0;
+        self.visit_legal_moves(color, |_| {
+            count += 1;
+            ControlFlow::Continue(())
+        });
+        count
     }
 
-    fn handle_castling_rights_update(&mut self, color: Color, r#move: Move) {
-        let castling_rights = match color {
-            Color::White => &mut self.white_castle,
-            Color::Black => &mut self.black_castle,
+    // stops generating as soon as enough moves are found, so `has_at_least(color, 1)` is a cheap stalemate/mate probe
+    pub fn has_at_least(&self, color: Color, n: usize) -> bool {
+        if n == 0 {
+            return true;
         };
 
-        match r#move {
-            Move::Castling { .. } => *castling_rights = (false, false),
-            Move::Simple { from: Coordinate { file: File::E, rank  }, .. } if rank == color.home_rank() => *castling_rights = (false, false), 
-            Move::Simple { from: Coordinate { file: File::H, rank }, .. } if rank == color.home_rank() => castling_rights.0 = false,
-            Move::Simple { from: Coordinate { file: File::A, rank }, .. } if rank == color.home_rank() => castling_rights.1 = false,
-            _ => {}
-        };
+        let mut count = 0;
+        self.visit_legal_moves(color, |_| {
+            count += 1;
+            if count >= n { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+        });
+        count >= n
     }
-    
-    pub fn play_move(&mut self, r#move: PlayerMove) -> Result<Option<GameOutcome>, MoveError> {
+
+    // false only for the dead positions no sequence of moves can mate from: bare kings, a single minor piece,
+    // or nothing but bishops all standing on squares of one color (k+b vs k+b with same-colored bishops among them)
+    fn is_material_sufficient_for_checkmate(&self) -> bool {
+        is_material_sufficient_for_checkmate(self.piece_list.iter().copied())
+    }
+
+    fn handle_castling_rights_update(&mut self, color: Color, r#move: Move) {
+        let castling = castling_after([self.white_castle, self.black_castle], color, r#move);
+        [self.white_castle, self.black_castle] = castling;
+    }
+
+    // has to be called after the move was made on the grid and the castling rights were updated
+    fn push_rights(&mut self, color: Color, r#move: Move) {
+        let en_passant = en_passant_after(self.grid(), color, r#move);
+        let rights = Rights { move_color: color.the_other(), white_castle: self.white_castle, black_castle: self.black_castle, en_passant };
+        self.keys.push(zobrist::key(self.grid(), &rights, &self.pockets()));
+        self.rights_history.push(rights);
+    }
+
+    // like displaying the board, but from `perspective`'s side
+    pub fn view(&self, perspective: Color) -> BoardView<'_> {
+        BoardView { board: self, perspective }
+    }
+
+    // the moves played on this board, oldest first. a board set up from a fen or an editor starts without any.
+    pub fn history(&self) -> &[(Move, Color)] {
+        &self.moves
+    }
+
+    // the number of the move being played, counting from the fen's when the board started from one
+    pub fn move_number(&self) -> usize {
+        1 + (self.first_ply as usize + self.grid_history.len() - 1)/2
+    }
+
+    // how many times the current position has occurred, itself included
+    pub fn repetitions(&self) -> usize {
+        self.position_key().map_or(0, |key| self.keys.iter().filter(|earlier| **earlier == key).count())
+    }
+
+    // the legal move (for the side to move) a player meant, if there is one
+    pub fn resolve_move(&self, r#move: &PlayerMove) -> Option<Move> {
+        self.resolve(r#move).ok()
+    }
+
+    // what `play_move` would make of a move, without playing it. for checking premoves and showing legality before committing.
+    pub fn is_legal(&self, r#move: &PlayerMove) -> Result<Move, MoveError> {
         if let Some(game_outcome) = self.game_outcome {
             return Err(MoveError::GameHasOutcome(game_outcome));
         };
 
-        if self.draw_pending.is_some() {
-            return Err(MoveError::DrawPending);
-        };
+        self.resolve(r#move)
+    }
 
-        let color_to_move = self.move_color;
-        let advancing_move;
-        match r#move {
-            PlayerMove::Internal(r#move) => {
-                if self.possible_moves(self.move_color).into_iter().any(|legal_move| legal_move == r#move) {
-                    self.grid_history.push(self.grid().clone());
-                    advancing_move = !self.grid_mut().r#move(r#move, color_to_move);
-                    self.handle_castling_rights_update(color_to_move, r#move);
-                } else {
-                    return Err(MoveError::IllegalMove);
-                };
-            },
+    fn resolve(&self, r#move: &PlayerMove) -> Result<Move, MoveError> {
+        let color = self.move_color;
+        let possible_moves = self.possible_moves(color);
+        match *r#move {
+            PlayerMove::Internal(r#move) => possible_moves.contains(&r#move).then_some(r#move).ok_or(MoveError::IllegalMove),
             PlayerMove::Long { from, to, promotion } => {
-                if let Some(r#move) = self.possible_moves(self.move_color).into_iter().find(|legal_move| legal_move.resolve_from(self.move_color) == from && legal_move.resolve_to(self.move_color) == to && match legal_move { Move::Promotion { piece, .. } => promotion.is_some() && *piece == promotion.unwrap(), _ => true }) {
-                    self.grid_history.push(self.grid().clone());
-                    advancing_move = self.grid_mut().r#move(r#move, color_to_move);
-                    self.handle_castling_rights_update(color_to_move, r#move);
-                } else {
-                    return Err(MoveError::IllegalMove);
-                };
+                let found = possible_moves.iter().copied().find(|legal_move| {
+                    !matches!(legal_move, Move::Drop { .. }) && legal_move.resolve_from(color) == from && legal_move.resolve_to(color) == to &&
+                        match legal_move { Move::Promotion { piece, .. } => promotion == Some(*piece), _ => true }
+                });
+                match found {
+                    Some(r#move) => Ok(r#move),
+                    None if promotion.is_none() && possible_moves.iter().any(|legal_move| matches!(legal_move, Move::Promotion { .. }) && legal_move.resolve_from(color) == from && legal_move.resolve_to(color) == to) => Err(MoveError::PromotionRequired),
+                    None => Err(MoveError::IllegalMove),
+                }
             },
-            // PlayerMove::Short { piece, to, from } => {
-            //     let possible_moves = self.possible_moves().into_iter().filter(|legal_move| {
-            //         let move_from = legal_move.resolve_from(self.move_color);
-            //         matches!(self.grid()[legal_move.resolve_from(self.move_color)], Some(Piece { kind, color }) if color == self.move_color && kind == piece) &&
-            //             legal_move.resolve_to(self.move_color) == to && from.0.is_none_or(|file| move_from.file == file) && from.1.is_none_or(|rank| move_from.rank == rank)
-            //     }).collect::<Vec<_>>();
-            //
-            //     match possible_moves.len() {
-            //         0 => return Err(MoveError::IllegalMove),
-            //         1 => {
-            //             self.grid_history.push(self.grid().clone());
-            //             self.grid_mut().r#move(*possible_moves.first().unwrap(), color_to_move);
-            //         },
-            //         _ => return Err(MoveError::AmbiguousMove),
-            //     };
-            // },
-            PlayerMove::Short { .. } => todo!(),
+            PlayerMove::Short { piece, to, from, capture, promotion } => {
+                let matching = possible_moves.iter().copied().filter(|legal_move| {
+                    let (move_from, move_to) = (legal_move.resolve_from(color), legal_move.resolve_to(color));
+                    self.grid()[move_from].is_some_and(|moved| moved.kind == piece) &&
+                        move_to.file == to.0 && to.1.is_none_or(|rank| move_to.rank == rank) &&
+                        from.0.is_none_or(|file| move_from.file == file) && from.1.is_none_or(|rank| move_from.rank == rank) &&
+                        (!capture || self.is_capture(*legal_move, color)) &&
+                        match legal_move { Move::Promotion { piece, .. } => promotion == Some(*piece), _ => promotion.is_none() }
+                }).collect::<Vec<_>>();
+
+                match matching.as_slice() {
+                    [r#move] => Ok(*r#move),
+                    [] => Err(MoveError::IllegalMove),
+                    _ => Err(MoveError::AmbiguousMove),
+                }
+            },
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
+    pub fn play_move(&mut self, r#move: PlayerMove) -> Result<Option<GameOutcome>, MoveError> {
+        let r#move = self.is_legal(&r#move)?;
+
+        let color_to_move = self.move_color;
+        let taken = captured(self.grid(), color_to_move, r#move);
+        let mut pockets = self.pockets();
+        self.grid_history.push(self.grid().clone());
+        let advancing_move = self.grid_mut().r#move(r#move, color_to_move);
+        let variant = self.variant;
+        follow_move(variant, self.grid_mut(), &mut pockets, color_to_move, r#move, taken);
+        if variant.drops() || !self.pockets_history.is_empty() {
+            if self.pockets_history.is_empty() {
+                self.pockets_history.push(Pockets::default());
+            };
+            self.pockets_history.push(pockets);
         };
+        self.handle_castling_rights_update(color_to_move, r#move);
+        self.push_rights(color_to_move, r#move);
+        self.annotations.follow(r#move, color_to_move);
+        self.moves.push((r#move, color_to_move));
+        self.last_move = Some(r#move);
+
+        self.refresh_piece_list();
 
         if !advancing_move {
             self.stale_plies += 1;
@@ -572,22 +670,20 @@ impl Board {
             self.stale_plies = 0;
         };
 
-        if self.possible_moves(self.move_color.the_other()).is_empty() {
-            let enemy_king_pos = self.find_piece(Piece { color: self.move_color.the_other(), kind: PieceKind::King }).unwrap();
-            if self.is_under_attack(self.move_color, enemy_king_pos, None) {
-                self.game_outcome = Some(GameOutcome::Decisive { won: self.move_color, reason: WinReason::Checkmate });
-            } else {
-                self.game_outcome = Some(GameOutcome::Draw(DrawReason::Stalemate));
-            };
-        } else if self.stale_plies == 100 {
+        if let Some(reason) = self.variant.won_by_move(self.grid(), color_to_move) {
+            self.game_outcome = Some(GameOutcome::Decisive { won: color_to_move, reason });
+        } else if !self.has_at_least(color_to_move.the_other(), 1) {
+            let enemy_king_pos = self.find_piece(Piece { color: color_to_move.the_other(), kind: PieceKind::King });
+            let in_check = self.king_rule == KingRule::Royal && enemy_king_pos.is_some_and(|king| self.is_under_attack(color_to_move, king, None));
+            self.game_outcome = Some(self.variant.stuck(color_to_move.the_other(), in_check));
+        } else if self.stale_plies >= AUTOMATIC_DRAW_PLIES {
             self.game_outcome = Some(GameOutcome::Draw(DrawReason::NoAdvancement));
-        } else if !self.is_material_sufficient_for_checkmate() {
+        } else if self.variant.draws_without_mating_material() && !self.is_material_sufficient_for_checkmate() {
             self.game_outcome = Some(GameOutcome::Draw(DrawReason::InsufficientMaterial));
         } else {
-            match self.grid_history.iter().filter(|position| *position == self.grid()).count() {
-                3 => self.draw_pending = Some((true, color_to_move)),
-                5 => self.game_outcome = Some(GameOutcome::Draw(DrawReason::FivefoldRepetition)),
-                _ => {}
+            // a threefold repetition is only drawn if it's claimed, see `can_claim_draw`
+            if self.repetitions() >= 5 {
+                self.game_outcome = Some(GameOutcome::Draw(DrawReason::FivefoldRepetition));
             };
         };
 
@@ -595,31 +691,146 @@ impl Board {
             self.move_color = color_to_move.the_other();
         };
 
+        #[cfg(feature = "validate")]
+        validate::debug_validate(self);
         Ok(self.game_outcome)
     }
     
-    pub fn propose_draw(&mut self, by: Color) {
-        if matches!(self.draw_pending, Some((_, color)) if color.the_other() == by) {
-            if let Some((true, _)) = self.draw_pending {
-                self.game_outcome = Some(GameOutcome::Draw(DrawReason::ThreefoldRepetition));
-            } else {
-                self.game_outcome = Some(GameOutcome::Draw(DrawReason::Agreement));
-            };
-        } else {
-            self.draw_pending = Some((false, by));
+    // passes the turn without moving, for null-move pruning: only the side to move flips and en passant goes away.
+    // nothing gets recorded, so it has to be taken back with `unmake_null_move` before anything else is played or undone.
+    // none for a board with an outcome, there's no turn to pass, or one saved before it kept rights.
+    pub fn make_null_move(&mut self) -> Option<NullMove> {
+        if self.game_outcome.is_some() {
+            return None;
         };
+
+        let rights = *self.rights_history.last()?;
+        let key = self.position_key()?;
+        self.move_color = self.move_color.the_other();
+        let passed = Rights { move_color: self.move_color, en_passant: None, ..rights };
+        *self.rights_history.last_mut()? = passed;
+        *self.keys.last_mut()? = zobrist::key(self.grid(), &passed, &self.pockets());
+        Some(NullMove { rights, key })
     }
-    
-    pub fn decline_draw(&mut self) {
-        self.draw_pending = None;
+
+    pub fn unmake_null_move(&mut self, null: NullMove) {
+        self.move_color = null.rights.move_color;
+        if let Some(rights) = self.rights_history.last_mut() {
+            *rights = null.rights;
+        };
+        if let Some(key) = self.keys.last_mut() {
+            *key = null.key;
+        };
     }
 
-    pub fn resign(&mut self, by: Color) {
-        self.game_outcome = Some(GameOutcome::Decisive { won: by.the_other(), reason: WinReason::Resignation });
+    // takes the last ply back, false when there's none. everything is as it was before it,
+    // except for piece annotations (which stay where it left them).
+    pub fn undo_move(&mut self) -> bool {
+        if self.grid_history.len() < 2 {
+            return false;
+        };
+
+        // not inferred from the outcome, a claimed draw or a flag finishes the game without a move
+        let mover = self.moves.last().map_or(self.move_color.the_other(), |(_, color)| *color);
+        self.grid_history.pop();
+        self.keys.pop();
+        self.pockets_history.pop();
+        if self.rights_history.pop().is_some() && let Some(rights) = self.rights_history.last() {
+            self.white_castle = rights.white_castle;
+            self.black_castle = rights.black_castle;
+        };
+        self.move_color = mover;
+        self.game_outcome = None;
+        self.moves.pop();
+        self.last_move = self.moves.last().map(|(r#move, _)| *r#move);
+
+        // a ply advanced the game if it captured something or moved (or dropped) a pawn, any other drop only adds a piece
+        let advancing = |before: &Grid, after: &Grid| {
+            let is_pawn = |square: &Option<Piece>| square.is_some_and(|piece| piece.kind == PieceKind::Pawn);
+            before.0.iter().flatten().filter(|square| square.is_some()).count() > after.0.iter().flatten().filter(|square| square.is_some()).count() ||
+                before.0.iter().flatten().zip(after.0.iter().flatten()).any(|(a, b)| a != b && (is_pawn(a) || is_pawn(b)))
+        };
+        let quiet = self.grid_history.windows(2).rev().take_while(|pair| !advancing(&pair[0], &pair[1])).count();
+        self.stale_plies = match quiet == self.grid_history.len() - 1 {
+            true => self.first_stale_plies.saturating_add(quiet as u8),
+            false => quiet as u8,
+        };
+
+        self.refresh_piece_list();
+        #[cfg(feature = "validate")]
+        validate::debug_validate(self);
+        true
+    }
+
+    // the position after playing all of `moves`, or the index of the first one that can't be played and why
+    pub fn apply_moves(&self, moves: &[PlayerMove]) -> Result<Board, (usize, MoveError)> {
+        let mut board = self.clone();
+        for (i, r#move) in moves.iter().enumerate() {
+            board.play_move(r#move.clone()).map_err(|err| (i, err))?;
+        };
+        Ok(board)
+    }
+
+    // a draw either player may end the game with right now, nothing happens until one of them does.
+    // the repetition goes first when both could be claimed, they draw the same.
+    pub fn can_claim_draw(&self) -> Option<DrawClaim> {
+        [DrawClaim::ThreefoldRepetition, DrawClaim::FiftyMoves].into_iter().find(|claim| self.is_claimable(*claim))
+    }
+
+    fn is_claimable(&self, claim: DrawClaim) -> bool {
+        self.game_outcome.is_none() && match claim {
+            DrawClaim::ThreefoldRepetition => self.repetitions() >= 3,
+            DrawClaim::FiftyMoves => self.stale_plies >= CLAIMABLE_DRAW_PLIES,
+        }
+    }
+
+    // returns whether the claim was valid, the game is drawn if it was
+    pub fn claim_draw(&mut self, claim: DrawClaim) -> bool {
+        let valid = self.is_claimable(claim);
+        if valid {
+            self.game_outcome = Some(GameOutcome::Draw(claim.into()));
+        };
+        valid
+    }
+
+    // whether `color` could mate at all, given any help from the opponent
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        let mut minors = 0;
+        for (piece, _) in self.pieces(color) {
+            match piece.kind {
+                PieceKind::King => {},
+                PieceKind::Knight | PieceKind::Bishop => minors += 1,
+                _ => return true,
+            };
+        };
+
+        minors > 1 || (minors == 1 && self.pieces(color.the_other()).any(|(piece, _)| piece.kind != PieceKind::King))
+    }
+}
+
+// what `Board::is_material_sufficient_for_checkmate` tells from both sides' pieces, for a `Position` as well
+pub(crate) fn is_material_sufficient_for_checkmate(pieces: impl Iterator<Item = (Piece, Coordinate)>) -> bool {
+    let mut knights = 0;
+    let mut bishop_squares = (false, false);
+    for (piece, coord) in pieces {
+        match piece.kind {
+            PieceKind::King => {},
+            PieceKind::Knight => knights += 1,
+            PieceKind::Bishop if (coord.file as u8 + coord.rank as u8).is_multiple_of(2) => bishop_squares.0 = true,
+            PieceKind::Bishop => bishop_squares.1 = true,
+            PieceKind::Pawn | PieceKind::Rook | PieceKind::Queen => return true,
+        };
+    };
+
+    match knights {
+        0 => bishop_squares.0 && bishop_squares.1,
+        1 => bishop_squares.0 || bishop_squares.1,
+        _ => true,
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PlayerMove {
     Internal(Move),
     Long {
@@ -638,6 +849,11 @@ pub enum PlayerMove {
 
 impl PlayerMove {
     pub fn parse(raw: &str) -> Option<Self> {
+        // every notation is ascii, so the lengths below are in characters too
+        if !raw.is_ascii() {
+            return None;
+        };
+
         if raw.len() == 4 &&
             let Some(from) = Coordinate::parse(&raw[0..2]) &&
             let Some(to) = Coordinate::parse(&raw[2..4]) {
@@ -651,7 +867,14 @@ impl PlayerMove {
             return Some(Self::Long { from, to, promotion: Some(piece_kind) });
         };
 
-        match &raw[0..1] {
+        // a drop as uci has it, `N@f3`
+        if raw.len() == 4 && raw.get(1..2) == Some("@") &&
+            let Some(piece) = PieceKind::parse(&raw[0..1].to_ascii_lowercase()) &&
+            let Some(to) = Coordinate::parse(&raw[2..4]) {
+            return Some(Self::Internal(Move::Drop { piece, to }));
+        };
+
+        match raw.get(0..1)? {
             "=" if raw.len() == 4 => {
                 if let Some(from) = File::parse(&raw[1..2]) &&
                     let Some(to) = File::parse(&raw[2..3]) &&
@@ -659,6 +882,12 @@ impl PlayerMove {
                     return Some(Self::Internal(Move::Promotion { from, to, piece }));
                 };
             },
+            "@" if raw.len() == 4 => {
+                if let Some(piece) = PieceKind::parse(&raw[1..2]) &&
+                    let Some(to) = Coordinate::parse(&raw[2..4]) {
+                    return Some(Self::Internal(Move::Drop { piece, to }));
+                };
+            },
             "~" if raw.len() == 3 => {
                 if let Some(from) = File::parse(&raw[1..2]) &&
                     let Some(to) = File::parse(&raw[2..3]) {
@@ -679,60 +908,103 @@ impl PlayerMove {
 }
 
 impl Display for PlayerMove {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Internal(r#move) => write!(f, "{move}")?,
             Self::Long { from, to, promotion } => {
                 write!(f, "{from}{to}")?;
                 if let Some(piece) = promotion {
-                    write!(f, "{piece}")?;
+                    write!(f, "{}", piece.letter())?;
+                };
+            },
+            // as much of the san as was given, e.g. `Nbxd7` or `e8=Q`
+            Self::Short { piece, to, from, capture, promotion } => {
+                if *piece != PieceKind::Pawn {
+                    write!(f, "{}", piece.letter().to_ascii_uppercase())?;
+                };
+                if let Some(file) = from.0 {
+                    write!(f, "{file}")?;
+                };
+                if let Some(rank) = from.1 {
+                    write!(f, "{rank}")?;
+                };
+                if *capture {
+                    write!(f, "x")?;
+                };
+                write!(f, "{}", to.0)?;
+                if let Some(rank) = to.1 {
+                    write!(f, "{rank}")?;
+                };
+                if let Some(piece) = promotion {
+                    write!(f, "={}", piece.letter().to_ascii_uppercase())?;
                 };
             },
-            _ => todo!()
         };
         Ok(())
     }
 }
 
 impl Display for Board {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "\nmove #{} (ply #{}), {}'s turn:\n{}", self.stale_plies, self.stale_plies.div_ceil(2), self.move_color, self.grid())
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.view(Color::White).fmt(f)
     }
 }
 
-impl Display for Grid {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "  ")?;
-        for rank in 0..8 {
-            write!(f, "{} ", File::try_from(rank).unwrap())?;
+// a board displayed with `perspective`'s side at the bottom, see `Board::view`
+pub struct BoardView<'a> {
+    board: &'a Board,
+    perspective: Color,
+}
+
+impl Display for BoardView<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let (board, options) = (self.board, render::RenderOptions::default());
+        let mut grid = board.grid().render(&options).with_perspective(self.perspective);
+        if let Some((r#move, by)) = board.history().last() {
+            grid = grid.with_last_move(r#move.resolve_from(*by), r#move.resolve_to(*by));
         };
-        writeln!(f)?;
-
-        let mut even_row = false;
-        for (i, (piece, Coordinate { rank, .. })) in self.iter_coord().rev().enumerate() {
-            if i % 8 == 0 {
-                even_row = !even_row;
-                if i != 0 {
-                    write!(f, " {}\n{rank} ", (rank + 1).unwrap())?;
-                } else {
-                    write!(f, "{rank} ")?;
-                };
-            };
+        if let Some(king) = board.checked_king() {
+            grid = grid.with_check(king);
+        };
+        write!(f, "\nmove #{} (ply #{}), {}'s turn:\n{grid}", board.stale_plies, board.stale_plies.div_ceil(2), board.move_color)
+    }
+}
 
-            let bg_code = if (i % 2 == 0)^even_row { "100" } else { "47" };
+impl Display for Grid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.render(&render::RenderOptions::default()).fmt(f)
+    }
+}
 
-            if let Some(piece) = piece {
-                write!(f, "\x1B[{bg_code}m{piece}")?;
-            } else {
-                write!(f, "\x1B[{bg_code}m  \x1B[0m")?;
-            };
-        };
 
-        write!(f, " 1\n  ")?;
-        for rank in 0..8 {
-            write!(f, "{} ", File::try_from(rank).unwrap())?;
-        };
+#[cfg(test)]
+mod tests {
+    use super::Board;
 
-        Ok(())
+    fn sufficient(fen: &str) -> bool {
+        Board::from_fen(fen).expect("a valid fen").is_material_sufficient_for_checkmate()
+    }
+
+    #[test]
+    fn dead_material() {
+        // bare kings
+        assert!(!sufficient("8/8/8/4k3/8/8/8/4K3 w - - 0 1"));
+        // a lone bishop or knight
+        assert!(!sufficient("8/8/8/4k3/8/8/8/2B1K3 w - - 0 1"));
+        assert!(!sufficient("8/8/8/4k3/8/8/8/1N2K3 w - - 0 1"));
+        // a bishop each, both on dark squares (c1 and f8)
+        assert!(!sufficient("5b2/8/8/4k3/8/8/8/2B1K3 w - - 0 1"));
     }
-} 
+
+    #[test]
+    fn mating_material() {
+        // opposite-colored bishops (c1 and c8) can help each other into a mate
+        assert!(sufficient("2b5/8/8/4k3/8/8/8/2B1K3 w - - 0 1"));
+        // a knight each, or a knight against a bishop
+        assert!(sufficient("1n6/8/8/4k3/8/8/8/1N2K3 w - - 0 1"));
+        assert!(sufficient("2b5/8/8/4k3/8/8/8/1N2K3 w - - 0 1"));
+        // a pawn can still promote, a rook mates on its own
+        assert!(sufficient("8/8/8/4k3/8/8/4P3/4K3 w - - 0 1"));
+        assert!(sufficient("8/8/8/4k3/8/8/8/R3K3 w - - 0 1"));
+    }
+}
@@ -0,0 +1,684 @@
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+use crate::{Board, KingRule};
+use crate::position::Position;
+use crate::coordinate::{Coordinate, File, Move, Offset, Rank, Side};
+use crate::grid::Grid;
+use crate::hand::Hand;
+use crate::piece::{Color, Piece, PieceKind};
+use crate::variant::Variant;
+
+const ORTHOGONAL: [(i8, i8); 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+const DIAGONAL: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+const KNIGHT: [(i8, i8); 8] = [(2, 1), (2, -1), (-2, 1), (-2, -1), (1, 2), (-1, 2), (1, -2), (-1, -2)];
+
+#[inline]
+pub(crate) fn bit(coord: Coordinate) -> u64 {
+    1 << (coord.rank as u8 * 8 + coord.file as u8)
+}
+
+// every piece of `by` attacking the square
+pub(crate) fn attackers(grid: &Grid, by: Color, coord: Coordinate) -> Vec<(Piece, Coordinate)> {
+    grid.iter_coord()
+        .filter_map(|(piece, from)| piece.filter(|piece| piece.color == by).map(|piece| (piece, from)))
+        .filter(|(piece, from)| Restrictions::attacks(grid, *piece, *from, coord) & bit(coord) != 0)
+        .collect()
+}
+
+// what move generation needs to know about a position, so a `Board` and a search `Position` generate the same moves
+pub(crate) trait Setup {
+    fn grid(&self) -> &Grid;
+    // king side, queen side
+    fn castling(&self, color: Color) -> (bool, bool);
+    // the file a pawn of `color` can take en passant on right now
+    fn en_passant(&self, color: Color) -> Option<File>;
+    fn king_rule(&self) -> KingRule;
+    fn variant(&self) -> Variant;
+    // what `color` can drop, if the variant lets it
+    fn hand(&self, color: Color) -> Hand;
+    fn pieces(&self, color: Color) -> impl Iterator<Item = (Piece, Coordinate)> + '_;
+}
+
+// every move the piece could make if its king's safety didn't matter, castling included (as long as the squares between are empty)
+pub(crate) fn piece_moves(setup: &impl Setup, for_color: Color, piece: PieceKind, coord: Coordinate, push: &mut impl FnMut(Move)) {
+    match piece {
+        PieceKind::Pawn => {
+            // first move
+            {
+                if coord.rank == for_color.pawn_rank() {
+                    let path = coord.checked_add_offset(Offset { vertical: for_color.direction(), horizontal: 0 }).unwrap();
+                    let to = coord.checked_add_offset(Offset { vertical: for_color.direction()*2, horizontal: 0 }).unwrap();
+                    if setup.grid()[to].is_none() && setup.grid()[path].is_none() {
+                        push(Move::Simple { from: coord, to });
+                    };
+                };
+            }
+
+            // move forward
+            {
+                if let Some(to) = coord.checked_add_offset(Offset { vertical: for_color.direction(), horizontal: 0 }) &&
+                    setup.grid()[to].is_none() {
+                    if to.rank != for_color.promotion_rank() {
+                        push(Move::Simple { from: coord, to });
+                    } else {
+                        for piece_kind in setup.variant().promotions().iter().copied() {
+                            push(Move::Promotion { from: coord.file, to: to.file, piece: piece_kind });
+                        };
+                    };
+                };
+            };
+
+            // move diagonally
+            {
+                for to in [1, -1].into_iter().filter_map(|of| coord.checked_add_offset(Offset { vertical: for_color.direction(), horizontal: of })) {
+                    if let Some(Piece { color, .. }) = setup.grid()[to] && color == for_color.the_other() {
+                        if to.rank != for_color.promotion_rank() {
+                            push(Move::Simple { from: coord, to });
+                        } else {
+                            for piece_kind in setup.variant().promotions().iter().copied() {
+                                push(Move::Promotion { from: coord.file, to: to.file, piece: piece_kind });
+                            };
+                        };
+                    };
+                }
+            };
+
+            // the rights only have the file when a pawn of ours stands next to the one just pushed
+            if coord.rank == for_color.en_passant_rank() &&
+                let Some(file) = setup.en_passant(for_color) &&
+                (coord.file as i8 - file as i8).abs() == 1 {
+                push(Move::EnPassant { from: coord.file, to: file });
+            };
+        },
+        PieceKind::Knight => {
+            for to in [
+                (2, 1), (2, -1),
+                (-2, 1), (-2, -1),
+                (1, 2), (-1, 2),
+                (1, -2), (-1, -2),
+            ].into_iter().filter_map(|of| coord.checked_add_offset(of.into())) {
+                let square = setup.grid()[to];
+                if square.is_none() || matches!(square, Some(Piece { color, .. }) if color == for_color.the_other()) {
+                    push(Move::Simple { from: coord, to });
+                };
+            };
+        },
+        PieceKind::Bishop => {
+            sliding_moves(setup, for_color, coord, &[
+                (1, 1), (1, -1),
+                (-1, 1), (-1, -1),
+            ], push);
+        },
+        PieceKind::Rook => {
+            sliding_moves(setup, for_color, coord, &[
+                (0, 1), (0, -1),
+                (1, 0), (-1, 0),
+            ], push);
+        },
+        PieceKind::Queen => {
+            sliding_moves(setup, for_color, coord, &[
+                (0, 1), (0, -1),
+                (1, 0), (-1, 0),
+                (1, 1), (1, -1),
+                (-1, 1), (-1, -1),
+            ], push);
+        },
+        PieceKind::King => {
+            for check_coord in [
+                (0, 1), (0, -1),
+                (1, 0), (-1, 0),
+                (1, 1), (1, -1),
+                (-1, 1), (-1, -1),
+            ].into_iter().filter_map(|of| coord.checked_add_offset(of.into())) {
+                let piece = setup.grid()[check_coord];
+
+                if piece.is_none() || matches!(piece, Some(Piece { color, .. }) if color == for_color.the_other()) {
+                    push(Move::Simple { from: coord, to: check_coord });
+                };
+            };
+
+            let castle_perm = if setup.variant().castles() { setup.castling(for_color) } else { (false, false) };
+
+            // whether the king passes through attacked squares is up to the caller
+            if castle_perm.0 &&
+                [File::F, File::G].into_iter().all(|file| setup.grid()[Coordinate { file, rank: for_color.home_rank() }].is_none()) {
+                push(Move::Castling { side: Side::King });
+            };
+
+            if castle_perm.1 &&
+                [File::D, File::C, File::B].into_iter().all(|file| setup.grid()[Coordinate { file, rank: for_color.home_rank() }].is_none()) {
+                push(Move::Castling { side: Side::Queen });
+            };
+        },
+    };
+}
+
+fn sliding_moves(setup: &impl Setup, for_color: Color, coord: Coordinate, offsets: &[(i8, i8)], push: &mut impl FnMut(Move)) {
+    for of in offsets {
+        let mut check_coord = coord;
+        while let Some(next) = check_coord.checked_add_offset((*of).into()) {
+            match setup.grid()[next] {
+                None => push(Move::Simple { from: coord, to: next }),
+                Some(Piece { color, .. }) => {
+                    if color == for_color.the_other() {
+                        push(Move::Simple { from: coord, to: next });
+                    };
+                    break;
+                },
+            };
+            check_coord = next;
+        };
+    };
+}
+
+// what the square holds after `color` made the move, without making it on a copy of the grid
+fn square_after(grid: &Grid, color: Color, r#move: Move, coord: Coordinate) -> Option<Piece> {
+    let (from, to) = (r#move.resolve_from(color), r#move.resolve_to(color));
+    let rank = color.home_rank();
+    match r#move {
+        Move::Promotion { piece, .. } | Move::Drop { piece, .. } if coord == to => Some(Piece { color, kind: piece }),
+        _ if coord == to => grid[from],
+        _ if coord == from => None,
+        Move::EnPassant { to: file, .. } if coord == (Coordinate { file, rank: color.en_passant_rank() }) => None,
+        Move::Castling { side } if coord == (Coordinate { file: side.rook_castled_file(), rank }) => grid[Coordinate { file: side.rook_home_file(), rank }],
+        Move::Castling { side } if coord == (Coordinate { file: side.rook_home_file(), rank }) => None,
+        _ => grid[coord],
+    }
+}
+
+// whether `by` attacks the square, after `color` made the move if there's one (and with `adapt`, following the piece on it when it moved)
+pub(crate) fn is_under_attack(grid: &Grid, by: Color, mut coord: Coordinate, after: Option<(Color, Move, bool)>) -> bool {
+    match after {
+        Some((color, r#move, adapt)) => {
+            if adapt && r#move.resolve_from(color) == coord {
+                coord = r#move.resolve_to(color);
+            };
+            is_attacked(|coord| square_after(grid, color, r#move, coord), by, coord)
+        },
+        None => is_attacked(|coord| grid[coord], by, coord),
+    }
+}
+
+// `square` tells what stands where
+fn is_attacked(square: impl Fn(Coordinate) -> Option<Piece>, by: Color, coord: Coordinate) -> bool {
+    // todo optional check if attacking piece is pinned
+
+    // check for pawn attacks
+    for coord in [-1, 1].into_iter().filter_map(|file_of| coord.checked_add_offset(Offset { vertical: -by.direction(), horizontal: file_of })) {
+        if let Some(Piece { kind: PieceKind::Pawn, color }) = square(coord) && color == by {
+            return true;
+        };
+    };
+
+    // check for knight attacks
+    for coord in [
+        (2, 1), (2, -1),
+        (-2, 1), (-2, -1),
+        (1, 2), (-1, 2),
+        (1, -2), (-1, -2),
+    ].into_iter().filter_map(|of| coord.checked_add_offset(of.into())) {
+        if let Some(Piece { kind: PieceKind::Knight, color }) = square(coord) && color == by {
+            return true;
+        };
+    };
+
+    let first_piece_on_ray = |of: (i8, i8)| {
+        let mut check_coord = coord.checked_add_offset(of.into())?;
+
+        while square(check_coord).is_none() {
+            check_coord = check_coord.checked_add_offset(of.into())?;
+        };
+
+        square(check_coord)
+    };
+
+    // check for rook/queen attacks
+    for of in [
+        (0, 1), (0, -1),
+        (1, 0), (-1, 0),
+    ] {
+        if let Some(Piece { kind: PieceKind::Rook | PieceKind::Queen, color }) = first_piece_on_ray(of) && color == by {
+            return true;
+        };
+    };
+
+    // check for bishop/queen attacks
+    for of in [
+        (1, 1), (1, -1),
+        (-1, 1), (-1, -1),
+    ] {
+        if let Some(Piece { kind: PieceKind::Bishop | PieceKind::Queen, color }) = first_piece_on_ray(of) && color == by {
+            return true;
+        };
+    };
+
+    // check for king attacks
+    for coord in [
+        (0, 1), (0, -1),
+        (1, 0), (-1, 0),
+        (1, 1), (1, -1),
+        (-1, 1), (-1, -1),
+    ].into_iter().filter_map(|of| coord.checked_add_offset(of.into())) {
+        if let Some(Piece { kind: PieceKind::King, color }) = square(coord) && color == by {
+            return true;
+        };
+    };
+
+    false
+}
+
+// whether the move checks the opponent's (royal) king, be it with the piece moved or one it uncovered
+pub(crate) fn gives_check(setup: &impl Setup, color: Color, r#move: Move) -> bool {
+    setup.king_rule() == KingRule::Royal && setup.pieces(color.the_other())
+        .find(|(piece, _)| piece.kind == PieceKind::King)
+        .is_some_and(|(_, king)| is_under_attack(setup.grid(), color, king, Some((color, r#move, false))))
+}
+
+// a legal move with what it does, told without playing it. see `Board::move_info`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MoveInfo {
+    pub r#move: Move,
+    pub capture: bool,
+    pub promotion: Option<PieceKind>,
+    pub check: bool,
+    // only looked for after a check, it takes generating the replies
+    pub checkmate: bool,
+}
+
+// the (royal) king of `color` and what keeping it out of check takes, reusing the restrictions if there are any.
+// none without one, there's nothing to keep out of check then and every pseudo-legal move goes.
+pub(crate) fn guard<'a>(setup: &impl Setup, color: Color, restrictions: &'a mut Option<Restrictions>) -> Option<(Coordinate, &'a Restrictions)> {
+    let king = match setup.king_rule() {
+        KingRule::Royal => setup.pieces(color).find(|(piece, _)| piece.kind == PieceKind::King).map(|(_, coord)| coord)?,
+        KingRule::Ordinary => return None,
+    };
+
+    let restrictions = match restrictions {
+        Some(restrictions) => {
+            restrictions.update(setup.grid(), color, king);
+            restrictions
+        },
+        None => restrictions.insert(Restrictions::new(setup.grid(), color, king)),
+    };
+    Some((king, restrictions))
+}
+
+// the legal moves of one of `color`'s pieces, see `guard`
+pub(crate) fn legal_piece_moves(setup: &impl Setup, color: Color, guard: Option<(Coordinate, &Restrictions)>, piece: Piece, coord: Coordinate, push: &mut impl FnMut(Move)) {
+    piece_moves(setup, color, piece.kind, coord, &mut |r#move| {
+        let legal = match (guard, r#move) {
+            (None, _) => true,
+            // removing both pawns from the rank can expose the king, simpler to just try it out
+            (Some((king, _)), Move::EnPassant { .. }) => !is_under_attack(setup.grid(), color.the_other(), king, Some((color, r#move, true))),
+            (Some((_, restrictions)), r#move) => restrictions.allows(r#move, color),
+        };
+
+        if legal {
+            push(r#move);
+        };
+    });
+}
+
+pub(crate) fn is_capture(grid: &Grid, color: Color, r#move: Move) -> bool {
+    match r#move {
+        Move::EnPassant { .. } => true,
+        Move::Castling { .. } | Move::Drop { .. } => false,
+        r#move => grid[r#move.resolve_to(color)].is_some(),
+    }
+}
+
+// whether the variant leaves `color` only its captures right now, because it forces them and there is one
+pub(crate) fn captures_only(setup: &impl Setup, color: Color, guard: Option<(Coordinate, &Restrictions)>) -> bool {
+    setup.variant().forces_captures() && setup.pieces(color).any(|(piece, coord)| {
+        let mut capture = false;
+        legal_piece_moves(setup, color, guard, piece, coord, &mut |r#move| capture |= is_capture(setup.grid(), color, r#move));
+        capture
+    })
+}
+
+// calls `visit` for every legal move until it breaks, reusing the restrictions if there are any (see `MoveGen`)
+pub(crate) fn visit_legal_moves(setup: &impl Setup, color: Color, restrictions: &mut Option<Restrictions>, mut visit: impl FnMut(Move) -> ControlFlow<()>) {
+    let guard = guard(setup, color, restrictions);
+    let captures_only = captures_only(setup, color, guard);
+    let mut stop = false;
+    for (piece, coord) in setup.pieces(color) {
+        legal_piece_moves(setup, color, guard, piece, coord, &mut |r#move| {
+            if !stop && (!captures_only || is_capture(setup.grid(), color, r#move)) {
+                stop = visit(r#move).is_break();
+            };
+        });
+
+        if stop {
+            return;
+        };
+    };
+
+    if !captures_only {
+        for r#move in Drops::new(setup, color, guard) {
+            if visit(r#move).is_break() {
+                return;
+            };
+        };
+    };
+}
+
+// the most moves a single piece can have: a queen in the middle of an empty board
+const PIECE_MOVES: usize = 27;
+
+// the legal moves of one piece, kept without allocating
+#[derive(Debug, Clone)]
+pub(crate) struct PieceMoves {
+    moves: [Move; PIECE_MOVES],
+    len: usize,
+    taken: usize,
+}
+
+impl PieceMoves {
+    // only the captures when `captures_only` said so for the whole side
+    pub(crate) fn new(setup: &impl Setup, color: Color, guard: Option<(Coordinate, &Restrictions)>, captures_only: bool, piece: Piece, coord: Coordinate) -> Self {
+        let mut moves = Self { moves: [Move::Castling { side: Side::King }; PIECE_MOVES], len: 0, taken: 0 };
+        legal_piece_moves(setup, color, guard, piece, coord, &mut |r#move| {
+            if !captures_only || is_capture(setup.grid(), color, r#move) {
+                moves.moves[moves.len] = r#move;
+                moves.len += 1;
+            };
+        });
+        moves
+    }
+}
+
+impl Iterator for PieceMoves {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        let r#move = self.moves[..self.len].get(self.taken).copied()?;
+        self.taken += 1;
+        Some(r#move)
+    }
+}
+
+// the kinds of pieces that can be held, in the order they're dropped
+const HELD: [PieceKind; 5] = [PieceKind::Pawn, PieceKind::Knight, PieceKind::Bishop, PieceKind::Rook, PieceKind::Queen];
+// the first and the last rank, where no pawn can be dropped
+const BACK_RANKS: u64 = 0xff00_0000_0000_00ff;
+
+// the legal drops of one side: every piece in hand on every empty square, only blocking a check when there's one.
+// nothing when the variant doesn't drop, kept without allocating like `PieceMoves`.
+#[derive(Debug, Clone)]
+pub(crate) struct Drops {
+    hand: Hand,
+    targets: u64,
+    // into `HELD`
+    next_kind: usize,
+    // the piece being dropped and the squares left for it
+    current: Option<(PieceKind, u64)>,
+}
+
+impl Drops {
+    pub(crate) fn new(setup: &impl Setup, color: Color, guard: Option<(Coordinate, &Restrictions)>) -> Self {
+        let hand = if setup.variant().drops() { setup.hand(color) } else { Hand::default() };
+        let empty = !setup.grid().iter_coord().filter(|(piece, _)| piece.is_some()).fold(0, |mask, (_, coord)| mask | bit(coord));
+        // a drop can't capture a checker, only step between it and the king
+        let targets = match guard {
+            Some((_, restrictions)) if restrictions.checkers > 1 => 0,
+            Some((_, restrictions)) if restrictions.checkers == 1 => empty & restrictions.evasions,
+            _ => empty,
+        };
+        Self { hand, targets, next_kind: 0, current: None }
+    }
+}
+
+impl Iterator for Drops {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        loop {
+            if let Some((piece, squares)) = &mut self.current && *squares != 0 {
+                let index = squares.trailing_zeros() as i8;
+                *squares &= *squares - 1;
+                let to = Coordinate { file: File::try_from(index % 8).ok()?, rank: Rank::try_from(index / 8).ok()? };
+                return Some(Move::Drop { piece: *piece, to });
+            };
+
+            let piece = *HELD.get(self.next_kind)?;
+            self.next_kind += 1;
+            if self.hand.count(piece) > 0 {
+                let squares = if piece == PieceKind::Pawn { self.targets & !BACK_RANKS } else { self.targets };
+                self.current = Some((piece, squares));
+            };
+        };
+    }
+}
+
+// the legal moves of one side, generated a piece at a time as they're asked for, see `Board::possible_moves_iter`
+#[derive(Debug)]
+pub struct LegalMoves<'a> {
+    board: &'a Board,
+    color: Color,
+    guard: Option<(Coordinate, Restrictions)>,
+    captures_only: bool,
+    // after the pieces' moves
+    drops: Drops,
+    // into the board's piece list
+    next_piece: usize,
+    current: Option<PieceMoves>,
+}
+
+impl<'a> LegalMoves<'a> {
+    pub(crate) fn new(board: &'a Board, color: Color) -> Self {
+        let mut restrictions = None;
+        let guard = guard(board, color, &mut restrictions);
+        let captures_only = captures_only(board, color, guard);
+        let drops = Drops::new(board, color, guard);
+        let king = guard.map(|(king, _)| king);
+        Self { board, color, guard: king.zip(restrictions), captures_only, drops, next_piece: 0, current: None }
+    }
+}
+
+impl Iterator for LegalMoves<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        loop {
+            if let Some(r#move) = self.current.as_mut().and_then(Iterator::next) {
+                return Some(r#move);
+            };
+
+            let Some(&(piece, coord)) = self.board.piece_list.get(self.next_piece) else {
+                return if self.captures_only { None } else { self.drops.next() };
+            };
+            self.next_piece += 1;
+            if piece.color == self.color {
+                let guard = self.guard.as_ref().map(|(king, restrictions)| (*king, restrictions));
+                self.current = Some(PieceMoves::new(self.board, self.color, guard, self.captures_only, piece, coord));
+            };
+        };
+    }
+}
+
+// generates moves over and over without allocating, for searches calling it millions of times.
+// `Board::possible_moves` is the same with a fresh vec and fresh restrictions every call.
+#[derive(Debug, Default)]
+pub struct MoveGen {
+    restrictions: Option<Restrictions>,
+}
+
+impl MoveGen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // replaces the moves with the legal ones of the side to move
+    pub fn generate(&mut self, board: &Board, moves: &mut Vec<Move>) {
+        self.generate_for(board, board.move_color, moves);
+    }
+
+    pub fn generate_for(&mut self, board: &Board, color: Color, moves: &mut Vec<Move>) {
+        moves.clear();
+        visit_legal_moves(board, color, &mut self.restrictions, |r#move| {
+            moves.push(r#move);
+            ControlFlow::Continue(())
+        });
+    }
+
+    // the same for a search position, see `Position::make`
+    pub fn generate_position(&mut self, position: &Position, moves: &mut Vec<Move>) {
+        moves.clear();
+        visit_legal_moves(position, position.move_color, &mut self.restrictions, |r#move| {
+            moves.push(r#move);
+            ControlFlow::Continue(())
+        });
+    }
+}
+
+// everything a legal move has to respect, computed once per position
+// instead of replaying every pseudo-legal move against the whole board.
+#[derive(Debug)]
+pub(crate) struct Restrictions {
+    king: Coordinate,
+    // squares attacked by the opponent as if our king wasn't there, so it can't hide behind itself
+    danger: u64,
+    checkers: u8,
+    // squares non-king moves have to land on while in check (capturing or blocking the checker)
+    evasions: u64,
+    // pinned piece and the ray it's allowed to move along (up to and including the pinner)
+    pins: Vec<(Coordinate, u64)>,
+}
+
+impl Restrictions {
+    pub fn new(grid: &Grid, color: Color, king: Coordinate) -> Self {
+        let mut restrictions = Self { king, danger: 0, checkers: 0, evasions: 0, pins: Vec::new() };
+        restrictions.update(grid, color, king);
+        restrictions
+    }
+
+    // the same as a new one, only the pins keep their allocation
+    pub fn update(&mut self, grid: &Grid, color: Color, king: Coordinate) {
+        let enemy = color.the_other();
+
+        let mut danger = 0;
+        let mut checkers = 0;
+        let mut evasions = 0;
+        for (piece, coord) in grid.iter_coord().filter_map(|(piece, coord)| piece.map(|piece| (piece, coord))) {
+            if piece.color != enemy {
+                continue;
+            };
+
+            let attacks = Self::attacks(grid, piece, coord, king);
+            danger |= attacks;
+
+            if attacks & bit(king) != 0 {
+                checkers += 1;
+                evasions |= bit(coord);
+                if matches!(piece.kind, PieceKind::Bishop | PieceKind::Rook | PieceKind::Queen) {
+                    let Offset { horizontal, vertical } = king - coord;
+                    let of = (horizontal.signum(), vertical.signum());
+                    let mut between = coord;
+                    while let Some(next) = between.checked_add_offset(of.into()) && next != king {
+                        evasions |= bit(next);
+                        between = next;
+                    };
+                };
+            };
+        };
+
+        let pins = &mut self.pins;
+        pins.clear();
+        for (of, diagonal) in ORTHOGONAL.map(|of| (of, false)).into_iter().chain(DIAGONAL.map(|of| (of, true))) {
+            let mut ray = 0;
+            let mut pinned = None;
+            let mut check_coord = king;
+            while let Some(next) = check_coord.checked_add_offset(of.into()) {
+                check_coord = next;
+                ray |= bit(next);
+
+                match grid[next] {
+                    None => {},
+                    Some(Piece { color: piece_color, .. }) if piece_color == color => {
+                        if pinned.is_some() {
+                            break;
+                        };
+                        pinned = Some(next);
+                    },
+                    Some(Piece { kind, .. }) => {
+                        let slides = match kind {
+                            PieceKind::Queen => true,
+                            PieceKind::Bishop => diagonal,
+                            PieceKind::Rook => !diagonal,
+                            _ => false,
+                        };
+
+                        if slides && let Some(pinned) = pinned {
+                            pins.push((pinned, ray));
+                        };
+                        break;
+                    },
+                };
+            };
+        };
+
+        self.king = king;
+        self.danger = danger;
+        self.checkers = checkers;
+        self.evasions = evasions;
+    }
+
+    fn attacks(grid: &Grid, piece: Piece, coord: Coordinate, transparent: Coordinate) -> u64 {
+        let step = |offsets: &[(i8, i8)]| offsets.iter()
+            .filter_map(|of| coord.checked_add_offset((*of).into()))
+            .fold(0, |mask, to| mask | bit(to));
+
+        let slide = |offsets: &[(i8, i8)]| {
+            let mut mask = 0;
+            for of in offsets {
+                let mut check_coord = coord;
+                while let Some(next) = check_coord.checked_add_offset((*of).into()) {
+                    mask |= bit(next);
+                    if grid[next].is_some() && next != transparent {
+                        break;
+                    };
+                    check_coord = next;
+                };
+            };
+            mask
+        };
+
+        match piece.kind {
+            PieceKind::Pawn => step(&[(1, piece.color.direction()), (-1, piece.color.direction())]),
+            PieceKind::Knight => step(&KNIGHT),
+            PieceKind::Bishop => slide(&DIAGONAL),
+            PieceKind::Rook => slide(&ORTHOGONAL),
+            PieceKind::Queen => slide(&ORTHOGONAL) | slide(&DIAGONAL),
+            PieceKind::King => step(&ORTHOGONAL) | step(&DIAGONAL),
+        }
+    }
+
+    pub fn in_check(&self) -> bool {
+        self.checkers > 0
+    }
+
+    // en passant isn't covered, as it removes a piece which isn't on the target square
+    pub fn allows(&self, r#move: Move, color: Color) -> bool {
+        if let Move::Castling { side } = r#move {
+            let rank = color.home_rank();
+            let path = match side {
+                Side::King => [File::F, File::G],
+                Side::Queen => [File::D, File::C],
+            };
+            return !self.in_check() && path.into_iter().all(|file| self.danger & bit(Coordinate { file, rank }) == 0);
+        };
+
+        let from = r#move.resolve_from(color);
+        let to = bit(r#move.resolve_to(color));
+
+        if from == self.king {
+            return self.danger & to == 0;
+        };
+
+        if self.checkers > 1 || (self.checkers == 1 && self.evasions & to == 0) {
+            return false;
+        };
+
+        self.pins.iter().find(|(pinned, _)| *pinned == from).is_none_or(|(_, ray)| ray & to != 0)
+    }
+}
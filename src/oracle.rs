@@ -0,0 +1,176 @@
+use std::fmt::{Display, Formatter};
+use shakmaty::{CastlingMode, Chess, EnPassantMode, Position};
+use shakmaty::fen::Fen;
+use crate::{Board, DrawReason, GameOutcome, PlayerMove, WinReason};
+use crate::coordinate::{Coordinate, File, Rank};
+use crate::piece::Color;
+
+// what differs between us and the reference at the first position they disagree on
+#[derive(Debug, Clone)]
+pub enum Mismatch {
+    // placement, side to move and castling rights
+    Position { ours: String, reference: String },
+    Moves { missing: Vec<String>, extra: Vec<String> },
+    Outcome { ours: Option<GameOutcome>, reference: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub start: Option<String>,
+    // uci moves from the start, enough to reproduce it
+    pub moves: Vec<String>,
+    pub fen: String,
+    pub mismatch: Mismatch,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "position {}{}", self.start.as_ref().map_or("startpos".to_string(), |fen| format!("fen {fen}")),
+            if self.moves.is_empty() { String::new() } else { format!(" moves {}", self.moves.join(" ")) })?;
+        writeln!(f, "fen {}", self.fen)?;
+        match &self.mismatch {
+            Mismatch::Position { ours, reference } => write!(f, "position is {ours}, reference has {reference}"),
+            Mismatch::Moves { missing, extra } => write!(f, "missing moves: [{}], extra moves: [{}]", missing.join(" "), extra.join(" ")),
+            Mismatch::Outcome { ours, reference } => write!(f, "outcome is {ours:?}, reference has {reference}"),
+        }
+    }
+}
+
+// the side the reference has to move: a game our board finished keeps the side that finished it in `move_color`
+fn to_move(board: &Board) -> Color {
+    match board.history().last() {
+        Some((_, mover)) if board.game_outcome.is_some() && *mover == board.move_color => mover.the_other(),
+        _ => board.move_color,
+    }
+}
+
+// placement, side to move and castling rights; en passant is left out as the reference only writes it when it's legal
+fn fen_head(board: &Board) -> String {
+    let mut placement = String::new();
+    for rank in (0..8).rev() {
+        let mut empty = 0;
+        for file in 0..8 {
+            let coord = Coordinate { file: File::try_from(file).unwrap(), rank: Rank::try_from(rank).unwrap() };
+            match board.grid()[coord] {
+                None => empty += 1,
+                Some(piece) => {
+                    if empty > 0 {
+                        placement.push_str(&empty.to_string());
+                        empty = 0;
+                    };
+                    placement.push(match piece.color {
+                        Color::White => piece.kind.letter().to_ascii_uppercase(),
+                        Color::Black => piece.kind.letter(),
+                    });
+                },
+            };
+        };
+        if empty > 0 {
+            placement.push_str(&empty.to_string());
+        };
+        if rank > 0 {
+            placement.push('/');
+        };
+    };
+
+    let mut castling = String::new();
+    for (right, letter) in [(board.white_castle.0, 'K'), (board.white_castle.1, 'Q'), (board.black_castle.0, 'k'), (board.black_castle.1, 'q')] {
+        if right {
+            castling.push(letter);
+        };
+    };
+    if castling.is_empty() {
+        castling.push('-');
+    };
+
+    let side = match to_move(board) {
+        Color::White => 'w',
+        Color::Black => 'b',
+    };
+    format!("{placement} {side} {castling}")
+}
+
+fn reference_head(position: &Chess) -> String {
+    Fen::from_position(position, EnPassantMode::Legal).to_string().split_whitespace().take(3).collect::<Vec<_>>().join(" ")
+}
+
+fn compare(board: &Board, reference: &Chess) -> Option<Mismatch> {
+    let (ours, theirs) = (fen_head(board), reference_head(reference));
+    if ours != theirs {
+        return Some(Mismatch::Position { ours, reference: theirs });
+    };
+
+    let color = to_move(board);
+    let mut ours = board.possible_moves(color).into_iter().map(|r#move| r#move.to_long_algebraic(color)).collect::<Vec<_>>();
+    let mut theirs = reference.legal_moves().into_iter().map(|r#move| r#move.to_uci(CastlingMode::Standard).to_string()).collect::<Vec<_>>();
+    ours.sort();
+    theirs.sort();
+    if ours != theirs {
+        return Some(Mismatch::Moves {
+            missing: theirs.iter().filter(|r#move| !ours.contains(r#move)).cloned().collect(),
+            extra: ours.iter().filter(|r#move| !theirs.contains(r#move)).cloned().collect(),
+        });
+    };
+
+    let outcome_matches = match board.game_outcome {
+        Some(GameOutcome::Decisive { reason: WinReason::Checkmate, .. }) => reference.is_checkmate(),
+        Some(GameOutcome::Draw(DrawReason::Stalemate)) => reference.is_stalemate(),
+        Some(GameOutcome::Draw(DrawReason::InsufficientMaterial)) => reference.is_insufficient_material(),
+        // we end games on the 75-move rule and repetitions, which the reference leaves to the caller
+        _ => !reference.is_checkmate() && !reference.is_stalemate(),
+    };
+    (!outcome_matches).then(|| Mismatch::Outcome {
+        ours: board.game_outcome,
+        reference: if reference.is_checkmate() { "checkmate" } else if reference.is_stalemate() { "stalemate" } else { "no outcome" }.to_string(),
+    })
+}
+
+// xorshift, good enough to pick moves and keeps the walks reproducible without pulling in rand
+fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+// plays up to `plies` random moves from `start` (or the starting position), checking every position on the way
+pub fn walk(start: Option<&str>, plies: usize, seed: u64) -> Result<(), Box<Divergence>> {
+    let mut board = start.map_or_else(|| Some(Board::default()), Board::from_fen).expect("invalid start fen");
+    let mut reference: Chess = match start {
+        Some(fen) => Fen::from_ascii(fen.as_bytes()).expect("invalid start fen").into_position(CastlingMode::Standard).expect("illegal start position"),
+        None => Chess::default(),
+    };
+
+    let mut state = seed.max(1);
+    let mut moves = Vec::new();
+    for _ in 0..=plies {
+        if let Some(mismatch) = compare(&board, &reference) {
+            return Err(Box::new(Divergence { start: start.map(str::to_string), moves, fen: fen_head(&board), mismatch }));
+        };
+        if board.game_outcome.is_some() {
+            break;
+        };
+
+        let legal_moves = board.possible_moves(board.move_color);
+        let Some(r#move) = legal_moves.get(next_random(&mut state) as usize % legal_moves.len().max(1)).copied() else {
+            break;
+        };
+        let uci = r#move.to_long_algebraic(board.move_color);
+        let reference_move = reference.legal_moves().into_iter().find(|candidate| candidate.to_uci(CastlingMode::Standard).to_string() == uci).unwrap();
+
+        board.play_move(PlayerMove::Internal(r#move)).unwrap();
+        reference.play_unchecked(reference_move);
+        moves.push(uci);
+    };
+
+    Ok(())
+}
+
+// `games` walks with seeds derived from `seed`, stopping at the first divergence
+pub fn fuzz(games: usize, plies: usize, seed: u64) -> Result<(), Box<Divergence>> {
+    let mut state = seed.max(1);
+    for _ in 0..games {
+        walk(None, plies, next_random(&mut state))?;
+    };
+    Ok(())
+}
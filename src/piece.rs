@@ -1,7 +1,10 @@
-use std::fmt::{Display, Formatter};
+use core::fmt::{Display, Formatter};
 use crate::coordinate::Rank;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PieceKind {
     Pawn,
     Knight,
@@ -23,10 +26,67 @@ impl PieceKind {
             _ => None,
         }
     }
+
+    pub fn letter(self) -> char {
+        match self {
+            Self::Pawn => 'p',
+            Self::Knight => 'n',
+            Self::Bishop => 'b',
+            Self::Rook => 'r',
+            Self::Queen => 'q',
+            Self::King => 'k',
+        }
+    }
+
+    pub fn value(self, table: &ValueTable) -> i32 {
+        match self {
+            Self::Pawn => table.pawn,
+            Self::Knight => table.knight,
+            Self::Bishop => table.bishop,
+            Self::Rook => table.rook,
+            Self::Queen => table.queen,
+            Self::King => 0,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Pawn => "pawn",
+            Self::Knight => "knight",
+            Self::Bishop => "bishop",
+            Self::Rook => "rook",
+            Self::Queen => "queen",
+            Self::King => "king",
+        }
+    }
+}
+
+// what the pieces are worth, for anything counting material. the king isn't in it, it can't be traded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ValueTable {
+    pub pawn: i32,
+    pub knight: i32,
+    pub bishop: i32,
+    pub rook: i32,
+    pub queen: i32,
+}
+
+impl ValueTable {
+    // in pawns, as every chess book counts
+    pub const CLASSICAL: Self = Self { pawn: 1, knight: 3, bishop: 3, rook: 5, queen: 9 };
+    // in centipawns, with the bishop pair's edge given to each bishop
+    pub const CENTIPAWNS: Self = Self { pawn: 100, knight: 320, bishop: 330, rook: 500, queen: 900 };
+}
+
+impl Default for ValueTable {
+    fn default() -> Self {
+        Self::CLASSICAL
+    }
 }
 
 impl Display for PieceKind {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             PieceKind::Pawn => write!(f, "🨅"),
             PieceKind::Knight => write!(f, "🨄"),
@@ -39,13 +99,14 @@ impl Display for PieceKind {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Color {
     Black,
     White,
 }
 
 impl Display for Color {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             Color::White => write!(f, "white"),
             Color::Black => write!(f, "black"),
@@ -112,6 +173,7 @@ impl Color {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Piece {
     pub kind: PieceKind,
     pub color: Color,
@@ -135,24 +197,40 @@ macro_rules! piece {
 }
 
 impl Display for Piece {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let (offset, color_code) = match self.color {
-            Color::White => (0, "255;255;255"),
-            Color::Black => (6, "0;0;0"),
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let color_code = match self.color {
+            Color::White => "255;255;255",
+            Color::Black => "0;0;0",
         };
+        write!(f, "\x1B[38;2;{color_code}m{} \x1B[0m", self.glyph())
+    }
+}
 
-        write!(f, "\x1B[38;2;{color_code}m{} \x1B[0m", char::from_u32(match self.kind {
+impl Piece {
+    // the outlined chess glyph for white, the filled one for black
+    pub fn glyph(self) -> char {
+        let offset = match self.color {
+            Color::White => 0,
+            Color::Black => 6,
+        };
+        char::from_u32(match self.kind {
             PieceKind::Pawn => '♙',
             PieceKind::Knight => '♘',
             PieceKind::Bishop => '♗',
             PieceKind::Rook => '♖',
             PieceKind::Queen => '♕',
             PieceKind::King => '♔',
-        } as u32 + offset).unwrap())
+        } as u32 + offset).unwrap()
+    }
+
+    // as in a fen, uppercase for white
+    pub fn letter(self) -> char {
+        match self.color {
+            Color::White => self.kind.letter().to_ascii_uppercase(),
+            Color::Black => self.kind.letter(),
+        }
     }
-}
 
-impl Piece {
     pub fn parse(raw: &str) -> Option<Self> {
         Some(match raw {
             "P" => Self { kind: PieceKind::Pawn, color: Color::White },
@@ -0,0 +1,397 @@
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use crate::{is_material_sufficient_for_checkmate, movegen, Board, DrawReason, GameOutcome, KingRule, Rights, AUTOMATIC_DRAW_PLIES};
+use crate::coordinate::{Coordinate, File, Move, Offset};
+use crate::grid::Grid;
+use crate::hand::{Hand, Pockets};
+use crate::movegen::{MoveInfo, Setup};
+use crate::piece::{Color, Piece, PieceKind};
+use crate::variant::Variant;
+use crate::zobrist::{self, PositionKey};
+
+// the castling rights (white's, black's) after `color` made the move
+pub(crate) fn castling_after(mut castling: [(bool, bool); 2], color: Color, r#move: Move) -> [(bool, bool); 2] {
+    let (own, enemy) = match color {
+        Color::White => (0, 1),
+        Color::Black => (1, 0),
+    };
+
+    match r#move {
+        Move::Castling { .. } => castling[own] = (false, false),
+        Move::Simple { from: Coordinate { file: File::E, rank }, .. } if rank == color.home_rank() => castling[own] = (false, false),
+        Move::Simple { from: Coordinate { file: File::H, rank }, .. } if rank == color.home_rank() => castling[own].0 = false,
+        Move::Simple { from: Coordinate { file: File::A, rank }, .. } if rank == color.home_rank() => castling[own].1 = false,
+        _ => {}
+    };
+
+    // a rook captured at home takes its side's right to castle with it
+    let to = r#move.resolve_to(color);
+    if to.rank == color.the_other().home_rank() {
+        match to.file {
+            File::H => castling[enemy].0 = false,
+            File::A => castling[enemy].1 = false,
+            _ => {},
+        };
+    };
+    castling
+}
+
+// the file the opponent can take en passant on after `color` made the move, `grid` is the one after it.
+// only set when an enemy pawn actually stands next to the pushed one.
+pub(crate) fn en_passant_after(grid: &Grid, color: Color, r#move: Move) -> Option<File> {
+    match r#move {
+        Move::Simple { from, to } if from.rank == color.pawn_rank() && to.rank == color.the_other().en_passant_rank() &&
+            grid[to] == Some(Piece { color, kind: PieceKind::Pawn }) => {
+            let capturable = [1, -1].into_iter()
+                .filter_map(|of| to.checked_add_offset(Offset { vertical: 0, horizontal: of }))
+                .any(|coord| grid[coord] == Some(Piece { color: color.the_other(), kind: PieceKind::Pawn }));
+            capturable.then_some(to.file)
+        },
+        _ => None,
+    }
+}
+
+// the piece the move by `color` takes and where it stands, `grid` is the one before it
+pub(crate) fn captured(grid: &Grid, color: Color, r#move: Move) -> Option<(Piece, Coordinate)> {
+    let at = match r#move {
+        Move::EnPassant { to, .. } => Coordinate { file: to, rank: color.en_passant_rank() },
+        Move::Castling { .. } | Move::Drop { .. } => return None,
+        r#move => r#move.resolve_to(color),
+    };
+    grid[at].map(|piece| (piece, at))
+}
+
+// whatever the variant does beyond the move itself, `grid` is the one after it and `captured` what `captured` said
+// before it. the pockets follow the move last, so the variant still sees whether the captured piece was promoted.
+pub(crate) fn follow_move(variant: Variant, grid: &mut Grid, pockets: &mut Pockets, color: Color, r#move: Move, captured: Option<(Piece, Coordinate)>) {
+    if let Some((piece, at)) = captured {
+        variant.capture(grid, pockets, color, piece, at);
+    };
+    pockets.play(r#move, color);
+}
+
+// a position without the game that led to it, for searches and perft: a move is made and unmade in place
+// instead of cloning a board with its whole history for every node. without the history it can't tell
+// repetitions and doesn't end games, `outcome` only says how one would stand. the rest is up to the caller (or a `Board`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Position {
+    pub grid: Grid,
+    pub move_color: Color,
+    pub white_castle: (bool, bool),
+    pub black_castle: (bool, bool),
+    // the file the side to move can take en passant on
+    pub en_passant: Option<File>,
+    // plies without a capture or a pawn move
+    pub stale_plies: u8,
+    pub king_rule: KingRule,
+    // its move generation, captures and wins
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub variant: Variant,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pockets: Pockets,
+}
+
+// what making a move changed that the move itself doesn't tell, for `Position::unmake`
+#[derive(Debug, Clone)]
+pub struct Unmake {
+    captured: Option<Piece>,
+    // the whole grid before the move, only kept when a capture did more to it than take the piece
+    grid: Option<Grid>,
+    white_castle: (bool, bool),
+    black_castle: (bool, bool),
+    en_passant: Option<File>,
+    stale_plies: u8,
+    pockets: Pockets,
+}
+
+impl Position {
+    // plays a legal move for the side to move, anything else leaves the position in a state `unmake` can still undo
+    pub fn make(&mut self, r#move: Move) -> Unmake {
+        let color = self.move_color;
+        let taken = captured(&self.grid, color, r#move);
+        let unmake = Unmake {
+            captured: taken.filter(|_| !matches!(r#move, Move::EnPassant { .. })).map(|(piece, _)| piece),
+            grid: (taken.is_some() && self.variant.capture_changes_grid()).then(|| self.grid.clone()),
+            white_castle: self.white_castle,
+            black_castle: self.black_castle,
+            en_passant: self.en_passant,
+            stale_plies: self.stale_plies,
+            pockets: self.pockets,
+        };
+
+        let advancing = self.grid.r#move(r#move, color);
+        let mut pockets = self.pockets;
+        follow_move(self.variant, &mut self.grid, &mut pockets, color, r#move, taken);
+        // like a board, only a variant with drops keeps what's in hand and which pieces were promoted
+        if self.variant.drops() {
+            self.pockets = pockets;
+        };
+        [self.white_castle, self.black_castle] = castling_after([self.white_castle, self.black_castle], color, r#move);
+        self.en_passant = en_passant_after(&self.grid, color, r#move);
+        self.stale_plies = if advancing { 0 } else { self.stale_plies.saturating_add(1) };
+        self.move_color = color.the_other();
+        unmake
+    }
+
+    // takes back the move `make` returned `unmake` for, which has to be the last one made
+    pub fn unmake(&mut self, r#move: Move, unmake: Unmake) {
+        let color = self.move_color.the_other();
+        let (from, to) = (r#move.resolve_from(color), r#move.resolve_to(color));
+        // a capture the variant did more with than taking the piece leaves the whole grid to put back
+        if let Some(grid) = unmake.grid {
+            self.grid = grid;
+        } else {
+            match r#move {
+                Move::Simple { .. } => {
+                    self.grid[from] = self.grid[to].take();
+                    self.grid[to] = unmake.captured;
+                },
+                Move::Promotion { .. } => {
+                    self.grid[from] = Some(Piece { color, kind: PieceKind::Pawn });
+                    self.grid[to] = unmake.captured;
+                },
+                Move::EnPassant { to: file, .. } => {
+                    self.grid[from] = self.grid[to].take();
+                    self.grid[Coordinate { file, rank: color.en_passant_rank() }] = Some(Piece { color: color.the_other(), kind: PieceKind::Pawn });
+                },
+                Move::Castling { side } => {
+                    self.grid[from] = self.grid[to].take();
+                    let rank = color.home_rank();
+                    self.grid[Coordinate { file: side.rook_home_file(), rank }] = self.grid[Coordinate { file: side.rook_castled_file(), rank }].take();
+                },
+                Move::Drop { .. } => self.grid[to] = None,
+            };
+        };
+
+        self.white_castle = unmake.white_castle;
+        self.black_castle = unmake.black_castle;
+        self.en_passant = unmake.en_passant;
+        self.stale_plies = unmake.stale_plies;
+        self.pockets = unmake.pockets;
+        self.move_color = color;
+    }
+
+    // passes the turn without moving, for null-move pruning: only the side to move flips and en passant goes away
+    pub fn make_null_move(&mut self) -> Unmake {
+        let unmake = Unmake {
+            captured: None,
+            grid: None,
+            white_castle: self.white_castle,
+            black_castle: self.black_castle,
+            en_passant: self.en_passant.take(),
+            stale_plies: self.stale_plies,
+            pockets: self.pockets,
+        };
+        self.move_color = self.move_color.the_other();
+        unmake
+    }
+
+    pub fn unmake_null_move(&mut self, unmake: Unmake) {
+        self.en_passant = unmake.en_passant;
+        self.move_color = self.move_color.the_other();
+    }
+
+    // how the game stands after the move that led here, by what the position tells on its own: the variant's win
+    // for the side that made it, no moves left, the 75-move rule and dead material, in the order `Board::play_move` looks.
+    // repetitions need the history, they're the caller's.
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        let mover = self.move_color.the_other();
+        if let Some(reason) = self.variant.won_by_move(&self.grid, mover) {
+            return Some(GameOutcome::Decisive { won: mover, reason });
+        };
+
+        let mut stuck = true;
+        self.visit_legal_moves(|_| {
+            stuck = false;
+            ControlFlow::Break(())
+        });
+        if stuck {
+            Some(self.variant.stuck(self.move_color, self.in_check()))
+        } else if self.stale_plies >= AUTOMATIC_DRAW_PLIES {
+            Some(GameOutcome::Draw(DrawReason::NoAdvancement))
+        } else if self.variant.draws_without_mating_material() && !is_material_sufficient_for_checkmate(self.grid.iter_coord().filter_map(|(piece, coord)| piece.map(|piece| (piece, coord)))) {
+            Some(GameOutcome::Draw(DrawReason::InsufficientMaterial))
+        } else {
+            None
+        }
+    }
+
+    pub fn pieces(&self, color: Color) -> impl Iterator<Item = (Piece, Coordinate)> + '_ {
+        self.grid.iter_coord().filter_map(move |(piece, coord)| piece.filter(|piece| piece.color == color).map(|piece| (piece, coord)))
+    }
+
+    pub fn pieces_of(&self, color: Color, kind: PieceKind) -> impl Iterator<Item = Coordinate> + '_ {
+        self.pieces(color).filter(move |(piece, _)| piece.kind == kind).map(|(_, coord)| coord)
+    }
+
+    // calls `visit` for every legal move of the side to move until it breaks, like `Board::visit_legal_moves`
+    pub fn visit_legal_moves(&self, visit: impl FnMut(Move) -> ControlFlow<()>) {
+        movegen::visit_legal_moves(self, self.move_color, &mut None, visit);
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        self.visit_legal_moves(|r#move| {
+            moves.push(r#move);
+            ControlFlow::Continue(())
+        });
+        moves
+    }
+
+    // the legal captures and promotions of the side to move, like `Board::capture_moves`
+    pub fn capture_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        self.visit_legal_moves(|r#move| {
+            if matches!(r#move, Move::Promotion { .. }) || movegen::is_capture(&self.grid, self.move_color, r#move) {
+                moves.push(r#move);
+            };
+            ControlFlow::Continue(())
+        });
+        moves
+    }
+
+    // whether the side to move's (royal) king is attacked
+    pub fn in_check(&self) -> bool {
+        self.king_rule == KingRule::Royal && self.pieces_of(self.move_color, PieceKind::King).next()
+            .is_some_and(|king| movegen::is_under_attack(&self.grid, self.move_color.the_other(), king, None))
+    }
+
+    // whether the (legal) move checks the opponent, without making it
+    pub fn gives_check(&self, r#move: Move) -> bool {
+        movegen::gives_check(self, self.move_color, r#move)
+    }
+
+    // what the (legal) move does, e.g. for notation or ordering moves. only a check gets made to look for a mate.
+    pub fn move_info(&self, r#move: Move) -> MoveInfo {
+        let check = self.gives_check(r#move);
+        let checkmate = check && {
+            let mut after = self.clone();
+            after.make(r#move);
+            let mut stuck = true;
+            after.visit_legal_moves(|_| {
+                stuck = false;
+                ControlFlow::Break(())
+            });
+            stuck
+        };
+
+        let capture = movegen::is_capture(&self.grid, self.move_color, r#move);
+        let promotion = match r#move {
+            Move::Promotion { piece, .. } => Some(piece),
+            _ => None,
+        };
+        MoveInfo { r#move, capture, promotion, check, checkmate }
+    }
+
+    // the same key the board gives the position, see `Board::position_key`
+    pub fn key(&self) -> PositionKey {
+        zobrist::key(&self.grid, &Rights { move_color: self.move_color, white_castle: self.white_castle, black_castle: self.black_castle, en_passant: self.en_passant }, &self.pockets)
+    }
+}
+
+impl Setup for Position {
+    fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    fn castling(&self, color: Color) -> (bool, bool) {
+        match color {
+            Color::White => self.white_castle,
+            Color::Black => self.black_castle,
+        }
+    }
+
+    fn en_passant(&self, color: Color) -> Option<File> {
+        self.en_passant.filter(|_| color == self.move_color)
+    }
+
+    fn king_rule(&self) -> KingRule {
+        self.king_rule
+    }
+
+    fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    fn hand(&self, color: Color) -> Hand {
+        *self.pockets.hand(color)
+    }
+
+    fn pieces(&self, color: Color) -> impl Iterator<Item = (Piece, Coordinate)> + '_ {
+        Position::pieces(self, color)
+    }
+}
+
+impl Board {
+    // the current position on its own, for searching it without cloning the board's history
+    pub fn position(&self) -> Position {
+        Position {
+            grid: self.grid().clone(),
+            move_color: self.move_color,
+            white_castle: self.white_castle,
+            black_castle: self.black_castle,
+            en_passant: Setup::en_passant(self, self.move_color),
+            stale_plies: self.stale_plies,
+            king_rule: self.king_rule,
+            variant: self.variant,
+            pockets: self.pockets(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{fen, Board, PlayerMove};
+    use crate::variant::Variant;
+
+    // every line `depth` plies deep: making a move has to land where the board does, and unmaking it where it started
+    fn walk(board: &Board, depth: u8) {
+        let mut position = board.position();
+        let before = position.clone();
+        for r#move in position.legal_moves() {
+            let unmake = position.make(r#move);
+            let mut child = board.clone();
+            child.play_move(PlayerMove::Internal(r#move)).expect("a legal move");
+            assert_eq!(position, child.position(), "{move} from {}", board.to_fen());
+            assert_eq!(Some(position.key()), child.position_key(), "{move} from {}", board.to_fen());
+
+            position.unmake(r#move, unmake);
+            assert_eq!(position, before, "{move} from {}", board.to_fen());
+            if depth > 1 && child.game_outcome.is_none() {
+                walk(&child, depth - 1);
+            };
+        };
+        assert_eq!(position.key(), board.position().key());
+    }
+
+    #[test]
+    fn make_and_unmake() {
+        for (raw, variant) in [
+            // castling both ways, promotions and en passant all show up within two plies
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", Variant::Standard),
+            ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", Variant::Standard),
+            ("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1", Variant::Standard),
+            // a promoted knight on c6 goes back to the hand as a pawn
+            ("r1bqk2r/pppp1ppp/2n~5/4p3/1b2P3/5N2/PPPP1PPP/RNBQK2R[QRPbn] w KQkq - 2 5", Variant::Crazyhouse),
+            ("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w - - 0 2", Variant::Antichess),
+        ] {
+            let (board, _) = fen::parse_variant(raw, variant).expect("a valid fen");
+            walk(&board, 2);
+        };
+    }
+
+    #[test]
+    fn null_move() {
+        let board = Board::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3").expect("a valid fen");
+        let mut position = board.position();
+        let unmake = position.make_null_move();
+        // only the side to move changes, and en passant goes away
+        assert_eq!(position.en_passant, None);
+        assert_ne!(position.move_color, board.move_color);
+        position.unmake_null_move(unmake);
+        assert_eq!(position, board.position());
+    }
+}
@@ -0,0 +1,389 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use core::sync::atomic::{AtomicU8, Ordering};
+use crate::annotation::{Annotations, Arrow, Mark, MarkColor};
+use crate::coordinate::{Coordinate, File, Rank};
+use crate::grid::Grid;
+use crate::piece::{Color, Piece, PieceKind};
+
+// the svg's square size in pixels
+const SQUARE: usize = 45;
+// the squares a move was played from and to, and a king in check
+const LAST_MOVE: (u8, u8, u8) = (170, 190, 110);
+const CHECK: (u8, u8, u8) = (220, 40, 40);
+
+impl MarkColor {
+    fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            MarkColor::Red => (200, 60, 60),
+            MarkColor::Green => (60, 160, 80),
+            MarkColor::Blue => (60, 110, 200),
+            MarkColor::Yellow => (220, 180, 40),
+        }
+    }
+
+    // the letters lichess uses in its `[%csl ...]` and `[%cal ...]` comments
+    fn letter(self) -> char {
+        match self {
+            MarkColor::Red => 'R',
+            MarkColor::Green => 'G',
+            MarkColor::Blue => 'B',
+            MarkColor::Yellow => 'Y',
+        }
+    }
+
+    fn from_letter(letter: char) -> Option<Self> {
+        match letter {
+            'R' => Some(MarkColor::Red),
+            'G' => Some(MarkColor::Green),
+            'B' => Some(MarkColor::Blue),
+            'Y' => Some(MarkColor::Yellow),
+            _ => None,
+        }
+    }
+}
+
+fn square(raw: &str) -> Option<Coordinate> {
+    (raw.len() == 2 && raw.is_ascii()).then(|| Coordinate::parse(raw)).flatten()
+}
+
+// the squares an arrow passes over between its ends, none for a knight's jump
+fn path(arrow: &Arrow) -> Vec<Coordinate> {
+    let (files, ranks) = (arrow.to.file as i8 - arrow.from.file as i8, arrow.to.rank as i8 - arrow.from.rank as i8);
+    if files != 0 && ranks != 0 && files.abs() != ranks.abs() {
+        return Vec::new();
+    };
+    (1..files.abs().max(ranks.abs())).filter_map(|step| Some(Coordinate {
+        file: File::try_from(arrow.from.file as i8 + files.signum()*step).ok()?,
+        rank: Rank::try_from(arrow.from.rank as i8 + ranks.signum()*step).ok()?,
+    })).collect()
+}
+
+// core has no sqrt without std. starting above the root, a few newton steps are plenty for pixels.
+fn hypot(dx: f32, dy: f32) -> f32 {
+    let square = dx*dx + dy*dy;
+    let mut root = dx.abs() + dy.abs();
+    for _ in 0..6 {
+        if root == 0.0 {
+            break;
+        };
+        root = (root + square/root)/2.0;
+    };
+    root
+}
+
+// what gets drawn over the board, e.g. an engine's suggestion or a user's analysis
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderOptions {
+    pub arrows: Vec<Arrow>,
+    pub marks: Vec<(Coordinate, MarkColor)>,
+}
+
+impl RenderOptions {
+    // the highlights and arrows of the board's annotations, comments have nothing to draw
+    pub fn from_annotations(annotations: &Annotations) -> Self {
+        let marks = annotations.squares.iter().flat_map(|(coord, marks)| marks.iter().filter_map(|mark| match mark {
+            Mark::Highlight(color) => Some((*coord, *color)),
+            Mark::Comment(_) => None,
+        })).collect();
+        Self { arrows: annotations.arrows.clone(), marks }
+    }
+
+    pub fn arrow(mut self, from: Coordinate, to: Coordinate, color: MarkColor) -> Self {
+        self.arrows.push(Arrow { from, to, color });
+        self
+    }
+
+    pub fn mark(mut self, coord: Coordinate, color: MarkColor) -> Self {
+        self.marks.push((coord, color));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.arrows.is_empty() && self.marks.is_empty()
+    }
+
+    // the commands lichess keeps in pgn comments, e.g. `[%csl Gd4,Re5][%cal Ge2e4]`.
+    // anything else in the text is skipped, as are entries that don't make sense.
+    pub fn parse_commands(raw: &str) -> Self {
+        let mut options = Self::default();
+        for command in raw.split('[').filter_map(|part| part.split(']').next()) {
+            let (name, entries) = command.trim().split_once(' ').unwrap_or((command, ""));
+            for entry in entries.split(',').map(str::trim) {
+                let mut chars = entry.chars();
+                let Some(color) = chars.next().and_then(MarkColor::from_letter) else {
+                    continue;
+                };
+                let squares = chars.as_str();
+                match (name, squares.len()) {
+                    ("%csl", 2) => if let Some(coord) = square(squares) {
+                        options.marks.push((coord, color));
+                    },
+                    ("%cal", 4) if squares.is_ascii() => if let (Some(from), Some(to)) = (square(&squares[..2]), square(&squares[2..])) {
+                        options.arrows.push(Arrow { from, to, color });
+                    },
+                    _ => {},
+                };
+            };
+        };
+        options
+    }
+
+    // the opposite of `parse_commands`
+    pub fn commands(&self) -> String {
+        let mut raw = String::new();
+        if !self.marks.is_empty() {
+            let marks = self.marks.iter().map(|(coord, color)| format!("{}{coord}", color.letter())).collect::<Vec<_>>();
+            raw += &format!("[%csl {}]", marks.join(","));
+        };
+        if !self.arrows.is_empty() {
+            let arrows = self.arrows.iter().map(|arrow| format!("{}{}{}", arrow.color.letter(), arrow.from, arrow.to)).collect::<Vec<_>>();
+            raw += &format!("[%cal {}]", arrows.join(","));
+        };
+        raw
+    }
+
+    // the last one drawn over a square wins: arrows over marks, an arrow's ends over the squares it passes
+    fn background(&self, coord: Coordinate) -> Option<MarkColor> {
+        let end = self.arrows.iter().rev().find(|arrow| arrow.from == coord || arrow.to == coord).map(|arrow| arrow.color);
+        end.or_else(|| self.marks.iter().rev().find(|(marked, _)| *marked == coord).map(|(_, color)| *color))
+    }
+
+    fn passing(&self, coord: Coordinate) -> Option<MarkColor> {
+        self.arrows.iter().rev().find(|arrow| path(arrow).contains(&coord)).map(|arrow| arrow.color)
+    }
+}
+
+// how a grid is drawn as text, for terminals that can't show everything
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    // chess glyphs, or the fen's letters (uppercase for white)
+    pub unicode: bool,
+    // ansi colors for the squares and the pieces. without them the marks can't be shown, arrows still are.
+    pub colors: bool,
+    // the files and the ranks around the board
+    pub coordinates: bool,
+}
+
+impl TextStyle {
+    pub const FANCY: Self = Self { unicode: true, colors: true, coordinates: true };
+    // for dumb terminals and old consoles, nothing but ascii
+    pub const PLAIN: Self = Self { unicode: false, colors: false, coordinates: true };
+
+    // the fancy one without colors when NO_COLOR is set (and not empty) or the terminal is dumb, see no-color.org
+    #[cfg(feature = "std")]
+    pub fn from_env() -> Self {
+        let set = |name: &str| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+        let dumb = std::env::var_os("TERM").is_some_and(|term| term == "dumb");
+        Self { colors: !set("NO_COLOR") && !dumb, ..Self::FANCY }
+    }
+
+    const fn bits(self) -> u8 {
+        self.unicode as u8 | (self.colors as u8) << 1 | (self.coordinates as u8) << 2
+    }
+
+    fn from_bits(bits: u8) -> Self {
+        Self { unicode: bits & 1 != 0, colors: bits & 2 != 0, coordinates: bits & 4 != 0 }
+    }
+
+    fn piece(self, piece: Piece) -> char {
+        if self.unicode { piece.glyph() } else { piece.letter() }
+    }
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self::FANCY
+    }
+}
+
+static STYLE: AtomicU8 = AtomicU8::new(TextStyle::FANCY.bits());
+
+// the style grids and boards are displayed in, unless a rendering is given its own.
+// a front-end sets it once from its flags, it's shared by every thread.
+pub fn set_style(style: TextStyle) {
+    STYLE.store(style.bits(), Ordering::Relaxed);
+}
+
+pub fn style() -> TextStyle {
+    TextStyle::from_bits(STYLE.load(Ordering::Relaxed))
+}
+
+// a grid drawn for the terminal with the options on top, see `Grid::render`
+pub struct Rendered<'a> {
+    grid: &'a Grid,
+    options: &'a RenderOptions,
+    style: TextStyle,
+    // the side at the bottom
+    perspective: Color,
+    last_move: Option<(Coordinate, Coordinate)>,
+    // the king's square
+    check: Option<Coordinate>,
+}
+
+impl Rendered<'_> {
+    pub fn with_style(self, style: TextStyle) -> Self {
+        Self { style, ..self }
+    }
+
+    // black's has the ranks and the files reversed, h1 in the top left corner
+    pub fn with_perspective(self, perspective: Color) -> Self {
+        Self { perspective, ..self }
+    }
+
+    // highlighted under the options' marks and arrows, which were put there on purpose
+    pub fn with_last_move(self, from: Coordinate, to: Coordinate) -> Self {
+        Self { last_move: Some((from, to)), ..self }
+    }
+
+    pub fn with_check(self, king: Coordinate) -> Self {
+        Self { check: Some(king), ..self }
+    }
+
+    fn highlight(&self, coord: Coordinate) -> Option<(u8, u8, u8)> {
+        if let Some(color) = self.options.background(coord) {
+            return Some(color.rgb());
+        };
+        if self.check == Some(coord) {
+            return Some(CHECK);
+        };
+        self.last_move.filter(|(from, to)| *from == coord || *to == coord).map(|_| LAST_MOVE)
+    }
+}
+
+impl Display for Rendered<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let style = self.style;
+        let flipped = self.perspective == Color::Black;
+        let order = |i: i8| if flipped { 7 - i } else { i };
+        let files = |f: &mut Formatter<'_>| (0..8).try_for_each(|file| write!(f, "{} ", File::try_from(order(file)).unwrap_or(File::A)));
+        if style.coordinates {
+            write!(f, "  ")?;
+            files(f)?;
+            writeln!(f)?;
+        };
+
+        let passing = if style.unicode { '•' } else { '*' };
+        for row in (0..8).rev() {
+            let rank = Rank::try_from(order(row)).unwrap_or(Rank::First);
+            if style.coordinates {
+                write!(f, "{rank} ")?;
+            };
+            for file in (0..8).map(order) {
+                let coord = Coordinate { file: File::try_from(file).unwrap_or(File::A), rank };
+                if !style.colors {
+                    match (self.grid[coord], self.options.passing(coord)) {
+                        (Some(piece), _) => write!(f, "{} ", style.piece(piece))?,
+                        (None, Some(_)) => write!(f, "{passing} ")?,
+                        (None, None) => write!(f, ". ")?,
+                    };
+                    continue;
+                };
+
+                let bg_code = match self.highlight(coord) {
+                    Some((r, g, b)) => format!("48;2;{r};{g};{b}"),
+                    None if (file + rank as i8) % 2 == 0 => String::from("100"),
+                    None => String::from("47"),
+                };
+
+                match (self.grid[coord], self.options.passing(coord)) {
+                    (Some(piece), _) if style.unicode => write!(f, "\x1B[{bg_code}m{piece}")?,
+                    (Some(piece), _) => {
+                        let fg_code = match piece.color {
+                            Color::White => "255;255;255",
+                            Color::Black => "0;0;0",
+                        };
+                        write!(f, "\x1B[{bg_code}m\x1B[38;2;{fg_code}m{} \x1B[0m", piece.letter())?;
+                    },
+                    (None, Some(color)) => {
+                        let (r, g, b) = color.rgb();
+                        write!(f, "\x1B[{bg_code}m\x1B[38;2;{r};{g};{b}m{passing} \x1B[0m")?;
+                    },
+                    (None, None) => write!(f, "\x1B[{bg_code}m  \x1B[0m")?,
+                };
+            };
+            if style.coordinates {
+                write!(f, " {rank}")?;
+            };
+            if row != 0 {
+                writeln!(f)?;
+            };
+        };
+
+        if style.coordinates {
+            write!(f, "\n  ")?;
+            files(f)?;
+        };
+        Ok(())
+    }
+}
+
+impl Grid {
+    // in the current `style()`, `with_style` to pick another
+    pub fn render<'a>(&'a self, options: &'a RenderOptions) -> Rendered<'a> {
+        Rendered { grid: self, options, style: style(), perspective: Color::White, last_move: None, check: None }
+    }
+
+    // a standalone svg image of the position, white at the bottom
+    pub fn to_svg(&self, options: &RenderOptions) -> String {
+        let size = SQUARE*8;
+        let center = |coord: Coordinate| (coord.file as usize*SQUARE + SQUARE/2, (7 - coord.rank as usize)*SQUARE + SQUARE/2);
+        let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" viewBox=\"0 0 {size} {size}\">\n");
+
+        for coord in Coordinate::iter() {
+            let (x, y) = (coord.file as usize*SQUARE, (7 - coord.rank as usize)*SQUARE);
+            let fill = if (coord.file as usize + coord.rank as usize).is_multiple_of(2) { "#b58863" } else { "#f0d9b5" };
+            svg += &format!("<rect x=\"{x}\" y=\"{y}\" width=\"{SQUARE}\" height=\"{SQUARE}\" fill=\"{fill}\"/>\n");
+        };
+        for (coord, color) in &options.marks {
+            let ((x, y), (r, g, b)) = (center(*coord), color.rgb());
+            svg += &format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{SQUARE}\" height=\"{SQUARE}\" fill=\"rgb({r},{g},{b})\" fill-opacity=\"0.5\"/>\n",
+                x - SQUARE/2, y - SQUARE/2,
+            );
+        };
+
+        for (piece, coord) in Coordinate::iter().filter_map(|coord| self[coord].map(|piece| (piece, coord))) {
+            let (x, y) = center(coord);
+            // the filled glyphs for both, the color comes from the fill and the outline
+            let glyph = match piece.kind {
+                PieceKind::Pawn => '♟',
+                PieceKind::Knight => '♞',
+                PieceKind::Bishop => '♝',
+                PieceKind::Rook => '♜',
+                PieceKind::Queen => '♛',
+                PieceKind::King => '♚',
+            };
+            let (fill, stroke) = match piece.color {
+                Color::White => ("#ffffff", "#000000"),
+                Color::Black => ("#000000", "#ffffff"),
+            };
+            svg += &format!(
+                "<text x=\"{x}\" y=\"{y}\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{fill}\" stroke=\"{stroke}\" stroke-width=\"0.8\">{glyph}</text>\n",
+                SQUARE*4/5,
+            );
+        };
+
+        for arrow in &options.arrows {
+            let ((x1, y1), (x2, y2), (r, g, b)) = (center(arrow.from), center(arrow.to), arrow.color.rgb());
+            // stops short of the center, so the head doesn't cover the piece entirely
+            let (dx, dy) = (x2 as f32 - x1 as f32, y2 as f32 - y1 as f32);
+            let length = hypot(dx, dy).max(1.0);
+            let (x2, y2) = (x2 as f32 - dx/length*(SQUARE as f32/4.0), y2 as f32 - dy/length*(SQUARE as f32/4.0));
+            let (head, width) = (SQUARE as f32/3.0, SQUARE as f32/6.0);
+            let (nx, ny) = (-dy/length, dx/length);
+            let (bx, by) = (x2 - dx/length*head, y2 - dy/length*head);
+            svg += &format!(
+                "<g fill=\"rgb({r},{g},{b})\" stroke=\"rgb({r},{g},{b})\" opacity=\"0.8\">\
+                 <line x1=\"{x1}\" y1=\"{y1}\" x2=\"{bx:.1}\" y2=\"{by:.1}\" stroke-width=\"{width:.1}\" stroke-linecap=\"round\"/>\
+                 <polygon points=\"{x2:.1},{y2:.1} {:.1},{:.1} {:.1},{:.1}\" stroke=\"none\"/></g>\n",
+                bx + nx*head/2.0, by + ny*head/2.0, bx - nx*head/2.0, by - ny*head/2.0,
+            );
+        };
+
+        svg += "</svg>\n";
+        svg
+    }
+}
@@ -0,0 +1,236 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use crate::{Board, PlayerMove};
+use crate::coordinate::{Coordinate, Move, Side};
+use crate::piece::{Piece, PieceKind};
+
+impl Board {
+    // standard algebraic notation of a legal move for the side to move, e.g. `Nbd7`, `exd6`, `e8=Q+`, `O-O-O#` or `N@f3`
+    pub fn san(&self, r#move: Move) -> String {
+        let color = self.move_color;
+        let mut san = match r#move {
+            Move::Castling { side: Side::King } => String::from("O-O"),
+            Move::Castling { side: Side::Queen } => String::from("O-O-O"),
+            Move::Drop { piece, to } => alloc::format!("{}@{to}", piece.letter().to_ascii_uppercase()),
+            _ => {
+                let (from, to) = (r#move.resolve_from(color), r#move.resolve_to(color));
+                let kind = self.grid()[from].map_or(PieceKind::Pawn, |piece| piece.kind);
+                let capture = self.is_capture(r#move, color);
+
+                let mut san = String::new();
+                if kind == PieceKind::Pawn {
+                    if capture {
+                        san.push_str(&from.file.to_string());
+                    };
+                } else {
+                    san.push(kind.letter().to_ascii_uppercase());
+
+                    // other pieces of the same kind that could go there too
+                    let rivals = self.possible_moves(color).into_iter()
+                        .filter(|other| *other != r#move && other.resolve_to(color) == to && self.grid()[other.resolve_from(color)] == Some(Piece { color, kind }))
+                        .map(|other| other.resolve_from(color))
+                        .collect::<Vec<_>>();
+                    if !rivals.is_empty() {
+                        if rivals.iter().all(|rival| rival.file != from.file) {
+                            san.push_str(&from.file.to_string());
+                        } else if rivals.iter().all(|rival| rival.rank != from.rank) {
+                            san.push_str(&from.rank.to_string());
+                        } else {
+                            san.push_str(&from.to_string());
+                        };
+                    };
+                };
+
+                if capture {
+                    san.push('x');
+                };
+                san.push_str(&to.to_string());
+                if let Move::Promotion { piece, .. } = r#move {
+                    san.push('=');
+                    san.push(piece.letter().to_ascii_uppercase());
+                };
+                san
+            },
+        };
+
+        let info = self.move_info(r#move);
+        if info.checkmate {
+            san.push('#');
+        } else if info.check {
+            san.push('+');
+        };
+
+        san
+    }
+
+    // the legal move a san names for the side to move, checks and annotations don't matter.
+    // `0-0`, `e8Q`, long moves like `Ng1f3` and drops without the pawn's letter (`@e4`) are understood too, an ambiguous one names nothing.
+    pub fn parse_san(&self, raw: &str) -> Option<Move> {
+        let color = self.move_color;
+        let san = raw.trim().trim_end_matches(['+', '#', '!', '?']).replace('0', "O");
+        let legal = self.possible_moves(color);
+        match san.as_str() {
+            "O-O" => return legal.into_iter().find(|r#move| *r#move == Move::Castling { side: Side::King }),
+            "O-O-O" => return legal.into_iter().find(|r#move| *r#move == Move::Castling { side: Side::Queen }),
+            _ => {},
+        };
+
+        if let Some((piece, to)) = san.split_once('@') {
+            let piece = match piece {
+                "" => PieceKind::Pawn,
+                letter => PieceKind::parse(&letter.to_ascii_lowercase())?,
+            };
+            let r#move = Move::Drop { piece, to: Coordinate::parse(to)? };
+            return legal.contains(&r#move).then_some(r#move);
+        };
+
+        let (san, promotion) = match san.split_once('=') {
+            Some((san, piece)) => (san.to_string(), Some(PieceKind::parse(&piece.to_ascii_lowercase())?)),
+            // some leave out the `=`
+            None if san.ends_with(['Q', 'R', 'B', 'N']) && san.len() > 2 => {
+                let (san, piece) = san.split_at(san.len() - 1);
+                (san.to_string(), Some(PieceKind::parse(&piece.to_ascii_lowercase())?))
+            },
+            None => (san, None),
+        };
+        let san = san.replace('x', "");
+        let (kind, rest) = match san.chars().next()? {
+            letter @ ('N' | 'B' | 'R' | 'Q' | 'K') => (PieceKind::parse(&letter.to_ascii_lowercase().to_string())?, &san[1..]),
+            _ => (PieceKind::Pawn, san.as_str()),
+        };
+        if rest.len() < 2 || !rest.is_ascii() {
+            return None;
+        };
+        let (from, to) = rest.split_at(rest.len() - 2);
+        let to = Coordinate::parse(to)?;
+
+        let mut found = legal.into_iter().filter(|r#move| {
+            let start = r#move.resolve_from(color);
+            self.grid()[start] == Some(Piece { color, kind }) && r#move.resolve_to(color) == to &&
+                !matches!(r#move, Move::Castling { .. }) &&
+                from.chars().all(|c| start.to_string().contains(c)) &&
+                match r#move { Move::Promotion { piece, .. } => promotion == Some(*piece), _ => promotion.is_none() }
+        });
+        let r#move = found.next()?;
+        found.next().is_none().then_some(r#move)
+    }
+
+    // the legal moves whose san starts with `prefix` and their san, for completing what a player is typing.
+    // captures and checks don't have to be typed, `Nf` is the start of `Nxf7+` too.
+    pub fn san_completions(&self, prefix: &str) -> Vec<(Move, String)> {
+        let prefix = prefix.trim().replace('x', "");
+        self.possible_moves(self.move_color).into_iter()
+            .map(|r#move| (r#move, self.san(r#move)))
+            .filter(|(_, san)| san_key(san).starts_with(&prefix))
+            .collect()
+    }
+
+    // the move a san or the start of only its san names, so a player short on time can type `Nf` for the one knight
+    // move to the f-file. a full san counts even when it starts another one, like `O-O` does `O-O-O`.
+    pub fn complete_san(&self, prefix: &str) -> Option<Move> {
+        if let Some(r#move) = self.parse_san(prefix) {
+            return Some(r#move);
+        };
+        match self.san_completions(prefix).as_slice() {
+            [(r#move, _)] => Some(*r#move),
+            _ => None,
+        }
+    }
+
+    // each legal move with the shortest start of its san that `complete_san` takes for it
+    pub fn shortest_prefixes(&self) -> Vec<(Move, String)> {
+        let sans = self.possible_moves(self.move_color).into_iter().map(|r#move| (r#move, self.san(r#move))).collect::<Vec<_>>();
+        let keys = sans.iter().map(|(_, san)| san_key(san)).collect::<Vec<_>>();
+        sans.iter().zip(&keys).map(|((r#move, san), key)| {
+            let shortest = (1..key.len()).map(|len| &key[..len])
+                .find(|prefix| keys.iter().filter(|other| other.starts_with(prefix)).count() == 1)
+                .map_or_else(|| san.trim_end_matches(['+', '#']).to_string(), ToString::to_string);
+            (*r#move, shortest)
+        }).collect()
+    }
+
+    // what a player typed, in san if it names a legal move and otherwise in long algebraic or internal notation
+    pub fn parse_move(&self, raw: &str) -> Option<PlayerMove> {
+        if raw.trim().is_empty() {
+            return None;
+        };
+        self.parse_san(raw).map(PlayerMove::Internal).or_else(|| PlayerMove::parse(raw))
+    }
+}
+
+// what's compared when completing a san, without the marks that don't have to be typed
+fn san_key(san: &str) -> String {
+    san.trim_end_matches(['+', '#']).replace('x', "")
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use crate::{fen, Board, PlayerMove};
+    use crate::coordinate::{Coordinate, File, Move, Rank};
+    use crate::piece::PieceKind;
+
+    fn square(raw: &str) -> Coordinate {
+        Coordinate::parse(raw).expect("a square")
+    }
+
+    #[test]
+    fn nothing_typed() {
+        let board = Board::default();
+        for raw in ["", " ", "\t", "  \n"] {
+            assert!(board.parse_move(raw).is_none());
+            assert_eq!(board.parse_san(raw), None);
+        };
+        assert!(PlayerMove::parse("").is_none());
+    }
+
+    #[test]
+    fn not_ascii() {
+        let board = Board::default();
+        for raw in ["é4", "Nf3é", "ё", "e2é4", "Ñ@e4"] {
+            assert!(board.parse_move(raw).is_none());
+            assert!(PlayerMove::parse(raw).is_none());
+        };
+        assert_eq!(Coordinate::parse("é1"), None);
+    }
+
+    #[test]
+    fn basic_moves() {
+        let board = Board::default();
+        let nf3 = Move::Simple { from: square("g1"), to: square("f3") };
+        for raw in ["Nf3", "Nf3+!?", "Ng1f3"] {
+            assert!(matches!(board.parse_move(raw), Some(PlayerMove::Internal(r#move)) if r#move == nf3), "{raw}");
+        };
+        assert!(matches!(board.parse_move("g1f3"), Some(PlayerMove::Long { from, to, promotion: None }) if (from, to) == (square("g1"), square("f3"))));
+        assert_eq!(board.san(nf3), "Nf3");
+        // nothing can go there
+        assert_eq!(board.parse_san("Nf4"), None);
+    }
+
+    #[test]
+    fn san_round_trip() {
+        for (raw, dialect) in [
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", fen::Dialect::Standard),
+            // both knights reach d4, and a7 promotes taking or not
+            ("1n2k3/P7/8/1N6/8/5N2/8/4K3 w - - 0 1", fen::Dialect::Standard),
+            ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", fen::Dialect::Standard),
+            ("r1bqk2r/pppp1ppp/2n5/4p3/1b2P3/5N2/PPPP1PPP/RNBQK2R[QRPbn] w KQkq - 2 5", fen::Dialect::Crazyhouse),
+        ] {
+            let (board, _) = fen::parse(raw, dialect).expect("a valid fen");
+            for r#move in board.possible_moves(board.move_color) {
+                let san = board.san(r#move);
+                assert_eq!(board.parse_san(&san), Some(r#move), "{san} in {raw}");
+            };
+        };
+    }
+
+    #[test]
+    fn short_display() {
+        let short = |piece, to, from, capture, promotion| PlayerMove::Short { piece, to, from, capture, promotion }.to_string();
+        assert_eq!(short(PieceKind::Knight, (File::D, Some(Rank::Seventh)), (Some(File::B), None), true, None), "Nbxd7");
+        assert_eq!(short(PieceKind::Pawn, (File::E, Some(Rank::Eighth)), (None, None), false, Some(PieceKind::Queen)), "e8=Q");
+        assert_eq!(short(PieceKind::Rook, (File::A, Some(Rank::First)), (None, Some(Rank::Third)), false, None), "R3a1");
+        // as much as was given
+        assert_eq!(short(PieceKind::Pawn, (File::D, None), (Some(File::E), None), true, None), "exd");
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use crate::{Board, PlayerMove};
+use crate::coordinate::Move;
+
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum StoreError {
+    NotFound,
+    // ids end up in file names, so they're limited to ascii letters, digits, `-` and `_`
+    InvalidId,
+    Corrupt,
+    Io(std::io::Error),
+    #[cfg(feature = "sqlite")]
+    Sqlite(rusqlite::Error),
+}
+
+impl std::fmt::Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "there is no such game"),
+            Self::InvalidId => write!(f, "ids can only have ascii letters, digits, - and _"),
+            Self::Corrupt => write!(f, "the stored game can't be read"),
+            Self::Io(err) => write!(f, "{err}"),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for StoreError {
+    fn from(err: std::io::Error) -> Self {
+        match err.kind() {
+            ErrorKind::NotFound => Self::NotFound,
+            _ => Self::Io(err),
+        }
+    }
+}
+
+// the board only remembers positions, so games are stored as the moves leading to them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GameRecord {
+    // none for the standard starting position
+    pub fen: Option<String>,
+    pub moves: Vec<Move>,
+}
+
+impl GameRecord {
+    pub fn board(&self) -> Option<Board> {
+        let mut board = match &self.fen {
+            Some(fen) => Board::from_fen(fen)?,
+            None => Board::default(),
+        };
+
+        for r#move in &self.moves {
+            board.play_move(PlayerMove::Internal(*r#move)).ok()?;
+        };
+
+        Some(board)
+    }
+
+    // .rgf: an optional `fen ...` line followed by one move per line in internal notation
+    pub fn to_rgf(&self) -> String {
+        let mut buf = String::new();
+        if let Some(fen) = &self.fen {
+            writeln!(buf, "fen {fen}").unwrap();
+        };
+        for r#move in &self.moves {
+            writeln!(buf, "{move}").unwrap();
+        };
+        buf
+    }
+
+    pub fn from_rgf(raw: &str) -> Option<Self> {
+        let mut lines = raw.lines().map(str::trim).filter(|line| !line.is_empty()).peekable();
+        let fen = lines.next_if(|line| line.starts_with("fen ")).map(|line| line[4..].to_string());
+
+        let mut record = Self { fen, moves: Vec::new() };
+        let mut board = record.board()?;
+        for line in lines {
+            // simple moves print the same as long algebraic ones, so those are resolved against the board
+            let r#move = board.resolve_move(&PlayerMove::parse(line)?)?;
+
+            board.play_move(PlayerMove::Internal(r#move)).ok()?;
+            record.moves.push(r#move);
+        };
+
+        Some(record)
+    }
+}
+
+pub trait GameStore {
+    fn save(&self, id: &str, game: &GameRecord) -> Result<(), StoreError>;
+    fn load(&self, id: &str) -> Result<GameRecord, StoreError>;
+    fn list(&self) -> Result<Vec<String>, StoreError>;
+}
+
+fn check_id(id: &str) -> Result<(), StoreError> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(StoreError::InvalidId);
+    };
+    Ok(())
+}
+
+// one `<id>.rgf` file per game
+pub struct FsStore {
+    dir: PathBuf,
+}
+
+impl FsStore {
+    pub fn new(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.rgf"))
+    }
+}
+
+impl GameStore for FsStore {
+    fn save(&self, id: &str, game: &GameRecord) -> Result<(), StoreError> {
+        check_id(id)?;
+        // written aside and renamed, so a crash never leaves a half written game behind
+        let temp = self.dir.join(format!("{id}.rgf.tmp"));
+        std::fs::write(&temp, game.to_rgf())?;
+        std::fs::rename(temp, self.path(id))?;
+        Ok(())
+    }
+
+    fn load(&self, id: &str) -> Result<GameRecord, StoreError> {
+        check_id(id)?;
+        GameRecord::from_rgf(&std::fs::read_to_string(self.path(id))?).ok_or(StoreError::Corrupt)
+    }
+
+    fn list(&self) -> Result<Vec<String>, StoreError> {
+        let mut ids = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "rgf") && let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) {
+                ids.push(id.to_string());
+            };
+        };
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryStore {
+    games: RwLock<BTreeMap<String, GameRecord>>,
+}
+
+impl GameStore for MemoryStore {
+    fn save(&self, id: &str, game: &GameRecord) -> Result<(), StoreError> {
+        check_id(id)?;
+        self.games.write().unwrap().insert(id.to_string(), game.clone());
+        Ok(())
+    }
+
+    fn load(&self, id: &str) -> Result<GameRecord, StoreError> {
+        self.games.read().unwrap().get(id).cloned().ok_or(StoreError::NotFound)
+    }
+
+    fn list(&self) -> Result<Vec<String>, StoreError> {
+        Ok(self.games.read().unwrap().keys().cloned().collect())
+    }
+}
@@ -0,0 +1,95 @@
+use std::path::Path;
+use std::sync::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use crate::{Board, PlayerMove};
+use super::{check_id, GameRecord, GameStore, StoreError};
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(err: rusqlite::Error) -> Self {
+        Self::Sqlite(err)
+    }
+}
+
+// games plus an index of every position reached in them
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn in_memory() -> Result<Self, StoreError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, StoreError> {
+        conn.execute_batch("
+            PRAGMA foreign_keys = ON;
+            CREATE TABLE IF NOT EXISTS games (
+                id TEXT PRIMARY KEY,
+                rgf TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS positions (
+                hash INTEGER NOT NULL,
+                game TEXT NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+                ply INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS positions_by_hash ON positions(hash);
+        ")?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    // ids of the games which went through the given position, in order
+    pub fn games_reaching(&self, board: &Board) -> Result<Vec<String>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare_cached("SELECT DISTINCT game FROM positions WHERE hash = ?1 ORDER BY game")?;
+        let ids = statement.query_map([board.position_hash() as i64], |row| row.get(0))?.collect::<Result<_, _>>()?;
+        Ok(ids)
+    }
+}
+
+impl GameStore for SqliteStore {
+    fn save(&self, id: &str, game: &GameRecord) -> Result<(), StoreError> {
+        check_id(id)?;
+
+        let mut board = match &game.fen {
+            Some(fen) => Board::from_fen(fen).ok_or(StoreError::Corrupt)?,
+            None => Board::default(),
+        };
+        let mut hashes = vec![board.position_hash() as i64];
+        for r#move in &game.moves {
+            board.play_move(PlayerMove::Internal(*r#move)).map_err(|_| StoreError::Corrupt)?;
+            hashes.push(board.position_hash() as i64);
+        };
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM positions WHERE game = ?1", [id])?;
+        tx.execute("INSERT OR REPLACE INTO games (id, rgf) VALUES (?1, ?2)", params![id, game.to_rgf()])?;
+        {
+            let mut insert = tx.prepare_cached("INSERT INTO positions (hash, game, ply) VALUES (?1, ?2, ?3)")?;
+            for (ply, hash) in hashes.into_iter().enumerate() {
+                insert.execute(params![hash, id, ply as i64])?;
+            };
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn load(&self, id: &str) -> Result<GameRecord, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let rgf: String = conn.query_row("SELECT rgf FROM games WHERE id = ?1", [id], |row| row.get(0))
+            .optional()?
+            .ok_or(StoreError::NotFound)?;
+        GameRecord::from_rgf(&rgf).ok_or(StoreError::Corrupt)
+    }
+
+    fn list(&self) -> Result<Vec<String>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare_cached("SELECT id FROM games ORDER BY id")?;
+        let ids = statement.query_map([], |row| row.get(0))?.collect::<Result<_, _>>()?;
+        Ok(ids)
+    }
+}
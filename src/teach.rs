@@ -0,0 +1,173 @@
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use crate::{movegen, Board, PlayerMove};
+use crate::coordinate::{Coordinate, File, Move, Side};
+use crate::piece::{Color, Piece, PieceKind};
+
+// why a move was rejected, phrased for someone still learning the rules
+#[derive(Debug, Clone, PartialEq)]
+pub enum IllegalReason {
+    GameOver,
+    NoPiece(Coordinate),
+    NotYourPiece(Piece, Coordinate),
+    OwnPieceOnTarget(Piece, Coordinate),
+    CannotMoveThere { piece: PieceKind, from: Coordinate, to: Coordinate },
+    MissingPromotion,
+    NoCastlingRights(Side),
+    CastlingBlocked(Piece, Coordinate),
+    CastlingOutOfCheck(Vec<(Piece, Coordinate)>),
+    CastlingThroughCheck(Coordinate, Vec<(Piece, Coordinate)>),
+    StillInCheck(Vec<(Piece, Coordinate)>),
+    IntoCheck(Vec<(Piece, Coordinate)>),
+    ExposesKing { piece: PieceKind, from: Coordinate, attackers: Vec<(Piece, Coordinate)> },
+}
+
+struct Attackers<'a>(&'a [(Piece, Coordinate)]);
+
+impl Display for Attackers<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for (i, (piece, coord)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", if i+1 == self.0.len() { " and " } else { ", " })?;
+            };
+            write!(f, "the {} on {coord}", piece.kind.name())?;
+        };
+        Ok(())
+    }
+}
+
+impl Display for IllegalReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::GameOver => write!(f, "the game is already over"),
+            Self::NoPiece(coord) => write!(f, "there is no piece on {coord}"),
+            Self::NotYourPiece(piece, coord) => write!(f, "the {} on {coord} is {}'s, not yours", piece.kind.name(), piece.color),
+            Self::OwnPieceOnTarget(piece, coord) => write!(f, "your own {} is already on {coord}", piece.kind.name()),
+            Self::CannotMoveThere { piece, from, to } => write!(f, "a {} can't get from {from} to {to}", piece.name()),
+            Self::MissingPromotion => write!(f, "a pawn reaching the last rank has to promote, add the piece to the move (e.g. e7e8q)"),
+            Self::NoCastlingRights(side) => write!(f, "you can't castle {} anymore, the king or that rook has already moved", match side {
+                Side::King => "king side",
+                Side::Queen => "queen side",
+            }),
+            Self::CastlingBlocked(piece, coord) => write!(f, "the {} on {coord} is in the way of castling", piece.kind.name()),
+            Self::CastlingOutOfCheck(attackers) => write!(f, "you can't castle out of check (from {})", Attackers(attackers)),
+            Self::CastlingThroughCheck(coord, attackers) => write!(f, "your king would pass through {coord}, which is attacked by {}", Attackers(attackers)),
+            Self::StillInCheck(attackers) => write!(f, "your king would still be in check from {}", Attackers(attackers)),
+            Self::IntoCheck(attackers) => write!(f, "your king would walk into check from {}", Attackers(attackers)),
+            Self::ExposesKing { piece, from, attackers } => write!(f, "the {} on {from} is pinned, moving it would put your king in check from {}", piece.name(), Attackers(attackers)),
+        }
+    }
+}
+
+impl Board {
+    // none if the move is actually legal (or written in a form that can't be explained)
+    pub fn explain_illegal(&self, r#move: PlayerMove) -> Option<IllegalReason> {
+        if self.game_outcome.is_some() {
+            return Some(IllegalReason::GameOver);
+        };
+
+        if self.resolve_move(&r#move).is_some() {
+            return None;
+        };
+
+        let color = self.move_color;
+        let (from, to, promotion) = match r#move {
+            PlayerMove::Internal(Move::Castling { side }) => return self.explain_castling(side),
+            PlayerMove::Internal(r#move) => (r#move.resolve_from(color), r#move.resolve_to(color), match r#move {
+                Move::Promotion { piece, .. } => Some(piece),
+                _ => None,
+            }),
+            PlayerMove::Long { from, to, promotion } => (from, to, promotion),
+            PlayerMove::Short { .. } => return None,
+        };
+
+        let piece = match self.grid()[from] {
+            None => return Some(IllegalReason::NoPiece(from)),
+            Some(piece) if piece.color != color => return Some(IllegalReason::NotYourPiece(piece, from)),
+            Some(piece) => piece,
+        };
+
+        if piece.kind == PieceKind::King && from == (Coordinate { file: File::E, rank: color.home_rank() }) && to.rank == from.rank {
+            for side in [Side::King, Side::Queen] {
+                if to.file == side.king_safespot_file() {
+                    return self.explain_castling(side);
+                };
+            };
+        };
+
+        if let Some(target) = self.grid()[to] && target.color == color {
+            return Some(IllegalReason::OwnPieceOnTarget(target, to));
+        };
+
+        let mut pseudo_legal = None;
+        movegen::piece_moves(self, color, piece.kind, from, &mut |candidate| {
+            if candidate.resolve_to(color) == to && !matches!(candidate, Move::Castling { .. }) {
+                pseudo_legal = Some(candidate);
+            };
+        });
+        let Some(pseudo_legal) = pseudo_legal else {
+            return Some(IllegalReason::CannotMoveThere { piece: piece.kind, from, to });
+        };
+        if matches!(pseudo_legal, Move::Promotion { .. }) && promotion.is_none() {
+            return Some(IllegalReason::MissingPromotion);
+        };
+
+        let king = self.find_piece(Piece { kind: PieceKind::King, color })?;
+        let in_check = !self.attackers(color.the_other(), king).is_empty();
+
+        let mut grid = self.grid().clone();
+        grid.r#move(pseudo_legal, color);
+        let king_after = if piece.kind == PieceKind::King { to } else { king };
+        let attackers = movegen::attackers(&grid, color.the_other(), king_after);
+        if attackers.is_empty() {
+            return None;
+        };
+
+        Some(if piece.kind == PieceKind::King {
+            IllegalReason::IntoCheck(attackers)
+        } else if in_check {
+            IllegalReason::StillInCheck(attackers)
+        } else {
+            IllegalReason::ExposesKing { piece: piece.kind, from, attackers }
+        })
+    }
+
+    fn explain_castling(&self, side: Side) -> Option<IllegalReason> {
+        let color = self.move_color;
+        let rank = color.home_rank();
+        let rights = match color {
+            Color::White => self.white_castle,
+            Color::Black => self.black_castle,
+        };
+        if !match side { Side::King => rights.0, Side::Queen => rights.1 } {
+            return Some(IllegalReason::NoCastlingRights(side));
+        };
+
+        let (between, path) = match side {
+            Side::King => (&[File::F, File::G][..], [File::F, File::G]),
+            Side::Queen => (&[File::D, File::C, File::B][..], [File::D, File::C]),
+        };
+        for file in between {
+            let coord = Coordinate { file: *file, rank };
+            if let Some(piece) = self.grid()[coord] {
+                return Some(IllegalReason::CastlingBlocked(piece, coord));
+            };
+        };
+
+        let king = Coordinate { file: File::E, rank };
+        let attackers = self.attackers(color.the_other(), king);
+        if !attackers.is_empty() {
+            return Some(IllegalReason::CastlingOutOfCheck(attackers));
+        };
+
+        for file in path {
+            let coord = Coordinate { file, rank };
+            let attackers = self.attackers(color.the_other(), coord);
+            if !attackers.is_empty() {
+                return Some(IllegalReason::CastlingThroughCheck(coord, attackers));
+            };
+        };
+
+        None
+    }
+}
@@ -0,0 +1,106 @@
+use core::fmt::{Display, Formatter};
+use crate::{Board, KingRule};
+use crate::coordinate::Coordinate;
+use crate::piece::{Color, PieceKind};
+use crate::zobrist;
+
+// what can go out of sync inside a board when its public fields are changed by hand, or by a bug.
+// none of these can come from playing legal moves, a board that has one has to be thrown away.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Corruption {
+    NoGrid,
+    // a piece list entry the grid doesn't have, or the other way around
+    PieceList(Coordinate),
+    // there's a rights entry for more positions than there are grids
+    RightsHistory { grids: usize, rights: usize },
+    // there are more moves than the grids could have come from
+    MoveHistory { grids: usize, moves: usize },
+    // the castling rights or the side to move aren't what the last rights entry says
+    Rights,
+    // the position keys don't match the positions, see `PositionKey`
+    PositionKeys,
+    // a royal king more than the one there can be
+    Kings(Color),
+    // more plies without a capture or a pawn move than were played, with the ones before the first grid
+    StalePlies(u8),
+    PawnOnBackRank(Coordinate),
+}
+
+impl Display for Corruption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::NoGrid => write!(f, "there is no grid"),
+            Self::PieceList(coord) => write!(f, "the piece list doesn't match the grid on {coord}"),
+            Self::RightsHistory { grids, rights } => write!(f, "there are {rights} rights for {grids} grids"),
+            Self::MoveHistory { grids, moves } => write!(f, "there are {moves} moves for {grids} grids"),
+            Self::Rights => write!(f, "the castling rights or the side to move differ from the rights history"),
+            Self::PositionKeys => write!(f, "the position keys differ from the positions"),
+            Self::Kings(color) => write!(f, "{color} has more than one royal king"),
+            Self::StalePlies(plies) => write!(f, "{plies} stale plies is more than have been played"),
+            Self::PawnOnBackRank(coord) => write!(f, "there is a pawn on {coord}"),
+        }
+    }
+}
+
+impl core::error::Error for Corruption {}
+
+// the first thing that's inconsistent about the board
+pub fn validate(board: &Board) -> Result<(), Corruption> {
+    if board.grid_history.is_empty() {
+        return Err(Corruption::NoGrid);
+    };
+
+    let grid = board.grid();
+    for coord in Coordinate::iter() {
+        let mut listed = board.piece_list.iter().filter(|(_, at)| *at == coord).map(|(piece, _)| *piece);
+        if (listed.next(), listed.next()) != (grid[coord], None) {
+            return Err(Corruption::PieceList(coord));
+        };
+    };
+
+    if board.rights_history.len() > board.grid_history.len() {
+        return Err(Corruption::RightsHistory { grids: board.grid_history.len(), rights: board.rights_history.len() });
+    };
+    if board.history().len() >= board.grid_history.len() {
+        return Err(Corruption::MoveHistory { grids: board.grid_history.len(), moves: board.history().len() });
+    };
+    if let Some(rights) = board.rights_history.last() {
+        // a game finished by its last move keeps the side that made it to move,
+        // one finished without a move (a claimed draw, a flag) keeps the side that was to move
+        let finished_by_move = board.game_outcome.is_some() && board.history().last().is_some_and(|(_, color)| *color == board.move_color);
+        if rights.white_castle != board.white_castle || rights.black_castle != board.black_castle || (rights.move_color != board.move_color && !finished_by_move) {
+            return Err(Corruption::Rights);
+        };
+    };
+
+    let last_key = board.rights_history.last().map(|rights| zobrist::key(grid, rights, &board.pockets()));
+    if board.keys.len() != board.rights_history.len() || board.position_key() != last_key {
+        return Err(Corruption::PositionKeys);
+    };
+
+    if board.king_rule == KingRule::Royal && let Some(color) = [Color::White, Color::Black].into_iter().find(|color| board.pieces_of(*color, PieceKind::King).count() > 1) {
+        return Err(Corruption::Kings(color));
+    };
+
+    let played = board.first_stale_plies as usize + board.grid_history.len() - 1;
+    if board.stale_plies as usize > played {
+        return Err(Corruption::StalePlies(board.stale_plies));
+    };
+
+    if let Some((_, coord)) = board.pieces(Color::White).chain(board.pieces(Color::Black))
+        .find(|(piece, coord)| piece.kind == PieceKind::Pawn && (coord.rank == Color::White.home_rank() || coord.rank == Color::Black.home_rank())) {
+        return Err(Corruption::PawnOnBackRank(coord));
+    };
+
+    Ok(())
+}
+
+// panics with what's wrong in debug builds, or in any build with the `validate` feature, and does nothing otherwise.
+// with the feature the board calls it itself after every move played or taken back.
+#[track_caller]
+pub fn debug_validate(board: &Board) {
+    if cfg!(any(debug_assertions, feature = "validate")) && let Err(corruption) = validate(board) {
+        panic!("the board is corrupted, {corruption}");
+    };
+}
@@ -0,0 +1,142 @@
+use core::fmt::{Display, Formatter};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use crate::{DrawReason, GameOutcome, KingRule, WinReason};
+use crate::coordinate::{Coordinate, File, Rank};
+use crate::fen::Dialect;
+use crate::grid::Grid;
+use crate::hand::Pockets;
+use crate::piece::{Color, Piece, PieceKind};
+
+const PROMOTIONS: [PieceKind; 4] = [PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight];
+const ANTICHESS_PROMOTIONS: [PieceKind; 5] = [PieceKind::Queen, PieceKind::Rook, PieceKind::Bishop, PieceKind::Knight, PieceKind::King];
+
+// the squares a king wins king of the hill on
+const HILL: [Coordinate; 4] = [
+    Coordinate { file: File::D, rank: Rank::Fourth },
+    Coordinate { file: File::E, rank: Rank::Fourth },
+    Coordinate { file: File::D, rank: Rank::Fifth },
+    Coordinate { file: File::E, rank: Rank::Fifth },
+];
+
+// the rules a board is played by. everything a variant changes goes through the hooks below, which move generation,
+// `Board::play_move` and the outcome detection ask, so a variant is a new arm in them instead of another board.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Variant {
+    #[default]
+    Standard,
+    // whoever runs out of moves (usually by losing every piece) wins, and a capture has to be made when there is one
+    Antichess,
+    // a king reaching one of the four center squares wins on the spot
+    KingOfTheHill,
+    // a captured piece goes to the capturer's hand, and can be dropped back on the board instead of moving
+    Crazyhouse,
+}
+
+impl Display for Variant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Standard => write!(f, "standard"),
+            Self::Antichess => write!(f, "antichess"),
+            Self::KingOfTheHill => write!(f, "king of the hill"),
+            Self::Crazyhouse => write!(f, "crazyhouse"),
+        }
+    }
+}
+
+impl Variant {
+    // by lichess' keys, e.g. `kingOfTheHill`
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "standard" => Some(Self::Standard),
+            "antichess" => Some(Self::Antichess),
+            "kingOfTheHill" => Some(Self::KingOfTheHill),
+            "crazyhouse" => Some(Self::Crazyhouse),
+            _ => None,
+        }
+    }
+
+    pub fn king_rule(self) -> KingRule {
+        match self {
+            Self::Antichess => KingRule::Ordinary,
+            Self::Standard | Self::KingOfTheHill | Self::Crazyhouse => KingRule::Royal,
+        }
+    }
+
+    // move generation: whether the rights to castle mean anything at all
+    pub fn castles(self) -> bool {
+        self != Self::Antichess
+    }
+
+    // move generation: what a pawn may become
+    pub fn promotions(self) -> &'static [PieceKind] {
+        match self {
+            Self::Antichess => &ANTICHESS_PROMOTIONS,
+            Self::Standard | Self::KingOfTheHill | Self::Crazyhouse => &PROMOTIONS,
+        }
+    }
+
+    // move generation: whether only captures are legal as long as there is one
+    pub fn forces_captures(self) -> bool {
+        self == Self::Antichess
+    }
+
+    // move generation: whether the pieces in hand can be dropped
+    pub fn drops(self) -> bool {
+        self == Self::Crazyhouse
+    }
+
+    // capture handling: whatever capturing `piece` on `at` does beyond taking it. the grid is the one after the move,
+    // the pockets the ones before it. in crazyhouse the piece goes to `color`'s hand, as the pawn it was if it was promoted,
+    // and atomic would clear the pieces around `at` off the grid.
+    pub(crate) fn capture(self, _grid: &mut Grid, pockets: &mut Pockets, color: Color, piece: Piece, at: Coordinate) {
+        match self {
+            Self::Crazyhouse => {
+                let kind = if pockets.is_promoted(at) { PieceKind::Pawn } else { piece.kind };
+                pockets.hand_mut(color).add(kind);
+            },
+            Self::Standard | Self::Antichess | Self::KingOfTheHill => {},
+        }
+    }
+
+    // whether `capture` changes the grid, a search `Position` keeps a copy of it to take the move back then
+    pub fn capture_changes_grid(self) -> bool {
+        match self {
+            Self::Standard | Self::Antichess | Self::KingOfTheHill | Self::Crazyhouse => false,
+        }
+    }
+
+    // outcome detection: a win the move by `color` just made gives it, before anything else is looked at
+    pub(crate) fn won_by_move(self, grid: &Grid, color: Color) -> Option<WinReason> {
+        match self {
+            Self::KingOfTheHill => HILL.iter().any(|coord| grid[*coord] == Some(Piece { kind: PieceKind::King, color }))
+                .then_some(WinReason::KingOfTheHill),
+            Self::Standard | Self::Antichess | Self::Crazyhouse => None,
+        }
+    }
+
+    // outcome detection: how the game ends when `color` has no legal moves
+    pub fn stuck(self, color: Color, in_check: bool) -> GameOutcome {
+        match self {
+            Self::Antichess => GameOutcome::Decisive { won: color, reason: WinReason::OutOfMoves },
+            _ if in_check => GameOutcome::Decisive { won: color.the_other(), reason: WinReason::Checkmate },
+            _ => GameOutcome::Draw(DrawReason::Stalemate),
+        }
+    }
+
+    // outcome detection: whether a position without mating material is drawn.
+    // a lone king still walks to the hill, antichess isn't about mating at all and in crazyhouse the material comes back.
+    pub fn draws_without_mating_material(self) -> bool {
+        self == Self::Standard
+    }
+
+    // how its fens are written, see `Board::to_fen`
+    pub fn dialect(self) -> Dialect {
+        match self {
+            Self::Crazyhouse => Dialect::Crazyhouse,
+            Self::Standard | Self::Antichess | Self::KingOfTheHill => Dialect::Standard,
+        }
+    }
+}
@@ -0,0 +1,63 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use crate::Rights;
+use crate::coordinate::Coordinate;
+use crate::grid::Grid;
+use crate::hand::Pockets;
+use crate::piece::Color;
+
+// what makes two positions the same one for the repetition rules: the pieces, the side to move, the castling rights,
+// whether a pawn can be taken en passant and what's in hand (in crazyhouse). it's a zobrist hash, xoring together a number for each of those.
+// the numbers come from a fixed seed, so keys stay the same between builds and can be stored.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PositionKey(pub u64);
+
+// 12 pieces on 64 squares, then 4 castling rights, 8 en passant files, black to move
+// and up to 16 of the 10 pieces that can be in hand
+const COUNT: usize = 12*64 + 4 + 8 + 1 + 10*HELD;
+const CASTLING: usize = 12*64;
+const EN_PASSANT: usize = CASTLING + 4;
+const BLACK_TO_MOVE: usize = EN_PASSANT + 8;
+const HAND: usize = BLACK_TO_MOVE + 1;
+const HELD: usize = 16;
+
+// splitmix64, which is good enough for spreading the bits and simple enough to run at compile time
+const NUMBERS: [u64; COUNT] = {
+    let mut numbers = [0; COUNT];
+    let mut state: u64 = 0x7265_7373_2d7a_6f62;
+    let mut i = 0;
+    while i < COUNT {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        numbers[i] = z ^ (z >> 31);
+        i += 1;
+    };
+    numbers
+};
+
+// empty pockets don't change the key, so positions without drops get the same one either way
+pub(crate) fn key(grid: &Grid, rights: &Rights, pockets: &Pockets) -> PositionKey {
+    let mut key = Coordinate::iter()
+        .filter_map(|coord| grid[coord].map(|piece| NUMBERS[(piece.kind as usize*2 + piece.color as usize)*64 + coord.rank as usize*8 + coord.file as usize]))
+        .fold(0, |key, number| key ^ number);
+
+    let castling = [rights.white_castle.0, rights.white_castle.1, rights.black_castle.0, rights.black_castle.1];
+    for (i, _) in castling.into_iter().enumerate().filter(|(_, allowed)| *allowed) {
+        key ^= NUMBERS[CASTLING + i];
+    };
+    if let Some(file) = rights.en_passant {
+        key ^= NUMBERS[EN_PASSANT + file as usize];
+    };
+    if rights.move_color == Color::Black {
+        key ^= NUMBERS[BLACK_TO_MOVE];
+    };
+    for color in [Color::White, Color::Black] {
+        for (kind, count) in pockets.hand(color).iter() {
+            key ^= NUMBERS[HAND + (kind as usize*2 + color as usize)*HELD + (count as usize).min(HELD) - 1];
+        };
+    };
+    PositionKey(key)
+}